@@ -24,7 +24,7 @@ where
 
 pub fn eval_lua(lua: &Lua, code: &str) -> Result<Value> {
 	let res = lua.load(code).eval::<mlua::Value>();
-	let res_lua_value = process_lua_eval_result(lua, res, code)?;
+	let res_lua_value = process_lua_eval_result(lua, res, code, None)?;
 	let value = serde_json::to_value(&res_lua_value)?;
 	Ok(value)
 }
@@ -0,0 +1,247 @@
+//! A minimal local HTTP server standing in for an OpenAI compatible provider.
+//!
+//! Each request gets the next queued response (the last one is repeated when the queue is exhausted).
+
+use crate::_test_support::Result;
+use genai::resolver::{AuthData, Endpoint};
+use genai::{Client, ServiceTarget};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+	status: u16,
+	content_type: &'static str,
+	body: String,
+	/// When true, the connection is closed before the end of the body (the content-length is larger)
+	truncated: bool,
+}
+
+/// Constructors
+impl MockResponse {
+	pub fn status(status: u16, body: impl Into<String>) -> Self {
+		MockResponse {
+			status,
+			content_type: "application/json",
+			body: body.into(),
+			truncated: false,
+		}
+	}
+
+	/// A successful OpenAI chat completion response with this content
+	pub fn chat_ok(content: &str) -> Self {
+		let body = serde_json::json!({
+			"id": "chatcmpl-mock",
+			"object": "chat.completion",
+			"model": "gpt-4o-mini",
+			"choices": [{
+				"index": 0,
+				"message": {"role": "assistant", "content": content},
+				"finish_reason": "stop"
+			}],
+			"usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+		});
+		Self::status(200, body.to_string())
+	}
+
+	/// A successful OpenAI chat completion response calling the tools `(call_id, fn_name, arguments)`
+	pub fn chat_tool_calls(tool_calls: &[(&str, &str, serde_json::Value)]) -> Self {
+		let tool_calls: Vec<serde_json::Value> = tool_calls
+			.iter()
+			.map(|(call_id, fn_name, arguments)| {
+				serde_json::json!({
+					"id": call_id,
+					"type": "function",
+					"function": {"name": fn_name, "arguments": arguments.to_string()}
+				})
+			})
+			.collect();
+		let body = serde_json::json!({
+			"id": "chatcmpl-mock",
+			"object": "chat.completion",
+			"model": "gpt-4o-mini",
+			"choices": [{
+				"index": 0,
+				"message": {"role": "assistant", "content": null, "tool_calls": tool_calls},
+				"finish_reason": "tool_calls"
+			}],
+			"usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+		});
+		Self::status(200, body.to_string())
+	}
+
+	/// A successful OpenAI chat completion stream (server-sent events), one event per chunk
+	pub fn chat_stream_ok(chunks: &[&str]) -> Self {
+		let mut body = String::new();
+		for chunk in chunks {
+			let event = serde_json::json!({
+				"id": "chatcmpl-mock",
+				"object": "chat.completion.chunk",
+				"model": "gpt-4o-mini",
+				"choices": [{"index": 0, "delta": {"content": chunk}, "finish_reason": null}]
+			});
+			body.push_str(&format!("data: {event}\n\n"));
+		}
+		let usage_event = serde_json::json!({
+			"id": "chatcmpl-mock",
+			"object": "chat.completion.chunk",
+			"model": "gpt-4o-mini",
+			"choices": [],
+			"usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+		});
+		body.push_str(&format!("data: {usage_event}\n\ndata: [DONE]\n\n"));
+
+		MockResponse {
+			status: 200,
+			content_type: "text/event-stream",
+			body,
+			truncated: false,
+		}
+	}
+
+	/// An OpenAI chat completion stream sending these chunks, then closing the connection before its end
+	pub fn chat_stream_broken(chunks: &[&str]) -> Self {
+		let mut body = String::new();
+		for chunk in chunks {
+			let event = serde_json::json!({
+				"id": "chatcmpl-mock",
+				"object": "chat.completion.chunk",
+				"model": "gpt-4o-mini",
+				"choices": [{"index": 0, "delta": {"content": chunk}, "finish_reason": null}]
+			});
+			body.push_str(&format!("data: {event}\n\n"));
+		}
+
+		MockResponse {
+			status: 200,
+			content_type: "text/event-stream",
+			body,
+			truncated: true,
+		}
+	}
+}
+
+pub struct MockAiServer {
+	base_url: String,
+	request_count: Arc<Mutex<usize>>,
+	request_bodies: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockAiServer {
+	pub async fn start(responses: Vec<MockResponse>) -> Result<Self> {
+		let listener = TcpListener::bind("127.0.0.1:0").await?;
+		let base_url = format!("http://{}/v1/", listener.local_addr()?);
+
+		let responses = Arc::new(Mutex::new(VecDeque::from(responses)));
+		let request_count = Arc::new(Mutex::new(0));
+		let request_bodies = Arc::new(Mutex::new(Vec::new()));
+
+		let count = request_count.clone();
+		let bodies = request_bodies.clone();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				let response = {
+					let mut responses = responses.lock().unwrap();
+					if responses.len() > 1 {
+						responses.pop_front()
+					} else {
+						responses.front().cloned()
+					}
+				};
+				*count.lock().unwrap() += 1;
+				if let Some(response) = response {
+					tokio::spawn(handle_connection(stream, response, bodies.clone()));
+				}
+			}
+		});
+
+		Ok(MockAiServer {
+			base_url,
+			request_count,
+			request_bodies,
+		})
+	}
+
+	/// The base url of the server (e.g., `http://127.0.0.1:1234/v1/`)
+	pub fn base_url(&self) -> &str {
+		&self.base_url
+	}
+
+	pub fn request_count(&self) -> usize {
+		*self.request_count.lock().unwrap()
+	}
+
+	/// The json bodies of the requests received so far (in order)
+	pub fn request_bodies(&self) -> Vec<String> {
+		self.request_bodies.lock().unwrap().clone()
+	}
+
+	/// Returns a genai client which sends all requests to this mock server
+	pub fn genai_client(&self) -> Client {
+		let base_url = self.base_url.clone();
+		Client::builder()
+			.with_service_target_resolver_fn(move |mut service_target: ServiceTarget| {
+				service_target.endpoint = Endpoint::from_owned(base_url);
+				service_target.auth = AuthData::from_single("mock-api-key");
+				Ok(service_target)
+			})
+			.build()
+	}
+}
+
+// region:    --- Support
+
+async fn handle_connection(
+	mut stream: TcpStream,
+	response: MockResponse,
+	request_bodies: Arc<Mutex<Vec<String>>>,
+) -> std::io::Result<()> {
+	// -- Read the request (headers, then body per content-length)
+	let mut buf: Vec<u8> = Vec::new();
+	let mut chunk = [0u8; 4096];
+	let header_end = loop {
+		let n = stream.read(&mut chunk).await?;
+		if n == 0 {
+			return Ok(());
+		}
+		buf.extend_from_slice(&chunk[..n]);
+		if let Some(idx) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+			break idx + 4;
+		}
+	};
+	let headers = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+	let content_length = headers
+		.lines()
+		.find_map(|l| l.strip_prefix("content-length:"))
+		.and_then(|v| v.trim().parse::<usize>().ok())
+		.unwrap_or(0);
+	while buf.len() < header_end + content_length {
+		let n = stream.read(&mut chunk).await?;
+		if n == 0 {
+			break;
+		}
+		buf.extend_from_slice(&chunk[..n]);
+	}
+	let body = String::from_utf8_lossy(&buf[header_end..]).to_string();
+	request_bodies.lock().unwrap().push(body);
+
+	// -- Write the response
+	let MockResponse {
+		status,
+		content_type,
+		body,
+		truncated,
+	} = response;
+	let content_length = if truncated { body.len() + 1024 } else { body.len() };
+	let http_response = format!(
+		"HTTP/1.1 {status} MOCK\r\ncontent-type: {content_type}\r\ncontent-length: {content_length}\r\nconnection: close\r\n\r\n{body}"
+	);
+	stream.write_all(http_response.as_bytes()).await?;
+	stream.shutdown().await?;
+
+	Ok(())
+}
+
+// endregion: --- Support
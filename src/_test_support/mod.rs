@@ -5,8 +5,10 @@ mod base;
 mod hub_capture;
 mod loaders;
 mod lua_test_support;
+mod mock_ai_server;
 mod runners;
 mod test_files;
+mod test_packs;
 
 pub use asserts::*;
 pub use base::*;
@@ -14,8 +16,10 @@ pub use base::*;
 pub use hub_capture::*;
 pub use loaders::*;
 pub use lua_test_support::*;
+pub use mock_ai_server::*;
 pub use runners::*;
 pub use test_files::*;
+pub use test_packs::*;
 
 pub type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -0,0 +1,95 @@
+use super::{Result, save_file_content};
+use crate::packer;
+use simple_fs::SPath;
+
+/// A `test_ns` pack to create with `create_test_pack` (e.g., `TestPack::new("pack-a", "0.1.0").with_dependencies(..)`)
+pub struct TestPack {
+	name: String,
+	version: String,
+	description: Option<String>,
+	/// The content of the `[dependencies]` table (e.g., `"test_ns@pack-b" = "^1.0.0"`)
+	dependencies: Option<String>,
+	main_content: String,
+	/// The other files of the pack, as `(rel_path, content)`
+	files: Vec<(String, String)>,
+}
+
+/// Constructor & Builders
+impl TestPack {
+	pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+		TestPack {
+			name: name.into(),
+			version: version.into(),
+			description: None,
+			dependencies: None,
+			main_content: "# Test Main\n\nSome test agent.".to_string(),
+			files: Vec::new(),
+		}
+	}
+
+	pub fn with_description(mut self, description: impl Into<String>) -> Self {
+		self.description = Some(description.into());
+		self
+	}
+
+	pub fn with_dependencies(mut self, dependencies: impl Into<String>) -> Self {
+		self.dependencies = Some(dependencies.into());
+		self
+	}
+
+	pub fn with_main_content(mut self, main_content: impl Into<String>) -> Self {
+		self.main_content = main_content.into();
+		self
+	}
+
+	pub fn with_file(mut self, rel_path: impl Into<String>, content: impl Into<String>) -> Self {
+		self.files.push((rel_path.into(), content.into()));
+		self
+	}
+}
+
+/// Create the source dir of the pack in `{base_dir}/pack_src/{name}-{version}/`, and pack it in `{base_dir}/packs/`
+///
+/// Returns the path of the .aipack file
+pub fn create_test_pack(base_dir: &SPath, test_pack: &TestPack) -> Result<SPath> {
+	create_test_pack_to(base_dir, test_pack, &base_dir.join("packs"))
+}
+
+/// Same as `create_test_pack`, but packed in `out_dir` (e.g., a registry dir)
+pub fn create_test_pack_to(base_dir: &SPath, test_pack: &TestPack, out_dir: &SPath) -> Result<SPath> {
+	let TestPack {
+		name,
+		version,
+		description,
+		dependencies,
+		main_content,
+		files,
+	} = test_pack;
+
+	let pack_dir = base_dir.join("pack_src").join(format!("{name}-{version}"));
+	let mut pack_toml = format!("[pack]\nnamespace = \"test_ns\"\nname = \"{name}\"\nversion = \"{version}\"\n");
+	if let Some(description) = description {
+		pack_toml.push_str(&format!("description = \"{description}\"\n"));
+	}
+	if let Some(dependencies) = dependencies {
+		pack_toml.push_str(&format!("\n[dependencies]\n{dependencies}\n"));
+	}
+	save_file_content(&pack_dir.join("pack.toml"), &pack_toml)?;
+	save_file_content(&pack_dir.join("main.aip"), main_content)?;
+	for (rel_path, content) in files {
+		save_file_content(&pack_dir.join(rel_path), content)?;
+	}
+
+	let pack_data = packer::pack_dir(&pack_dir, out_dir)?;
+	Ok(pack_data.pack_file)
+}
+
+/// Serve the registry dir (of `create_test_pack_to` .aipack files) on a local port
+///
+/// Returns the url of the registry (ending with `/`)
+pub async fn start_test_registry_server(registry_dir: SPath) -> Result<String> {
+	let server = packer::RegistryServer::bind(registry_dir, "127.0.0.1:0").await?;
+	let url = server.url()?;
+	tokio::spawn(server.serve());
+	Ok(url)
+}
@@ -0,0 +1,274 @@
+use super::*;
+use crate::_test_support::{
+	TestPack, create_test_pack, create_test_pack_to, remove_test_dir, save_file_content, start_test_registry_server,
+};
+use crate::packer::install_pack;
+use crate::run::Runtime;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+#[tokio::test]
+async fn test_installed_packs_uninstall_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	let pack_a_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_dependencies(format!(
+			r#""test_ns@pack-b" = {{ version = "^1.0", uri = "{pack_b_file}" }}"#
+		)),
+	)?;
+	install_pack(dir_context, pack_a_file.to_str(), &unsigned_options()).await?;
+	assert_eq!(list_installed_packs(dir_context)?.len(), 2);
+
+	// -- Exec & Check
+	// pack-b is required by pack-a
+	let err = uninstall_pack(dir_context, "test_ns@pack-b", false)
+		.err()
+		.ok_or("Should fail")?;
+	assert!(err.to_string().contains("test_ns@pack-a"), "{err}");
+
+	let pack_toml = uninstall_pack(dir_context, "test_ns@pack-a", false)?;
+	assert_eq!(pack_toml.version, "0.1.0");
+	uninstall_pack(dir_context, "test_ns@pack-b", false)?;
+
+	assert!(list_installed_packs(dir_context)?.is_empty());
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	assert!(
+		!installed_dir.join("test_ns").exists(),
+		"namespace dir should be removed"
+	);
+	let pack_lock = PackLock::load(dir_context)?.ok_or("pack.lock should exist")?;
+	assert!(pack_lock.packs().is_empty(), "pack.lock should be empty");
+
+	// Not installed
+	assert!(uninstall_pack(dir_context, "test_ns@pack-a", false).is_err());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installed_packs_outdated_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let registry_dir = setup_test_registry(dir_context).await?;
+	install_pack(dir_context, "test_ns@pack-a", &unsigned_options()).await?;
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	install_pack(dir_context, pack_b_file.to_str(), &unsigned_options()).await?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.2.0"),
+		&registry_dir,
+	)?;
+
+	// -- Exec
+	let statuses = check_outdated_packs(dir_context).await?;
+
+	// -- Check
+	assert_eq!(statuses.len(), 2);
+	let pack_a = &statuses[0];
+	assert_eq!(pack_a.pack_toml.version, "0.1.0");
+	assert_eq!(pack_a.latest_version.as_deref(), Some("0.2.0"));
+	assert!(pack_a.is_outdated());
+	// pack-b was installed from a local file, so not checked
+	let pack_b = &statuses[1];
+	assert_eq!(pack_b.non_registry_source.as_deref(), Some(pack_b_file.to_str()));
+	assert!(pack_b.latest_version.is_none());
+	assert!(!pack_b.is_outdated());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installed_packs_upgrade_and_rollback_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let registry_dir = setup_test_registry(dir_context).await?;
+	install_pack(dir_context, "test_ns@pack-a", &unsigned_options()).await?;
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	install_pack(dir_context, pack_b_file.to_str(), &unsigned_options()).await?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.2.0"),
+		&registry_dir,
+	)?;
+	let pack_identity = PackIdentity::from_str("test_ns@pack-a")?;
+
+	// -- Exec & Check - upgrade
+	// pack-b was installed from a local file, so not upgraded
+	let changes = upgrade_packs(dir_context, None, &unsigned_options()).await?;
+	assert_eq!(changes.len(), 1);
+	assert_eq!(changes[0].identity, "test_ns@pack-a");
+	assert_eq!(changes[0].from_version, "0.1.0");
+	assert_eq!(changes[0].to_version, "0.2.0");
+	let installed = load_installed_pack_toml(dir_context, &pack_identity)?.ok_or("Should be installed")?;
+	assert_eq!(installed.version, "0.2.0");
+	let err = upgrade_packs(dir_context, Some("test_ns@pack-b"), &unsigned_options())
+		.await
+		.err()
+		.ok_or("Should fail")?;
+	assert!(err.to_string().contains("not installed from a registry"), "{err}");
+	// Already up to date
+	assert!(upgrade_packs(dir_context, None, &unsigned_options()).await?.is_empty());
+
+	// -- Exec & Check - rollback
+	let change = rollback_pack(dir_context, "test_ns@pack-a")?;
+	assert_eq!(change.from_version, "0.2.0");
+	assert_eq!(change.to_version, "0.1.0");
+	let installed = load_installed_pack_toml(dir_context, &pack_identity)?.ok_or("Should be installed")?;
+	assert_eq!(installed.version, "0.1.0");
+	let pack_lock = PackLock::load(dir_context)?.ok_or("pack.lock should exist")?;
+	let locked_pack = pack_lock.get("test_ns@pack-a").ok_or("pack-a should be locked")?;
+	assert_eq!(locked_pack.version, "0.1.0");
+
+	// rollback of the rollback
+	let change = rollback_pack(dir_context, "test_ns@pack-a")?;
+	assert_eq!(change.to_version, "0.2.0");
+	let pack_lock = PackLock::load(dir_context)?.ok_or("pack.lock should exist")?;
+	assert_eq!(
+		pack_lock.get("test_ns@pack-a").map(|p| p.version.as_str()),
+		Some("0.2.0")
+	);
+
+	// No backup
+	assert!(rollback_pack(dir_context, "test_ns@pack-b").is_err());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installed_packs_upgrade_failed_restore_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let registry_dir = setup_test_registry(dir_context).await?;
+	install_pack(dir_context, "test_ns@pack-a", &unsigned_options()).await?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.2.0"),
+		&registry_dir,
+	)?;
+	upgrade_packs(dir_context, Some("test_ns@pack-a"), &unsigned_options()).await?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.3.0"),
+		&registry_dir,
+	)?;
+
+	// -- Exec
+	// Note: The test packs are not signed, so the install of the new version fails
+	let res = upgrade_packs(dir_context, Some("test_ns@pack-a"), &InstallOptions::default()).await;
+
+	// -- Check
+	assert!(res.is_err(), "Upgrade should fail");
+	let pack_identity = PackIdentity::from_str("test_ns@pack-a")?;
+	let installed = load_installed_pack_toml(dir_context, &pack_identity)?.ok_or("Should be restored")?;
+	assert_eq!(installed.version, "0.2.0");
+	let pack_lock = PackLock::load(dir_context)?.ok_or("pack.lock should exist")?;
+	assert_eq!(
+		pack_lock.get("test_ns@pack-a").map(|p| p.version.as_str()),
+		Some("0.2.0")
+	);
+	assert!(!pending_backup_dir(dir_context, &pack_identity)?.exists());
+	// The backup of the previous upgrade is kept
+	let change = rollback_pack(dir_context, "test_ns@pack-a")?;
+	assert_eq!(change.from_version, "0.2.0");
+	assert_eq!(change.to_version, "0.1.0");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installed_packs_upgrade_partial_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let registry_dir = setup_test_registry(dir_context).await?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-b", "0.1.0"),
+		&registry_dir,
+	)?;
+	install_pack(dir_context, "test_ns@pack-a", &unsigned_options()).await?;
+	install_pack(dir_context, "test_ns@pack-b", &unsigned_options()).await?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.2.0"),
+		&registry_dir,
+	)?;
+	// Note: The dependency is not in the registry, so the install of pack-b v0.2.0 fails
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-b", "0.2.0").with_dependencies(r#""test_ns@pack-z" = "^1.0""#),
+		&registry_dir,
+	)?;
+
+	// -- Exec
+	let err = upgrade_packs(dir_context, None, &unsigned_options())
+		.await
+		.err()
+		.ok_or("Upgrade of pack-b should fail")?;
+
+	// -- Check
+	let Error::PackUpgradeFailed {
+		pack_identity,
+		upgraded,
+		..
+	} = &err
+	else {
+		return Err(format!("Should be a PackUpgradeFailed, but was: {err}").into());
+	};
+	assert_eq!(pack_identity, "test_ns@pack-b");
+	assert_eq!(upgraded, &["test_ns@pack-a 0.1.0 -> 0.2.0"]);
+	assert!(
+		err.to_string().contains("Already upgraded: test_ns@pack-a 0.1.0 -> 0.2.0"),
+		"{err}"
+	);
+	assert!(err.to_string().contains("test_ns@pack-z"), "{err}");
+	let pack_a = load_installed_pack_toml(dir_context, &PackIdentity::from_str("test_ns@pack-a")?)?;
+	assert_eq!(pack_a.map(|p| p.version).as_deref(), Some("0.2.0"));
+	let pack_b = load_installed_pack_toml(dir_context, &PackIdentity::from_str("test_ns@pack-b")?)?;
+	assert_eq!(pack_b.map(|p| p.version).as_deref(), Some("0.1.0"));
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+// region:    --- Support
+
+/// The test packs are not signed
+fn unsigned_options() -> InstallOptions {
+	InstallOptions { allow_unsigned: true }
+}
+
+/// Serve a registry with `test_ns@pack-a` v0.1.0, as the only registry of the workspace
+///
+/// Returns the registry dir (to add versions)
+async fn setup_test_registry(dir_context: &DirContext) -> Result<SPath> {
+	let registry_dir = dir_context.current_dir().join("registry");
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0"),
+		&registry_dir,
+	)?;
+	let server = start_test_registry_server(registry_dir.clone()).await?;
+	save_file_content(
+		&dir_context.aipack_paths().get_wks_config_toml_path()?,
+		&format!("[install]\nregistries = [\"{server}\"]\ndefault_registry = false\n"),
+	)?;
+	Ok(registry_dir)
+}
+
+// endregion: --- Support
@@ -0,0 +1,140 @@
+use super::*;
+use crate::_test_support::{remove_test_dir, save_file_content};
+use crate::packer::pack_lock::PackLock;
+use crate::packer::{InstallOptions, PackUri, install_locked_packs, install_pack};
+use crate::run::Runtime;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+#[test]
+fn test_installer_git_parse_uri() -> Result<()> {
+	// -- Exec
+	let full = GitSource::parse("git+https://host/repo.git#v1.0.0:packs/pack-a/")?.ok_or("Should parse")?;
+	let no_subdir = GitSource::parse("git+file:///tmp/repo.git#main")?.ok_or("Should parse")?;
+	let url_only = GitSource::parse("git+file:///tmp/repo.git")?.ok_or("Should parse")?;
+	let not_git = GitSource::parse("https://host/pack.aipack")?;
+	let option_url = GitSource::parse("git+--upload-pack=touch /tmp/pwned");
+	let option_ref = GitSource::parse("git+https://host/repo.git#--output=/tmp/pwned");
+	let parent_subdir = GitSource::parse("git+https://host/repo.git#v1:../../..");
+	let inner_parent_subdir = GitSource::parse("git+https://host/repo.git#v1:packs/../../other");
+	let absolute_subdir = GitSource::parse("git+https://host/repo.git#v1:/etc");
+	let windows_subdir = GitSource::parse("git+https://host/repo.git#v1:..\\other");
+
+	// -- Check
+	assert_eq!(full.url, "https://host/repo.git");
+	assert_eq!(full.git_ref.as_deref(), Some("v1.0.0"));
+	assert_eq!(full.subdir.as_deref(), Some("packs/pack-a"));
+	assert_eq!(
+		full.to_uri_with_commit("abc123"),
+		"git+https://host/repo.git#abc123:packs/pack-a"
+	);
+	assert_eq!(no_subdir.git_ref.as_deref(), Some("main"));
+	assert_eq!(no_subdir.subdir, None);
+	assert_eq!(url_only.git_ref, None);
+	assert!(not_git.is_none());
+	assert!(option_url.is_err());
+	assert!(option_ref.is_err());
+	assert!(PackUri::parse("git+-c core.sshCommand=touch").is_err());
+	assert!(parent_subdir.is_err());
+	assert!(inner_parent_subdir.is_err());
+	assert!(absolute_subdir.is_err());
+	assert!(windows_subdir.is_err());
+	// Note: As for the dependency uris of a pack.toml
+	assert!(PackUri::parse("git+https://host/repo.git#v1:../../..").is_err());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installer_git_install_tag_and_branch() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let test_dir = dir_context.current_dir().canonicalize()?;
+	let work_dir = test_dir.join("work");
+	let bare_dir = test_dir.join("repo.git");
+	save_test_pack(&work_dir, "0.1.0")?;
+	git(None, &["init", "--quiet", "--initial-branch=main", work_dir.to_str()])?;
+	git(Some(&work_dir), &["add", "-A"])?;
+	git(Some(&work_dir), &["commit", "--quiet", "-m", "v0.1.0"])?;
+	git(Some(&work_dir), &["tag", "v0.1.0"])?;
+	let tag_commit = git(Some(&work_dir), &["rev-parse", "HEAD"])?;
+	git(
+		None,
+		&["clone", "--quiet", "--bare", work_dir.to_str(), bare_dir.to_str()],
+	)?;
+	let repo_url = format!("file://{bare_dir}");
+	let options = InstallOptions { allow_unsigned: true };
+
+	// -- Exec & Check - The tag
+	let installed_pack = install_pack(dir_context, &format!("git+{repo_url}#v0.1.0:packs/pack-a"), &options).await?;
+	assert_eq!(installed_pack.pack_toml.version, "0.1.0");
+	assert!(installed_pack.path.join("main.aip").exists());
+	let pack_lock = PackLock::load(dir_context)?.ok_or("Should have a pack.lock")?;
+	let locked_pack = pack_lock.get("test_ns@pack-a").ok_or("Should have a locked pack")?;
+	assert_eq!(locked_pack.source, format!("git+{repo_url}#{tag_commit}:packs/pack-a"));
+
+	// -- Exec & Check - The branch, after a new commit pushed (fetched in the cache)
+	save_test_pack(&work_dir, "0.2.0")?;
+	git(Some(&work_dir), &["commit", "--quiet", "-am", "v0.2.0"])?;
+	git(Some(&work_dir), &["push", "--quiet", bare_dir.to_str(), "main"])?;
+	let branch_commit = git(Some(&work_dir), &["rev-parse", "HEAD"])?;
+	let installed_pack = install_pack(dir_context, &format!("git+{repo_url}#main:packs/pack-a"), &options).await?;
+	assert_eq!(installed_pack.pack_toml.version, "0.2.0");
+	let pack_lock = PackLock::load(dir_context)?.ok_or("Should have a pack.lock")?;
+	let locked_pack = pack_lock.get("test_ns@pack-a").ok_or("Should have a locked pack")?;
+	assert_eq!(
+		locked_pack.source,
+		format!("git+{repo_url}#{branch_commit}:packs/pack-a")
+	);
+
+	// -- Exec & Check - The locked commit is re-installed (same SHA-256, as the archives are reproducible)
+	std::fs::remove_dir_all(&installed_pack.path)?;
+	let synced_packs = install_locked_packs(dir_context, None, &options).await?;
+	assert_eq!(synced_packs.len(), 1);
+	let synced_pack = synced_packs[0].installed.as_ref().ok_or("Should have re-installed the pack")?;
+	assert_eq!(synced_pack.pack_toml.version, "0.2.0");
+
+	// -- Exec & Check - No pack.toml in the dir
+	let result = install_pack(dir_context, &format!("git+{repo_url}#v0.1.0:packs/other"), &options).await;
+	let err = result.err().ok_or("Should fail without pack.toml")?;
+	assert!(err.to_string().contains("No pack.toml"), "err: {err}");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+// region:    --- Support
+
+/// Save the `test_ns@pack-a` pack of this version in the `packs/pack-a/` of the work dir
+fn save_test_pack(work_dir: &SPath, version: &str) -> Result<()> {
+	let pack_dir = work_dir.join("packs/pack-a");
+	let pack_toml = format!(
+		r#"
+[pack]
+namespace = "test_ns"
+name = "pack-a"
+version = "{version}"
+"#
+	);
+	save_file_content(&pack_dir.join("pack.toml"), &pack_toml)?;
+	save_file_content(&pack_dir.join("main.aip"), &format!("# Test Main\n\nVersion {version}"))?;
+	Ok(())
+}
+
+/// Run git with a test identity (without the user git config)
+fn git(cwd: Option<&SPath>, args: &[&str]) -> Result<String> {
+	let mut git_args = vec![
+		"-c",
+		"user.name=test",
+		"-c",
+		"user.email=test@test",
+		"-c",
+		"commit.gpgsign=false",
+	];
+	git_args.extend_from_slice(args);
+	Ok(run_git(cwd, &git_args)?)
+}
+
+// endregion: --- Support
@@ -1,6 +1,7 @@
 use super::*;
-use crate::_test_support::{remove_test_dir, save_file_content};
-use crate::packer::{self, install_pack};
+use crate::_test_support::{TestPack, create_test_pack, remove_test_dir, save_file_content};
+use crate::packer::pack_lock::PackLock;
+use crate::packer::{self, install_locked_packs, install_pack};
 use crate::run::Runtime;
 use simple_fs::{SPath, ensure_dir};
 
@@ -18,7 +19,7 @@ async fn test_installer_impl_local_file_simple() -> Result<()> {
 	let aipack_file_path = pack_result.pack_file;
 
 	// -- Exec
-	let installed_pack = install_pack(dir_context, aipack_file_path.to_str()).await?;
+	let installed_pack = install_pack(dir_context, aipack_file_path.to_str(), &unsigned_options()).await?;
 
 	// -- Check
 	// Verify that the pack was installed correctly
@@ -76,7 +77,7 @@ version = "0.2.0"
 	let old_pack_data = packer::pack_dir(&old_pack_dir, dir_context.current_dir())?;
 	let old_pack_file = old_pack_data.pack_file;
 	// Install the old pack (version 0.2.0)
-	let _installed_old_pack = install_pack(dir_context, old_pack_file.to_str()).await?;
+	let _installed_old_pack = install_pack(dir_context, old_pack_file.to_str(), &unsigned_options()).await?;
 	// (Optional: assert that installed_old_pack.pack_toml.version == "0.2.0")
 
 	// Step 2: Create new pack directory (version 0.1.0)
@@ -94,7 +95,7 @@ version = "0.1.0"
 	let new_pack_file = new_pack_data.pack_file;
 
 	// -- Execute: Try to install the new pack (version 0.1.0)
-	let result = install_pack(dir_context, new_pack_file.to_str()).await;
+	let result = install_pack(dir_context, new_pack_file.to_str(), &unsigned_options()).await;
 
 	// -- Check: The new pack installation should fail.
 	assert!(result.is_err(), "Installing lower version should fail");
@@ -146,7 +147,7 @@ version = "0.1.0-alpha"
 	let pack_file_str = pack_data.pack_file.to_str();
 
 	// Attempt to install the pack, expecting an error due to invalid prerelease format
-	let result = install_pack(dir_context, pack_file_str).await;
+	let result = install_pack(dir_context, pack_file_str, &unsigned_options()).await;
 
 	assert!(
 		result.is_err(),
@@ -166,3 +167,278 @@ version = "0.1.0-alpha"
 	remove_test_dir(dir_context.current_dir())?;
 	Ok(())
 }
+
+#[tokio::test]
+async fn test_installer_impl_dependencies_transitive_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	// test_ns@pack-a -> test_ns@pack-b -> test_ns@pack-c
+	let pack_c_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-c", "1.2.0"))?;
+	let pack_b_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-b", "0.2.1").with_dependencies(format!(
+			r#""test_ns@pack-c" = {{ version = "^1.1", uri = "{pack_c_file}" }}"#
+		)),
+	)?;
+	let pack_a_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_dependencies(format!(
+			r#""test_ns@pack-b" = {{ version = "^0.2", uri = "{pack_b_file}" }}"#
+		)),
+	)?;
+
+	// -- Exec
+	let installed_pack = install_pack(dir_context, pack_a_file.to_str(), &unsigned_options()).await?;
+
+	// -- Check
+	assert_eq!(installed_pack.pack_toml.name, "pack-a");
+	let dependency_names: Vec<&str> = installed_pack.dependencies.iter().map(|d| d.pack_toml.name.as_str()).collect();
+	assert_eq!(dependency_names, vec!["pack-c", "pack-b"]);
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	for name in ["pack-a", "pack-b", "pack-c"] {
+		let pack_toml_path = installed_dir.join("test_ns").join(name).join("pack.toml");
+		assert!(pack_toml_path.exists(), "{name} should have been installed");
+	}
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installer_impl_dependencies_conflict_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	// pack-a requires pack-b ^1.0 and pack-c, but pack-c requires pack-b ^2.0
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	let pack_c_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-c", "0.1.0").with_dependencies(format!(
+			r#""test_ns@pack-b" = {{ version = "^2.0", uri = "{pack_b_file}" }}"#
+		)),
+	)?;
+	let pack_a_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_dependencies(format!(
+			r#""test_ns@pack-b" = {{ version = "^1.0", uri = "{pack_b_file}" }}
+"test_ns@pack-c" = {{ version = "^0.1", uri = "{pack_c_file}" }}"#
+		)),
+	)?;
+
+	// -- Exec
+	let result = install_pack(dir_context, pack_a_file.to_str(), &unsigned_options()).await;
+
+	// -- Check
+	match result {
+		Err(Error::InstallDependencyConflict {
+			pack_identity,
+			requirements,
+		}) => {
+			assert_eq!(pack_identity, "test_ns@pack-b");
+			assert!(requirements.contains("^1.0"), "{requirements}");
+			assert!(requirements.contains("^2.0"), "{requirements}");
+			assert!(requirements.contains("test_ns@pack-c v0.1.0"), "{requirements}");
+		}
+		Err(other) => panic!("Expected InstallDependencyConflict error, got: {:?}", other),
+		Ok(_) => panic!("Installation should fail because of the conflicting requirements"),
+	}
+	// nothing should have been installed
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	assert!(
+		!installed_dir.join("test_ns").exists(),
+		"No pack should have been installed"
+	);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installer_impl_dependencies_unsatisfied_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	let pack_a_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_dependencies(format!(
+			r#""test_ns@pack-b" = {{ version = "^2.0", uri = "{pack_b_file}" }}"#
+		)),
+	)?;
+
+	// -- Exec
+	let result = install_pack(dir_context, pack_a_file.to_str(), &unsigned_options()).await;
+
+	// -- Check
+	match result {
+		Err(Error::InstallDependencyUnresolved {
+			pack_identity,
+			required_by,
+			cause,
+			..
+		}) => {
+			assert_eq!(pack_identity, "test_ns@pack-b");
+			assert_eq!(required_by, "test_ns@pack-a v0.1.0");
+			assert!(cause.contains("1.0.0"), "{cause}");
+		}
+		Err(other) => panic!("Expected InstallDependencyUnresolved error, got: {:?}", other),
+		Ok(_) => panic!("Installation should fail because the dependency version does not match"),
+	}
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	assert!(
+		!installed_dir.join("test_ns").exists(),
+		"No pack should have been installed"
+	);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installer_impl_pack_lock_record_and_sync_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	let pack_a_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_dependencies(format!(
+			r#""test_ns@pack-b" = {{ version = "^1.0", uri = "{pack_b_file}" }}"#
+		)),
+	)?;
+	install_pack(dir_context, pack_a_file.to_str(), &unsigned_options()).await?;
+
+	// -- Check the pack.lock
+	let pack_lock = PackLock::load(dir_context)?.ok_or("pack.lock should have been created")?;
+	let locked_a = pack_lock.get("test_ns@pack-a").ok_or("pack-a should be locked")?;
+	assert_eq!(locked_a.version, "0.1.0");
+	assert_eq!(locked_a.source, "packs/test_ns@pack-a-v0-1-0.aipack");
+	assert_eq!(locked_a.sha256.len(), 64);
+	assert_eq!(locked_a.dependencies, vec!["test_ns@pack-b".to_string()]);
+	assert!(pack_lock.get("test_ns@pack-b").is_some(), "pack-b should be locked");
+
+	// -- Exec: remove the installed packs, and sync pack-a
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	std::fs::remove_dir_all(installed_dir.join("test_ns"))?;
+	let synced_packs = install_locked_packs(dir_context, Some("test_ns@pack-a"), &unsigned_options()).await?;
+
+	// -- Check
+	let synced_names: Vec<&str> = synced_packs.iter().map(|p| p.locked_pack.name.as_str()).collect();
+	assert_eq!(synced_names, vec!["pack-b", "pack-a"]);
+	assert!(synced_packs.iter().all(|p| p.installed.is_some()));
+	assert!(installed_dir.join("test_ns/pack-a/pack.toml").exists());
+	assert!(installed_dir.join("test_ns/pack-b/pack.toml").exists());
+
+	// Sync again, everything already installed
+	let synced_packs = install_locked_packs(dir_context, None, &unsigned_options()).await?;
+	assert_eq!(synced_packs.len(), 2);
+	assert!(synced_packs.iter().all(|p| p.installed.is_none()));
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installer_impl_pack_lock_installed_dependency_and_sync_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	// test_ns@pack-a and test_ns@pack-c both depend on test_ns@pack-b
+	let pack_b_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-b", "1.0.0"))?;
+	let dependencies = format!(r#""test_ns@pack-b" = {{ version = "^1.0", uri = "{pack_b_file}" }}"#);
+	let pack_a_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_dependencies(&dependencies),
+	)?;
+	let pack_c_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-c", "0.3.0").with_dependencies(dependencies),
+	)?;
+	install_pack(dir_context, pack_a_file.to_str(), &unsigned_options()).await?;
+	let locked_b_sha256 = PackLock::load(dir_context)?
+		.and_then(|pack_lock| pack_lock.get("test_ns@pack-b").map(|p| p.sha256.clone()))
+		.ok_or("pack-b should be locked")?;
+	// As another workspace, sharing the same installed packs
+	std::fs::remove_file(dir_context.aipack_paths().get_wks_pack_lock_path()?.path())?;
+
+	// -- Exec: pack-b is already installed
+	let installed_pack = install_pack(dir_context, pack_c_file.to_str(), &unsigned_options()).await?;
+
+	// -- Check the pack.lock
+	assert!(
+		installed_pack.dependencies.is_empty(),
+		"pack-b should not be installed again"
+	);
+	let pack_lock = PackLock::load(dir_context)?.ok_or("pack.lock should have been created")?;
+	assert!(pack_lock.get("test_ns@pack-c").is_some(), "pack-c should be locked");
+	let locked_b = pack_lock.get("test_ns@pack-b").ok_or("pack-b should be locked")?;
+	assert_eq!(locked_b.version, "1.0.0");
+	assert_eq!(locked_b.source, "packs/test_ns@pack-b-v1-0-0.aipack");
+	assert_eq!(locked_b.sha256, locked_b_sha256);
+
+	// -- Exec: sync into an empty base dir
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	std::fs::remove_dir_all(installed_dir.path())?;
+	let synced_packs = install_locked_packs(dir_context, None, &unsigned_options()).await?;
+
+	// -- Check
+	let synced_names: Vec<&str> = synced_packs.iter().map(|p| p.locked_pack.name.as_str()).collect();
+	assert_eq!(synced_names, vec!["pack-b", "pack-c"]);
+	assert!(synced_packs.iter().all(|p| p.installed.is_some()));
+	assert!(installed_dir.join("test_ns/pack-b/pack.toml").exists());
+	assert!(installed_dir.join("test_ns/pack-c/pack.toml").exists());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_installer_impl_pack_lock_checksum_mismatch_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-a", "0.1.0"))?;
+	install_pack(dir_context, pack_file.to_str(), &unsigned_options()).await?;
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	std::fs::remove_dir_all(installed_dir.join("test_ns"))?;
+	// Same identity and version, but different content
+	create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_main_content("# Test Main\n\nSome changed test agent."),
+	)?;
+
+	// -- Exec
+	let result = install_locked_packs(dir_context, None, &unsigned_options()).await;
+
+	// -- Check
+	match result {
+		Err(Error::PackLockChecksumMismatch { pack_identity, .. }) => {
+			assert_eq!(pack_identity, "test_ns@pack-a");
+		}
+		Err(other) => panic!("Expected PackLockChecksumMismatch error, got: {:?}", other),
+		Ok(_) => panic!("Sync should fail because of the checksum mismatch"),
+	}
+	assert!(
+		!installed_dir.join("test_ns/pack-a").exists(),
+		"pack-a should not be installed"
+	);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+// region:    --- Support
+
+/// The test packs are not signed
+fn unsigned_options() -> InstallOptions {
+	InstallOptions { allow_unsigned: true }
+}
+
+// endregion: --- Support
@@ -0,0 +1,146 @@
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+use crate::_test_support::load_test_agent;
+use crate::run::Runtime;
+
+#[test]
+fn test_lua_engine_pool_reset_isolation() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let agent = load_test_agent("./other/demo", &runtime)?;
+	let agent_dir = agent.file_dir()?;
+	let pool = runtime.lua_engine_pool();
+
+	// -- Exec
+	{
+		let engine = runtime.lua_engine_for_agent(&agent)?;
+		engine.eval(
+			r#"
+_G.leak = "leaked"
+package.path = "/tmp/?.lua;" .. package.path
+local demo = require("demo")
+demo.name_one = "Changed"
+"#,
+			Some(engine.create_table()?),
+			Some(&[agent_dir.to_str()]),
+		)?;
+	}
+	let idle_count_after_release = pool.idle_count(&agent);
+	let engine = runtime.lua_engine_for_agent(&agent)?;
+	let res = engine.eval(
+		r#"
+return {
+	leak        = leak == nil,
+	loaded      = package.loaded["demo"] == nil,
+	path        = string.find(package.path, "/tmp/?.lua", 1, true) == nil,
+	aip         = type(aip.file.load) == "function",
+	name_one    = require("demo").name_one,
+}
+"#,
+		Some(engine.create_table()?),
+		Some(&[agent_dir.to_str()]),
+	)?;
+
+	// -- Check
+	assert_eq!(idle_count_after_release, 1);
+	assert_eq!(pool.idle_count(&agent), 0);
+	let res = serde_json::to_value(res)?;
+	for name in ["leak", "loaded", "path", "aip"] {
+		assert_eq!(res.get(name).and_then(|v| v.as_bool()), Some(true), "'{name}' in {res}");
+	}
+	assert_eq!(res.get("name_one").and_then(|v| v.as_str()), Some("Demo One"));
+
+	Ok(())
+}
+
+#[test]
+fn test_lua_engine_pool_reset_library_tables() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let agent = load_test_agent("./other/demo", &runtime)?;
+
+	// -- Exec
+	{
+		let engine = runtime.lua_engine_for_agent(&agent)?;
+		engine.eval(
+			r#"
+string.upper = function() return "hacked" end
+string.extra = "extra"
+getmetatable("").__index = { len = function() return -1 end }
+setmetatable(table, { __index = function() return "hacked" end })
+utils.file.load = function() return "hacked" end
+utils.extra = {}
+aip.text = nil
+"#,
+			None,
+			None,
+		)?;
+	}
+	let engine = runtime.lua_engine_for_agent(&agent)?;
+	let res = engine.eval(
+		r#"
+return {
+	upper        = ("abc"):upper() == "ABC",
+	string_extra = string.extra == nil,
+	len          = ("abc"):len() == 3,
+	table_mt     = getmetatable(table) == nil and table.anything == nil,
+	file_load    = utils.file.load ~= nil and utils.file.load == aip.file.load,
+	utils_extra  = utils.extra == nil,
+	aip_text     = type(aip.text) == "table",
+}
+"#,
+		None,
+		None,
+	)?;
+
+	// -- Check
+	let res = serde_json::to_value(res)?;
+	for name in [
+		"upper",
+		"string_extra",
+		"len",
+		"table_mt",
+		"file_load",
+		"utils_extra",
+		"aip_text",
+	] {
+		assert_eq!(res.get(name).and_then(|v| v.as_bool()), Some(true), "'{name}' in {res}");
+	}
+
+	Ok(())
+}
+
+/// The pooled engines are created once, then reused (versus a new engine for each eval without the pool)
+#[test]
+fn test_lua_engine_pool_reuse_vs_new_engine() -> Result<()> {
+	// -- Setup & Fixtures
+	const EVAL_COUNT: usize = 100;
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let agent = load_test_agent("./other/demo", &runtime)?;
+	let pool = runtime.lua_engine_pool();
+	let script = "return 1 + 2";
+
+	// -- Exec
+	for _ in 0..EVAL_COUNT {
+		let engine = runtime.lua_engine_for_agent(&agent)?;
+		let res = engine.eval(script, None, None)?;
+		assert_eq!(serde_json::to_value(res)?, 3);
+	}
+	let created_count_sequential = pool.created_count();
+	// Two engines in use at the same time cannot be the same one
+	let engine_a = runtime.lua_engine_for_agent(&agent)?;
+	let engine_b = runtime.lua_engine_for_agent(&agent)?;
+	let created_count_concurrent = pool.created_count();
+	drop((engine_a, engine_b));
+
+	// -- Check
+	assert_eq!(
+		created_count_sequential, 1,
+		"the {EVAL_COUNT} evals should reuse the same engine"
+	);
+	assert_eq!(created_count_concurrent, 2);
+	assert_eq!(pool.created_count(), 2);
+	assert_eq!(pool.idle_count(&agent), 2);
+
+	Ok(())
+}
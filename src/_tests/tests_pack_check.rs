@@ -0,0 +1,128 @@
+use super::*;
+use crate::_test_support::{remove_test_dir, save_file_content};
+use crate::run::Runtime;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+const PACK_TOML: &str = r#"
+[pack]
+namespace = "test_ns"
+name = "pack-a"
+version = "0.1.0"
+"#;
+
+#[test]
+fn test_pack_check_valid_pack_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let pack_dir = runtime.dir_context().current_dir().join("pack-a");
+	save_file_content(&pack_dir.join("pack.toml"), PACK_TOML)?;
+	save_file_content(
+		&pack_dir.join("main.aip"),
+		r#"# Options
+
+```toml
+model = "gpt-4o-mini"
+input_concurrency = 2
+```
+
+# Data
+
+```lua
+local utils = require("utils")
+return { value = utils.one() }
+```
+
+# Instruction
+
+Hello {{data.value}}
+{{#if input}}with input{{/if}}
+
+# Output
+
+```lua
+return ai_response.content
+```
+"#,
+	)?;
+	save_file_content(
+		&pack_dir.join("lua/utils.lua"),
+		"return { one = function() return 1 end }",
+	)?;
+
+	// -- Exec
+	let report = check_pack_dir(&pack_dir)?;
+
+	// -- Check
+	assert!(
+		report.diagnostics.is_empty(),
+		"Should have no diagnostics, but got: {:?}",
+		report.diagnostics
+	);
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+	Ok(())
+}
+
+#[test]
+fn test_pack_check_diagnostics_with_lines() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let pack_dir = runtime.dir_context().current_dir().join("pack-a");
+	save_file_content(&pack_dir.join("pack.toml"), PACK_TOML)?;
+	// Note: The line numbers matter for the checks below
+	save_file_content(
+		&pack_dir.join("main.aip"),
+		r#"# Options
+```toml
+model = "gpt-4o-mini"
+unknown_opt = 1
+```
+
+# Data
+```lua
+local a = 1
+local b = = 2
+```
+
+# Instruction
+
+Hello {{#if data}} never closed
+"#,
+	)?;
+	save_file_content(&pack_dir.join("lua/utils.lua"), "return {\n  a = 1,\n  b = ,\n}")?;
+
+	// -- Exec
+	let report = check_pack_dir(&pack_dir)?;
+
+	// -- Check
+	assert_eq!(report.error_count(), 3, "diagnostics: {:?}", report.diagnostics);
+	assert_eq!(report.warning_count(), 1, "diagnostics: {:?}", report.diagnostics);
+
+	let find = |file: &str, text: &str| {
+		report
+			.diagnostics
+			.iter()
+			.find(|d| d.file == file && d.message.contains(text))
+			.ok_or_else(|| format!("No diagnostic '{text}' for '{file}' in {:?}", report.diagnostics))
+	};
+
+	let unknown_opt = find("main.aip", "unknown_opt")?;
+	assert_eq!(unknown_opt.level, DiagnosticLevel::Warning);
+	assert_eq!(unknown_opt.line, Some(4));
+
+	let data_err = find("main.aip", "# Data lua syntax error")?;
+	assert_eq!(data_err.line, Some(10));
+	assert!(data_err.to_string().starts_with("main.aip:10: error:"));
+
+	let hbs_err = find("main.aip", "handlebars")?;
+	assert!(hbs_err.line.is_some_and(|line| line >= 15), "line: {:?}", hbs_err.line);
+
+	let module_err = find("lua/utils.lua", "lua module lua syntax error")?;
+	assert_eq!(module_err.line, Some(3));
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+	Ok(())
+}
@@ -0,0 +1,192 @@
+use super::*;
+use crate::_test_support::{
+	MockAiServer, MockResponse, TestPack, create_test_pack, remove_test_dir, save_file_content,
+};
+use crate::packer::{InstallOptions, RegistryServer, install_pack};
+use crate::run::Runtime;
+use simple_fs::ensure_dir;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+const MAIN_AIP: &str = r#"# Options
+
+```toml
+model = "gpt-4o-mini"
+```
+
+# Description
+
+Proofread the markdown files,
+and fix the typos.
+
+# Instruction
+
+Proofread {{input}}
+"#;
+
+#[tokio::test]
+async fn test_pack_info_installed_pack() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_file = create_test_pack(
+		dir_context.current_dir(),
+		&info_test_pack("pack-a", "The proofreading pack"),
+	)?;
+	let before_install = OffsetDateTime::now_utc();
+	install_pack(
+		dir_context,
+		pack_file.to_str(),
+		&InstallOptions { allow_unsigned: true },
+	)
+	.await?;
+	let after_install = OffsetDateTime::now_utc();
+
+	// -- Exec
+	let pack_info = load_pack_info(dir_context, "test_ns@pack-a")?;
+
+	// -- Check
+	let pack_toml = pack_info.pack_toml.as_ref().ok_or("Should have a pack.toml")?;
+	assert_eq!(pack_toml.version, "0.1.0");
+	assert_eq!(pack_toml.description.as_deref(), Some("The proofreading pack"));
+	assert!(matches!(pack_info.pack_dir.repo_kind, RepoKind::BaseInstalled));
+	let source = pack_info.source.as_deref().ok_or("Should have the pack.lock source")?;
+	assert!(source.ends_with("test_ns@pack-a-v0-1-0.aipack"), "source: {source}");
+	let installed_at = pack_info.installed_at.ok_or("Should have the install time")?;
+	assert!(
+		installed_at >= before_install && installed_at <= after_install,
+		"installed_at: {installed_at}"
+	);
+	assert!(pack_info.skipped_pack_dirs.is_empty());
+	assert!(pack_info.size > 0);
+
+	let agents: Vec<(&str, Option<&str>)> = pack_info
+		.agents
+		.iter()
+		.map(|agent| (agent.name.as_str(), agent.description.as_deref()))
+		.collect();
+	assert_eq!(
+		agents,
+		[
+			("main.aip", Some("Proofread the markdown files, and fix the typos.")),
+			("sub/other.aip", Some("Other agent")),
+		]
+	);
+
+	// Not installed
+	assert!(load_pack_info(dir_context, "test_ns@pack-b").is_err());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_info_skip_invalid_custom_pack() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_file = create_test_pack(
+		dir_context.current_dir(),
+		&info_test_pack("pack-a", "The proofreading pack"),
+	)?;
+	install_pack(
+		dir_context,
+		pack_file.to_str(),
+		&InstallOptions { allow_unsigned: true },
+	)
+	.await?;
+	let custom_pack_dir = dir_context
+		.aipack_paths()
+		.get_wks_pack_custom_dir()?
+		.join("test_ns")
+		.join("pack-a");
+	save_file_content(&custom_pack_dir.join("pack.toml"), "[pack]\nnamespace = \"test_ns\"\n")?;
+	save_file_content(&custom_pack_dir.join("main.aip"), "# Instruction\n\nHello")?;
+
+	// -- Exec
+	let pack_info = load_pack_info(dir_context, "test_ns@pack-a")?;
+
+	// -- Check
+	// The installed pack, as the custom one has an invalid pack.toml
+	assert!(matches!(pack_info.pack_dir.repo_kind, RepoKind::BaseInstalled));
+	assert_eq!(pack_info.skipped_pack_dirs.len(), 1);
+	let (skipped_pack_dir, _cause) = &pack_info.skipped_pack_dirs[0];
+	assert!(skipped_pack_dir.contains("custom"), "skipped: {skipped_pack_dir}");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_info_search_installed_and_registries() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_file = create_test_pack(
+		dir_context.current_dir(),
+		&info_test_pack("pack-a", "The proofreading pack"),
+	)?;
+	install_pack(
+		dir_context,
+		pack_file.to_str(),
+		&InstallOptions { allow_unsigned: true },
+	)
+	.await?;
+	let registry_dir = dir_context.current_dir().join("registry");
+	ensure_dir(&registry_dir)?;
+	let registry_pack_file = create_test_pack(
+		dir_context.current_dir(),
+		&info_test_pack("pack-r", "Release notes writer"),
+	)?;
+	std::fs::copy(&registry_pack_file, registry_dir.join(registry_pack_file.name()))?;
+	let server = RegistryServer::bind(registry_dir, "127.0.0.1:0").await?;
+	let registry_url = server.url()?;
+	tokio::spawn(server.serve());
+	// Note: The second registry has no index.toml, and the third is not reachable (the public registry is not used)
+	let no_index_server = MockAiServer::start(vec![MockResponse::status(404, "Not found")]).await?;
+	let no_index_url = no_index_server.base_url();
+	save_file_content(
+		&dir_context.aipack_paths().get_base_config_toml_path()?,
+		&format!(
+			"[install]\nregistries = [\"{registry_url}\", \"{no_index_url}\", \"http://127.0.0.1:1/\"]\ndefault_registry = false\n"
+		),
+	)?;
+
+	// -- Exec
+	let proofread_result = search_packs(dir_context, "PROOFREAD").await?;
+	let typos_result = search_packs(dir_context, "typos").await?;
+	let release_result = search_packs(dir_context, "release").await?;
+
+	// -- Check
+	let identities =
+		|result: &PackSearchResult| -> Vec<String> { result.hits.iter().map(|hit| hit.identity.clone()).collect() };
+	assert_eq!(identities(&proofread_result), ["test_ns@pack-a"]);
+	// Matches the agent description
+	assert_eq!(identities(&typos_result), ["test_ns@pack-a"]);
+	assert_eq!(identities(&release_result), ["test_ns@pack-r"]);
+	let registry_hit = &release_result.hits[0];
+	assert_eq!(registry_hit.location, registry_url);
+	assert_eq!(registry_hit.version.as_deref(), Some("0.1.0"));
+	assert_eq!(registry_hit.description.as_deref(), Some("Release notes writer"));
+	// The registry without index.toml has no results, but is not an error
+	assert_eq!(release_result.registry_errors.len(), 1);
+	assert_eq!(release_result.registry_errors[0].0, "http://127.0.0.1:1/");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+// region:    --- Support
+
+/// The `test_ns@{name}` test pack (v0.1.0) with this description, a main agent with options, and a sub agent
+fn info_test_pack(name: &str, description: &str) -> TestPack {
+	TestPack::new(name, "0.1.0")
+		.with_description(description)
+		.with_main_content(MAIN_AIP)
+		.with_file("sub/other.aip", "Other agent\n\n# Instruction\n\nHello")
+}
+
+// endregion: --- Support
@@ -0,0 +1,98 @@
+use super::*;
+use crate::_test_support::{remove_test_dir, save_file_content};
+use crate::dir_context::{RepoKind, find_pack_dirs, find_to_run_pack_dir};
+use crate::packer::{self, InstallOptions, install_pack};
+use crate::run::Runtime;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+#[tokio::test]
+async fn test_pack_link_resolve_before_installed() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let src_dir = dir_context.current_dir().join("dev/my-pack-src");
+	save_test_pack(&src_dir, "0.1.0")?;
+	let pack_file = packer::pack_dir(&src_dir, dir_context.current_dir().join("packs"))?.pack_file;
+	install_pack(
+		dir_context,
+		pack_file.to_str(),
+		&InstallOptions { allow_unsigned: true },
+	)
+	.await?;
+	save_test_pack(&src_dir, "0.2.0-dev")?;
+
+	// -- Exec
+	let linked_pack = link_pack(dir_context, src_dir.to_str())?;
+
+	// -- Check
+	assert_eq!(linked_pack.identity, "test_ns@pack-a");
+	assert_eq!(linked_pack.version.as_deref(), Some("0.2.0-dev"));
+	let pack_dir = find_to_run_pack_dir(dir_context, Some("test_ns"), Some("pack-a"))?;
+	assert!(matches!(pack_dir.repo_kind, RepoKind::BaseLinked));
+	assert_eq!(pack_dir.name, "pack-a");
+	assert_eq!(pack_dir.path.to_str(), src_dir.canonicalize()?.to_str());
+	// The installed one is listed after the linked one
+	let kinds: Vec<String> = find_pack_dirs(dir_context, None, None)?
+		.iter()
+		.map(|p| format!("{p} {:?}", p.repo_kind))
+		.collect();
+	assert_eq!(kinds, ["test_ns@pack-a BaseLinked", "test_ns@pack-a BaseInstalled"]);
+
+	// -- Exec & Check - Unlink (by source dir), back to the installed pack
+	let unlinked_pack = unlink_pack(dir_context, src_dir.to_str())?;
+	assert_eq!(unlinked_pack.identity, "test_ns@pack-a");
+	let pack_dir = find_to_run_pack_dir(dir_context, Some("test_ns"), Some("pack-a"))?;
+	assert!(matches!(pack_dir.repo_kind, RepoKind::BaseInstalled));
+	assert!(unlink_pack(dir_context, "test_ns@pack-a").is_err());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[test]
+fn test_pack_link_not_installed_and_errors() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let src_dir = dir_context.current_dir().join("dev/pack-b");
+	save_test_pack(&src_dir, "0.1.0")?;
+
+	// -- Exec
+	link_pack(dir_context, src_dir.to_str())?;
+
+	// -- Check
+	// Note: No installed dir yet
+	let pack_dir = find_to_run_pack_dir(dir_context, None, Some("pack-a"))?;
+	assert!(matches!(pack_dir.repo_kind, RepoKind::BaseLinked));
+	let err = link_pack(dir_context, dir_context.current_dir().join("dev").to_str())
+		.err()
+		.ok_or("Should fail without pack.toml")?;
+	assert!(err.to_string().contains("pack.toml"), "err: {err}");
+	unlink_pack(dir_context, "test_ns@pack-a")?;
+	assert!(find_to_run_pack_dir(dir_context, None, Some("pack-a")).is_err());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+// region:    --- Support
+
+/// Save the `test_ns@pack-a` pack source of this version in the dir
+fn save_test_pack(dir: &SPath, version: &str) -> Result<()> {
+	let pack_toml = format!(
+		r#"
+[pack]
+namespace = "test_ns"
+name = "pack-a"
+version = "{version}"
+"#
+	);
+	save_file_content(&dir.join("pack.toml"), &pack_toml)?;
+	save_file_content(&dir.join("main.aip"), &format!("# Test Main\n\nVersion {version}"))?;
+	Ok(())
+}
+
+// endregion: --- Support
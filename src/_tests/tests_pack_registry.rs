@@ -0,0 +1,171 @@
+use super::*;
+use crate::_test_support::{
+	TestPack, create_test_pack_to, remove_test_dir, save_file_content, start_test_registry_server,
+};
+use crate::packer::pack_lock::PackLock;
+use crate::packer::{InstallOptions, install_pack};
+use crate::run::Runtime;
+use simple_fs::ensure_dir;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+#[tokio::test]
+async fn test_pack_registry_serve_latest_toml() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let registry_dir = dir_context.current_dir().join("registry");
+	for version in ["0.1.0", "0.2.0", "0.3.0-alpha.1"] {
+		create_test_pack_to(
+			dir_context.current_dir(),
+			&TestPack::new("pack-a", version),
+			&registry_dir,
+		)?;
+	}
+	let server = start_test_registry_server(registry_dir).await?;
+
+	// -- Exec
+	let latest_toml = reqwest::get(format!("{server}pack/test_ns/pack-a/stable/latest.toml"))
+		.await?
+		.text()
+		.await?;
+	let not_found = reqwest::get(format!("{server}pack/test_ns/pack-b/stable/latest.toml")).await?;
+
+	// -- Check
+	// Note: The prerelease is not the latest stable
+	assert_eq!(
+		latest_toml,
+		"[latest_stable]\nversion = \"0.2.0\"\nrel_path = \"test_ns@pack-a-v0-2-0.aipack\"\n"
+	);
+	assert_eq!(not_found.status().as_u16(), 404);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_registry_serve_sub_dirs_same_file_name() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let registry_dir = dir_context.current_dir().join("registry");
+	for (version, sub_dir) in [("0.1.0", "v1"), ("0.2.0", "v2")] {
+		let pack_file = create_test_pack_to(
+			dir_context.current_dir(),
+			&TestPack::new("pack-a", version),
+			&registry_dir.join(sub_dir),
+		)?;
+		std::fs::rename(&pack_file, registry_dir.join(sub_dir).join("pack.aipack"))?;
+	}
+	let server = start_test_registry_server(registry_dir.clone()).await?;
+	let base_url = format!("{server}pack/test_ns/pack-a/stable/");
+
+	// -- Exec
+	let latest_toml = reqwest::get(format!("{base_url}latest.toml")).await?.text().await?;
+	let v1_content = reqwest::get(format!("{base_url}v1/pack.aipack")).await?.bytes().await?;
+	let not_found = reqwest::get(format!("{base_url}pack.aipack")).await?;
+
+	// -- Check
+	assert_eq!(
+		latest_toml,
+		"[latest_stable]\nversion = \"0.2.0\"\nrel_path = \"v2/pack.aipack\"\n"
+	);
+	assert_eq!(
+		v1_content.as_ref(),
+		std::fs::read(registry_dir.join("v1/pack.aipack"))?.as_slice()
+	);
+	assert_eq!(not_found.status().as_u16(), 404);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_registry_install_from_registries_in_order() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let empty_registry_dir = dir_context.current_dir().join("registry-empty");
+	ensure_dir(&empty_registry_dir)?;
+	let registry_dir = dir_context.current_dir().join("registry");
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0"),
+		&registry_dir,
+	)?;
+	create_test_pack_to(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.2.0"),
+		&registry_dir,
+	)?;
+	let empty_server = start_test_registry_server(empty_registry_dir).await?;
+	let server = start_test_registry_server(registry_dir).await?;
+	// The workspace registries are tried before the base ones (without the public registry)
+	save_file_content(
+		&dir_context.aipack_paths().get_wks_config_toml_path()?,
+		&format!("[install]\nregistries = [\"{empty_server}\"]\ndefault_registry = false\n"),
+	)?;
+	save_file_content(
+		&dir_context.aipack_paths().get_base_config_toml_path()?,
+		&format!("[install]\nregistries = [\"{}\"]\n", server.trim_end_matches('/')),
+	)?;
+
+	// -- Exec
+	let registries = load_registries(dir_context)?;
+	let installed_pack = install_pack(dir_context, "test_ns@pack-a", &InstallOptions { allow_unsigned: true }).await?;
+
+	// -- Check
+	assert_eq!(registries, [empty_server, server.clone()]);
+	assert_eq!(installed_pack.pack_toml.version, "0.2.0");
+	assert!(installed_pack.path.join("main.aip").exists());
+	let pack_lock = PackLock::load(dir_context)?.ok_or("Should have a pack.lock")?;
+	let locked_pack = pack_lock.get("test_ns@pack-a").ok_or("Should have a locked pack")?;
+	assert_eq!(
+		locked_pack.source,
+		format!("{server}pack/test_ns/pack-a/stable/test_ns@pack-a-v0-2-0.aipack")
+	);
+
+	// Not in any registry
+	let result = install_pack(dir_context, "test_ns@pack-b", &InstallOptions { allow_unsigned: true }).await;
+	let err = result.err().ok_or("Should fail for a pack not in the registries")?;
+	assert!(err.to_string().contains(&server), "Should list the registries: {err}");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[test]
+fn test_pack_registry_load_registries_default() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+
+	let config_toml_path = dir_context.aipack_paths().get_wks_config_toml_path()?;
+
+	// -- Exec & Check
+	assert_eq!(load_registries(dir_context)?, [DEFAULT_REGISTRY_URL]);
+
+	// The public registry is tried after the configured ones
+	save_file_content(
+		&config_toml_path,
+		"[install]\nregistries = [\"http://my-team-host:8787\"]\n",
+	)?;
+	assert_eq!(
+		load_registries(dir_context)?,
+		["http://my-team-host:8787/", DEFAULT_REGISTRY_URL]
+	);
+
+	// Unless disabled
+	save_file_content(
+		&config_toml_path,
+		"[install]\nregistries = [\"http://my-team-host:8787\"]\ndefault_registry = false\n",
+	)?;
+	assert_eq!(load_registries(dir_context)?, ["http://my-team-host:8787/"]);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
@@ -0,0 +1,189 @@
+use super::*;
+use crate::_test_support::{TestPack, create_test_pack, remove_test_dir, save_file_content};
+use crate::packer::pack_lock::PackLock;
+use crate::packer::support::compute_file_sha256;
+use crate::packer::{InstallOptions, install_locked_packs, install_pack};
+use crate::run::Runtime;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+#[tokio::test]
+async fn test_pack_signature_signed_trusted_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let key_file = dir_context.current_dir().join("signing-key");
+	let public_key = generate_signing_key(&key_file)?;
+	save_trusted_keys(dir_context, &[&public_key])?;
+	let pack_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-a", "0.1.0"))?;
+	sign_aipack_file(&pack_file, &key_file)?;
+
+	// -- Exec
+	let installed_pack = install_pack(dir_context, pack_file.to_str(), &InstallOptions::default()).await?;
+
+	// -- Check
+	assert_eq!(installed_pack.pack_toml.name, "pack-a");
+	assert!(installed_pack.path.join("main.aip").exists());
+	assert!(
+		!installed_pack.path.join(SIGNATURE_FILE_NAME).exists(),
+		"the signature file should not be installed"
+	);
+	assert!(key_file.exists());
+	assert!(SPath::new(format!("{key_file}.pub")).exists());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_signature_unsigned_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-a", "0.1.0"))?;
+
+	// -- Exec
+	let result = install_pack(dir_context, pack_file.to_str(), &InstallOptions::default()).await;
+
+	// -- Check
+	assert!(
+		matches!(result, Err(Error::PackUnsigned { .. })),
+		"Expected PackUnsigned error"
+	);
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	assert!(!installed_dir.join("test_ns/pack-a").exists());
+
+	// With --allow-unsigned
+	install_pack(
+		dir_context,
+		pack_file.to_str(),
+		&InstallOptions { allow_unsigned: true },
+	)
+	.await?;
+	assert!(installed_dir.join("test_ns/pack-a/main.aip").exists());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_signature_untrusted_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let trusted_public_key = generate_signing_key(&dir_context.current_dir().join("trusted-key"))?;
+	save_trusted_keys(dir_context, &[&trusted_public_key])?;
+	let other_key_file = dir_context.current_dir().join("other-key");
+	let other_public_key = generate_signing_key(&other_key_file)?;
+	let pack_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-a", "0.1.0"))?;
+	sign_aipack_file(&pack_file, &other_key_file)?;
+
+	// -- Exec
+	let result = install_pack(dir_context, pack_file.to_str(), &InstallOptions::default()).await;
+
+	// -- Check
+	match result {
+		Err(Error::PackSignatureUntrusted { public_key, .. }) => assert_eq!(public_key, other_public_key),
+		Err(other) => panic!("Expected PackSignatureUntrusted error, got: {:?}", other),
+		Ok(_) => panic!("Install should fail because the key is not trusted"),
+	}
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_signature_tampered_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let key_file = dir_context.current_dir().join("signing-key");
+	let public_key = generate_signing_key(&key_file)?;
+	save_trusted_keys(dir_context, &[&public_key])?;
+	let pack_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-a", "0.1.0"))?;
+	sign_aipack_file(&pack_file, &key_file)?;
+	let signature_content = zip::extract_text_content(&pack_file, SIGNATURE_FILE_NAME)?;
+	// Same pack with a changed main.aip, and the signature of the original one
+	let pack_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_main_content("# Test Main\n\nSome tampered agent."),
+	)?;
+	zip::append_file_content(&pack_file, SIGNATURE_FILE_NAME, &signature_content)?;
+
+	// -- Exec
+	// Note: An invalid signature is refused even with `allow_unsigned`
+	let result = install_pack(
+		dir_context,
+		pack_file.to_str(),
+		&InstallOptions { allow_unsigned: true },
+	)
+	.await;
+
+	// -- Check
+	assert!(
+		matches!(result, Err(Error::PackSignatureInvalid { .. })),
+		"Expected PackSignatureInvalid error"
+	);
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	assert!(!installed_dir.join("test_ns/pack-a").exists());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_pack_signature_locked_unsigned_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let key_file = dir_context.current_dir().join("signing-key");
+	let public_key = generate_signing_key(&key_file)?;
+	save_trusted_keys(dir_context, &[&public_key])?;
+	let pack_file = create_test_pack(dir_context.current_dir(), &TestPack::new("pack-a", "0.1.0"))?;
+	sign_aipack_file(&pack_file, &key_file)?;
+	install_pack(dir_context, pack_file.to_str(), &InstallOptions::default()).await?;
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	std::fs::remove_dir_all(installed_dir.join("test_ns"))?;
+	// The pack.lock source replaced by an unsigned pack, with the checksum updated to match
+	let pack_file = create_test_pack(
+		dir_context.current_dir(),
+		&TestPack::new("pack-a", "0.1.0").with_main_content("# Test Main\n\nSome tampered agent."),
+	)?;
+	let mut pack_lock = PackLock::load(dir_context)?.ok_or("Should have a pack.lock")?;
+	let mut locked_pack = pack_lock.get("test_ns@pack-a").ok_or("Should be locked")?.clone();
+	locked_pack.sha256 = compute_file_sha256(&pack_file)?;
+	pack_lock.upsert(locked_pack);
+	pack_lock.save(dir_context)?;
+
+	// -- Exec
+	let result = install_locked_packs(dir_context, None, &InstallOptions::default()).await;
+
+	// -- Check
+	assert!(
+		matches!(result, Err(Error::PackUnsigned { .. })),
+		"Expected PackUnsigned error"
+	);
+	assert!(!installed_dir.join("test_ns/pack-a").exists());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+// region:    --- Support
+
+fn save_trusted_keys(dir_context: &DirContext, public_keys: &[&str]) -> Result<()> {
+	let keys: Vec<String> = public_keys.iter().map(|key| format!("\"{key}\"")).collect();
+	let config_path = dir_context.aipack_paths().get_base_config_toml_path()?;
+	save_file_content(
+		&config_path,
+		&format!("[install]\ntrusted_keys = [{}]\n", keys.join(", ")),
+	)?;
+	Ok(())
+}
+
+// endregion: --- Support
@@ -1,6 +1,7 @@
-use crate::_test_support::remove_test_dir;
+use crate::_test_support::{remove_test_dir, save_file_content};
 use crate::packer::{self};
 use crate::run::Runtime;
+use crate::support::zip;
 use simple_fs::SPath;
 use std::fs;
 
@@ -39,6 +40,101 @@ fn test_packer_impl_pack_simple() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn test_packer_impl_pack_files_selection() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let pack_dir = dir_context.current_dir().join("pack-a");
+	save_file_content(
+		&pack_dir.join("pack.toml"),
+		r#"
+[pack]
+namespace = "test_ns"
+name = "pack-a"
+version = "0.1.0"
+
+[pack.files]
+exclude = ["tests/**"]
+"#,
+	)?;
+	save_file_content(&pack_dir.join(".aipackignore"), "# scratch files\n*.bak\n/scratch/\n")?;
+	for name in [
+		"main.aip",
+		"lua/utils.lua",
+		"README.md",
+		"tests/fixture.txt",
+		"notes.bak",
+		"sub/other.bak",
+		"scratch/draft.aip",
+		".git/config",
+		"main.aip.swp",
+	] {
+		save_file_content(&pack_dir.join(name), "some content")?;
+	}
+
+	// -- Exec
+	let (_, pack_files) = packer::list_pack_dir_files(&pack_dir)?;
+	let pack_result = packer::pack_dir(&pack_dir, dir_context.current_dir().join("packs"))?;
+
+	// -- Check
+	let names: Vec<&str> = pack_files.iter().map(|f| f.name.as_str()).collect();
+	assert_eq!(names, ["README.md", "lua/utils.lua", "main.aip", "pack.toml"]);
+	let zip_names: Vec<String> = zip::zip_files_sha256(&pack_result.pack_file)?
+		.into_iter()
+		.map(|(name, _)| name)
+		.collect();
+	assert_eq!(zip_names, names);
+
+	// With the include globs (pack.toml is always included)
+	save_file_content(
+		&pack_dir.join("pack.toml"),
+		r#"
+[pack]
+namespace = "test_ns"
+name = "pack-a"
+version = "0.1.0"
+
+[pack.files]
+include = ["**/*.aip"]
+"#,
+	)?;
+	let (_, pack_files) = packer::list_pack_dir_files(&pack_dir)?;
+	let names: Vec<&str> = pack_files.iter().map(|f| f.name.as_str()).collect();
+	assert_eq!(names, ["main.aip", "pack.toml"]);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+
+	Ok(())
+}
+
+#[test]
+fn test_packer_impl_pack_reproducible() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let to_pack_dir = SPath::new("tests-data/test_packs_folder/test_pack_01");
+
+	// -- Exec
+	let pack_file_a = packer::pack_dir(&to_pack_dir, dir_context.current_dir().join("packs-a"))?.pack_file;
+	// Note: So that a time based entry timestamp would differ
+	std::thread::sleep(std::time::Duration::from_millis(1100));
+	let pack_file_b = packer::pack_dir(&to_pack_dir, dir_context.current_dir().join("packs-b"))?.pack_file;
+
+	// -- Check
+	assert_eq!(
+		fs::read(&pack_file_a)?,
+		fs::read(&pack_file_b)?,
+		"The same pack dir should produce byte-identical archives"
+	);
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+
+	Ok(())
+}
+
 // region:    --- Support
 
 // Test helper to verify the structure of a created .aipack file
@@ -0,0 +1,993 @@
+//! Tests running agents against a local mock server standing in for the provider (stream, retry, cache, output schema, tools, session, budget, run summary, run history).
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+use super::*;
+use crate::_test_support::{
+	MockAiServer, MockResponse, assert_contains, load_inline_agent, remove_test_dir, run_test_agent, save_file_content,
+};
+use crate::history::RunStore;
+use crate::hub::{HubEvent, get_hub};
+use crate::run::RunBaseOptions;
+use crate::run::replay_run_inputs;
+use crate::session::SessionStore;
+use crate::tui::{AiStreamEvent, PrintEvent};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use value_ext::JsonValueExt;
+
+#[tokio::test]
+async fn test_run_agent_mock_stream_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::chat_stream_ok(&["Hello", " from", "\nstream"])]).await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let content = agent_content(&MockAgentOptions {
+		stream: true,
+		..Default::default()
+	});
+	let agent = load_inline_agent("./mock/stream-agent.aip", content)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res.x_get_str("content")?, "Hello from\nstream");
+	assert_eq!(res.x_get::<i64>("/usage/completion_tokens")?, 5);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_mock_stream_concurrent_inputs_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_stream_ok(&["One", " two", " three"]),
+		MockResponse::chat_stream_ok(&["Un", " deux", " trois"]),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let content = agent_content(&MockAgentOptions {
+		stream: true,
+		..Default::default()
+	});
+	let agent = load_inline_agent("./mock/stream-agent.aip", content)?;
+	// Note: The input indexes of this test, not to be mixed with the streams of other tests (the hub is global)
+	let (idx_a, idx_b) = (1001, 1002);
+	let mut hub_rx = get_hub().subscriber();
+	// The streamed (started, chunks, ended) by input index, until both inputs are ended
+	let collector = tokio::spawn(async move {
+		let mut streamed: HashMap<usize, (bool, String, bool)> = HashMap::new();
+		let is_ended = |streamed: &HashMap<usize, (bool, String, bool)>, idx| streamed.get(&idx).is_some_and(|s| s.2);
+		while !is_ended(&streamed, idx_a) || !is_ended(&streamed, idx_b) {
+			let event = match hub_rx.recv().await {
+				Ok(event) => event,
+				Err(RecvError::Lagged(_)) => continue,
+				Err(_) => break,
+			};
+			let HubEvent::Print(print_event) = event else {
+				continue;
+			};
+			let PrintEvent::AiStream(stream_event) = print_event.as_ref() else {
+				continue;
+			};
+			match stream_event {
+				AiStreamEvent::Start { input_idx, .. } => streamed.entry(*input_idx).or_default().0 = true,
+				AiStreamEvent::Chunk { input_idx, content } => {
+					streamed.entry(*input_idx).or_default().1.push_str(content)
+				}
+				AiStreamEvent::ReasoningChunk { .. } => (),
+				AiStreamEvent::End { input_idx } => streamed.entry(*input_idx).or_default().2 = true,
+				AiStreamEvent::Interrupted { .. } => (),
+			}
+		}
+		streamed
+	});
+
+	// -- Exec
+	let run_options = RunBaseOptions::default();
+	let (res_a, res_b) = tokio::join!(
+		run_command_agent_input_for_test(idx_a, &runtime, &agent, Value::Null, "input-a", &run_options),
+		run_command_agent_input_for_test(idx_b, &runtime, &agent, Value::Null, "input-b", &run_options),
+	);
+
+	// -- Check
+	let mut streamed = tokio::time::timeout(Duration::from_secs(5), collector).await??;
+	for (idx, res) in [(idx_a, res_a), (idx_b, res_b)] {
+		let res = res?.map(|v| v.into_value()).unwrap_or_default();
+		let (started, chunks, ended) = streamed.remove(&idx).ok_or(format!("input {idx} should have been streamed"))?;
+		assert!(started && ended, "input {idx} stream should have started and ended");
+		// The chunks of each input are its own response (whatever the order of the responses)
+		assert_eq!(chunks, res.x_get_str("content")?);
+	}
+	assert_eq!(server.request_count(), 2);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_mock_stream_broken_then_retry_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_stream_broken(&["Hello", " from broken"]),
+		MockResponse::chat_stream_ok(&["Hello", " from retry"]),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let content = agent_content(&MockAgentOptions {
+		stream: true,
+		max_attempts: 2,
+		..Default::default()
+	});
+	let agent = load_inline_agent("./mock/stream-agent.aip", content)?;
+	// Note: The input index of this test, not to be mixed with the streams of other tests (the hub is global)
+	let input_idx = 1003;
+	let mut hub_rx = get_hub().subscriber();
+	// The stream events of the input, until its end
+	let collector = tokio::spawn(async move {
+		let mut events: Vec<String> = Vec::new();
+		loop {
+			let event = match hub_rx.recv().await {
+				Ok(event) => event,
+				Err(RecvError::Lagged(_)) => continue,
+				Err(_) => break,
+			};
+			let HubEvent::Print(print_event) = event else {
+				continue;
+			};
+			let PrintEvent::AiStream(stream_event) = print_event.as_ref() else {
+				continue;
+			};
+			match stream_event {
+				AiStreamEvent::Start { input_idx: idx, .. } if *idx == input_idx => events.push("start".to_string()),
+				AiStreamEvent::Chunk {
+					input_idx: idx,
+					content,
+				} if *idx == input_idx => events.push(content.clone()),
+				AiStreamEvent::Interrupted { input_idx: idx } if *idx == input_idx => {
+					events.push("interrupted".to_string())
+				}
+				AiStreamEvent::End { input_idx: idx } if *idx == input_idx => {
+					events.push("end".to_string());
+					break;
+				}
+				_ => (),
+			}
+		}
+		events
+	});
+
+	// -- Exec
+	let run_options = RunBaseOptions::default();
+	let res = run_command_agent_input_for_test(input_idx, &runtime, &agent, Value::Null, "input", &run_options).await?;
+
+	// -- Check
+	let res = res.map(|v| v.into_value()).unwrap_or_default();
+	assert_eq!(res.x_get_str("content")?, "Hello from retry");
+	assert_eq!(server.request_count(), 2);
+	let events = tokio::time::timeout(Duration::from_secs(5), collector).await??;
+	// The failed attempt is marked as interrupted before the retry streams again from the start
+	assert_eq!(
+		events,
+		[
+			"start",
+			"Hello",
+			" from broken",
+			"interrupted",
+			"start",
+			"Hello",
+			" from retry",
+			"end"
+		]
+	);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_retry_429_then_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::status(429, r#"{"error": "rate limit"}"#),
+		MockResponse::status(503, r#"{"error": "unavailable"}"#),
+		MockResponse::chat_ok("Hello after retries"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/retry-agent.aip",
+		agent_content(&MockAgentOptions {
+			max_attempts: 3,
+			..Default::default()
+		}),
+	)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res.x_get_str("content")?, "Hello after retries");
+	assert_contains(res.x_get_str("info")?, "Retries: 2");
+	assert_eq!(server.request_count(), 3);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_retry_exhausted_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::status(429, r#"{"error": "rate limit"}"#)]).await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/retry-agent.aip",
+		agent_content(&MockAgentOptions {
+			max_attempts: 2,
+			..Default::default()
+		}),
+	)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await;
+
+	// -- Check
+	assert!(res.is_err(), "Should fail after the max attempts");
+	assert_eq!(server.request_count(), 2);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_retry_not_retryable_err() -> Result<()> {
+	// -- Setup & Fixtures
+	// 429 is not part of the retry_on list, so, no retry
+	let server = MockAiServer::start(vec![
+		MockResponse::status(429, r#"{"error": "rate limit"}"#),
+		MockResponse::chat_ok("Should not get there"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/retry-agent.aip",
+		agent_content(&MockAgentOptions {
+			max_attempts: 3,
+			retry_on: &["server"],
+			..Default::default()
+		}),
+	)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await;
+
+	// -- Check
+	assert!(res.is_err(), "Should fail without retry");
+	assert_eq!(server.request_count(), 1);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_cache_hit_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok("First response"),
+		MockResponse::chat_ok("Second response"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_for_temp_dir_with_genai_client(server.genai_client())?;
+	let content = agent_content(&MockAgentOptions {
+		cache: true,
+		..Default::default()
+	});
+	let agent = load_inline_agent("./mock/cache-agent.aip", content)?;
+
+	// -- Exec
+	let res_1 = run_test_agent(&runtime, &agent).await?;
+	let res_2 = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res_1.x_get_str("content")?, "First response");
+	assert!(!res_1.x_get::<bool>("cached")?, "first response should not be cached");
+	assert_eq!(res_2.x_get_str("content")?, "First response");
+	assert!(res_2.x_get::<bool>("cached")?, "second response should be cached");
+	assert_eq!(server.request_count(), 1);
+	let cache_dir = runtime.dir_context().aipack_paths().get_wks_cache_dir()?;
+	assert!(cache_dir.join("ai_responses").exists(), "cache dir should exist");
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_output_schema_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::chat_ok(
+		"```json\n{\"name\": \"John\", \"age\": 42}\n```",
+	)])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent("./mock/schema-agent.aip", agent_schema_content(""))?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res.x_get_str("/json/name")?, "John");
+	assert_eq!(res.x_get::<i64>("/json/age")?, 42);
+	assert_eq!(server.request_count(), 1);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_output_schema_null_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::chat_ok("null")]).await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let content = r#"
+# Instruction
+
+Give me a person, or null
+
+# Output Schema
+
+```json
+{ "type": ["object", "null"] }
+```
+
+# Output
+
+```lua
+return { json_type = type(ai_response.json) }
+```
+"#;
+	let agent = load_inline_agent("./mock/schema-agent.aip", content)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	// The json null is nil for the script (not a userdata)
+	assert_eq!(res.x_get_str("json_type")?, "nil");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_output_schema_repair_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok(r#"{"name": "John", "age": "forty-two"}"#),
+		MockResponse::chat_ok(r#"{"name": "John", "age": 42}"#),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent("./mock/schema-agent.aip", agent_schema_content(""))?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res.x_get::<i64>("/json/age")?, 42);
+	assert_contains(res.x_get_str("info")?, "Repairs: 1");
+	// usage is summed across the repair requests
+	assert_eq!(res.x_get::<i64>("/usage/completion_tokens")?, 10);
+	assert_eq!(server.request_count(), 2);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_output_schema_repair_exhausted_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::chat_ok("Not even json")]).await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/schema-agent.aip",
+		agent_schema_content("output_schema_max_repairs = 2"),
+	)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await;
+
+	// -- Check
+	let err = res.err().ok_or("Should fail after the max repairs")?;
+	assert_contains(&err.to_string(), "does not match the Output Schema after 3 attempt(s)");
+	assert_eq!(server.request_count(), 3);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_tools_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_tool_calls(&[("call_1", "get_weather", json!({"city": "Paris"}))]),
+		MockResponse::chat_ok("It is 20C in Paris"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent("./mock/tools-agent.aip", agent_tools_content(""))?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res.x_get_str("content")?, "It is 20C in Paris");
+	assert_contains(res.x_get_str("info")?, "Tool calls: 1");
+	assert_eq!(server.request_count(), 2);
+	let request_bodies = server.request_bodies();
+	let first_req: Value = serde_json::from_str(&request_bodies[0])?;
+	assert_eq!(first_req.x_get_str("/tools/0/function/name")?, "get_weather");
+	assert_eq!(
+		first_req.x_get_str("/tools/0/function/description")?,
+		"Get the weather for a city."
+	);
+	let second_req: Value = serde_json::from_str(&request_bodies[1])?;
+	let tool_msg = second_req.x_get::<Value>("/messages/2")?;
+	assert_eq!(tool_msg.x_get_str("role")?, "tool");
+	assert_eq!(tool_msg.x_get_str("tool_call_id")?, "call_1");
+	let tool_content: Value = serde_json::from_str(tool_msg.x_get_str("content")?)?;
+	assert_eq!(tool_content, json!({"city": "Paris", "temp": 20}));
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_tools_cache_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let tool_calls_response = MockResponse::chat_tool_calls(&[("call_1", "get_weather", json!({"city": "Paris"}))]);
+	let server = MockAiServer::start(vec![
+		tool_calls_response.clone(),
+		MockResponse::chat_ok("It is 20C in Paris"),
+		tool_calls_response,
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_for_temp_dir_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/tools-agent.aip",
+		agent_tools_content("cache = { enabled = true }"),
+	)?;
+
+	// -- Exec
+	let res_1 = run_test_agent(&runtime, &agent).await?;
+	let res_2 = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert!(!res_1.x_get::<bool>("cached")?, "first response should not be cached");
+	// Note: The tool calls response is asked again, and only the final response is from the cache
+	assert_eq!(server.request_count(), 3);
+	assert_eq!(res_2.x_get_str("content")?, "It is 20C in Paris");
+	assert!(res_2.x_get::<bool>("cached")?, "final response should be cached");
+	assert_contains(res_2.x_get_str("info")?, "Tool calls: 1");
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_tools_max_iterations_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::chat_tool_calls(&[(
+		"call_1",
+		"get_weather",
+		json!({"city": "Paris"}),
+	)])])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/tools-agent.aip",
+		agent_tools_content("tools_max_iterations = 2"),
+	)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await;
+
+	// -- Check
+	let err = res.err().ok_or("Should fail after the max tool iterations")?;
+	assert_contains(&err.to_string(), "max of 2 tool iterations");
+	assert_eq!(server.request_count(), 3);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_tools_lua_limits_err() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_tool_calls(&[("call_1", "get_weather", json!({"city": "Paris"}))]),
+		MockResponse::chat_ok("The weather tool failed"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let agent = load_inline_agent(
+		"./mock/tools-agent.aip",
+		agent_tools_script_content(
+			"lua_limits = { tools = { max_instructions = 100000 } }",
+			"while true do end",
+		),
+	)?;
+
+	// -- Exec
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	// Note: The looping tool is stopped by the limit, and its error is the tool response for the AI
+	assert_eq!(res.x_get_str("content")?, "The weather tool failed");
+	let request_bodies = server.request_bodies();
+	let second_req: Value = serde_json::from_str(&request_bodies[1])?;
+	let tool_content = second_req.x_get_str("/messages/2/content")?;
+	assert_contains(
+		tool_content,
+		"Lua instruction budget (100000 instructions) exceeded in the tools stage",
+	);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_session_replay_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok("First answer"),
+		MockResponse::chat_ok("Second answer"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_for_temp_dir_with_genai_client(server.genai_client())?;
+	let content = r#"
+# Options
+
+```toml
+session = "chat-01"
+```
+
+# System
+
+Be concise
+
+# Instruction
+
+Say hello
+
+# Output
+
+```lua
+return ai_response
+```
+"#;
+	let agent = load_inline_agent("./mock/session-agent.aip", content)?;
+
+	// -- Exec
+	run_test_agent(&runtime, &agent).await?;
+	let res = run_test_agent(&runtime, &agent).await?;
+
+	// -- Check
+	assert_eq!(res.x_get_str("content")?, "Second answer");
+	let second_req: Value = serde_json::from_str(&server.request_bodies()[1])?;
+	let messages = second_req.x_get::<Vec<Value>>("messages")?;
+	let messages: Vec<(&str, &str)> = messages
+		.iter()
+		.map(|m| Ok((m.x_get_str("role")?, m.x_get_str("content")?.trim())))
+		.collect::<Result<_>>()?;
+	assert_eq!(
+		messages,
+		vec![
+			("system", "Be concise"),
+			("user", "Say hello"),
+			("assistant", "First answer"),
+			("user", "Say hello"),
+		]
+	);
+	let session = SessionStore::new(runtime.dir_context())?.load("chat-01")?;
+	assert_eq!(session.len(), 4);
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_session_input_concurrency_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok("Answer one"),
+		MockResponse::chat_ok("Answer two"),
+		MockResponse::chat_ok("Answer three"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_for_temp_dir_with_genai_client(server.genai_client())?;
+	let content = r#"
+# Options
+
+```toml
+session = "chat-02"
+input_concurrency = 4
+```
+
+# Data
+
+```lua
+return { name = input }
+```
+
+# Instruction
+
+Say hello to {{data.name}}
+"#;
+	let agent = load_inline_agent("./mock/session-concurrency-agent.aip", content)?;
+	let inputs = vec![json!("one"), json!("two"), json!("three")];
+
+	// -- Exec
+	run_command_agent(&runtime, agent, Some(inputs), &RunBaseOptions::default(), false).await?;
+
+	// -- Check
+	// Note: One input at a time, so each input replays all the turns of the previous ones
+	let session = SessionStore::new(runtime.dir_context())?.load("chat-02")?;
+	let session: Vec<String> = session.into_iter().map(|m| m.content.trim().to_string()).collect();
+	assert_eq!(
+		session,
+		vec![
+			"Say hello to one",
+			"Answer one",
+			"Say hello to two",
+			"Answer two",
+			"Say hello to three",
+			"Answer three",
+		]
+	);
+	let last_req: Value = serde_json::from_str(&server.request_bodies()[2])?;
+	assert_eq!(last_req.x_get::<Vec<Value>>("messages")?.len(), 5);
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_budget_max_tokens_skip_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	// The first input reserves ~507 tokens (prompt + completion estimate), and uses 15 tokens,
+	// so, the second one would exceed the 520 tokens budget
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok("First answer"),
+		MockResponse::chat_ok("Should not get there"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let content = agent_content(&MockAgentOptions {
+		max_tokens_total: Some(520),
+		..Default::default()
+	});
+	let agent = load_inline_agent("./mock/budget-agent.aip", content)?;
+	let inputs = vec![json!("one"), json!("two")];
+
+	// -- Exec
+	let res = run_command_agent(&runtime, agent, Some(inputs), &RunBaseOptions::default(), true).await?;
+
+	// -- Check
+	let outputs = res.outputs.ok_or("Should have outputs")?;
+	assert_eq!(outputs[0].x_get_str("content")?, "First answer");
+	assert_eq!(outputs[1], Value::Null);
+	assert!(res.errors.is_empty(), "budget skip should not be an error");
+	assert_eq!(server.request_count(), 1);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_run_summary_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok("First answer"),
+		MockResponse::chat_ok("Third answer"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_sandbox_01_with_genai_client(server.genai_client())?;
+	let content = r#"
+# Data
+
+```lua
+if input == "two" then
+    return aipack.skip("Skip the 'two' at data stage")
+end
+```
+
+# Instruction
+
+Say hello
+
+# After All
+
+```lua
+return run_summary
+```
+"#;
+	let agent = load_inline_agent("./mock/summary-agent.aip", content)?;
+	let inputs = vec![json!("one"), json!("two"), json!("three")];
+
+	// -- Exec
+	let res = run_command_agent(&runtime, agent, Some(inputs), &RunBaseOptions::default(), true).await?;
+
+	// -- Check
+	let summary = &res.summary;
+	assert_eq!(summary.inputs, 3);
+	assert_eq!(summary.skipped, 1);
+	assert_eq!(summary.failed, 0);
+	assert_eq!(summary.ai_responses, 2);
+	assert_eq!(summary.prompt_tokens, 20);
+	assert_eq!(summary.completion_tokens, 10);
+	assert_eq!(summary.total_tokens, 30);
+	assert_eq!(summary.models.len(), 1);
+	assert_eq!(summary.models[0].ai_responses, 2);
+	// the after all gets the same summary
+	let after_all = res.after_all.ok_or("Should have after_all")?;
+	assert_eq!(after_all.x_get::<i64>("skipped")?, 1);
+	assert_eq!(after_all.x_get::<i64>("total_tokens")?, 30);
+	assert_eq!(
+		after_all.x_get_str("/models/0/model_name")?,
+		summary.models[0].model_name
+	);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_history_record_replay_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![
+		MockResponse::chat_ok("First answer"),
+		MockResponse::chat_ok("Second answer"),
+	])
+	.await?;
+	let runtime = Runtime::new_test_runtime_for_temp_dir_with_genai_client(server.genai_client())?;
+	let content = r#"
+# Data
+
+```lua
+return { name = input }
+```
+
+# Instruction
+
+Say hello to {{data.name}}
+
+# Output
+
+```lua
+return input .. ": " .. ai_response.content
+```
+"#;
+	let agent = load_inline_agent("./mock/history-agent.aip", content)?;
+	let inputs = vec![json!("one"), json!("two")];
+
+	// -- Exec
+	run_command_agent(
+		&runtime,
+		agent.clone(),
+		Some(inputs),
+		&RunBaseOptions::new_with_history(),
+		false,
+	)
+	.await?;
+
+	// -- Check record
+	let store = RunStore::new(runtime.dir_context())?;
+	let run_records = store.list()?;
+	assert_eq!(run_records.len(), 1);
+	let run_record = &run_records[0];
+	assert_eq!(run_record.inputs.len(), 2);
+	assert_eq!(run_record.summary.as_ref().map(|s| s.ai_responses), Some(2));
+	let input_records = store.load_inputs(&run_record.id)?;
+	assert_eq!(input_records.len(), 2);
+	assert_eq!(input_records[1].input, json!("two"));
+	assert_eq!(input_records[1].data.x_get_str("name")?, "two");
+	assert_eq!(input_records[1].messages[0].content.trim(), "Say hello to two");
+	assert_eq!(input_records[1].output, json!("two: Second answer"));
+
+	// -- Check replay (no new AI call)
+	let input_records = input_records.into_iter().filter(|r| r.index == 1).collect();
+	let outputs = replay_run_inputs(&runtime, &agent, run_record.before_all.clone(), input_records, false).await?;
+	assert_eq!(outputs, vec![json!("two: Second answer")]);
+	assert_eq!(server.request_count(), 2);
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_history_max_runs_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	let server = MockAiServer::start(vec![MockResponse::chat_ok("Some answer")]).await?;
+	let runtime = Runtime::new_test_runtime_for_temp_dir_with_genai_client(server.genai_client())?;
+	save_file_content(
+		&runtime.dir_context().aipack_paths().get_wks_config_toml_path()?,
+		"[history]\nmax_runs = 2\n",
+	)?;
+	let agent = load_inline_agent("./mock/history-agent.aip", agent_content(&MockAgentOptions::default()))?;
+
+	// -- Exec
+	let mut run_ids = Vec::new();
+	for _ in 0..3 {
+		run_command_agent(
+			&runtime,
+			agent.clone(),
+			None,
+			&RunBaseOptions::new_with_history(),
+			false,
+		)
+		.await?;
+		let store = RunStore::new(runtime.dir_context())?;
+		run_ids.push(store.list()?.first().map(|r| r.id.clone()).ok_or("Should have a run")?);
+	}
+
+	// -- Check
+	// Note: The first run was removed when the third one was created (the most recent first)
+	let store = RunStore::new(runtime.dir_context())?;
+	let ids: Vec<String> = store.list()?.into_iter().map(|r| r.id).collect();
+	assert_eq!(ids, vec![run_ids[2].clone(), run_ids[1].clone()]);
+
+	// -- Cleanup
+	remove_test_dir(runtime.dir_context().current_dir())?;
+
+	Ok(())
+}
+
+// region:    --- Support
+
+/// The options of the mock agent of `agent_content`
+struct MockAgentOptions {
+	max_attempts: u32,
+	retry_on: &'static [&'static str],
+	stream: bool,
+	cache: bool,
+	max_tokens_total: Option<u64>,
+}
+
+impl Default for MockAgentOptions {
+	fn default() -> Self {
+		Self {
+			max_attempts: 1,
+			retry_on: &[],
+			stream: false,
+			cache: false,
+			max_tokens_total: None,
+		}
+	}
+}
+
+fn agent_content(options: &MockAgentOptions) -> String {
+	let MockAgentOptions {
+		max_attempts,
+		retry_on,
+		stream,
+		cache,
+		max_tokens_total,
+	} = options;
+
+	let mut retry_props = format!("max_attempts = {max_attempts}");
+	if !retry_on.is_empty() {
+		let retry_on: Vec<String> = retry_on.iter().map(|v| format!("\"{v}\"")).collect();
+		retry_props.push_str(&format!(", retry_on = [{}]", retry_on.join(", ")));
+	}
+	let mut toml_options = vec![format!("retry = {{ {retry_props}, base_delay_ms = 10, jitter = false }}")];
+	if *stream {
+		toml_options.push("stream = true".to_string());
+	}
+	if *cache {
+		toml_options.push("cache = { enabled = true }".to_string());
+	}
+	if let Some(max_tokens_total) = max_tokens_total {
+		toml_options.push(format!("max_tokens_total = {max_tokens_total}"));
+	}
+	let toml_options = toml_options.join("\n");
+
+	format!(
+		r#"
+# Options
+
+```toml
+{toml_options}
+```
+
+# Instruction
+
+Say hello
+
+# Output
+
+```lua
+return ai_response
+```
+"#
+	)
+}
+
+fn agent_schema_content(options: &str) -> String {
+	format!(
+		r#"
+# Options
+
+```toml
+{options}
+```
+
+# Instruction
+
+Give me a person
+
+# Output Schema
+
+```json
+{{
+  "type": "object",
+  "properties": {{
+    "name": {{ "type": "string" }},
+    "age": {{ "type": "integer" }}
+  }},
+  "required": ["name", "age"]
+}}
+```
+
+# Output
+
+```lua
+return ai_response
+```
+"#
+	)
+}
+
+fn agent_tools_content(options: &str) -> String {
+	agent_tools_script_content(options, "return { city = args.city, temp = 20 }")
+}
+
+/// Same as `agent_tools_content`, with the given `get_weather` tool Lua script
+fn agent_tools_script_content(options: &str, tool_script: &str) -> String {
+	format!(
+		r#"
+# Options
+
+```toml
+{options}
+```
+
+# Instruction
+
+What is the weather in Paris?
+
+# Tools
+
+## get_weather
+
+Get the weather for a city.
+
+```json
+{{
+  "type": "object",
+  "properties": {{ "city": {{ "type": "string" }} }},
+  "required": ["city"]
+}}
+```
+
+```lua
+{tool_script}
+```
+
+# Output
+
+```lua
+return ai_response
+```
+"#
+	)
+}
+
+// endregion: --- Support
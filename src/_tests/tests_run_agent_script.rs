@@ -219,3 +219,276 @@ return "output for: " .. input
 
 	Ok(())
 }
+
+#[tokio::test]
+async fn test_run_agent_script_on_error_continue() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let agent = load_inline_agent("./dummy/path.aip", on_error_agent_content("continue"))?;
+
+	// -- Exec
+	let inputs = vec!["one".into(), "two".into(), "three".into()];
+	let res = run_command_agent(&runtime, agent, Some(inputs), &RunBaseOptions::default(), true).await?;
+
+	// -- Check
+	let outputs = res.outputs.ok_or("Should have outputs")?;
+	assert_eq!(outputs.len(), 3, "outputs should be in sync with inputs");
+	assert_eq!(outputs[0].as_str(), Some("output for: one"));
+	assert_eq!(outputs[1], Value::Null);
+	assert_eq!(outputs[2].as_str(), Some("output for: three"));
+
+	assert_eq!(res.errors.len(), 1);
+	let input_error = &res.errors[0];
+	assert_eq!(input_error.index, 1);
+	assert_eq!(input_error.label, "input index: 1");
+	assert_contains(&input_error.error, "Failed on two");
+
+	// the after all gets the errors
+	let after_all = res.after_all.ok_or("Should have after_all")?;
+	assert_eq!(after_all.x_get_str("/errors/0/label")?, "input index: 1");
+	assert_eq!(after_all.x_get::<i64>("/errors/0/index")?, 1);
+	assert_contains(after_all.x_get_str("/errors/0/error")?, "Failed on two");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_script_on_error_abort() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let agent = load_inline_agent("./dummy/path.aip", on_error_agent_content("abort"))?;
+
+	// -- Exec
+	let inputs = vec!["one".into(), "two".into(), "three".into()];
+	let res = run_command_agent(&runtime, agent, Some(inputs), &RunBaseOptions::default(), true).await;
+
+	// -- Check
+	let err = res.err().ok_or("Should have failed")?;
+	assert_contains(&err.to_string(), "Failed on two");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_script_lua_limits() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let fx_agent = r#"
+# Options
+
+```toml
+lua_limits = { max_instructions = 1000000, output = { timeout_ms = 100, max_instructions = 100000000000 }, after_all = { memory_mb = 8 } }
+```
+
+# Data
+
+```lua
+if input == "loop" then
+  -- Note: The pcall does not catch the limit for good
+  pcall(function() while true do end end)
+  while true do end
+end
+return input
+```
+
+# Output
+
+```lua
+if input == "slow" then
+  while true do end
+end
+return "output for: " .. input
+```
+
+# After All
+
+```lua
+if inputs[1] == "big" then
+  local items = {}
+  for i = 1, 10000000 do
+    items[i] = "item " .. i
+  end
+end
+return "done"
+```
+"#;
+	let agent = load_inline_agent("./dummy/limits.aip", fx_agent)?;
+
+	// -- Exec & Check - instruction budget (data)
+	let err = run_test_agent_with_input(&runtime, &agent, "loop")
+		.await
+		.err()
+		.ok_or("Should exceed the instructions")?;
+	assert_contains(
+		&err.to_string(),
+		"Lua instruction budget (1000000 instructions) exceeded in the data stage of agent 'inline-agent'",
+	);
+
+	// -- Exec & Check - timeout (output)
+	let err = run_test_agent_with_input(&runtime, &agent, "slow")
+		.await
+		.err()
+		.ok_or("Should timeout")?;
+	assert_contains(
+		&err.to_string(),
+		"Lua timeout (100 ms) exceeded in the output stage of agent 'inline-agent' for input 'input index: 0'",
+	);
+
+	// -- Exec & Check - memory (after all), and the other inputs are within the limits
+	let res = run_command_agent(
+		&runtime,
+		agent.clone(),
+		Some(vec!["ok".into()]),
+		&RunBaseOptions::default(),
+		true,
+	)
+	.await?;
+	assert_eq!(
+		res.outputs.ok_or("Should have outputs")?,
+		vec![Value::from("output for: ok")]
+	);
+	let err = run_command_agent(
+		&runtime,
+		agent,
+		Some(vec!["big".into()]),
+		&RunBaseOptions::default(),
+		true,
+	)
+	.await
+	.err()
+	.ok_or("Should exceed the memory")?;
+	assert_contains(
+		&err.to_string(),
+		"Lua memory limit (8 MB) exceeded in the after_all stage",
+	);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_script_lua_error_location() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	let fx_agent = r#"# Data
+
+```lua
+local name = input
+if name == "data" then
+  local x = foo.bar
+end
+return name
+```
+
+# Output
+
+```lua
+if input == "output_async" then
+  aip.file.load_async(nil)
+end
+aip.file.load(nil)
+```
+"#;
+	let agent = load_inline_agent("./dummy/error-agent.aip", fx_agent)?;
+
+	// -- Exec & Check - Data (runtime error)
+	let err = run_test_agent_with_input(&runtime, &agent, "data")
+		.await
+		.err()
+		.ok_or("Should fail in data")?;
+	let err = err.to_string();
+	assert_contains(
+		&err,
+		"./dummy/error-agent.aip:6:13: runtime error: attempt to index a nil value (global 'foo') (in # Data)",
+	);
+	assert_contains(&err, "6 |   local x = foo.bar\n  |             ^");
+	assert_contains(&err, "./dummy/error-agent.aip:6: in main chunk");
+
+	// -- Exec & Check - Output (error of an aip function)
+	let err = run_test_agent_with_input(&runtime, &agent, "output")
+		.await
+		.err()
+		.ok_or("Should fail in output")?;
+	let err = err.to_string();
+	assert_contains(&err, "./dummy/error-agent.aip:17:10: bad argument #1");
+	assert_contains(&err, "(in # Output)");
+
+	// -- Exec & Check - Output (error of an aip async function, through its `aip.async` wrapper)
+	let err = run_test_agent_with_input(&runtime, &agent, "output_async")
+		.await
+		.err()
+		.ok_or("Should fail in output")?;
+	let err = err.to_string();
+	assert_contains(&err, "./dummy/error-agent.aip:15:");
+	assert_contains(&err, "aip.async:");
+	assert!(!err.contains(".rs:"), "Should not have a rust source location:\n{err}");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn test_run_agent_script_lua_error_location_second_block() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_sandbox_01()?;
+	// Note: The code blocks of the `# Data` sections are joined into one script
+	let fx_agent = r#"# Data
+
+```lua
+local name = input
+```
+
+# Data
+
+```lua
+local x = foo.bar
+return name
+```
+"#;
+	let agent = load_inline_agent("./dummy/error-agent.aip", fx_agent)?;
+
+	// -- Exec
+	let err = run_test_agent_with_input(&runtime, &agent, "data")
+		.await
+		.err()
+		.ok_or("Should fail in data")?;
+
+	// -- Check
+	let err = err.to_string();
+	assert_contains(
+		&err,
+		"./dummy/error-agent.aip:10:11: runtime error: attempt to index a nil value (global 'foo') (in # Data)",
+	);
+	assert_contains(&err, "10 | local x = foo.bar");
+
+	Ok(())
+}
+
+// region:    --- Support
+
+fn on_error_agent_content(on_error: &str) -> String {
+	format!(
+		r#"
+# Options
+
+```toml
+on_error = "{on_error}"
+```
+
+# Output
+
+```lua
+if input == "two" then
+  error("Failed on two")
+end
+return "output for: " .. input
+```
+
+# After All
+
+```lua
+return {{ errors = errors }}
+```
+"#
+	)
+}
+
+// endregion: --- Support
@@ -0,0 +1,215 @@
+use super::*;
+use crate::_test_support::{assert_contains, remove_test_dir, save_file_content};
+use crate::agent::find_agent;
+use crate::packer::{self, InstallOptions, install_pack};
+use crate::run::Runtime;
+use crate::script::LuaEngine;
+
+type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+#[test]
+fn test_sandbox_checks() -> Result<()> {
+	// -- Setup & Fixtures
+	let permissions: PackPermissions = toml::from_str(
+		r#"
+fs.write = ["src/**", "/tmp/out/*.txt"]
+cmd = ["cargo", "git"]
+net = ["api.example.com", "*.docs.rs"]
+"#,
+	)?;
+	let sandbox = Sandbox::new(
+		"test_ns@pack-a",
+		SPath::new("/wks"),
+		SPath::new("/packs/pack-a"),
+		permissions,
+	)?;
+
+	// -- Check - read (workspace and pack dir, as no fs.read)
+	assert!(sandbox.check_read(&SPath::new("docs/readme.md")).is_ok());
+	assert!(sandbox.check_read(&SPath::new("/packs/pack-a/lua/utils.lua")).is_ok());
+	assert!(sandbox.check_read(&SPath::new("/etc/passwd")).is_err());
+	assert!(sandbox.check_read(&SPath::new("../other/secret.txt")).is_err());
+
+	// -- Check - write
+	assert!(sandbox.check_write(&SPath::new("src/main.rs")).is_ok());
+	assert!(sandbox.check_write(&SPath::new("./src/sub/../lib.rs")).is_ok());
+	assert!(sandbox.check_write(&SPath::new("/tmp/out/report.txt")).is_ok());
+	assert!(sandbox.check_write(&SPath::new("Cargo.toml")).is_err());
+	assert!(sandbox.check_write(&SPath::new("src/../../escape.rs")).is_err());
+
+	// -- Check - cmd
+	assert!(sandbox.check_cmd("cargo").is_ok());
+	assert!(sandbox.check_cmd("./cargo").is_err());
+	assert!(sandbox.check_cmd("/usr/bin/git").is_err());
+	assert!(sandbox.check_cmd("rm").is_err());
+
+	// -- Check - net
+	assert!(sandbox.check_net("https://api.example.com/v1/items").is_ok());
+	assert!(sandbox.check_net("https://docs.rs/serde").is_ok());
+	assert!(sandbox.check_net("https://crates.docs.rs/").is_ok());
+	assert!(sandbox.check_net("https://example.com").is_err());
+	assert!(sandbox.check_net("not a url").is_err());
+
+	let err = sandbox.check_cmd("rm").err().ok_or("Should be denied")?;
+	assert!(err.to_string().contains("test_ns@pack-a"), "err: {err}");
+	assert!(err.is_permission_denied());
+
+	Ok(())
+}
+
+#[test]
+fn test_sandbox_lua_engine() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let wks_dir = dir_context.wks_dir().clone();
+	let permissions: PackPermissions = toml::from_str(r#"fs.write = ["out/**"]"#)?;
+	let sandbox = Sandbox::new("test_ns@pack-a", wks_dir.clone(), wks_dir.join("pack-a"), permissions)?;
+	let engine = LuaEngine::new(runtime.context().with_sandbox(Some(sandbox)))?;
+
+	// -- Exec & Check - allowed
+	engine.eval(r#"aip.file.save("out/result.md", "Hello")"#, None, None)?;
+	let res = engine.eval(r#"return aip.file.load("out/result.md").content"#, None, None)?;
+	assert_eq!(serde_json::to_value(res)?.as_str(), Some("Hello"));
+	let res = engine.eval("return type(os.time())", None, None)?;
+	assert_eq!(serde_json::to_value(res)?.as_str(), Some("number"));
+	let res = engine.eval(r#"return load("return 1 + 1")()"#, None, None)?;
+	assert_eq!(serde_json::to_value(res)?.as_i64(), Some(2));
+	// Note: The binary chunks are refused (load returns nil and the error)
+	let res = engine.eval(
+		r#"return load(string.dump(function() return 1 end)) == nil"#,
+		None,
+		None,
+	)?;
+	assert_eq!(serde_json::to_value(res)?.as_bool(), Some(true));
+
+	// -- Exec & Check - denied
+	for script in [
+		r#"aip.file.save("src/main.rs", "x")"#,
+		r#"aip.file.load("/etc/hosts")"#,
+		r#"aip.cmd.exec("echo", {"hello"})"#,
+		r#"aip.web.get("https://example.com")"#,
+		r#"io.open("out/other.md", "w")"#,
+		r#"os.execute("echo hello")"#,
+		r#"dofile("out/result.md")"#,
+		r#"require("os").execute("echo hello")"#,
+		r#"package.loaded.io.open("out/other.md", "w")"#,
+		r#"debug.getregistry()"#,
+		r#"require("debug").getregistry()"#,
+		r#"aip.session.append("chat-01", "user", "Hello")"#,
+		r#"aip.session.clear("chat-01")"#,
+	] {
+		let err = engine
+			.eval(script, None, None)
+			.err()
+			.ok_or(format!("Should be denied: {script}"))?;
+		assert!(err.is_permission_denied(), "script: {script}\nerr: {err}");
+	}
+
+	// -- Exec & Check - the Lua error has the permission denied as source
+	let err = engine
+		.eval(r#"aip.file.load("/etc/hosts")"#, None, None)
+		.err()
+		.ok_or("Should be denied")?;
+	let source = std::error::Error::source(&err).ok_or("Should have a source")?;
+	assert_contains(&source.to_string(), "Permission denied for pack test_ns@pack-a");
+
+	// -- Exec & Check - a Lua error with the same message is not a permission denied
+	let err = engine
+		.eval(
+			r#"error("Permission denied for pack test_ns@pack-a: fake")"#,
+			None,
+			None,
+		)
+		.err()
+		.ok_or("Should have failed")?;
+	assert!(!err.is_permission_denied(), "err: {err}");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[test]
+fn test_sandbox_lua_require() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	let wks_dir = dir_context.wks_dir().clone();
+	save_file_content(&wks_dir.join("lua/ok_mod.lua"), "return { name = 'ok_mod' }")?;
+	save_file_content(&wks_dir.join("secret/secret_mod.lua"), "return { name = 'secret_mod' }")?;
+	let permissions: PackPermissions = toml::from_str(r#"fs.read = ["lua/**"]"#)?;
+	let sandbox = Sandbox::new("test_ns@pack-a", wks_dir.clone(), wks_dir.join("pack-a"), permissions)?;
+	let engine = LuaEngine::new(runtime.context().with_sandbox(Some(sandbox)))?;
+
+	// -- Exec & Check - allowed
+	let res = engine.eval(
+		r#"
+package.path = "lua/?.lua;" .. package.path
+return { name = require("ok_mod").name, searchers = #package.searchers }
+"#,
+		None,
+		None,
+	)?;
+	let res = serde_json::to_value(res)?;
+	assert_eq!(res.get("name").and_then(|v| v.as_str()), Some("ok_mod"));
+	// Note: Only the preload and the sandbox Lua searchers (no C modules)
+	assert_eq!(res.get("searchers").and_then(|v| v.as_i64()), Some(2));
+
+	// -- Exec & Check - denied (even with a changed package.path)
+	let script = format!(r#"package.path = "{wks_dir}/secret/?.lua"; return require("secret_mod")"#);
+	let err = engine.eval(&script, None, None).err().ok_or("Should be denied")?;
+	assert!(err.is_permission_denied(), "err: {err}");
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sandbox_for_agent_installed_permissions() -> Result<()> {
+	// -- Setup & Fixtures
+	let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+	let dir_context = runtime.dir_context();
+	save_file_content(
+		&dir_context.aipack_paths().get_base_config_toml_path()?,
+		"[default_options]\nmodel = \"gpt-4o-mini\"\n",
+	)?;
+	save_file_content(&dir_context.aipack_paths().get_wks_config_toml_path()?, "")?;
+	// pack-a without [permissions], pack-b with an empty [permissions]
+	for (name, permissions) in [("pack-a", ""), ("pack-b", "\n[permissions]\n")] {
+		let src_dir = dir_context.current_dir().join("dev").join(name);
+		let pack_toml =
+			format!("[pack]\nnamespace = \"test_ns\"\nname = \"{name}\"\nversion = \"0.1.0\"\n{permissions}");
+		save_file_content(&src_dir.join("pack.toml"), &pack_toml)?;
+		save_file_content(&src_dir.join("main.aip"), "# Test Main\n")?;
+		let pack_file = packer::pack_dir(&src_dir, dir_context.current_dir().join("packs"))?.pack_file;
+		install_pack(
+			dir_context,
+			pack_file.to_str(),
+			&InstallOptions { allow_unsigned: true },
+		)
+		.await?;
+	}
+	let agent_a = find_agent("test_ns@pack-a", dir_context)?;
+	let agent_b = find_agent("test_ns@pack-b", dir_context)?;
+
+	// -- Exec
+	let sandbox_a = Sandbox::for_agent(dir_context, &agent_a)?.ok_or("Installed pack-a should be sandboxed")?;
+	let sandbox_b = Sandbox::for_agent(dir_context, &agent_b)?.ok_or("Installed pack-b should be sandboxed")?;
+	let allow_all_runtime = runtime.for_agent(&agent_a, true)?;
+
+	// -- Check
+	// Note: No [permissions] (pack-a), and empty [permissions] (pack-b), so no write, cmd, nor net
+	for sandbox in [&sandbox_a, &sandbox_b] {
+		assert!(sandbox.check_read(&SPath::new("README.md")).is_ok());
+		assert!(sandbox.check_write(&SPath::new("README.md")).is_err());
+		assert!(sandbox.check_cmd("git").is_err());
+		assert!(sandbox.check_net("https://example.com").is_err());
+	}
+	assert!(allow_all_runtime.context().sandbox().is_none());
+
+	// -- Cleanup
+	remove_test_dir(dir_context.current_dir())?;
+	Ok(())
+}
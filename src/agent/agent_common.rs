@@ -1,9 +1,11 @@
-use crate::agent::PromptPart;
 use crate::agent::agent_options::AgentOptions;
 use crate::agent::agent_ref::AgentRef;
+use crate::agent::{AgentTool, PromptPart};
 use crate::{Error, Result};
 use genai::ModelName;
-use genai::chat::ChatOptions;
+use genai::adapter::AdapterKind;
+use genai::chat::{ChatOptions, ChatResponseFormat, JsonSpec};
+use serde_json::Value;
 use simple_fs::SPath;
 use std::sync::Arc;
 
@@ -31,6 +33,7 @@ impl Agent {
 		let model_resolved = inner.agent_options.resolve_model().map(|v| v.into()).unwrap_or(model.clone());
 
 		let chat_options = ChatOptions::from(&*inner.agent_options);
+		let chat_options = with_output_schema(chat_options, &model_resolved, inner.output_schema.as_ref());
 
 		Ok(Agent {
 			inner,
@@ -53,6 +56,7 @@ impl Agent {
 
 		// -- Build the genai chat optoins
 		let chat_options = ChatOptions::from(&options);
+		let chat_options = with_output_schema(chat_options, &model_resolved, inner.output_schema.as_ref());
 
 		// -- Returns
 		Ok(Agent {
@@ -127,6 +131,16 @@ impl Agent {
 		self.inner.data_script.as_deref()
 	}
 
+	/// The tools of the `# Tools` section
+	pub fn tools(&self) -> &[AgentTool] {
+		&self.inner.tools
+	}
+
+	/// The JSON Schema of the `# Output Schema` section
+	pub fn output_schema(&self) -> Option<&Value> {
+		self.inner.output_schema.as_ref()
+	}
+
 	pub fn output_script(&self) -> Option<&str> {
 		self.inner.output_script.as_deref()
 	}
@@ -134,8 +148,47 @@ impl Agent {
 	pub fn after_all_script(&self) -> Option<&str> {
 		self.inner.after_all_script.as_deref()
 	}
+
+	/// The line (1-based) in the agent file of the first line of the stage script
+	/// (`before_all`, `data`, `output`, or `after_all`)
+	pub fn script_start_line(&self, stage: &str) -> Option<usize> {
+		let lines = &self.inner.script_start_lines;
+		match stage {
+			"before_all" => lines.before_all,
+			"data" => lines.data,
+			"output" => lines.output,
+			"after_all" => lines.after_all,
+			_ => None,
+		}
+	}
+}
+
+// region:    --- Support
+
+/// Set the output schema as the structured output response format
+/// when the provider supports it natively (otherwise, the response is only validated locally).
+fn with_output_schema(
+	mut chat_options: ChatOptions,
+	model_resolved: &str,
+	output_schema: Option<&Value>,
+) -> ChatOptions {
+	let Some(output_schema) = output_schema else {
+		return chat_options;
+	};
+
+	if matches!(
+		AdapterKind::from_model(model_resolved),
+		Ok(AdapterKind::OpenAI | AdapterKind::Gemini)
+	) {
+		let json_spec = JsonSpec::new("output_schema", output_schema.clone());
+		chat_options.response_format = Some(ChatResponseFormat::JsonSpec(json_spec));
+	}
+
+	chat_options
 }
 
+// endregion: --- Support
+
 // region:    --- AgentInner
 
 /// AgentInner is ok to be public to allow user-code to build Agent simply.
@@ -159,10 +212,28 @@ pub(super) struct AgentInner {
 	/// Contains the instruction, system, assistant in order of the file
 	pub prompt_parts: Vec<PromptPart>,
 
+	/// The tools from the `# Tools` section
+	pub tools: Vec<AgentTool>,
+
+	/// The JSON Schema from the `# Output Schema` section
+	pub output_schema: Option<Value>,
+
 	/// Script
 	pub data_script: Option<String>,
 	pub output_script: Option<String>,
 	pub after_all_script: Option<String>,
+
+	/// The line of each script in the agent file (for the Lua error locations)
+	pub script_start_lines: ScriptStartLines,
+}
+
+/// The line number (1-based) of the first line of each script in the agent file
+#[derive(Debug, Clone, Default)]
+pub(super) struct ScriptStartLines {
+	pub before_all: Option<usize>,
+	pub data: Option<usize>,
+	pub output: Option<usize>,
+	pub after_all: Option<usize>,
 }
 
 // endregion: --- AgentInner
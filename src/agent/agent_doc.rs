@@ -1,10 +1,11 @@
-use crate::Result;
 use crate::agent::agent_options::AgentOptions;
 use crate::agent::agent_ref::AgentRef;
-use crate::agent::{Agent, AgentInner, PartKind, PromptPart};
+use crate::agent::{Agent, AgentInner, AgentTool, PartKind, PromptPart, ScriptStartLines};
 use crate::support::md::InBlockState;
 use crate::support::tomls::parse_toml;
+use crate::{Error, Result};
 use genai::ModelName;
+use serde_json::Value;
 use simple_fs::{SPath, read_to_string};
 use std::path::Path;
 use std::sync::Arc;
@@ -15,6 +16,74 @@ pub struct AgentDoc {
 	raw_content: String,
 }
 
+// region:    --- AgentDocParts
+
+/// The raw parts of an agent file, as captured by `AgentDoc::parse_parts`
+#[derive(Debug, Default)]
+pub struct AgentDocParts {
+	pub options_toml: Option<DocBlock>,
+	pub before_all_script: Option<DocBlock>,
+	pub data_script: Option<DocBlock>,
+	/// The instruction, system, assistant parts in order of the file
+	pub prompt_parts: Vec<(PartKind, DocBlock)>,
+	pub tools: Vec<DocTool>,
+	pub output_schema_json: Option<DocBlock>,
+	pub output_script: Option<DocBlock>,
+	pub after_all_script: Option<DocBlock>,
+}
+
+/// A code block (or prompt part) content of the agent file
+#[derive(Debug, Clone)]
+pub struct DocBlock {
+	pub content: String,
+	/// The line number (1-based) of the first line of the content in the agent file
+	pub start_line: usize,
+}
+
+/// A tool of the `# Tools` section
+#[derive(Debug)]
+pub struct DocTool {
+	pub name: String,
+	/// The line number (1-based) of the `## tool_name` heading
+	pub line: usize,
+	pub description: Option<String>,
+	pub schema: Option<DocBlock>,
+	pub script: Option<DocBlock>,
+}
+
+impl DocTool {
+	fn into_agent_tool(self, agent_path: &str) -> Result<AgentTool> {
+		let tool_invalid = |cause: String| Error::AgentToolInvalid {
+			agent_path: agent_path.to_string(),
+			tool_name: self.name.to_string(),
+			cause,
+		};
+
+		let schema: Option<Value> = match self.schema.as_ref() {
+			Some(schema) => Some(
+				serde_json::from_str(&schema.content)
+					.map_err(|err| tool_invalid(format!("parameters json is invalid. Cause: {err}")))?,
+			),
+			None => None,
+		};
+
+		let script = self
+			.script
+			.as_ref()
+			.map(|b| b.content.clone())
+			.ok_or_else(|| tool_invalid("lua block is missing".to_string()))?;
+
+		Ok(AgentTool {
+			name: self.name.to_string(),
+			description: self.description.clone(),
+			schema,
+			script,
+		})
+	}
+}
+
+// endregion: --- AgentDocParts
+
 // region:    --- Capture State
 
 #[derive(Debug)]
@@ -37,6 +106,18 @@ enum CaptureMode {
 
 	PromptPart,
 
+	// Below the tools heading (a `## tool_name` per tool)
+	ToolsSection,
+	// Inside the tool json code block (the parameters schema)
+	ToolsJsonBlock,
+	// Inside the tool lua code block
+	ToolsLuaBlock,
+
+	// Below the output schema heading (perhaps not in a code block)
+	OutputSchemaSection,
+	// Inside the json code block
+	OutputSchemaJsonBlock,
+
 	// Below the output heading (perhaps not in a code block)
 	OutputSection,
 	// Inside the code block
@@ -58,6 +139,9 @@ impl CaptureMode {
 		matches!(
 			self,
 			CaptureMode::OptionsTomlBlock
+				| CaptureMode::ToolsJsonBlock
+				| CaptureMode::ToolsLuaBlock
+				| CaptureMode::OutputSchemaJsonBlock
 				| CaptureMode::BeforeAllCodeBlock
 				| CaptureMode::DataCodeBlock
 				| CaptureMode::OutputCodeBlock
@@ -83,18 +167,115 @@ impl AgentDoc {
 	}
 
 	/// Internal method to create the first part of the agent inner
-	/// This is sort of a Lexer, but very customize to extracting the Agent parts
 	fn into_agent_inner(self, name: &str, agent_ref: AgentRef, agent_options: AgentOptions) -> Result<AgentInner> {
+		let AgentDocParts {
+			options_toml,
+			before_all_script,
+			data_script,
+			prompt_parts,
+			tools,
+			output_schema_json,
+			output_script,
+			after_all_script,
+		} = self.parse_parts();
+
+		// -- Returning the data
+
+		let agent_options_ov: Option<AgentOptions> = if let Some(options_toml) = options_toml {
+			Some(AgentOptions::from_options_value(parse_toml(&options_toml.content)?)?)
+		} else {
+			None
+		};
+
+		let agent_options = match agent_options_ov {
+			Some(agent_options_ov) => agent_options.merge(agent_options_ov)?,
+			None => agent_options,
+		};
+
+		let output_schema: Option<Value> =
+			match output_schema_json {
+				Some(output_schema_json) => Some(serde_json::from_str(&output_schema_json.content).map_err(|err| {
+					Error::OutputSchemaInvalid {
+						agent_path: self.spath.to_str().to_string(),
+						cause: err.to_string(),
+					}
+				})?),
+				None => None,
+			};
+
+		let tools = tools
+			.into_iter()
+			.map(|doc_tool| doc_tool.into_agent_tool(self.spath.to_str()))
+			.collect::<Result<Vec<_>>>()?;
+
+		let prompt_parts = prompt_parts
+			.into_iter()
+			.map(|(kind, block)| PromptPart {
+				kind,
+				content: block.content,
+			})
+			.collect();
+
+		// -- Get the model name
+		let model_name = agent_options.model().map(ModelName::from);
+
+		let script_start_lines = ScriptStartLines {
+			before_all: before_all_script.as_ref().map(|b| b.start_line),
+			data: data_script.as_ref().map(|b| b.start_line),
+			output: output_script.as_ref().map(|b| b.start_line),
+			after_all: after_all_script.as_ref().map(|b| b.start_line),
+		};
+
+		// -- Build the AgentInner
+		let agent_inner = AgentInner {
+			agent_options: Arc::new(agent_options),
+
+			name: name.to_string(),
+			agent_ref,
+
+			file_name: self.spath.name().to_string(),
+			file_path: self.spath.to_str().to_string(),
+
+			model_name,
+
+			before_all_script: before_all_script.map(|b| b.content),
+			data_script: data_script.map(|b| b.content),
+
+			prompt_parts,
+
+			tools,
+
+			output_schema,
+
+			output_script: output_script.map(|b| b.content),
+			after_all_script: after_all_script.map(|b| b.content),
+
+			script_start_lines,
+		};
+
+		Ok(agent_inner)
+	}
+}
+
+/// Lexer
+impl AgentDoc {
+	/// Extract the raw parts of the agent file (sections and code blocks), with the line of each
+	///
+	/// This is sort of a Lexer, but very customize to extracting the Agent parts
+	/// (does not validate the content of the parts)
+	pub fn parse_parts(&self) -> AgentDocParts {
 		let mut capture_mode = CaptureMode::None;
 
 		// -- The buffers
-		let mut options_toml: Vec<&str> = Vec::new();
-		let mut before_all_script: Vec<&str> = Vec::new();
-		let mut data_script: Vec<&str> = Vec::new();
-		let mut output_script: Vec<&str> = Vec::new();
-		let mut after_all_script: Vec<&str> = Vec::new();
-
-		let mut prompt_parts: Vec<PromptPart> = Vec::new();
+		let mut options_toml = BlockBuffer::default();
+		let mut before_all_script = BlockBuffer::default();
+		let mut data_script = BlockBuffer::default();
+		let mut tools_raw: Vec<CurrentTool> = Vec::new();
+		let mut output_schema_json = BlockBuffer::default();
+		let mut output_script = BlockBuffer::default();
+		let mut after_all_script = BlockBuffer::default();
+
+		let mut prompt_parts: Vec<(PartKind, DocBlock)> = Vec::new();
 		// the vec String allow to be more efficient (as join later is more efficient)
 		let mut current_part: Option<CurrentPromptPart> = None;
 
@@ -105,7 +286,10 @@ impl AgentDoc {
 
 		let mut block_state = InBlockState::Out;
 
-		for line in self.raw_content.lines() {
+		for (line_idx, line) in self.raw_content.lines().enumerate() {
+			// The 1-based line number of the next line (e.g., the first line of a code block)
+			let next_line_num = line_idx + 2;
+
 			block_state = block_state.compute_new(line);
 			// If heading we decide the capture mode
 			if block_state.is_out() && line.starts_with('#') && !line.starts_with("##") {
@@ -116,6 +300,10 @@ impl AgentDoc {
 					capture_mode = CaptureMode::BeforeAllSection;
 				} else if header == "data" {
 					capture_mode = CaptureMode::DataSection;
+				} else if header == "tools" {
+					capture_mode = CaptureMode::ToolsSection;
+				} else if header == "output schema" {
+					capture_mode = CaptureMode::OutputSchemaSection;
 				} else if header == "output" {
 					capture_mode = CaptureMode::OutputSection;
 				} else if header == "after all" {
@@ -125,7 +313,7 @@ impl AgentDoc {
 					// we finalize the previous part if present
 					finalize_current_prompt_part(&mut current_part, &mut prompt_parts);
 					// then, we create the new current_part
-					current_part = Some(CurrentPromptPart(part_kind, Vec::new()));
+					current_part = Some(CurrentPromptPart(part_kind, next_line_num, Vec::new()));
 				} else {
 					// Stop processing current section if new top-level header
 					capture_mode = CaptureMode::None;
@@ -140,6 +328,7 @@ impl AgentDoc {
 				CaptureMode::OptionsSection => {
 					if line.starts_with("```toml") {
 						capture_mode = CaptureMode::OptionsTomlBlock;
+						options_toml.start(next_line_num);
 						continue;
 					}
 				}
@@ -149,7 +338,7 @@ impl AgentDoc {
 						capture_mode = CaptureMode::None;
 						continue;
 					} else {
-						push_line(&mut options_toml, line);
+						options_toml.push_line(line);
 					}
 				}
 
@@ -157,6 +346,7 @@ impl AgentDoc {
 				CaptureMode::BeforeAllSection => {
 					if line.starts_with("```lua") {
 						capture_mode = CaptureMode::BeforeAllCodeBlock;
+						before_all_script.start(next_line_num);
 						continue;
 					}
 				}
@@ -165,7 +355,7 @@ impl AgentDoc {
 						capture_mode = CaptureMode::None;
 						continue;
 					} else {
-						push_line(&mut before_all_script, line);
+						before_all_script.push_line(line);
 					}
 				}
 
@@ -173,6 +363,7 @@ impl AgentDoc {
 				CaptureMode::DataSection => {
 					if line.starts_with("```lua") {
 						capture_mode = CaptureMode::DataCodeBlock;
+						data_script.start(next_line_num);
 						continue;
 					}
 				}
@@ -181,24 +372,73 @@ impl AgentDoc {
 						capture_mode = CaptureMode::None;
 						continue;
 					} else {
-						push_line(&mut data_script, line);
+						data_script.push_line(line);
 					}
 				}
 
 				// -- Pompt Part
 				CaptureMode::PromptPart => {
 					if let Some(current_part) = &mut current_part {
-						current_part.1.push(line);
+						current_part.2.push(line);
 					} else {
 						// This should not happen, as the current_part should be been created when we enterred the section
 						// TODO: Need to capture warning if we reach this point.
 					}
 				}
 
+				// -- Tools
+				CaptureMode::ToolsSection => {
+					if let Some(name) = line.strip_prefix("## ") {
+						tools_raw.push(CurrentTool::new(name.trim(), line_idx + 1));
+					} else if let Some(current_tool) = tools_raw.last_mut() {
+						if line.starts_with("```json") {
+							capture_mode = CaptureMode::ToolsJsonBlock;
+							current_tool.schema.start(next_line_num);
+						} else if line.starts_with("```lua") {
+							capture_mode = CaptureMode::ToolsLuaBlock;
+							current_tool.script.start(next_line_num);
+						} else if !line.trim().is_empty() {
+							current_tool.description.push(line.trim());
+						}
+					}
+				}
+				CaptureMode::ToolsJsonBlock => {
+					if line.starts_with("```") {
+						capture_mode = CaptureMode::ToolsSection;
+					} else if let Some(current_tool) = tools_raw.last_mut() {
+						current_tool.schema.push_line(line);
+					}
+				}
+				CaptureMode::ToolsLuaBlock => {
+					if line.starts_with("```") {
+						capture_mode = CaptureMode::ToolsSection;
+					} else if let Some(current_tool) = tools_raw.last_mut() {
+						current_tool.script.push_line(line);
+					}
+				}
+
+				// -- Output Schema
+				CaptureMode::OutputSchemaSection => {
+					if line.starts_with("```json") {
+						capture_mode = CaptureMode::OutputSchemaJsonBlock;
+						output_schema_json.start(next_line_num);
+						continue;
+					}
+				}
+				CaptureMode::OutputSchemaJsonBlock => {
+					if line.starts_with("```") {
+						capture_mode = CaptureMode::None;
+						continue;
+					} else {
+						output_schema_json.push_line(line);
+					}
+				}
+
 				// -- Output
 				CaptureMode::OutputSection => {
 					if line.starts_with("```lua") {
 						capture_mode = CaptureMode::OutputCodeBlock;
+						output_script.start(next_line_num);
 						continue;
 					}
 				}
@@ -207,7 +447,7 @@ impl AgentDoc {
 						capture_mode = CaptureMode::None;
 						continue;
 					} else {
-						push_line(&mut output_script, line);
+						output_script.push_line(line);
 					}
 				}
 
@@ -215,6 +455,7 @@ impl AgentDoc {
 				CaptureMode::AfterAllSection => {
 					if line.starts_with("```lua") {
 						capture_mode = CaptureMode::AfterAllCodeBlock;
+						after_all_script.start(next_line_num);
 						continue;
 					}
 				}
@@ -223,7 +464,7 @@ impl AgentDoc {
 						capture_mode = CaptureMode::None;
 						continue;
 					} else {
-						push_line(&mut after_all_script, line);
+						after_all_script.push_line(line);
 					}
 				}
 			}
@@ -232,46 +473,16 @@ impl AgentDoc {
 		// -- We finilize the last part if it was not closed
 		finalize_current_prompt_part(&mut current_part, &mut prompt_parts);
 
-		// -- Returning the data
-
-		let options_toml = buffer_to_string(options_toml);
-
-		let agent_options_ov: Option<AgentOptions> = if let Some(options_toml) = options_toml {
-			Some(AgentOptions::from_options_value(parse_toml(&options_toml)?)?)
-		} else {
-			None
-		};
-
-		let agent_options = match agent_options_ov {
-			Some(agent_options_ov) => agent_options.merge(agent_options_ov)?,
-			None => agent_options,
-		};
-
-		// -- Get the model name
-		let model_name = agent_options.model().map(ModelName::from);
-
-		// -- Build the AgentInner
-		let agent_inner = AgentInner {
-			agent_options: Arc::new(agent_options),
-
-			name: name.to_string(),
-			agent_ref,
-
-			file_name: self.spath.name().to_string(),
-			file_path: self.spath.to_str().to_string(),
-
-			model_name,
-
-			before_all_script: buffer_to_string(before_all_script),
-			data_script: buffer_to_string(data_script),
-
+		AgentDocParts {
+			options_toml: options_toml.into_block(),
+			before_all_script: before_all_script.into_block(),
+			data_script: data_script.into_block(),
 			prompt_parts,
-
-			output_script: buffer_to_string(output_script),
-			after_all_script: buffer_to_string(after_all_script),
-		};
-
-		Ok(agent_inner)
+			tools: tools_raw.into_iter().map(CurrentTool::into_doc_tool).collect(),
+			output_schema_json: output_schema_json.into_block(),
+			output_script: output_script.into_block(),
+			after_all_script: after_all_script.into_block(),
+		}
 	}
 }
 
@@ -299,34 +510,103 @@ fn get_prompt_part_kind(header: &str) -> Option<PartKind> {
 	}
 }
 
-/// Type of the function below and the `into_agent_inner` lexer
-struct CurrentPromptPart<'a>(PartKind, Vec<&'a str>);
+/// Type of the function below and the `parse_parts` lexer (kind, start line, lines)
+struct CurrentPromptPart<'a>(PartKind, usize, Vec<&'a str>);
 
 /// Finalize a eventual current_part
-fn finalize_current_prompt_part(current_part: &mut Option<CurrentPromptPart<'_>>, prompt_parts: &mut Vec<PromptPart>) {
+fn finalize_current_prompt_part(
+	current_part: &mut Option<CurrentPromptPart<'_>>,
+	prompt_parts: &mut Vec<(PartKind, DocBlock)>,
+) {
 	if let Some(current_part) = current_part.take() {
 		// to have the last line
-		let kind = current_part.0;
-		let mut content = current_part.1;
+		let CurrentPromptPart(kind, start_line, mut content) = current_part;
 		content.push("");
 		let content = content.join("\n");
 
-		let part = PromptPart { kind, content };
-		prompt_parts.push(part);
+		prompt_parts.push((kind, DocBlock { content, start_line }));
 	}
 }
 
-/// Push a new line and the a \n to respect the new line
-fn push_line<'a, 'b, 'c: 'b>(content: &'a mut Vec<&'b str>, line: &'c str) {
-	content.push(line);
-	content.push("\n");
+/// The buffer of a code block of the `parse_parts` lexer
+#[derive(Default)]
+struct BlockBuffer<'a> {
+	start_line: usize,
+	/// The number of lines pushed (including the empty lines between the code blocks)
+	line_count: usize,
+	content: Vec<&'a str>,
 }
 
-fn buffer_to_string(content: Vec<&str>) -> Option<String> {
-	if content.is_empty() {
-		None
-	} else {
-		Some(content.join(""))
+impl<'a> BlockBuffer<'a> {
+	/// Mark the start of a code block
+	///
+	/// Note: When there are more code blocks (e.g., the same section twice, or a tool with two `lua` blocks),
+	///       they are joined, with an empty line for each line between them,
+	///       so that the script lines keep the same offset as in the agent file.
+	fn start(&mut self, start_line: usize) {
+		if self.content.is_empty() {
+			self.start_line = start_line;
+			return;
+		}
+		let gap = start_line.saturating_sub(self.start_line + self.line_count);
+		for _ in 0..gap {
+			self.push_line("");
+		}
+	}
+
+	/// Push a new line and the a \n to respect the new line
+	fn push_line(&mut self, line: &'a str) {
+		self.content.push(line);
+		self.content.push("\n");
+		self.line_count += 1;
+	}
+
+	fn into_block(self) -> Option<DocBlock> {
+		if self.content.is_empty() {
+			None
+		} else {
+			Some(DocBlock {
+				content: self.content.join(""),
+				start_line: self.start_line,
+			})
+		}
+	}
+}
+
+/// The raw tool captured in the `# Tools` section (and the `parse_parts` lexer)
+struct CurrentTool<'a> {
+	name: &'a str,
+	line: usize,
+	description: Vec<&'a str>,
+	schema: BlockBuffer<'a>,
+	script: BlockBuffer<'a>,
+}
+
+impl<'a> CurrentTool<'a> {
+	fn new(name: &'a str, line: usize) -> Self {
+		CurrentTool {
+			name,
+			line,
+			description: Vec::new(),
+			schema: BlockBuffer::default(),
+			script: BlockBuffer::default(),
+		}
+	}
+
+	fn into_doc_tool(self) -> DocTool {
+		let description = if self.description.is_empty() {
+			None
+		} else {
+			Some(self.description.join(" "))
+		};
+
+		DocTool {
+			name: self.name.to_string(),
+			line: self.line,
+			description,
+			schema: self.schema.into_block(),
+			script: self.script.into_block(),
+		}
 	}
 }
 
@@ -23,6 +23,37 @@ pub struct AgentOptions {
 	// Runtime settings
 	input_concurrency: Option<usize>,
 
+	/// When true, the AI response is streamed (chunks published to the hub as they arrive)
+	stream: Option<bool>,
+
+	/// The retry policy when the AI call fails with a transient error (e.g., 429, 5xx)
+	retry: Option<RetryOptions>,
+
+	/// What to do when an input fails ("abort" by default, or "continue")
+	on_error: Option<OnError>,
+
+	/// The AI response cache (in `.aipack/.cache/`), off by default
+	cache: Option<CacheOptions>,
+
+	/// Max number of re-asks when the AI response does not validate against the `# Output Schema` (default 1)
+	output_schema_max_repairs: Option<u32>,
+
+	/// Max number of AI round trips calling the `# Tools` for one input (default 10)
+	tools_max_iterations: Option<u32>,
+
+	/// The conversation session name (in `.aipack/sessions/`). When set, the history is replayed and appended.
+	/// Note: The inputs share the session, so they run one at a time, in order (`input_concurrency` is ignored).
+	session: Option<String>,
+
+	/// The maximum cost in USD of a run (inputs exceeding it are skipped)
+	max_cost_usd: Option<f64>,
+
+	/// The maximum total tokens (prompt + completion) of a run (inputs exceeding it are skipped)
+	max_tokens_total: Option<u64>,
+
+	/// The limits of each Lua stage evaluation (timeout, memory, instructions), none by default
+	lua_limits: Option<LuaLimitsOptions>,
+
 	model_aliases: Option<ModelAliases>,
 }
 
@@ -105,6 +136,380 @@ impl mlua::IntoLua for &ModelAliases {
 
 // endregion: --- ModelAliases
 
+// region:    --- OnError
+
+/// The behavior of the run when one of the inputs fails.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr, strum::EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum OnError {
+	/// Stop the run on the first failed input (the remaining inputs are dropped)
+	#[default]
+	Abort,
+	/// Capture the error of the failed input and continue with the other inputs
+	Continue,
+}
+
+// endregion: --- OnError
+
+// region:    --- CacheOptions
+
+/// The AI response cache options (e.g., `cache = { enabled = true, ttl_secs = 3600 }`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CacheOptions {
+	enabled: Option<bool>,
+
+	/// The time to live of a cached response, in seconds (no expiration if absent)
+	ttl_secs: Option<u64>,
+
+	/// The max size of the cache, in MB (oldest responses are evicted first)
+	max_size_mb: Option<u64>,
+}
+
+/// Getters
+impl CacheOptions {
+	pub fn enabled(&self) -> bool {
+		self.enabled.unwrap_or(false)
+	}
+
+	pub fn ttl_secs(&self) -> Option<u64> {
+		self.ttl_secs
+	}
+
+	pub fn max_size_mb(&self) -> Option<u64> {
+		self.max_size_mb
+	}
+}
+
+impl CacheOptions {
+	pub fn merge(self, cache_ov: Option<CacheOptions>) -> CacheOptions {
+		let Some(cache_ov) = cache_ov else {
+			return self;
+		};
+		CacheOptions {
+			enabled: cache_ov.enabled.or(self.enabled),
+			ttl_secs: cache_ov.ttl_secs.or(self.ttl_secs),
+			max_size_mb: cache_ov.max_size_mb.or(self.max_size_mb),
+		}
+	}
+
+	pub fn merge_new(&self, cache_ov: Option<CacheOptions>) -> CacheOptions {
+		self.clone().merge(cache_ov)
+	}
+}
+
+impl mlua::FromLua for CacheOptions {
+	fn from_lua(value: mlua::Value, _lua: &mlua::Lua) -> mlua::Result<Self> {
+		let mlua::Value::Table(table) = value else {
+			return Err(mlua::Error::runtime(format!(
+				"cache invalid.\n    Cause: for agent options must be of type table (e.g., {{ enabled = true }}), but was {value:?}"
+			)));
+		};
+
+		Ok(CacheOptions {
+			enabled: table.get("enabled")?,
+			ttl_secs: table.get("ttl_secs")?,
+			max_size_mb: table.get("max_size_mb")?,
+		})
+	}
+}
+
+impl mlua::IntoLua for &CacheOptions {
+	fn into_lua(self, lua: &mlua::Lua) -> mlua::Result<mlua::Value> {
+		let table = lua.create_table()?;
+		table.set("enabled", self.enabled)?;
+		table.set("ttl_secs", self.ttl_secs)?;
+		table.set("max_size_mb", self.max_size_mb)?;
+		Ok(mlua::Value::Table(table))
+	}
+}
+
+// endregion: --- CacheOptions
+
+// region:    --- RetryOptions
+
+/// The retry policy for the AI calls (e.g., `retry = { max_attempts = 3, base_delay_ms = 1000 }`)
+///
+/// Note: All properties are optional, and the defaults are applied by the runner.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RetryOptions {
+	/// The total number of attempts, including the first one (1 means no retry)
+	max_attempts: Option<u32>,
+
+	/// The base delay, in milliseconds, which gets doubled on each retry
+	base_delay_ms: Option<u64>,
+
+	/// When true, a random part is applied to the delay so that concurrent inputs do not retry at the same time
+	jitter: Option<bool>,
+
+	/// The error classes that are retryable
+	retry_on: Option<Vec<RetryErrorKind>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, strum::AsRefStr, strum::EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum RetryErrorKind {
+	/// HTTP 429 (Too Many Requests)
+	RateLimit,
+	/// HTTP 5xx
+	Server,
+	/// Connection, timeout, and other transport errors
+	Network,
+}
+
+/// Getters
+impl RetryOptions {
+	pub fn max_attempts(&self) -> Option<u32> {
+		self.max_attempts
+	}
+
+	pub fn base_delay_ms(&self) -> Option<u64> {
+		self.base_delay_ms
+	}
+
+	pub fn jitter(&self) -> Option<bool> {
+		self.jitter
+	}
+
+	pub fn retry_on(&self) -> Option<&[RetryErrorKind]> {
+		self.retry_on.as_deref()
+	}
+}
+
+impl RetryOptions {
+	pub fn merge(self, retry_ov: Option<RetryOptions>) -> RetryOptions {
+		let Some(retry_ov) = retry_ov else {
+			return self;
+		};
+		RetryOptions {
+			max_attempts: retry_ov.max_attempts.or(self.max_attempts),
+			base_delay_ms: retry_ov.base_delay_ms.or(self.base_delay_ms),
+			jitter: retry_ov.jitter.or(self.jitter),
+			retry_on: retry_ov.retry_on.or(self.retry_on),
+		}
+	}
+
+	pub fn merge_new(&self, retry_ov: Option<RetryOptions>) -> RetryOptions {
+		self.clone().merge(retry_ov)
+	}
+}
+
+impl mlua::FromLua for RetryOptions {
+	fn from_lua(value: mlua::Value, _lua: &mlua::Lua) -> mlua::Result<Self> {
+		let mlua::Value::Table(table) = value else {
+			return Err(mlua::Error::runtime(format!(
+				"retry invalid.\n    Cause: for agent options must be of type table (e.g., {{ max_attempts = 3 }}), but was {value:?}"
+			)));
+		};
+
+		let retry_on = table
+			.get::<Option<Vec<String>>>("retry_on")?
+			.map(|kinds| {
+				kinds
+					.iter()
+					.map(|kind| {
+						kind.parse::<RetryErrorKind>().map_err(|_| {
+							mlua::Error::runtime(format!(
+								"retry.retry_on value '{kind}' is invalid. Should be 'rate_limit', 'server', or 'network'"
+							))
+						})
+					})
+					.collect::<mlua::Result<Vec<_>>>()
+			})
+			.transpose()?;
+
+		Ok(RetryOptions {
+			max_attempts: table.get("max_attempts")?,
+			base_delay_ms: table.get("base_delay_ms")?,
+			jitter: table.get("jitter")?,
+			retry_on,
+		})
+	}
+}
+
+impl mlua::IntoLua for &RetryOptions {
+	fn into_lua(self, lua: &mlua::Lua) -> mlua::Result<mlua::Value> {
+		let table = lua.create_table()?;
+		table.set("max_attempts", self.max_attempts)?;
+		table.set("base_delay_ms", self.base_delay_ms)?;
+		table.set("jitter", self.jitter)?;
+		if let Some(retry_on) = self.retry_on.as_ref() {
+			let retry_on: Vec<&str> = retry_on.iter().map(|k| k.as_ref()).collect();
+			table.set("retry_on", retry_on)?;
+		}
+		Ok(mlua::Value::Table(table))
+	}
+}
+
+// endregion: --- RetryOptions
+
+// region:    --- LuaLimitsOptions
+
+/// The limits of one Lua stage evaluation (e.g., `{ timeout_ms = 5000, memory_mb = 256 }`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LuaLimits {
+	/// The max wall-clock time of the stage script, in milliseconds
+	timeout_ms: Option<u64>,
+
+	/// The max memory the stage script can allocate, in MB
+	memory_mb: Option<u64>,
+
+	/// The max number of Lua VM instructions of the stage script
+	max_instructions: Option<u64>,
+}
+
+/// The `lua_limits` option, applied to each stage, with optional overrides per stage
+/// (e.g., `lua_limits = { timeout_ms = 5000, data = { timeout_ms = 30000 } }`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LuaLimitsOptions {
+	#[serde(flatten)]
+	limits: LuaLimits,
+
+	before_all: Option<LuaLimits>,
+	data: Option<LuaLimits>,
+	output: Option<LuaLimits>,
+	after_all: Option<LuaLimits>,
+	/// The `# Tools` scripts (each tool call)
+	tools: Option<LuaLimits>,
+}
+
+/// Getters
+impl LuaLimits {
+	pub fn timeout_ms(&self) -> Option<u64> {
+		self.timeout_ms
+	}
+
+	pub fn memory_mb(&self) -> Option<u64> {
+		self.memory_mb
+	}
+
+	pub fn max_instructions(&self) -> Option<u64> {
+		self.max_instructions
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.timeout_ms.is_none() && self.memory_mb.is_none() && self.max_instructions.is_none()
+	}
+}
+
+impl LuaLimits {
+	pub fn merge(self, limits_ov: Option<LuaLimits>) -> LuaLimits {
+		let Some(limits_ov) = limits_ov else {
+			return self;
+		};
+		LuaLimits {
+			timeout_ms: limits_ov.timeout_ms.or(self.timeout_ms),
+			memory_mb: limits_ov.memory_mb.or(self.memory_mb),
+			max_instructions: limits_ov.max_instructions.or(self.max_instructions),
+		}
+	}
+}
+
+impl LuaLimitsOptions {
+	/// The limits of this stage (`before_all`, `data`, `output`, `after_all`, or `tools`), over the common ones
+	pub fn for_stage(&self, stage: &str) -> LuaLimits {
+		let stage_limits = match stage {
+			"before_all" => self.before_all,
+			"data" => self.data,
+			"output" => self.output,
+			"after_all" => self.after_all,
+			"tools" => self.tools,
+			_ => None,
+		};
+		self.limits.merge(stage_limits)
+	}
+
+	pub fn merge(self, limits_ov: Option<LuaLimitsOptions>) -> LuaLimitsOptions {
+		let Some(limits_ov) = limits_ov else {
+			return self;
+		};
+		let merge_stage = |base: Option<LuaLimits>, ov: Option<LuaLimits>| match base {
+			Some(base) => Some(base.merge(ov)),
+			None => ov,
+		};
+		LuaLimitsOptions {
+			limits: self.limits.merge(Some(limits_ov.limits)),
+			before_all: merge_stage(self.before_all, limits_ov.before_all),
+			data: merge_stage(self.data, limits_ov.data),
+			output: merge_stage(self.output, limits_ov.output),
+			after_all: merge_stage(self.after_all, limits_ov.after_all),
+			tools: merge_stage(self.tools, limits_ov.tools),
+		}
+	}
+
+	pub fn merge_new(&self, limits_ov: Option<LuaLimitsOptions>) -> LuaLimitsOptions {
+		self.clone().merge(limits_ov)
+	}
+}
+
+impl mlua::FromLua for LuaLimits {
+	fn from_lua(value: mlua::Value, _lua: &mlua::Lua) -> mlua::Result<Self> {
+		let mlua::Value::Table(table) = value else {
+			return Err(mlua::Error::runtime(format!(
+				"lua_limits invalid.\n    Cause: must be of type table (e.g., {{ timeout_ms = 5000 }}), but was {value:?}"
+			)));
+		};
+
+		Ok(LuaLimits {
+			timeout_ms: table.get("timeout_ms")?,
+			memory_mb: table.get("memory_mb")?,
+			max_instructions: table.get("max_instructions")?,
+		})
+	}
+}
+
+impl mlua::FromLua for LuaLimitsOptions {
+	fn from_lua(value: mlua::Value, lua: &mlua::Lua) -> mlua::Result<Self> {
+		let limits = LuaLimits::from_lua(value.clone(), lua)?;
+		let mlua::Value::Table(table) = value else {
+			return Ok(LuaLimitsOptions::default());
+		};
+		let stage = |name: &str| -> mlua::Result<Option<LuaLimits>> {
+			table
+				.get::<Option<mlua::Value>>(name)?
+				.map(|v| LuaLimits::from_lua(v, lua))
+				.transpose()
+		};
+
+		Ok(LuaLimitsOptions {
+			limits,
+			before_all: stage("before_all")?,
+			data: stage("data")?,
+			output: stage("output")?,
+			after_all: stage("after_all")?,
+			tools: stage("tools")?,
+		})
+	}
+}
+
+impl mlua::IntoLua for &LuaLimits {
+	fn into_lua(self, lua: &mlua::Lua) -> mlua::Result<mlua::Value> {
+		let table = lua.create_table()?;
+		table.set("timeout_ms", self.timeout_ms)?;
+		table.set("memory_mb", self.memory_mb)?;
+		table.set("max_instructions", self.max_instructions)?;
+		Ok(mlua::Value::Table(table))
+	}
+}
+
+impl mlua::IntoLua for &LuaLimitsOptions {
+	fn into_lua(self, lua: &mlua::Lua) -> mlua::Result<mlua::Value> {
+		let table = lua.create_table()?;
+		table.set("timeout_ms", self.limits.timeout_ms)?;
+		table.set("memory_mb", self.limits.memory_mb)?;
+		table.set("max_instructions", self.limits.max_instructions)?;
+		table.set("before_all", self.before_all.as_ref())?;
+		table.set("data", self.data.as_ref())?;
+		table.set("output", self.output.as_ref())?;
+		table.set("after_all", self.after_all.as_ref())?;
+		table.set("tools", self.tools.as_ref())?;
+		Ok(mlua::Value::Table(table))
+	}
+}
+
+// endregion: --- LuaLimitsOptions
+
 // Getters
 impl AgentOptions {
 	/// Returns the raw model name from this options given in the config/options
@@ -131,6 +536,54 @@ impl AgentOptions {
 		self.temperature
 	}
 
+	pub fn stream(&self) -> Option<bool> {
+		self.stream
+	}
+
+	pub fn retry(&self) -> Option<&RetryOptions> {
+		self.retry.as_ref()
+	}
+
+	pub fn on_error(&self) -> Option<OnError> {
+		self.on_error
+	}
+
+	pub fn cache(&self) -> Option<&CacheOptions> {
+		self.cache.as_ref()
+	}
+
+	pub fn output_schema_max_repairs(&self) -> Option<u32> {
+		self.output_schema_max_repairs
+	}
+
+	pub fn tools_max_iterations(&self) -> Option<u32> {
+		self.tools_max_iterations
+	}
+
+	pub fn session(&self) -> Option<&str> {
+		self.session.as_deref()
+	}
+
+	pub fn max_cost_usd(&self) -> Option<f64> {
+		self.max_cost_usd
+	}
+
+	pub fn max_tokens_total(&self) -> Option<u64> {
+		self.max_tokens_total
+	}
+
+	pub fn lua_limits(&self) -> Option<&LuaLimitsOptions> {
+		self.lua_limits.as_ref()
+	}
+
+	/// The keys supported in the `# Options` section (and `[default_options]` of the config.toml)
+	pub fn known_keys() -> Vec<String> {
+		match serde_json::to_value(AgentOptions::default()) {
+			Ok(Value::Object(map)) => map.into_iter().map(|(key, _)| key).collect(),
+			_ => Vec::new(),
+		}
+	}
+
 	#[allow(unused)]
 	fn get_model_for_alias(&self, alias: &str) -> Option<&str> {
 		self.model_aliases
@@ -171,11 +624,36 @@ impl AgentOptions {
 			None => options_ov.model_aliases,
 		};
 
+		let retry = match self.retry {
+			Some(retry) => Some(retry.merge(options_ov.retry)),
+			None => options_ov.retry,
+		};
+
+		let cache = match self.cache {
+			Some(cache) => Some(cache.merge(options_ov.cache)),
+			None => options_ov.cache,
+		};
+
+		let lua_limits = match self.lua_limits {
+			Some(lua_limits) => Some(lua_limits.merge(options_ov.lua_limits)),
+			None => options_ov.lua_limits,
+		};
+
 		Ok(AgentOptions {
 			legacy: options_ov.legacy, // only take the value of the legacy
 			model: options_ov.model.or(self.model),
 			temperature: options_ov.temperature.or(self.temperature),
 			input_concurrency: options_ov.input_concurrency.or(self.input_concurrency),
+			stream: options_ov.stream.or(self.stream),
+			retry,
+			on_error: options_ov.on_error.or(self.on_error),
+			cache,
+			output_schema_max_repairs: options_ov.output_schema_max_repairs.or(self.output_schema_max_repairs),
+			tools_max_iterations: options_ov.tools_max_iterations.or(self.tools_max_iterations),
+			session: options_ov.session.or(self.session),
+			max_cost_usd: options_ov.max_cost_usd.or(self.max_cost_usd),
+			max_tokens_total: options_ov.max_tokens_total.or(self.max_tokens_total),
+			lua_limits,
 			model_aliases,
 		})
 	}
@@ -186,11 +664,36 @@ impl AgentOptions {
 			None => options_ov.model_aliases.clone(),
 		};
 
+		let retry = match &self.retry {
+			Some(retry) => Some(retry.merge_new(options_ov.retry)),
+			None => options_ov.retry,
+		};
+
+		let cache = match &self.cache {
+			Some(cache) => Some(cache.merge_new(options_ov.cache)),
+			None => options_ov.cache,
+		};
+
+		let lua_limits = match &self.lua_limits {
+			Some(lua_limits) => Some(lua_limits.merge_new(options_ov.lua_limits)),
+			None => options_ov.lua_limits,
+		};
+
 		Ok(AgentOptions {
 			legacy: options_ov.legacy, // only take the value of the legacy
 			model: options_ov.model.or(self.model.clone()),
 			temperature: options_ov.temperature.or(self.temperature),
 			input_concurrency: options_ov.input_concurrency.or(self.input_concurrency),
+			stream: options_ov.stream.or(self.stream),
+			retry,
+			on_error: options_ov.on_error.or(self.on_error),
+			cache,
+			output_schema_max_repairs: options_ov.output_schema_max_repairs.or(self.output_schema_max_repairs),
+			tools_max_iterations: options_ov.tools_max_iterations.or(self.tools_max_iterations),
+			session: options_ov.session.or(self.session.clone()),
+			max_cost_usd: options_ov.max_cost_usd.or(self.max_cost_usd),
+			max_tokens_total: options_ov.max_tokens_total.or(self.max_tokens_total),
+			lua_limits,
 			model_aliases,
 		})
 	}
@@ -205,6 +708,16 @@ impl mlua::IntoLua for &AgentOptions {
 		table.set("resolved_model", self.resolve_model())?;
 		table.set("temperature", self.temperature)?;
 		table.set("input_concurrency", self.input_concurrency)?;
+		table.set("stream", self.stream)?;
+		table.set("retry", self.retry.as_ref())?;
+		table.set("on_error", self.on_error.as_ref().map(|v| v.as_ref()))?;
+		table.set("cache", self.cache.as_ref())?;
+		table.set("output_schema_max_repairs", self.output_schema_max_repairs)?;
+		table.set("tools_max_iterations", self.tools_max_iterations)?;
+		table.set("session", self.session.as_deref())?;
+		table.set("max_cost_usd", self.max_cost_usd)?;
+		table.set("max_tokens_total", self.max_tokens_total)?;
+		table.set("lua_limits", self.lua_limits.as_ref())?;
 
 		let model_aliases = self.model_aliases.as_ref();
 		table.set("model_aliases", model_aliases)?;
@@ -219,16 +732,52 @@ impl mlua::FromLua for AgentOptions {
 			let model = table.get::<Option<String>>("model")?;
 			let temperature = table.get::<Option<f64>>("temperature")?;
 			let input_concurrency = table.get::<Option<usize>>("input_concurrency")?;
+			let stream = table.get::<Option<bool>>("stream")?;
+			let output_schema_max_repairs = table.get::<Option<u32>>("output_schema_max_repairs")?;
+			let tools_max_iterations = table.get::<Option<u32>>("tools_max_iterations")?;
+			let session = table.get::<Option<String>>("session")?;
+			let max_cost_usd = table.get::<Option<f64>>("max_cost_usd")?;
+			let max_tokens_total = table.get::<Option<u64>>("max_tokens_total")?;
 
 			// --
 			let model_aliases = table.get::<Option<mlua::Value>>("model_aliases")?;
 			let model_aliases = model_aliases.map(|v| ModelAliases::from_lua(v, lua)).transpose()?;
 
+			let retry = table.get::<Option<mlua::Value>>("retry")?;
+			let retry = retry.map(|v| RetryOptions::from_lua(v, lua)).transpose()?;
+
+			let on_error = table
+				.get::<Option<String>>("on_error")?
+				.map(|v| {
+					v.parse::<OnError>().map_err(|_| {
+						mlua::Error::runtime(format!(
+							"on_error value '{v}' is invalid. Should be 'abort' or 'continue'"
+						))
+					})
+				})
+				.transpose()?;
+
+			let cache = table.get::<Option<mlua::Value>>("cache")?;
+			let cache = cache.map(|v| CacheOptions::from_lua(v, lua)).transpose()?;
+
+			let lua_limits = table.get::<Option<mlua::Value>>("lua_limits")?;
+			let lua_limits = lua_limits.map(|v| LuaLimitsOptions::from_lua(v, lua)).transpose()?;
+
 			let options = AgentOptions {
 				legacy: false,
 				model,
 				temperature,
 				input_concurrency,
+				stream,
+				retry,
+				on_error,
+				cache,
+				output_schema_max_repairs,
+				tools_max_iterations,
+				session,
+				max_cost_usd,
+				max_tokens_total,
+				lua_limits,
 				model_aliases,
 			};
 
@@ -242,6 +791,7 @@ impl mlua::FromLua for AgentOptions {
 
 // region:    --- Parsing
 
+#[allow(clippy::large_enum_variant)]
 enum OptionsParsing {
 	Parsed(AgentOptions),
 	Unparsed(Value),
@@ -299,6 +849,16 @@ To update your config.toml:
 			model,
 			temperature,
 			input_concurrency,
+			stream: None,
+			retry: None,
+			on_error: None,
+			cache: None,
+			output_schema_max_repairs: None,
+			tools_max_iterations: None,
+			session: None,
+			max_cost_usd: None,
+			max_tokens_total: None,
+			lua_limits: None,
 			model_aliases: None,
 		})
 	}
@@ -316,6 +876,16 @@ impl AgentOptions {
 			model: Some(model_name.into()),
 			temperature: None,
 			input_concurrency: None,
+			stream: None,
+			retry: None,
+			on_error: None,
+			cache: None,
+			output_schema_max_repairs: None,
+			tools_max_iterations: None,
+			session: None,
+			max_cost_usd: None,
+			max_tokens_total: None,
+			lua_limits: None,
 			model_aliases: None,
 		}
 	}
@@ -395,6 +965,7 @@ return {
 			r#"
 	model = "gpt-4o-mini"
 	temperature = 0.3
+	stream = true
 	model_aliases = { small = "flash-001" }		
 		"#,
 		)?;
@@ -407,6 +978,7 @@ return {
 		let options_table = options_lua.as_table().ok_or("Should be a table")?;
 		assert_eq!(&options_table.get::<String>("model")?, "gpt-4o-mini");
 		assert_eq!(options_table.get::<f64>("temperature")?, 0.3);
+		assert_eq!(options_table.get::<Option<bool>>("stream")?, Some(true));
 		let aliases_table = options_table.get::<mlua::Value>("model_aliases")?;
 		let aliases_table = aliases_table.as_table().ok_or("model_aliases should be table")?;
 		assert_eq!(&aliases_table.get::<String>("small")?, "flash-001");
@@ -0,0 +1,36 @@
+use genai::chat::Tool;
+use serde_json::{Value, json};
+
+/// A tool from the agent `# Tools` section, which the AI can call.
+///
+/// Each tool is a `## tool_name` sub section with an optional description,
+/// an optional json block for the JSON Schema of the parameters, and the lua block executed on call.
+#[derive(Debug, Clone)]
+pub struct AgentTool {
+	pub name: String,
+	pub description: Option<String>,
+	/// The JSON Schema of the tool arguments
+	pub schema: Option<Value>,
+	/// The Lua script executed on a tool call (with the `args` in scope)
+	pub script: String,
+}
+
+// region:    --- Froms
+
+impl From<&AgentTool> for Tool {
+	fn from(agent_tool: &AgentTool) -> Self {
+		// Note: Some providers require the parameters, so, we default to an object without properties
+		let schema = agent_tool
+			.schema
+			.clone()
+			.unwrap_or_else(|| json!({"type": "object", "properties": {}}));
+
+		let tool = Tool::new(&agent_tool.name).with_schema(schema);
+		match agent_tool.description.as_ref() {
+			Some(description) => tool.with_description(description),
+			None => tool,
+		}
+	}
+}
+
+// endregion: --- Froms
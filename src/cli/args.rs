@@ -1,3 +1,4 @@
+use crate::agent::OnError;
 use crate::exec::ExecCommand;
 use clap::{Parser, Subcommand, command};
 
@@ -37,11 +38,47 @@ pub enum CliCommand {
 	/// List the available aipacks `aip run list` or `aip run list demo@`
 	List(ListArgs),
 
-	/// Pack a directory into a .aipack file
+	/// Pack a directory into a .aipack file `aip pack path/to/dir`, or check it `aip pack check path/to/dir`
 	Pack(PackArgs),
 
+	/// Generate an ed25519 key pair to sign the .aipack files `aip keygen path/to/key`
+	Keygen(KeygenArgs),
+
 	/// Install an aipack file
 	Install(InstallArgs),
+
+	/// Install exactly the packs listed in the workspace `.aipack/pack.lock` (same as `aip install --locked`)
+	Sync(SyncArgs),
+
+	/// Uninstall an installed pack `aip uninstall ns@name`
+	Uninstall(UninstallArgs),
+
+	/// List the installed packs with a newer version in the repo
+	Outdated,
+
+	/// Link a pack source dir as the installed location of its pack (for development) `aip link path/to/pack-dir`
+	Link(LinkArgs),
+
+	/// Remove the link of a linked pack `aip unlink ns@name`
+	Unlink(UnlinkArgs),
+
+	/// Show the information of a pack (metadata, source, size, agents) `aip info ns@name`
+	Info(InfoArgs),
+
+	/// Search the packs (custom, installed, and in the registries) by name and description `aip search term`
+	Search(SearchArgs),
+
+	/// Upgrade the installed packs to their latest version `aip upgrade [ns@name]`, or `aip upgrade ns@name --rollback`
+	Upgrade(UpgradeArgs),
+
+	/// Serve a directory of .aipack files as a pack registry `aip registry serve path/to/dir`
+	Registry(RegistryArgs),
+
+	/// Manage the conversation sessions of the workspace `aip session list|show|clear`
+	Session(SessionArgs),
+
+	/// Manage the run history of the workspace `aip runs list|show|replay`
+	Runs(RunsArgs),
 }
 
 /// Custom function
@@ -57,7 +94,19 @@ impl CliCommand {
 			// CliCommand::New(_) => false,
 			CliCommand::List(_) => false,
 			CliCommand::Pack(_) => false,
+			CliCommand::Keygen(_) => false,
 			CliCommand::Install(_) => false,
+			CliCommand::Sync(_) => false,
+			CliCommand::Uninstall(_) => false,
+			CliCommand::Outdated => false,
+			CliCommand::Link(_) => false,
+			CliCommand::Unlink(_) => false,
+			CliCommand::Info(_) => false,
+			CliCommand::Search(_) => false,
+			CliCommand::Upgrade(_) => false,
+			CliCommand::Registry(_) => false,
+			CliCommand::Session(_) => false,
+			CliCommand::Runs(_) => false,
 		}
 	}
 }
@@ -100,6 +149,39 @@ pub struct RunArgs {
 	#[arg(long = "dry", value_parser = ["req", "res"])]
 	pub dry_mode: Option<String>,
 
+	/// Stream the AI responses as they are generated (same as the agent option `stream = true`)
+	#[arg(long = "stream")]
+	pub stream: bool,
+
+	/// Do not use the AI response cache (even if enabled in the options)
+	#[arg(long = "no-cache")]
+	pub no_cache: bool,
+
+	/// What to do when an input fails, either 'abort' (default) or 'continue'
+	/// (same as the agent option `on_error`)
+	#[arg(long = "on-error")]
+	pub on_error: Option<OnError>,
+
+	/// The maximum cost in USD of the run (same as the agent option `max_cost_usd`)
+	#[arg(long = "max-cost-usd")]
+	pub max_cost_usd: Option<f64>,
+
+	/// The maximum total tokens of the run (same as the agent option `max_tokens_total`)
+	#[arg(long = "max-tokens-total")]
+	pub max_tokens_total: Option<u64>,
+
+	/// Write the run summary (usage, cost, timing) as json to this file (e.g., `--report run-report.json`)
+	#[arg(long = "report")]
+	pub report: Option<String>,
+
+	/// Do not record the run in the run history (`.aipack/runs/`)
+	#[arg(long = "no-history")]
+	pub no_history: bool,
+
+	/// Run the agent without the sandbox of its pack (the `[permissions]` of its `pack.toml` are not enforced)
+	#[arg(long = "allow-all")]
+	pub allow_all: bool,
+
 	/// Non-interactive mode (one-shot execution)
 	#[arg(long = "not-interactive", alias = "ni")]
 	pub not_interactive: bool,
@@ -107,14 +189,43 @@ pub struct RunArgs {
 
 /// Arguments for the `pack` subcommand
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct PackArgs {
+	#[command(subcommand)]
+	pub cmd: Option<PackCommand>,
+
 	/// The directory to pack into a .aipack file
-	pub dir_path: String,
+	#[arg(required = true)]
+	pub dir_path: Option<String>,
 
 	/// Optional destination directory for the .aipack file
 	/// If not provided, the .aipack file will be created in the current directory
 	#[arg(short = 'o', long = "output")]
 	pub output_dir: Option<String>,
+
+	/// Sign the .aipack file with this key file (as generated by `aip keygen`)
+	#[arg(long = "sign", value_name = "KEY_FILE")]
+	pub sign_key_file: Option<String>,
+
+	/// Only list the files that would be packed (see `[pack.files]` and `.aipackignore`), without packing
+	#[arg(long)]
+	pub list: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PackCommand {
+	/// Check the pack directory (agent files, Lua, handlebars, options) without packing it
+	Check {
+		/// The pack directory to check
+		dir_path: String,
+	},
+}
+
+/// Arguments for the `keygen` subcommand
+#[derive(Parser, Debug)]
+pub struct KeygenArgs {
+	/// The secret key file to create (the public key is written to `path.pub`)
+	pub key_path: String,
 }
 
 /// Arguments for the `install` subcommand
@@ -122,8 +233,81 @@ pub struct PackArgs {
 pub struct InstallArgs {
 	/// The path to the .aipack file to install
 	/// Can be the path to the `path/to/some-pack.aipack`
-	/// Or later, can be `namspace@pack_name` and in this case, it will look aipack.ai registry
-	pub aipack_ref: String,
+	/// Or `namespace@pack_name`, resolved with the `[install] registries` of the config.toml (aipack.ai registry by default)
+	///
+	/// With `--locked`, must be a `namespace@pack_name` of the pack.lock (all the locked packs when absent)
+	#[arg(required_unless_present = "locked")]
+	pub aipack_ref: Option<String>,
+
+	/// Install exactly the packs listed in `.aipack/pack.lock` (fails on checksum mismatch)
+	#[arg(long)]
+	pub locked: bool,
+
+	/// Install packs that are not signed, or signed by a key not in the `[install] trusted_keys`
+	#[arg(long)]
+	pub allow_unsigned: bool,
+}
+
+/// Arguments for the `sync` subcommand
+#[derive(Parser, Debug)]
+pub struct SyncArgs {
+	/// Install packs that are not signed, or signed by a key not in the `[install] trusted_keys`
+	#[arg(long)]
+	pub allow_unsigned: bool,
+}
+
+/// Arguments for the `uninstall` subcommand
+#[derive(Parser, Debug)]
+pub struct UninstallArgs {
+	/// The installed pack to uninstall, e.g., `demo@craft`
+	pub pack_identity: String,
+
+	/// Uninstall even if other installed packs depend on it
+	#[arg(long)]
+	pub force: bool,
+}
+
+/// Arguments for the `link` subcommand
+#[derive(Parser, Debug)]
+pub struct LinkArgs {
+	/// The pack source dir, with its `pack.toml`, e.g., `path/to/my-pack`
+	pub dir: String,
+}
+
+/// Arguments for the `unlink` subcommand
+#[derive(Parser, Debug)]
+pub struct UnlinkArgs {
+	/// The linked pack, e.g., `demo@craft`, or its source dir
+	pub pack_ref: String,
+}
+
+/// Arguments for the `info` subcommand
+#[derive(Parser, Debug)]
+pub struct InfoArgs {
+	/// The custom or installed pack, e.g., `demo@craft`
+	pub pack_identity: String,
+}
+
+/// Arguments for the `search` subcommand
+#[derive(Parser, Debug)]
+pub struct SearchArgs {
+	/// The term to search (case insensitive) in the pack names and descriptions
+	pub term: String,
+}
+
+/// Arguments for the `upgrade` subcommand
+#[derive(Parser, Debug)]
+pub struct UpgradeArgs {
+	/// The installed pack to upgrade, e.g., `demo@craft` (all the installed packs when absent)
+	pub pack_identity: Option<String>,
+
+	/// Restore the version before the last upgrade of this pack
+	#[arg(long, requires = "pack_identity")]
+	pub rollback: bool,
+
+	/// Install packs that are not signed, or signed by a key not in the `[install] trusted_keys`
+	#[arg(long)]
+	pub allow_unsigned: bool,
 }
 
 /// Arguments for the `run` subcommand
@@ -152,6 +336,88 @@ pub struct NewArgs {
 	pub open: bool,
 }
 
+/// Arguments for the `registry` subcommand
+#[derive(Parser, Debug)]
+pub struct RegistryArgs {
+	#[command(subcommand)]
+	pub cmd: RegistryCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryCommand {
+	/// Serve the .aipack files of the directory (at any depth) over HTTP, with the generated `latest.toml` indexes
+	Serve {
+		/// The directory of the .aipack files
+		dir: String,
+
+		/// The address to listen on (e.g., `0.0.0.0:8787` to serve the other machines)
+		#[arg(long = "addr", default_value = "127.0.0.1:8787")]
+		addr: String,
+	},
+}
+
+/// Arguments for the `session` subcommand
+#[derive(Parser, Debug)]
+pub struct SessionArgs {
+	#[command(subcommand)]
+	pub cmd: SessionCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SessionCommand {
+	/// List the sessions of the workspace (in `.aipack/sessions/`)
+	List,
+
+	/// Show the messages of a session
+	Show {
+		/// The session name
+		name: String,
+	},
+
+	/// Delete a session
+	Clear {
+		/// The session name
+		name: String,
+	},
+}
+
+/// Arguments for the `runs` subcommand
+#[derive(Parser, Debug)]
+pub struct RunsArgs {
+	#[command(subcommand)]
+	pub cmd: RunsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RunsCommand {
+	/// List the runs of the workspace (in `.aipack/runs/`), the most recent first
+	List,
+
+	/// Show a run (agent, summary, and the inputs with their AI response and output)
+	Show {
+		/// The run id (from `aip runs list`)
+		id: String,
+	},
+
+	/// Re-execute the `# Output` stage of a run with the recorded AI responses (no new AI call)
+	Replay {
+		/// The run id (from `aip runs list`)
+		id: String,
+
+		/// Only replay the input at this index (0 based)
+		#[arg(long = "input")]
+		input: Option<usize>,
+
+		/// Dry mode, only 'res' (print the recorded AI responses without executing the `# Output` stage)
+		#[arg(long = "dry", value_parser = ["res"])]
+		dry_mode: Option<String>,
+
+		/// Replay without the sandbox of the agent pack (same as `aip run --allow-all`)
+		#[arg(long = "allow-all")]
+		allow_all: bool,
+	},
+}
+
 #[derive(Parser, Debug)]
 pub struct InitArgs {
 	/// The optional path of were to init the .aipack (relative to current directory)
@@ -172,7 +438,19 @@ impl From<CliCommand> for ExecCommand {
 			// CliCommand::New(new_args) => ExecCommand::NewCommandAgent(new_args),
 			CliCommand::List(list_args) => ExecCommand::List(list_args),
 			CliCommand::Pack(pack_args) => ExecCommand::Pack(pack_args),
+			CliCommand::Keygen(keygen_args) => ExecCommand::Keygen(keygen_args),
 			CliCommand::Install(install_args) => ExecCommand::Install(install_args),
+			CliCommand::Sync(sync_args) => ExecCommand::Sync(sync_args),
+			CliCommand::Uninstall(uninstall_args) => ExecCommand::Uninstall(uninstall_args),
+			CliCommand::Outdated => ExecCommand::Outdated,
+			CliCommand::Link(link_args) => ExecCommand::Link(link_args),
+			CliCommand::Unlink(unlink_args) => ExecCommand::Unlink(unlink_args),
+			CliCommand::Info(info_args) => ExecCommand::Info(info_args),
+			CliCommand::Search(search_args) => ExecCommand::Search(search_args),
+			CliCommand::Upgrade(upgrade_args) => ExecCommand::Upgrade(upgrade_args),
+			CliCommand::Registry(registry_args) => ExecCommand::Registry(registry_args),
+			CliCommand::Session(session_args) => ExecCommand::Session(session_args),
+			CliCommand::Runs(runs_args) => ExecCommand::Runs(runs_args),
 		}
 	}
 }
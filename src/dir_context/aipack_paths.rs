@@ -1,6 +1,9 @@
 use super::path_consts::PACK_INSTALLED;
-use super::path_consts::{AIPACK_BASE, AIPACK_DIR_NAME, CONFIG_FILE_NAME, PACK_CUSTOM};
-use crate::dir_context::path_consts::PACK_DOWNLOAD;
+use super::path_consts::{
+	AIPACK_BASE, AIPACK_DIR_NAME, CONFIG_FILE_NAME, PACK_CUSTOM, WKS_CACHE_DIR, WKS_PACK_LOCK_FILE_NAME, WKS_RUNS_DIR,
+	WKS_SESSIONS_DIR,
+};
+use crate::dir_context::path_consts::{PACK_BACKUP, PACK_DOWNLOAD, PACK_GIT_CACHE, PACK_LINKS_FILE};
 use crate::{Error, Result};
 use home::home_dir;
 use simple_fs::SPath;
@@ -93,6 +96,8 @@ impl AipackPaths {
 pub enum RepoKind {
 	WksCustom,
 	BaseCustom,
+	/// A source dir linked as the installed location of its pack (`aip link`)
+	BaseLinked,
 	BaseInstalled,
 }
 
@@ -101,6 +106,7 @@ impl RepoKind {
 		match self {
 			Self::WksCustom => "workspace custom - .aipack/pack/custom",
 			Self::BaseCustom => "base custom - ~/.aipack-base/pack/custom",
+			Self::BaseLinked => "base linked - ~/.aipack-base/pack/links.toml",
 			Self::BaseInstalled => "base installed - ~/.aipack-base/pack/installed",
 		}
 		.to_string()
@@ -139,7 +145,7 @@ impl AipackPaths {
 	// TOOD: PRobably to return paths of wks, and base
 	pub fn get_wks_config_toml_paths(&self) -> Result<Vec<SPath>> {
 		let wks_config_path = self.get_wks_config_toml_path()?;
-		let base_config_path = self.base_aipack_dir.join(CONFIG_FILE_NAME);
+		let base_config_path = self.get_base_config_toml_path()?;
 		Ok(vec![base_config_path, wks_config_path])
 	}
 
@@ -147,10 +153,40 @@ impl AipackPaths {
 		let dir = self.wks_aipack_dir.join(PACK_CUSTOM);
 		Ok(dir)
 	}
+
+	/// The `.aipack/.cache/` dir (might not exist)
+	pub fn get_wks_cache_dir(&self) -> Result<SPath> {
+		let dir = self.wks_aipack_dir.join(WKS_CACHE_DIR);
+		Ok(dir)
+	}
+
+	/// The `.aipack/sessions/` dir (might not exist)
+	pub fn get_wks_sessions_dir(&self) -> Result<SPath> {
+		let dir = self.wks_aipack_dir.join(WKS_SESSIONS_DIR);
+		Ok(dir)
+	}
+
+	/// The `.aipack/runs/` dir (might not exist)
+	pub fn get_wks_runs_dir(&self) -> Result<SPath> {
+		let dir = self.wks_aipack_dir.join(WKS_RUNS_DIR);
+		Ok(dir)
+	}
+
+	/// The `.aipack/pack.lock` file (might not exist)
+	pub fn get_wks_pack_lock_path(&self) -> Result<SPath> {
+		let path = self.wks_aipack_dir.join(WKS_PACK_LOCK_FILE_NAME);
+		Ok(path)
+	}
 	// endregion: --- Workspace Files & Dirs
 
 	// region:    --- Base Files & Dirs
 
+	/// The `~/.aipack-base/config.toml` file
+	pub fn get_base_config_toml_path(&self) -> Result<SPath> {
+		let path = self.base_aipack_dir.join(CONFIG_FILE_NAME);
+		Ok(path)
+	}
+
 	pub fn get_base_pack_custom_dir(&self) -> Result<SPath> {
 		let dir = self.base_aipack_dir.join(PACK_CUSTOM);
 		Ok(dir)
@@ -166,6 +202,23 @@ impl AipackPaths {
 		Ok(dir)
 	}
 
+	pub fn get_base_pack_backup_dir(&self) -> Result<SPath> {
+		let dir = self.base_aipack_dir.join(PACK_BACKUP);
+		Ok(dir)
+	}
+
+	/// The `~/.aipack-base/pack/.git-cache/` dir (might not exist)
+	pub fn get_base_pack_git_cache_dir(&self) -> Result<SPath> {
+		let dir = self.base_aipack_dir.join(PACK_GIT_CACHE);
+		Ok(dir)
+	}
+
+	/// The `~/.aipack-base/pack/links.toml` file of the linked packs (might not exist)
+	pub fn get_base_pack_links_path(&self) -> Result<SPath> {
+		let path = self.base_aipack_dir.join(PACK_LINKS_FILE);
+		Ok(path)
+	}
+
 	// endregion: --- Base Files & Dirs
 
 	/// Returns the list of pack dirs, in the order of precedence.
@@ -3,10 +3,12 @@
 mod aipack_paths;
 mod base;
 mod pack_dir;
+mod pack_links;
 mod path_consts;
 
 pub use aipack_paths::*;
 pub use base::*;
 pub use pack_dir::*;
+pub use pack_links::*;
 
 // endregion: --- Modules
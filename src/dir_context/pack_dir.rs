@@ -1,4 +1,4 @@
-use super::{DirContext, RepoKind};
+use super::{DirContext, PackLinks, RepoKind};
 use crate::support::files::list_dirs;
 use crate::support::paths;
 use crate::{Error, Result};
@@ -27,11 +27,15 @@ impl PackDir {
 
 impl PackDir {
 	pub fn pretty_path(&self) -> String {
+		// The linked source dir can be anywhere
+		if let RepoKind::BaseLinked = self.repo_kind {
+			return format!("{} (linked)", self.path);
+		}
 		let last_five = paths::path_last_components(&self.path, 5);
 		let prefix = match self.repo_kind {
 			RepoKind::WksCustom => "",
 			RepoKind::BaseCustom => "~/",
-			RepoKind::BaseInstalled => "~/",
+			RepoKind::BaseLinked | RepoKind::BaseInstalled => "~/",
 		};
 		format!("{}{}", prefix, last_five)
 	}
@@ -80,17 +84,31 @@ pub fn find_to_run_pack_dir(dir_context: &DirContext, ns: Option<&str>, pack_nam
 
 /// Get the matching pack_dir for this namespace and pack_name
 /// - if no namespace, then, will return all of the matching PackDir with this pack_name
-/// - if a namespace, will return only the first matching one (following the custom/installed preferences)
+/// - if a namespace, will return only the first matching one (following the custom/linked/installed preferences)
 pub fn find_pack_dirs(dir_context: &DirContext, ns: Option<&str>, pack_name: Option<&str>) -> Result<Vec<PackDir>> {
 	let aipack_paths = dir_context.aipack_paths();
 
 	let repo_dirs = aipack_paths.get_pack_repo_dirs()?;
+	let pack_links = PackLinks::load(aipack_paths)?;
+	let is_direct = ns.is_some() && pack_name.is_some();
 
 	let mut pack_dirs = Vec::new();
+	let mut links_pending = true;
 
 	for repo_dir in repo_dirs {
 		let repo_kind = repo_dir.kind;
 
+		// -- The linked packs, before the installed ones (they are the installed location of their pack)
+		if let RepoKind::BaseInstalled = repo_kind {
+			links_pending = false;
+			let linked_pack_dirs = pack_links.find_pack_dirs(ns, pack_name);
+			if is_direct && !linked_pack_dirs.is_empty() {
+				pack_dirs.extend(linked_pack_dirs);
+				break;
+			}
+			pack_dirs.extend(linked_pack_dirs);
+		}
+
 		match (ns, pack_name) {
 			(Some(ns_name), Some(pack_name)) => {
 				let ns_dirs = list_dirs(repo_dir.path(), 1, true);
@@ -129,6 +147,11 @@ pub fn find_pack_dirs(dir_context: &DirContext, ns: Option<&str>, pack_name: Opt
 		}
 	}
 
+	// When no installed dir yet (and not already a direct match)
+	if links_pending && (!is_direct || pack_dirs.is_empty()) {
+		pack_dirs.extend(pack_links.find_pack_dirs(ns, pack_name));
+	}
+
 	Ok(pack_dirs)
 }
 
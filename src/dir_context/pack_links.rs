@@ -0,0 +1,79 @@
+//! The linked packs of the `~/.aipack-base/pack/links.toml` (`aip link path/to/pack-dir`, `aip unlink ns@name`)
+//!
+//! A linked source dir is the installed location of the `namespace@name` of its `pack.toml`,
+//! so that the pack under development runs from its sources, without `aip pack` and `aip install` after each edit.
+//!
+//! The linked packs come after the custom packs, and before the installed ones.
+
+use super::{AipackPaths, PackDir, RepoKind};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use simple_fs::{SPath, ensure_file_dir};
+use std::collections::BTreeMap;
+use std::fs;
+
+const PACK_LINKS_HEADER: &str = "# This file is generated by `aip link` and `aip unlink`.\n\n";
+
+/// The `namespace@name` to source dir (absolute) of the linked packs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PackLinks {
+	#[serde(default)]
+	links: BTreeMap<String, String>,
+}
+
+/// Load & Save
+impl PackLinks {
+	/// Load the links.toml (empty if it does not exist)
+	pub fn load(aipack_paths: &AipackPaths) -> Result<Self> {
+		let path = aipack_paths.get_base_pack_links_path()?;
+		if !path.exists() {
+			return Ok(PackLinks::default());
+		}
+
+		let content = fs::read_to_string(&path)?;
+		toml::from_str(&content).map_err(|err| Error::cc(format!("Invalid links.toml at '{path}'"), err))
+	}
+
+	pub fn save(&self, aipack_paths: &AipackPaths) -> Result<()> {
+		let path = aipack_paths.get_base_pack_links_path()?;
+		ensure_file_dir(&path)?;
+		let content = toml::to_string(self).map_err(|err| Error::cc("Cannot serialize links.toml", err))?;
+		fs::write(&path, format!("{PACK_LINKS_HEADER}{content}"))?;
+		Ok(())
+	}
+}
+
+impl PackLinks {
+	/// The linked packs (identity, source dir), sorted by identity
+	pub fn links(&self) -> impl Iterator<Item = (&str, SPath)> {
+		self.links.iter().map(|(identity, dir)| (identity.as_str(), SPath::new(dir)))
+	}
+
+	/// Add or replace the link of this identity
+	pub fn insert(&mut self, identity: impl Into<String>, dir: &SPath) {
+		self.links.insert(identity.into(), dir.to_string());
+	}
+
+	/// Remove the link of this identity, and return its source dir
+	pub fn remove(&mut self, identity: &str) -> Option<SPath> {
+		self.links.remove(identity).map(SPath::from)
+	}
+
+	/// The pack dirs of the links matching the namespace and pack name (when given)
+	///
+	/// Note: The links whose source dir does not exist anymore are ignored.
+	pub(super) fn find_pack_dirs(&self, ns: Option<&str>, pack_name: Option<&str>) -> Vec<PackDir> {
+		self.links()
+			.filter_map(|(identity, dir)| {
+				let (link_ns, link_name) = identity.split_once('@')?;
+				let pass = ns.is_none_or(|ns| ns == link_ns) && pack_name.is_none_or(|name| name == link_name);
+				(pass && dir.is_dir()).then(|| PackDir {
+					repo_kind: RepoKind::BaseLinked,
+					namespace: link_ns.to_string(),
+					name: link_name.to_string(),
+					path: dir,
+				})
+			})
+			.collect()
+	}
+}
@@ -11,6 +11,18 @@ pub const AIPACK_DIR_NAME: &str = ".aipack";
 
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 
+// The cache dir of the workspace `.aipack/.cache/` (can be deleted at any time)
+pub const WKS_CACHE_DIR: &str = ".cache";
+
+// The conversation sessions dir of the workspace `.aipack/sessions/`
+pub const WKS_SESSIONS_DIR: &str = "sessions";
+
+// The run history dir of the workspace `.aipack/runs/`
+pub const WKS_RUNS_DIR: &str = "runs";
+
+// The lock file of the installed packs of the workspace `.aipack/pack.lock`
+pub const WKS_PACK_LOCK_FILE_NAME: &str = "pack.lock";
+
 // -- Common Path (for .aipack/ and ~/.aipack-base/)
 
 // TODO: probably need to add a common lua, or perhaps allow `require("jc@utils/lua/somefile")`
@@ -19,5 +31,11 @@ pub const CONFIG_FILE_NAME: &str = "config.toml";
 pub const PACK_CUSTOM: &str = "pack/custom";
 pub const PACK_INSTALLED: &str = "pack/installed";
 pub const PACK_DOWNLOAD: &str = "pack/.download";
+// The previous version of the upgraded packs (for `aip upgrade --rollback`)
+pub const PACK_BACKUP: &str = "pack/.backup";
+// The clones of the git repositories of the `git+...` packs (can be deleted at any time)
+pub const PACK_GIT_CACHE: &str = "pack/.git-cache";
+// The source dirs linked as installed packs (`aip link`)
+pub const PACK_LINKS_FILE: &str = "pack/links.toml";
 
 // -- New Agent Templates
@@ -1,5 +1,6 @@
 use derive_more::From;
 use derive_more::derive::Display;
+use std::sync::Arc;
 use tokio::runtime::TryCurrentError;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -17,6 +18,19 @@ pub enum Error {
 		agent_path: String,
 	},
 
+	#[display("Output Schema section is not valid json for agent path: {agent_path}\nCause: {cause}")]
+	OutputSchemaInvalid {
+		agent_path: String,
+		cause: String,
+	},
+
+	#[display("Tool '{tool_name}' is invalid for agent path: {agent_path}\nCause: {cause}")]
+	AgentToolInvalid {
+		agent_path: String,
+		tool_name: String,
+		cause: String,
+	},
+
 	// -- Config
 	#[display("Config invalid (config path: {path})\n  reason: {reason}")]
 	Config {
@@ -55,6 +69,62 @@ pub enum Error {
 		new_version: String,
 	},
 
+	#[display("Conflicting version requirements for pack {pack_identity}\n{requirements}")]
+	InstallDependencyConflict {
+		pack_identity: String,
+		requirements: String,
+	},
+
+	#[display("Cannot resolve dependency {pack_identity} ({version_req}) required by {required_by}\nCause: {cause}")]
+	InstallDependencyUnresolved {
+		pack_identity: String,
+		version_req: String,
+		required_by: String,
+		cause: String,
+	},
+
+	#[display(
+		"Checksum mismatch for pack {pack_identity} from '{source}'\n  pack.lock sha256: {expected}\n  file sha256:      {actual}"
+	)]
+	PackLockChecksumMismatch {
+		pack_identity: String,
+		source: String,
+		expected: String,
+		actual: String,
+	},
+
+	#[display(
+		"Pack {aipack_ref} is not signed.\n   Use '--allow-unsigned' to install it anyway (only if you trust its source)."
+	)]
+	PackUnsigned {
+		aipack_ref: String,
+	},
+
+	#[display(
+		"Pack {aipack_ref} is signed by an untrusted key '{public_key}'.\n   Add this key to the '[install] trusted_keys' of '~/.aipack-base/config.toml' to trust it, or use '--allow-unsigned'."
+	)]
+	PackSignatureUntrusted {
+		aipack_ref: String,
+		public_key: String,
+	},
+
+	#[display("Invalid signature for pack {aipack_ref} (the archive might have been tampered with)\nCause: {cause}")]
+	PackSignatureInvalid {
+		aipack_ref: String,
+		cause: String,
+	},
+
+	#[display(
+		"Upgrade of pack {pack_identity} failed (its installed version was kept){}\nCause: {cause}",
+		upgraded.iter().map(|change| format!("\n   Already upgraded: {change}")).collect::<String>()
+	)]
+	PackUpgradeFailed {
+		pack_identity: String,
+		/// The packs upgraded before this one (e.g., `ns@name 0.1.0 -> 0.2.0`)
+		upgraded: Vec<String>,
+		cause: Box<Error>,
+	},
+
 	#[display("Invalid prerelease format in version {version}. Prereleases must end with .number (e.g., -alpha.1)")]
 	InvalidPrereleaseFormat {
 		version: String,
@@ -65,6 +135,56 @@ pub enum Error {
 		cause: String,
 	},
 
+	#[display("AI response does not match the Output Schema after {attempts} attempt(s)\n{errors}")]
+	OutputSchemaValidationFail {
+		attempts: u32,
+		errors: String,
+	},
+
+	#[display("{failed_count} input(s) failed (with on_error = \"continue\")")]
+	RunInputsFailed {
+		failed_count: usize,
+	},
+
+	#[display("Run failed\nCause: {cause}")]
+	RunFailed {
+		cause: Arc<Error>,
+	},
+
+	#[display(
+		"AI still calling tools after the max of {max_iterations} tool iterations (see `tools_max_iterations` option)"
+	)]
+	ToolsMaxIterations {
+		max_iterations: u32,
+	},
+
+	#[display(
+		"Lua {limit} exceeded in the {stage} stage of agent '{agent_name}'{}\n   (see the `lua_limits` option)",
+		input_label.as_ref().map(|label| format!(" for input '{label}'")).unwrap_or_default()
+	)]
+	LuaLimitExceeded {
+		stage: String,
+		agent_name: String,
+		input_label: Option<String>,
+		limit: String,
+	},
+
+	// -- Sandbox
+	#[display(
+		"Permission denied for pack {pack_identity}: {action}\n   Add it to the [permissions] of its pack.toml, or use 'aip run --allow-all' to run it without restriction (only if you trust it)."
+	)]
+	PermissionDenied {
+		pack_identity: String,
+		action: String,
+	},
+
+	/// A Lua error caused by a `PermissionDenied` (e.g., of an `aip.*` function), with the Lua message (location, traceback)
+	#[display("{lua_msg}")]
+	LuaPermissionDenied {
+		lua_msg: String,
+		cause: Box<Error>,
+	},
+
 	// -- TokioSync
 	TokioTryCurrent(TryCurrentError),
 
@@ -248,6 +368,11 @@ impl Error {
 	pub fn cc(context: impl Into<String>, cause: impl std::fmt::Display) -> Self {
 		Self::CustomAndCause(context.into(), cause.to_string())
 	}
+
+	/// True if this error is (or was caused by) a `PermissionDenied` of the sandbox
+	pub fn is_permission_denied(&self) -> bool {
+		matches!(self, Error::PermissionDenied { .. } | Error::LuaPermissionDenied { .. })
+	}
 }
 
 impl From<&str> for Error {
@@ -260,6 +385,26 @@ impl From<&str> for Error {
 
 // region:    --- Error Boilerplate
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::LuaPermissionDenied { cause, .. } => Some(cause.as_ref()),
+			Error::RunFailed { cause } => Some(cause.as_ref()),
+			Error::PackUpgradeFailed { cause, .. } => Some(cause.as_ref()),
+			Error::TokioTryCurrent(err) => Some(err),
+			Error::Serde(err) => Some(err),
+			Error::Toml(err) => Some(err),
+			Error::JsonValueExt(err) => Some(err),
+			Error::Handlebars(err) => Some(err),
+			Error::GenAI(err) => Some(err),
+			Error::SimpleFs(err) => Some(err),
+			Error::Keyring(err) => Some(err),
+			Error::Clap(err) => Some(err),
+			Error::Reqwest(err) => Some(err),
+			Error::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
 
 // endregion: --- Error Boilerplate
@@ -2,7 +2,10 @@
 //! Note: For now, the content of the variant of the ExecCommand often contain the CliArgs,
 //!       but this will eventual change to have it's own
 
-use crate::cli::{InitArgs, InstallArgs, ListArgs, NewArgs, PackArgs, RunArgs};
+use crate::cli::{
+	InfoArgs, InitArgs, InstallArgs, KeygenArgs, LinkArgs, ListArgs, NewArgs, PackArgs, RegistryArgs, RunArgs,
+	RunsArgs, SearchArgs, SessionArgs, SyncArgs, UninstallArgs, UnlinkArgs, UpgradeArgs,
+};
 
 /// This is the Executor Command that needs to be performed
 /// NOTE: This is not the `ExecStateEvent` which is sent to the hub.
@@ -15,7 +18,21 @@ pub enum ExecCommand {
 	NewCommandAgent(NewArgs),
 	List(ListArgs),
 	Pack(PackArgs),
+	Keygen(KeygenArgs),
 	Install(InstallArgs),
+	Sync(SyncArgs),
+	Uninstall(UninstallArgs),
+	Outdated,
+	Link(LinkArgs),
+	Unlink(UnlinkArgs),
+	Info(InfoArgs),
+	Search(SearchArgs),
+	Upgrade(UpgradeArgs),
+	Registry(RegistryArgs),
+	Session(SessionArgs),
+	Runs(RunsArgs),
 	Redo,
+	/// Redo the last run with `--allow-all`
+	RedoAllowAll,
 	OpenAgent,
 }
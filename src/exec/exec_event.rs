@@ -1,6 +1,8 @@
 //! The executor event
 
+use crate::Error;
 use derive_more::derive::Display;
+use std::sync::Arc;
 
 /// This is the status event sent by the executor to the Hub.
 ///
@@ -15,6 +17,10 @@ pub enum ExecEvent {
 	/// Emitted at the start of the Run/Redo of agent
 	RunStart,
 
+	/// Emitted before the RunEnd when the Run/Redo of agent failed
+	/// (including when some inputs failed with on_error = "continue")
+	RunFailed(Arc<Error>),
+
 	/// Emitted at the end of the Run/Redo of agent
 	RunEnd,
 
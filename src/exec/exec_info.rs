@@ -0,0 +1,93 @@
+use crate::cli::InfoArgs;
+use crate::dir_context::{DirContext, RepoKind};
+use crate::hub::get_hub;
+use crate::packer::load_pack_info;
+use crate::{Error, Result};
+use size::Size;
+use time_tz::OffsetDateTimeExt;
+
+/// Executes the info command which shows the information of a custom or installed pack
+pub async fn exec_info(dir_context: DirContext, info_args: InfoArgs) -> Result<()> {
+	let pack_info = load_pack_info(&dir_context, &info_args.pack_identity)?;
+	for (pack_dir, cause) in pack_info.skipped_pack_dirs.iter() {
+		get_hub()
+			.publish(format!(
+				"WARNING - Skipping '{pack_dir}' (invalid pack.toml). Cause: {cause}"
+			))
+			.await;
+	}
+
+	let mut msg = format!("\n==== Pack {}\n\n", pack_info.pack_dir);
+	let mut push_line = |label: &str, value: &str| msg.push_str(&format!("{:>14} {value}\n", format!("{label}:")));
+
+	match pack_info.pack_toml.as_ref() {
+		Some(pack_toml) => {
+			push_line("Version", &pack_toml.version);
+			let metadata = [
+				("Description", &pack_toml.description),
+				("License", &pack_toml.license),
+				("Author", &pack_toml.author),
+				("Email", &pack_toml.email),
+				("Homepage", &pack_toml.homepage),
+				("Repo", &pack_toml.repo),
+			];
+			for (label, value) in metadata {
+				if let Some(value) = value {
+					push_line(label, value);
+				}
+			}
+			for (idx, dependency) in pack_toml.dependencies.iter().enumerate() {
+				let label = if idx == 0 { "Dependencies" } else { "" };
+				push_line(label, &format!("{} {}", dependency.identity, dependency.version_req));
+			}
+			let permission_lines = pack_toml.permissions.as_ref().map(|p| p.summary_lines()).unwrap_or_default();
+			// Note: The installed packs without [permissions] are sandboxed with none
+			let is_installed = matches!(pack_info.pack_dir.repo_kind, RepoKind::BaseInstalled);
+			if pack_toml.permissions.is_none() && !is_installed {
+				push_line("Permissions", "(no [permissions], not sandboxed)");
+			} else if permission_lines.is_empty() {
+				push_line("Permissions", "(none)");
+			}
+			for (idx, line) in permission_lines.iter().enumerate() {
+				let label = if idx == 0 { "Permissions" } else { "" };
+				push_line(label, line);
+			}
+		}
+		None => push_line("Version", "(no pack.toml)"),
+	}
+
+	push_line("Location", &pack_info.pack_dir.pretty_path());
+	if let Some(source) = pack_info.source.as_deref() {
+		push_line("Source", source);
+	}
+	if let Some(installed_at) = pack_info.installed_at {
+		// Note: Same machine, so the local time when available
+		let installed_at = match time_tz::system::get_timezone() {
+			Ok(local) => installed_at.to_timezone(local),
+			Err(_) => installed_at,
+		};
+		let format = time::format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]")
+			.map_err(|err| Error::cc("Invalid install date format", err))?;
+		let installed_at = installed_at
+			.format(&format)
+			.map_err(|err| Error::cc("Cannot format the install date", err))?;
+		push_line("Installed", &installed_at);
+	}
+	push_line("Size", &Size::from_bytes(pack_info.size as u64).to_string());
+
+	if pack_info.agents.is_empty() {
+		push_line("Agents", "(none)");
+	} else {
+		msg.push_str(&format!("\n{:>14}\n", "Agents:"));
+		for agent in pack_info.agents.iter() {
+			match agent.description.as_deref() {
+				Some(description) => msg.push_str(&format!("\n  {}\n      {description}\n", agent.name)),
+				None => msg.push_str(&format!("\n  {}\n", agent.name)),
+			}
+		}
+	}
+
+	get_hub().publish(msg).await;
+
+	Ok(())
+}
@@ -1,8 +1,8 @@
 use crate::Result;
-use crate::cli::InstallArgs;
+use crate::cli::{InstallArgs, SyncArgs};
 use crate::dir_context::DirContext;
 use crate::hub::get_hub;
-use crate::packer::install_pack;
+use crate::packer::{InstallOptions, install_locked_packs, install_pack};
 use size::Size;
 
 // region:    --- InstallRef
@@ -11,14 +11,23 @@ use size::Size;
 
 /// Executes the install command which installs an aipack file
 pub async fn exec_install(dir_context: DirContext, install_args: InstallArgs) -> Result<()> {
+	if install_args.locked {
+		let install_options = InstallOptions {
+			allow_unsigned: install_args.allow_unsigned,
+		};
+		return exec_install_locked(dir_context, install_args.aipack_ref.as_deref(), &install_options).await;
+	}
+
 	let hub = get_hub();
-	hub.publish(format!(
-		"\n==== Installing aipack:\n\n{:>15} {}",
-		"From:", install_args.aipack_ref
-	))
-	.await;
+	// Note: Clap requires the aipack_ref when not `--locked`
+	let aipack_ref = install_args.aipack_ref.unwrap_or_default();
+	hub.publish(format!("\n==== Installing aipack:\n\n{:>15} {}", "From:", aipack_ref))
+		.await;
 
-	let installed_pack = install_pack(&dir_context, &install_args.aipack_ref).await?;
+	let install_options = InstallOptions {
+		allow_unsigned: install_args.allow_unsigned,
+	};
+	let installed_pack = install_pack(&dir_context, &aipack_ref, &install_options).await?;
 
 	// Format the zip size using the size crate
 	let formatted_zip_size = Size::from_bytes(installed_pack.zip_size as u64).to_string();
@@ -40,7 +49,75 @@ pub async fn exec_install(dir_context: DirContext, install_args: InstallArgs) ->
 	))
 	.await;
 
+	if !installed_pack.dependencies.is_empty() {
+		let mut msg = format!("{:>15}", "Dependencies:");
+		for dependency in installed_pack.dependencies.iter() {
+			msg.push_str(&format!(
+				" {}@{} v{}\n{:>15}",
+				dependency.pack_toml.namespace, dependency.pack_toml.name, dependency.pack_toml.version, ""
+			));
+		}
+		hub.publish(msg.trim_end().to_string()).await;
+	}
+
+	// The agents of the installed packs are sandboxed by these permissions (see `aip run --allow-all`)
+	let permission_lines = (installed_pack.pack_toml.permissions.as_ref())
+		.map(|permissions| permissions.summary_lines())
+		.unwrap_or_default();
+	let mut msg = format!("{:>15}", "Permissions:");
+	if permission_lines.is_empty() {
+		msg.push_str(" (none, no file writes, commands, nor network)");
+	}
+	for line in permission_lines.iter() {
+		msg.push_str(&format!(" {line}\n{:>15}", ""));
+	}
+	hub.publish(msg.trim_end().to_string()).await;
+
 	hub.publish("\n==== DONE".to_string()).await;
 
 	Ok(())
 }
+
+/// Executes the sync command which installs the packs of the pack.lock
+pub async fn exec_sync(dir_context: DirContext, sync_args: SyncArgs) -> Result<()> {
+	let install_options = InstallOptions {
+		allow_unsigned: sync_args.allow_unsigned,
+	};
+	exec_install_locked(dir_context, None, &install_options).await
+}
+
+async fn exec_install_locked(
+	dir_context: DirContext,
+	pack_identity: Option<&str>,
+	install_options: &InstallOptions,
+) -> Result<()> {
+	let hub = get_hub();
+	hub.publish(format!(
+		"\n==== Installing aipacks from pack.lock{}:\n",
+		pack_identity.map(|p| format!(" ({p})")).unwrap_or_default()
+	))
+	.await;
+
+	let synced_packs = install_locked_packs(&dir_context, pack_identity, install_options).await?;
+
+	let mut msg = String::new();
+	for synced_pack in synced_packs.iter() {
+		let status = match synced_pack.installed.as_ref() {
+			Some(installed_pack) => format!("installed at {}", installed_pack.path),
+			None => "already installed".to_string(),
+		};
+		msg.push_str(&format!(
+			"{:>30} v{:<12} {status}\n",
+			synced_pack.locked_pack.identity(),
+			synced_pack.locked_pack.version
+		));
+	}
+	if synced_packs.is_empty() {
+		msg.push_str("No packs in pack.lock\n");
+	}
+	hub.publish(msg).await;
+
+	hub.publish("==== DONE".to_string()).await;
+
+	Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::Result;
+use crate::cli::{LinkArgs, UnlinkArgs};
+use crate::dir_context::DirContext;
+use crate::hub::get_hub;
+use crate::packer::{link_pack, unlink_pack};
+
+/// Executes the link command which links a pack source dir as the installed location of its pack
+pub async fn exec_link(dir_context: DirContext, link_args: LinkArgs) -> Result<()> {
+	let hub = get_hub();
+
+	let linked_pack = link_pack(&dir_context, &link_args.dir)?;
+
+	hub.publish(format!(
+		"\n==== Linked aipack:\n\n{:>15} {}\n{:>15} {}\n{:>15} {}",
+		"Pack:",
+		linked_pack.identity,
+		"Version:",
+		linked_pack.version.as_deref().unwrap_or_default(),
+		"Source dir:",
+		linked_pack.dir
+	))
+	.await;
+
+	hub.publish(format!(
+		"\nThe agents of {} now run from this source dir (until 'aip unlink {}')",
+		linked_pack.identity, linked_pack.identity
+	))
+	.await;
+
+	Ok(())
+}
+
+/// Executes the unlink command which removes the link of a linked pack
+pub async fn exec_unlink(dir_context: DirContext, unlink_args: UnlinkArgs) -> Result<()> {
+	let hub = get_hub();
+
+	let linked_pack = unlink_pack(&dir_context, &unlink_args.pack_ref)?;
+
+	hub.publish(format!(
+		"\n==== Unlinked aipack:\n\n{:>15} {}\n{:>15} {}",
+		"Pack:", linked_pack.identity, "Source dir:", linked_pack.dir
+	))
+	.await;
+
+	hub.publish("\n==== DONE".to_string()).await;
+
+	Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::Result;
+use crate::dir_context::DirContext;
+use crate::hub::get_hub;
+use crate::packer::check_outdated_packs;
+
+/// Executes the outdated command which compares the installed packs with the repo latest versions
+pub async fn exec_outdated(dir_context: DirContext) -> Result<()> {
+	let hub = get_hub();
+	hub.publish("\n==== Checking installed aipacks:\n").await;
+
+	let statuses = check_outdated_packs(&dir_context).await?;
+	if statuses.is_empty() {
+		hub.publish("No installed packs").await;
+		return Ok(());
+	}
+
+	let mut msg = format!("{:<30} {:<14} {:<14}\n", "Pack", "Installed", "Latest");
+	for status in statuses.iter() {
+		let latest = match (&status.non_registry_source, &status.latest_version) {
+			(Some(_), _) => "(not from repo)",
+			(None, Some(latest_version)) => latest_version,
+			(None, None) => "(not in repo)",
+		};
+		let note = if status.is_outdated() { "outdated" } else { "" };
+		msg.push_str(&format!(
+			"{:<30} {:<14} {:<14} {note}\n",
+			status.pack_toml.identity().to_string(),
+			status.pack_toml.version,
+			latest
+		));
+	}
+	let outdated_count = statuses.iter().filter(|s| s.is_outdated()).count();
+	if outdated_count > 0 {
+		msg.push_str(&format!(
+			"\n{outdated_count} outdated pack(s). Run 'aip upgrade' to upgrade them."
+		));
+	} else {
+		msg.push_str("\nAll the packs are up to date.");
+	}
+	hub.publish(msg).await;
+
+	Ok(())
+}
@@ -1,23 +1,36 @@
-use crate::cli::PackArgs;
+use crate::cli::{KeygenArgs, PackArgs, PackCommand};
 use crate::hub::get_hub;
 use crate::init::extract_template_pack_toml_zfile;
-use crate::packer::pack_dir;
+use crate::packer::{
+	PackDirData, check_pack_dir, generate_signing_key, list_pack_dir_files, pack_dir, sign_aipack_file,
+};
 use crate::{Error, Result};
 use aho_corasick::AhoCorasick;
 use camino::Utf8PathBuf;
+use simple_fs::SPath;
+use size::Size;
 use std::fs;
 use std::io::{self};
 
 /// Execute the pack command which creates a .aipack file from a directory
 pub async fn exec_pack(pack_args: &PackArgs) -> Result<()> {
+	if let Some(PackCommand::Check { dir_path }) = &pack_args.cmd {
+		return exec_pack_check(dir_path).await;
+	}
+
 	let hub = get_hub();
 
 	// Get source directory path
-	let src_dir = Utf8PathBuf::from(&pack_args.dir_path);
+	// Note: Clap requires the dir_path when no sub command
+	let src_dir = Utf8PathBuf::from(pack_args.dir_path.as_deref().unwrap_or_default());
 	if !src_dir.exists() {
 		return Err(Error::custom(format!("Source directory '{}' does not exist", src_dir)));
 	}
 
+	if pack_args.list {
+		return exec_pack_list(&src_dir).await;
+	}
+
 	// Get destination directory (default to current directory if not specified)
 	let dest_dir = if let Some(output_dir) = &pack_args.output_dir {
 		Utf8PathBuf::from(output_dir)
@@ -30,6 +43,11 @@ pub async fn exec_pack(pack_args: &PackArgs) -> Result<()> {
 		std::fs::create_dir_all(&dest_dir)?;
 	}
 
+	// Check the pack first (when no pack.toml, one gets generated below, and the check is done before the retry)
+	if src_dir.join("pack.toml").exists() {
+		run_pack_check(&src_dir).await?;
+	}
+
 	// Perform the packing
 	hub.publish(format!("\nPacking directory '{}' into a .aipack file...", src_dir))
 		.await;
@@ -41,7 +59,7 @@ pub async fn exec_pack(pack_args: &PackArgs) -> Result<()> {
 				pack_data.pack_file
 			))
 			.await;
-			Ok(())
+			sign_pack_file(pack_args, &pack_data).await
 		}
 		Err(Error::AipackTomlMissing(_missing_toml_path)) => {
 			// Generate template pack.toml
@@ -71,10 +89,12 @@ pub async fn exec_pack(pack_args: &PackArgs) -> Result<()> {
 
 			if input.trim().to_uppercase() == "Y" {
 				// Try packing again
+				run_pack_check(&src_dir).await?;
 				match pack_dir(&src_dir, &dest_dir) {
 					Ok(pack_data) => {
 						hub.publish(format!("Successfully packed directory into '{}'", pack_data.pack_file))
 							.await;
+						sign_pack_file(pack_args, &pack_data).await?;
 					}
 					Err(retry_err) => {
 						hub.publish(format!(
@@ -94,6 +114,107 @@ pub async fn exec_pack(pack_args: &PackArgs) -> Result<()> {
 	}
 }
 
+/// Execute the `pack check` command which lints a pack directory without packing it
+async fn exec_pack_check(dir_path: &str) -> Result<()> {
+	let src_dir = Utf8PathBuf::from(dir_path);
+	if !src_dir.exists() {
+		return Err(Error::custom(format!("Pack directory '{}' does not exist", src_dir)));
+	}
+
+	get_hub().publish(format!("\n==== Checking pack '{src_dir}':\n")).await;
+	run_pack_check(&src_dir).await?;
+	get_hub().publish("\n==== DONE".to_string()).await;
+
+	Ok(())
+}
+
+/// List the files that `aip pack` would put in the .aipack file
+async fn exec_pack_list(src_dir: &Utf8PathBuf) -> Result<()> {
+	let (pack_toml, files) = list_pack_dir_files(src_dir)?;
+
+	let mut msg = format!(
+		"\nFiles of {}@{} v{} (from '{src_dir}'):\n\n",
+		pack_toml.namespace, pack_toml.name, pack_toml.version
+	);
+	let mut total_size = 0;
+	for file in files.iter() {
+		total_size += file.size;
+		msg.push_str(&format!(
+			"{:>10}  {}\n",
+			Size::from_bytes(file.size).to_string(),
+			file.name
+		));
+	}
+	msg.push_str(&format!(
+		"\n{} file(s), {} (uncompressed)",
+		files.len(),
+		Size::from_bytes(total_size)
+	));
+	get_hub().publish(msg).await;
+
+	Ok(())
+}
+
+/// Execute the keygen command which creates a key pair to sign the .aipack files
+pub async fn exec_keygen(keygen_args: &KeygenArgs) -> Result<()> {
+	let hub = get_hub();
+
+	let key_file = SPath::new(&keygen_args.key_path);
+	let public_key = generate_signing_key(&key_file)?;
+
+	hub.publish(format!(
+		"\nSecret key written to '{key_file}' (keep it private)\nPublic key written to '{key_file}.pub'\n\n\
+		Sign packs with:\n  aip pack path/to/pack-dir --sign {key_file}\n\n\
+		Trust this key by adding it to '~/.aipack-base/config.toml':\n  [install]\n  trusted_keys = [\"{public_key}\"]"
+	))
+	.await;
+
+	Ok(())
+}
+
+/// Run the pack check and publish its diagnostics
+///
+/// Returns an error when the check has errors (the warnings do not fail)
+async fn run_pack_check(src_dir: &Utf8PathBuf) -> Result<()> {
+	let hub = get_hub();
+
+	let report = check_pack_dir(&SPath::new(src_dir.as_str()))?;
+	for diagnostic in report.diagnostics.iter() {
+		hub.publish(diagnostic.to_string()).await;
+	}
+
+	let (error_count, warning_count) = (report.error_count(), report.warning_count());
+	if report.has_errors() {
+		return Err(Error::custom(format!(
+			"Pack check of '{src_dir}' failed with {error_count} error(s) and {warning_count} warning(s)"
+		)));
+	}
+
+	let summary = if warning_count > 0 {
+		format!("Pack check of '{src_dir}' passed with {warning_count} warning(s)")
+	} else {
+		format!("Pack check of '{src_dir}' passed")
+	};
+	hub.publish(summary).await;
+
+	Ok(())
+}
+
+/// Sign the packed file when `--sign key-file`
+async fn sign_pack_file(pack_args: &PackArgs, pack_data: &PackDirData) -> Result<()> {
+	let Some(key_file) = pack_args.sign_key_file.as_deref() else {
+		return Ok(());
+	};
+
+	let pack_file = &pack_data.pack_file;
+	let public_key = sign_aipack_file(pack_file, &SPath::new(key_file))?;
+	get_hub()
+		.publish(format!("Signed '{pack_file}' with public key {public_key}"))
+		.await;
+
+	Ok(())
+}
+
 /// Generates a default pack.toml file from the template
 async fn generate_pack_toml(dir_path: &Utf8PathBuf) -> Result<()> {
 	let hub = get_hub();
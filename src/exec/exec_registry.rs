@@ -0,0 +1,35 @@
+use crate::Result;
+use crate::cli::{RegistryArgs, RegistryCommand};
+use crate::hub::get_hub;
+use crate::packer::{RegistryIndex, RegistryServer};
+use simple_fs::SPath;
+
+/// Executes the registry commands (serve)
+pub async fn exec_registry(registry_args: RegistryArgs) -> Result<()> {
+	let hub = get_hub();
+
+	match registry_args.cmd {
+		RegistryCommand::Serve { dir, addr } => {
+			let registry_dir = SPath::new(dir);
+			let server = RegistryServer::bind(registry_dir.clone(), &addr).await?;
+			let url = server.url()?;
+
+			let index = RegistryIndex::load(&registry_dir)?;
+			for (file, cause) in index.invalid_files.iter() {
+				hub.publish(format!("WARNING - Ignoring '{file}'. Cause: {cause}")).await;
+			}
+			hub.publish(format!(
+				"\nServing the registry '{registry_dir}' ({} packs) at {url}\n\n\
+				To use it, add it to the '.aipack/config.toml' or '~/.aipack-base/config.toml':\n  \
+				[install]\n  registries = [\"{url}\"]\n\n\
+				(Ctrl-C to stop)",
+				index.pack_count()
+			))
+			.await;
+
+			server.serve().await?;
+		}
+	}
+
+	Ok(())
+}
@@ -2,13 +2,14 @@ use super::support::open_vscode;
 use crate::agent::{Agent, find_agent};
 use crate::cli::RunArgs;
 use crate::dir_context::DirContext;
+use crate::exec::ExecEvent;
 use crate::hub::{HubEvent, get_hub}; // Importing get_hub
 use crate::run::RunCommandOptions;
 use crate::run::{Runtime, run_command_agent};
 use crate::support::jsons::into_values;
 use crate::types::FileMeta;
 use crate::{Error, Result};
-use simple_fs::{SEventKind, SPath, list_files, watch};
+use simple_fs::{SEventKind, SPath, ensure_file_dir, list_files, watch};
 use std::sync::Arc;
 
 // region:    --- RunRedoCtx
@@ -18,6 +19,8 @@ pub struct RunRedoCtx {
 	runtime: Runtime,
 	agent: Agent,
 	run_options: RunCommandOptions,
+	/// When interactive, the permission denied errors propose the `A` redo with `--allow-all`
+	interactive: bool,
 }
 
 /// getters
@@ -60,6 +63,7 @@ pub async fn exec_run_first(run_args: RunArgs, dir_context: DirContext) -> Resul
 	let hub = get_hub();
 
 	let cmd_agent_name = &run_args.cmd_agent_name;
+	let interactive = !run_args.not_interactive;
 
 	let runtime = Runtime::new(dir_context)?;
 
@@ -73,13 +77,21 @@ pub async fn exec_run_first(run_args: RunArgs, dir_context: DirContext) -> Resul
 
 	match do_run(&run_options, &runtime, &agent).await {
 		Ok(_) => (),
-		Err(err) => hub.publish(format!("ERROR: {}", err)).await,
+		Err(err) => {
+			let permission_denied = err.is_permission_denied();
+			hub.publish(format!("ERROR: {}", err)).await;
+			if permission_denied && interactive {
+				publish_allow_all_prompt().await;
+			}
+			hub.publish(ExecEvent::RunFailed(err.into())).await;
+		}
 	};
 
 	Ok(RunRedoCtx {
 		runtime,
 		agent,
 		run_options,
+		interactive,
 	})
 }
 
@@ -92,6 +104,7 @@ pub async fn exec_run_redo(run_redo_ctx: &RunRedoCtx) -> Option<RunRedoCtx> {
 		runtime,
 		agent,
 		run_options,
+		interactive,
 	} = run_redo_ctx;
 
 	// make sure to reload the agent
@@ -108,14 +121,49 @@ pub async fn exec_run_redo(run_redo_ctx: &RunRedoCtx) -> Option<RunRedoCtx> {
 			runtime: runtime.clone(),
 			agent,
 			run_options: run_options.clone(),
+			interactive: *interactive,
 		}),
 		Err(err) => {
-			hub.publish(Error::cc("Error while Replay", err)).await;
+			let permission_denied = err.is_permission_denied();
+			let err = Arc::new(err);
+			hub.publish(Error::cc("Error while Replay", &err)).await;
+			if permission_denied && *interactive {
+				publish_allow_all_prompt().await;
+			}
+			hub.publish(ExecEvent::RunFailed(err)).await;
 			None
 		}
 	}
 }
 
+/// Redo the exec_run, but without the sandbox of the agent pack (the `A` key after a permission denied)
+/// NOTE: The returned ctx keeps the `--allow-all` for the next redos.
+pub async fn exec_run_redo_allow_all(run_redo_ctx: &RunRedoCtx) -> Option<RunRedoCtx> {
+	get_hub()
+		.publish(format!(
+			"\n==== Running {} with --allow-all\n",
+			run_redo_ctx.agent.name()
+		))
+		.await;
+	let run_redo_ctx = RunRedoCtx {
+		runtime: run_redo_ctx.runtime.clone(),
+		agent: run_redo_ctx.agent.clone(),
+		run_options: run_redo_ctx.run_options.with_allow_all(),
+		interactive: run_redo_ctx.interactive,
+	};
+	// Note: When the redo fails, we still keep the allow all ctx (so that `r` does not go back to the sandbox)
+	match exec_run_redo(&run_redo_ctx).await {
+		Some(redo_ctx) => Some(redo_ctx),
+		None => Some(run_redo_ctx),
+	}
+}
+
+async fn publish_allow_all_prompt() {
+	get_hub()
+		.publish("\nPress [ A ] (shift+a) to run it again with --allow-all (only if you trust this pack)")
+		.await;
+}
+
 /// Exec the run watch.
 /// NOTE: This is not async, because we want to have it run in parallel
 ///       so it will spawn it's own tokio task
@@ -196,7 +244,7 @@ async fn do_run(run_command_options: &RunCommandOptions, runtime: &Runtime, agen
 		None
 	};
 
-	run_command_agent(
+	let run_response = run_command_agent(
 		runtime,
 		agent.clone(),
 		inputs,
@@ -205,5 +253,21 @@ async fn do_run(run_command_options: &RunCommandOptions, runtime: &Runtime, agen
 	)
 	.await?;
 
+	// -- Write the eventual run summary report
+	if let Some(report_path) = run_command_options.report() {
+		let report_path = SPath::new(report_path);
+		ensure_file_dir(&report_path)?;
+		let content = serde_json::to_string_pretty(&run_response.summary)?;
+		std::fs::write(&report_path, content)?;
+		get_hub().publish(format!("-> Run report written to {report_path}")).await;
+	}
+
+	// Note: With on_error = "continue", the run completes, but still fails when some inputs failed
+	if !run_response.errors.is_empty() {
+		return Err(Error::RunInputsFailed {
+			failed_count: run_response.errors.len(),
+		});
+	}
+
 	Ok(())
 }
@@ -0,0 +1,125 @@
+use crate::agent::find_agent;
+use crate::cli::{RunsArgs, RunsCommand};
+use crate::dir_context::DirContext;
+use crate::history::{RunRecord, RunStore};
+use crate::hub::get_hub;
+use crate::run::{Runtime, replay_run_inputs};
+use crate::{Error, Result};
+
+/// Executes the runs commands (list, show, replay)
+pub async fn exec_runs(dir_context: DirContext, runs_args: RunsArgs) -> Result<()> {
+	let hub = get_hub();
+	let store = RunStore::new(&dir_context)?;
+
+	match runs_args.cmd {
+		RunsCommand::List => {
+			let run_records = store.list()?;
+			if run_records.is_empty() {
+				hub.publish("No runs found (runs are in .aipack/runs/)").await;
+			} else {
+				let mut msg = String::from("\n==== Runs:\n");
+				for run_record in run_records {
+					msg.push_str(&format!(
+						"\n{:<24} {:<30} {:>4} inputs   {}",
+						run_record.id,
+						run_record.agent_name,
+						run_record.inputs.len(),
+						run_status(&run_record)
+					));
+				}
+				hub.publish(msg).await;
+			}
+		}
+
+		RunsCommand::Show { id } => {
+			let run_record = store.load_run(&id)?;
+			let input_records = store.load_inputs(&id)?;
+
+			let mut msg = format!(
+				"\n==== Run: {}\n\nStarted at: {}\nAgent:      {} ({})\nStatus:     {}\n",
+				run_record.id,
+				run_record.started_at,
+				run_record.agent_name,
+				run_record.agent_path,
+				run_status(&run_record)
+			);
+			if let Some(summary) = run_record.summary.as_ref() {
+				msg.push_str(&format!("\n{summary}\n"));
+			}
+
+			for input_record in input_records {
+				msg.push_str(&format!("\n-- Input {}: {}\n", input_record.index, input_record.label));
+				if let Some(skip_reason) = input_record.skip_reason.as_ref() {
+					msg.push_str(&format!("Skipped: {skip_reason}\n"));
+				}
+				if let Some(error) = input_record.error.as_ref() {
+					msg.push_str(&format!("Error: {error}\n"));
+				}
+				if let Some(ai_response) = input_record.ai_response.as_ref() {
+					msg.push_str(&format!(
+						"AI response ({}):\n{}\n",
+						ai_response.info,
+						ai_response.content.as_deref().unwrap_or_default()
+					));
+				}
+				if !input_record.output.is_null() {
+					let output = match input_record.output.as_str() {
+						Some(output) => output.to_string(),
+						None => serde_json::to_string_pretty(&input_record.output)?,
+					};
+					msg.push_str(&format!("Output:\n{output}\n"));
+				}
+			}
+			hub.publish(msg).await;
+		}
+
+		RunsCommand::Replay {
+			id,
+			input,
+			dry_mode,
+			allow_all,
+		} => {
+			let run_record = store.load_run(&id)?;
+			let mut input_records = store.load_inputs(&id)?;
+			if let Some(input_idx) = input {
+				input_records.retain(|r| r.index == input_idx);
+				if input_records.is_empty() {
+					return Err(Error::custom(format!(
+						"Run '{id}' does not have a recorded input {input_idx}"
+					)));
+				}
+			}
+
+			let runtime = Runtime::new(dir_context)?;
+			let agent = find_agent(&run_record.agent_name, runtime.dir_context())?;
+			let runtime = runtime.for_agent(&agent, allow_all)?;
+
+			hub.publish(format!(
+				"\n======= REPLAYING: {} (run: {id}, no AI call)",
+				run_record.agent_name
+			))
+			.await;
+			let dry_res = dry_mode.as_deref() == Some("res");
+			replay_run_inputs(&runtime, &agent, run_record.before_all, input_records, dry_res).await?;
+			hub.publish(format!("\n======= REPLAY COMPLETED: {}", run_record.agent_name))
+				.await;
+		}
+	}
+
+	Ok(())
+}
+
+// region:    --- Support
+
+fn run_status(run_record: &RunRecord) -> String {
+	match run_record.summary.as_ref() {
+		None => "incomplete".to_string(),
+		Some(_) if !run_record.errors.is_empty() => format!("completed with {} error(s)", run_record.errors.len()),
+		Some(summary) => match summary.price_usd {
+			Some(price_usd) => format!("completed (~${price_usd:.4})"),
+			None => "completed".to_string(),
+		},
+	}
+}
+
+// endregion: --- Support
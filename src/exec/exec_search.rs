@@ -0,0 +1,34 @@
+use crate::Result;
+use crate::cli::SearchArgs;
+use crate::dir_context::DirContext;
+use crate::hub::get_hub;
+use crate::packer::search_packs;
+
+/// Executes the search command (custom, installed, and registry packs)
+pub async fn exec_search(dir_context: DirContext, search_args: SearchArgs) -> Result<()> {
+	let hub = get_hub();
+	hub.publish(format!("\n==== Searching packs for '{}':\n", search_args.term))
+		.await;
+
+	let result = search_packs(&dir_context, &search_args.term).await?;
+
+	let mut msg = String::new();
+	if result.hits.is_empty() {
+		msg.push_str("No packs found\n");
+	} else {
+		msg.push_str(&format!("{:<30} {:<14} {}\n", "Pack", "Version", "Where"));
+		for hit in result.hits.iter() {
+			let version = hit.version.as_deref().unwrap_or("-");
+			msg.push_str(&format!("{:<30} {version:<14} {}\n", hit.identity, hit.location));
+			if let Some(description) = hit.description.as_deref() {
+				msg.push_str(&format!("    {description}\n"));
+			}
+		}
+	}
+	for (registry, cause) in result.registry_errors.iter() {
+		msg.push_str(&format!("\nNote: Registry '{registry}' not searched. Cause: {cause}"));
+	}
+	hub.publish(msg.trim_end().to_string()).await;
+
+	Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::Result;
+use crate::cli::{SessionArgs, SessionCommand};
+use crate::dir_context::DirContext;
+use crate::hub::get_hub;
+use crate::session::SessionStore;
+
+/// Executes the session commands (list, show, clear)
+pub async fn exec_session(dir_context: DirContext, session_args: SessionArgs) -> Result<()> {
+	let hub = get_hub();
+	let store = SessionStore::new(&dir_context)?;
+
+	match session_args.cmd {
+		SessionCommand::List => {
+			let infos = store.list()?;
+			if infos.is_empty() {
+				hub.publish("No sessions found (sessions are in .aipack/sessions/)").await;
+			} else {
+				let mut msg = String::from("\n==== Sessions:\n");
+				for info in infos {
+					msg.push_str(&format!("\n{:<30} {} messages", info.name, info.message_count));
+				}
+				hub.publish(msg).await;
+			}
+		}
+
+		SessionCommand::Show { name } => {
+			let messages = store.load(&name)?;
+			if messages.is_empty() {
+				hub.publish(format!("Session '{name}' is empty or does not exist")).await;
+			} else {
+				let mut msg = format!("\n==== Session: {name}\n");
+				for message in messages {
+					msg.push_str(&format!("\n-- {}:\n{}\n", message.role.as_ref(), message.content));
+				}
+				hub.publish(msg).await;
+			}
+		}
+
+		SessionCommand::Clear { name } => {
+			if store.clear(&name)? {
+				hub.publish(format!("Session '{name}' cleared")).await;
+			} else {
+				hub.publish(format!("Session '{name}' does not exist")).await;
+			}
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::Result;
+use crate::cli::UninstallArgs;
+use crate::dir_context::DirContext;
+use crate::hub::get_hub;
+use crate::packer::uninstall_pack;
+
+/// Executes the uninstall command which removes an installed pack
+pub async fn exec_uninstall(dir_context: DirContext, uninstall_args: UninstallArgs) -> Result<()> {
+	let hub = get_hub();
+
+	let pack_toml = uninstall_pack(&dir_context, &uninstall_args.pack_identity, uninstall_args.force)?;
+
+	hub.publish(format!(
+		"\n==== Uninstalled aipack:\n\n{:>15} {}@{}\n{:>15} {}",
+		"Pack:", pack_toml.namespace, pack_toml.name, "Version:", pack_toml.version
+	))
+	.await;
+
+	hub.publish("\n==== DONE".to_string()).await;
+
+	Ok(())
+}
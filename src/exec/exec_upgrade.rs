@@ -0,0 +1,54 @@
+use crate::Result;
+use crate::cli::UpgradeArgs;
+use crate::dir_context::DirContext;
+use crate::hub::get_hub;
+use crate::packer::{InstallOptions, PackVersionChange, rollback_pack, upgrade_packs};
+
+/// Executes the upgrade command which upgrades the installed packs to their latest repo version (or rollback one)
+pub async fn exec_upgrade(dir_context: DirContext, upgrade_args: UpgradeArgs) -> Result<()> {
+	let hub = get_hub();
+	let pack_identity = upgrade_args.pack_identity.as_deref();
+
+	let changes = match (upgrade_args.rollback, pack_identity) {
+		(true, Some(pack_identity)) => {
+			hub.publish(format!("\n==== Rolling back aipack {pack_identity}:\n")).await;
+			vec![rollback_pack(&dir_context, pack_identity)?]
+		}
+		_ => {
+			hub.publish(format!(
+				"\n==== Upgrading aipack{}:\n",
+				pack_identity.map(|p| format!(" {p}")).unwrap_or_else(|| "s".to_string())
+			))
+			.await;
+			let install_options = InstallOptions {
+				allow_unsigned: upgrade_args.allow_unsigned,
+			};
+			upgrade_packs(&dir_context, pack_identity, &install_options).await?
+		}
+	};
+
+	hub.publish(format_changes(&changes)).await;
+	hub.publish("\n==== DONE".to_string()).await;
+
+	Ok(())
+}
+
+// region:    --- Support
+
+fn format_changes(changes: &[PackVersionChange]) -> String {
+	if changes.is_empty() {
+		return "Already up to date".to_string();
+	}
+
+	let mut msg = String::new();
+	for change in changes {
+		msg.push_str(&format!(
+			"{:>30} {} -> {}\n",
+			change.identity, change.from_version, change.to_version
+		));
+	}
+	msg.push_str("\n(previous versions kept, use 'aip upgrade ns@name --rollback' to restore one)");
+	msg
+}
+
+// endregion: --- Support
@@ -4,7 +4,11 @@
 use crate::agent::Agent;
 use crate::exec::exec_command::ExecCommand;
 use crate::exec::support::open_vscode;
-use crate::exec::{ExecEvent, RunRedoCtx, exec_install, exec_list, exec_new, exec_pack, exec_run, exec_run_redo};
+use crate::exec::{
+	ExecEvent, RunRedoCtx, exec_info, exec_install, exec_keygen, exec_link, exec_list, exec_new, exec_outdated,
+	exec_pack, exec_registry, exec_run, exec_run_redo, exec_run_redo_allow_all, exec_runs, exec_search, exec_session,
+	exec_sync, exec_uninstall, exec_unlink, exec_upgrade,
+};
 use crate::hub::get_hub;
 use crate::init::{init_base, init_wks};
 use crate::{Error, Result};
@@ -96,8 +100,34 @@ impl Executor {
 
 				ExecCommand::Pack(pack_args) => exec_pack(&pack_args).await?,
 
+				ExecCommand::Keygen(keygen_args) => exec_keygen(&keygen_args).await?,
+
 				ExecCommand::Install(install_args) => exec_install(init_wks(None, false).await?, install_args).await?,
 
+				ExecCommand::Sync(sync_args) => exec_sync(init_wks(None, false).await?, sync_args).await?,
+
+				ExecCommand::Uninstall(uninstall_args) => {
+					exec_uninstall(init_wks(None, false).await?, uninstall_args).await?
+				}
+
+				ExecCommand::Outdated => exec_outdated(init_wks(None, false).await?).await?,
+
+				ExecCommand::Link(link_args) => exec_link(init_wks(None, false).await?, link_args).await?,
+
+				ExecCommand::Unlink(unlink_args) => exec_unlink(init_wks(None, false).await?, unlink_args).await?,
+
+				ExecCommand::Info(info_args) => exec_info(init_wks(None, false).await?, info_args).await?,
+
+				ExecCommand::Search(search_args) => exec_search(init_wks(None, false).await?, search_args).await?,
+
+				ExecCommand::Upgrade(upgrade_args) => exec_upgrade(init_wks(None, false).await?, upgrade_args).await?,
+
+				ExecCommand::Registry(registry_args) => exec_registry(registry_args).await?,
+
+				ExecCommand::Session(session_args) => exec_session(init_wks(None, false).await?, session_args).await?,
+
+				ExecCommand::Runs(runs_args) => exec_runs(init_wks(None, false).await?, runs_args).await?,
+
 				ExecCommand::RunCommandAgent(run_args) => {
 					hub.publish(ExecEvent::RunStart).await;
 					let redo = exec_run(run_args, init_wks(None, false).await?).await?;
@@ -123,6 +153,19 @@ impl Executor {
 					hub.publish(ExecEvent::RunEnd).await;
 				}
 
+				ExecCommand::RedoAllowAll => {
+					let Some(RedoCtx::RunRedoCtx(redo_ctx)) = self.current_redo_ctx.as_ref() else {
+						hub.publish(Error::custom("No redo available to be performed")).await;
+						continue;
+					};
+
+					hub.publish(ExecEvent::RunStart).await;
+					if let Some(redo_ctx) = exec_run_redo_allow_all(redo_ctx).await {
+						self.current_redo_ctx = Some(redo_ctx.into())
+					}
+					hub.publish(ExecEvent::RunEnd).await;
+				}
+
 				ExecCommand::OpenAgent => {
 					//
 					if let Some(agent_file_path) = self.get_agent_file_path() {
@@ -1,17 +1,35 @@
 // region:    --- Modules
 
+mod exec_info;
 mod exec_install;
+mod exec_link;
 mod exec_list;
 mod exec_new;
+mod exec_outdated;
 mod exec_pack;
+mod exec_registry;
 mod exec_run;
+mod exec_runs;
+mod exec_search;
+mod exec_session;
+mod exec_uninstall;
+mod exec_upgrade;
 mod support;
 
+use exec_info::*;
 use exec_install::*;
+use exec_link::*;
 use exec_list::*;
 use exec_new::*;
+use exec_outdated::*;
 use exec_pack::*;
+use exec_registry::*;
 use exec_run::*;
+use exec_runs::*;
+use exec_search::*;
+use exec_session::*;
+use exec_uninstall::*;
+use exec_upgrade::*;
 
 mod exec_command;
 mod exec_event;
@@ -0,0 +1,9 @@
+// region:    --- Modules
+
+mod run_record;
+mod run_store;
+
+pub use run_record::*;
+pub use run_store::*;
+
+// endregion: --- Modules
@@ -0,0 +1,55 @@
+use crate::run::{AiResponse, InputError, RunSummary};
+use crate::session::SessionMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The record of a run, stored as `.aipack/runs/{id}/run.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+	pub id: String,
+	/// The start time of the run (RFC 3339, UTC)
+	pub started_at: String,
+	/// The agent name as given to `aip run` (used to find the agent on replay)
+	pub agent_name: String,
+	pub agent_path: String,
+	/// The agent options of the run (after the eventual `# Before All` merge)
+	pub options: Value,
+	pub inputs: Vec<Value>,
+	pub before_all: Value,
+	/// None if the run did not complete (e.g., aborted on an input error)
+	pub summary: Option<RunSummary>,
+	pub errors: Vec<InputError>,
+}
+
+/// The record of one input of a run, stored as `.aipack/runs/{id}/inputs/{index}.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputRecord {
+	/// The index of the input (same as in the run `inputs`)
+	pub index: usize,
+	pub label: String,
+	pub input: Value,
+	/// The value returned by the `# Data` stage
+	pub data: Value,
+	/// The rendered messages sent to the AI (with the eventual session history)
+	pub messages: Vec<SessionMessage>,
+	pub ai_response: Option<AiResponse>,
+	pub output: Value,
+	pub skip_reason: Option<String>,
+	pub error: Option<String>,
+}
+
+impl InputRecord {
+	pub fn new(index: usize, label: impl Into<String>, input: Value) -> Self {
+		InputRecord {
+			index,
+			label: label.into(),
+			input,
+			data: Value::Null,
+			messages: Vec::new(),
+			ai_response: None,
+			output: Value::Null,
+			skip_reason: None,
+			error: None,
+		}
+	}
+}
@@ -0,0 +1,202 @@
+//! The run history store, stored in `.aipack/runs/`
+//!
+//! Each run is a `{id}/` dir, with the `run.json` record, and one `inputs/{index}.json` record per input.
+//! The id starts with the UTC start time, so, the ids sort in run order.
+//!
+//! Only the most recent runs are kept, `[history] max_runs` of the config.toml (100 by default, 0 for no limit).
+
+use crate::dir_context::DirContext;
+use crate::history::{InputRecord, RunRecord};
+use crate::support::tomls::parse_toml;
+use crate::{Error, Result};
+use simple_fs::{SPath, ensure_dir};
+use std::fs;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use value_ext::JsonValueExt as _;
+
+const RUN_FILE_NAME: &str = "run.json";
+const INPUTS_DIR_NAME: &str = "inputs";
+
+/// The max number of runs kept when the config.toml has no `[history] max_runs`
+const DEFAULT_MAX_RUNS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct RunStore {
+	dir: SPath,
+	/// 0 for no limit
+	max_runs: usize,
+}
+
+/// Constructors
+impl RunStore {
+	pub fn new(dir_context: &DirContext) -> Result<Self> {
+		let dir = dir_context.aipack_paths().get_wks_runs_dir()?;
+		let max_runs = load_max_runs(dir_context)?;
+		Ok(RunStore { dir, max_runs })
+	}
+}
+
+impl RunStore {
+	/// Create the dir of a new run.
+	///
+	/// Returns the `(id, started_at)` of the new run
+	pub fn create_run(&self) -> Result<(String, String)> {
+		let now = OffsetDateTime::now_utc();
+		let started_at = now
+			.format(&Rfc3339)
+			.map_err(|err| Error::cc("Cannot format run start time", err))?;
+		let format = time::format_description::parse("[year][month][day]-[hour][minute][second]")
+			.map_err(|err| Error::cc("Invalid run id format", err))?;
+		let timestamp = now.format(&format).map_err(|err| Error::cc("Cannot format run id", err))?;
+		let base_id = format!("{timestamp}-{:03}", now.millisecond());
+
+		// Note: Two runs in the same millisecond get a suffix
+		let mut id = base_id.clone();
+		let mut suffix = 1;
+		while self.dir.join(&id).exists() {
+			id = format!("{base_id}-{suffix}");
+			suffix += 1;
+		}
+		ensure_dir(self.dir.join(&id).join(INPUTS_DIR_NAME))?;
+
+		Ok((id, started_at))
+	}
+
+	/// Remove the oldest runs above the `max_runs` (e.g., when a new run is created)
+	///
+	/// Returns the number of removed runs
+	pub fn prune(&self) -> Result<usize> {
+		if self.max_runs == 0 || !self.dir.exists() {
+			return Ok(0);
+		}
+
+		let mut ids: Vec<String> = fs::read_dir(&self.dir)?
+			.flatten()
+			.filter(|dir_entry| dir_entry.path().is_dir())
+			.filter_map(|dir_entry| dir_entry.file_name().to_str().map(|s| s.to_string()))
+			.filter(|id| self.run_dir(id).is_ok())
+			.collect();
+		if ids.len() <= self.max_runs {
+			return Ok(0);
+		}
+
+		// Note: The ids sort in run order, so, the oldest are first
+		ids.sort();
+		let remove_count = ids.len() - self.max_runs;
+		for id in ids.iter().take(remove_count) {
+			fs::remove_dir_all(self.run_dir(id)?)?;
+		}
+
+		Ok(remove_count)
+	}
+
+	pub fn save_run(&self, run_record: &RunRecord) -> Result<()> {
+		let file = self.run_dir(&run_record.id)?.join(RUN_FILE_NAME);
+		fs::write(&file, serde_json::to_string_pretty(run_record)?)?;
+		Ok(())
+	}
+
+	pub fn save_input(&self, run_id: &str, input_record: &InputRecord) -> Result<()> {
+		let inputs_dir = self.run_dir(run_id)?.join(INPUTS_DIR_NAME);
+		ensure_dir(&inputs_dir)?;
+		let file = inputs_dir.join(format!("{}.json", input_record.index));
+		fs::write(&file, serde_json::to_string_pretty(input_record)?)?;
+		Ok(())
+	}
+
+	/// List the runs, the most recent first
+	pub fn list(&self) -> Result<Vec<RunRecord>> {
+		if !self.dir.exists() {
+			return Ok(Vec::new());
+		}
+
+		let mut records = Vec::new();
+		for dir_entry in fs::read_dir(&self.dir)?.flatten() {
+			let Some(id) = dir_entry.file_name().to_str().map(|s| s.to_string()) else {
+				continue;
+			};
+			if !dir_entry.path().join(RUN_FILE_NAME).is_file() {
+				continue;
+			}
+			records.push(self.load_run(&id)?);
+		}
+		records.sort_by(|a, b| b.id.cmp(&a.id));
+
+		Ok(records)
+	}
+
+	pub fn load_run(&self, id: &str) -> Result<RunRecord> {
+		let file = self.run_dir(id)?.join(RUN_FILE_NAME);
+		if !file.exists() {
+			return Err(Error::custom(format!(
+				"Run '{id}' not found (runs are in .aipack/runs/)"
+			)));
+		}
+		let content = fs::read_to_string(&file)?;
+		let run_record = serde_json::from_str(&content)
+			.map_err(|err| Error::cc(format!("Run '{id}' has an invalid record"), err))?;
+		Ok(run_record)
+	}
+
+	/// Load the input records of the run, sorted by input index
+	pub fn load_inputs(&self, id: &str) -> Result<Vec<InputRecord>> {
+		let inputs_dir = self.run_dir(id)?.join(INPUTS_DIR_NAME);
+		if !inputs_dir.exists() {
+			return Ok(Vec::new());
+		}
+
+		let mut records = Vec::new();
+		for dir_entry in fs::read_dir(&inputs_dir)?.flatten() {
+			let path = dir_entry.path();
+			if path.extension().is_none_or(|ext| ext != "json") {
+				continue;
+			}
+			let content = fs::read_to_string(&path)?;
+			let input_record: InputRecord = serde_json::from_str(&content)
+				.map_err(|err| Error::cc(format!("Run '{id}' has an invalid input record"), err))?;
+			records.push(input_record);
+		}
+		records.sort_by_key(|r| r.index);
+
+		Ok(records)
+	}
+}
+
+/// Private
+impl RunStore {
+	fn run_dir(&self, id: &str) -> Result<SPath> {
+		let valid = !id.is_empty()
+			&& !id.starts_with('.')
+			&& id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+		if !valid {
+			return Err(Error::custom(format!("Run id '{id}' is invalid")));
+		}
+		Ok(self.dir.join(id))
+	}
+}
+
+// region:    --- Support
+
+/// Load the `[history] max_runs` of the workspace, or else of the base config.toml
+fn load_max_runs(dir_context: &DirContext) -> Result<usize> {
+	let aipack_paths = dir_context.aipack_paths();
+	let config_paths = [
+		aipack_paths.get_wks_config_toml_path()?,
+		aipack_paths.get_base_config_toml_path()?,
+	];
+
+	for config_path in config_paths {
+		if !config_path.exists() {
+			continue;
+		}
+		let config_value = parse_toml(&fs::read_to_string(&config_path)?)?;
+		if let Ok(max_runs) = config_value.x_get::<usize>("/history/max_runs") {
+			return Ok(max_runs);
+		}
+	}
+
+	Ok(DEFAULT_MAX_RUNS)
+}
+
+// endregion: --- Support
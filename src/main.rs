@@ -5,6 +5,7 @@ mod cli;
 mod dir_context;
 mod error;
 mod exec;
+mod history;
 mod hub;
 mod init;
 mod pack;
@@ -12,6 +13,7 @@ mod packer;
 mod pricing;
 mod run;
 mod script;
+mod session;
 mod support;
 mod tui;
 mod types;
@@ -25,6 +27,7 @@ use crate::hub::{HubEvent, get_hub};
 use crate::tui::TuiApp;
 use clap::{Parser, crate_version};
 use error::{Error, Result};
+use std::process::ExitCode;
 use std::time::Duration;
 
 pub static VERSION: &str = crate_version!();
@@ -32,7 +35,7 @@ pub static VERSION: &str = crate_version!();
 // endregion: --- Modules
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
 	// -- Command arguments
 	let args = CliArgs::parse(); // Will fail early, but that’s okay.
 
@@ -51,7 +54,8 @@ async fn main() -> Result<()> {
 	// -- Start UI
 	let tui = TuiApp::new(executor_tx);
 	// This will wait until all done
-	tui.start_with_args(args).await?;
+	// Note: Returns the error of the last run, if it failed (so that the process exit status is not 0)
+	let res = tui.start_with_args(args).await;
 
 	// -- End
 	// Tokio wait for 100ms
@@ -61,5 +65,13 @@ async fn main() -> Result<()> {
 	tokio::time::sleep(Duration::from_millis(100)).await;
 	println!("\n     ---- Until next one, happy coding! ----");
 
-	Ok(())
+	match res {
+		Ok(()) => ExitCode::SUCCESS,
+		// Note: The run error was already displayed by the TUI
+		Err(Error::RunFailed { .. }) => ExitCode::FAILURE,
+		Err(err) => {
+			eprintln!("Error: {err}");
+			ExitCode::FAILURE
+		}
+	}
 }
@@ -1,9 +1,11 @@
 // region:    --- Modules
 
 mod pack_identity;
+mod pack_permissions;
 mod pack_ref;
 
 pub use pack_identity::*;
+pub use pack_permissions::*;
 pub use pack_ref::*;
 
 // endregion: --- Modules
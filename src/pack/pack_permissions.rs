@@ -0,0 +1,101 @@
+//! The `[permissions]` of a `pack.toml`, what the Lua scripts of the pack agents are allowed to do
+//!
+//! ```toml
+//! [permissions]
+//! fs.read  = ["docs/**", "src/**"]  # Optional, the workspace and the pack dir when absent
+//! fs.write = ["src/**"]             # globs relative to the workspace dir
+//! cmd      = ["cargo", "git"]       # the commands of `aip.cmd.exec` (and `git` for `aip.git.*`)
+//! net      = ["api.example.com"]    # the hosts of `aip.web.*` (`*.example.com` for the sub domains)
+//! ```
+//!
+//! See `run::Sandbox` for how they are enforced.
+
+use crate::{Error, Result};
+use serde::Deserialize;
+use simple_fs::{SPath, get_glob_set};
+use std::fs;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackPermissions {
+	#[serde(default)]
+	pub fs: FsPermissions,
+	#[serde(default)]
+	pub cmd: Vec<String>,
+	#[serde(default)]
+	pub net: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FsPermissions {
+	/// When None, the files of the workspace dir (and of the pack dir) can be read
+	pub read: Option<Vec<String>>,
+	#[serde(default)]
+	pub write: Vec<String>,
+}
+
+/// Only the `[permissions]` of the pack.toml (the other sections might not be valid for custom packs)
+#[derive(Deserialize)]
+struct PermissionsToml {
+	permissions: Option<PackPermissions>,
+}
+
+impl PackPermissions {
+	/// Load the `[permissions]` of the `pack.toml` of this pack dir
+	///
+	/// Returns None when the pack dir has no `pack.toml`, or its `pack.toml` has no `[permissions]`.
+	pub fn load_from_pack_dir(pack_dir: &SPath) -> Result<Option<Self>> {
+		let toml_path = pack_dir.join("pack.toml");
+		if !toml_path.exists() {
+			return Ok(None);
+		}
+		let content = fs::read_to_string(&toml_path)?;
+		let permissions_toml: PermissionsToml = toml::from_str(&content)
+			.map_err(|err| Error::cc(format!("Invalid [permissions] in '{toml_path}'"), err))?;
+		let Some(permissions) = permissions_toml.permissions else {
+			return Ok(None);
+		};
+		permissions.validate(toml_path.to_str())?;
+
+		Ok(Some(permissions))
+	}
+
+	/// The declared permissions, one line per kind (e.g., `fs.write: src/**, docs/**`)
+	pub fn summary_lines(&self) -> Vec<String> {
+		let mut lines = Vec::new();
+		let kinds = [
+			("fs.read", self.fs.read.as_ref()),
+			("fs.write", Some(&self.fs.write)),
+			("cmd", Some(&self.cmd)),
+			("net", Some(&self.net)),
+		];
+		for (kind, values) in kinds {
+			if let Some(values) = values.filter(|v| !v.is_empty()) {
+				lines.push(format!("{kind}: {}", values.join(", ")));
+			}
+		}
+		lines
+	}
+
+	/// Validate the globs, commands, and hosts
+	pub fn validate(&self, toml_path: &str) -> Result<()> {
+		let read_globs = self.fs.read.iter().flatten();
+		for glob in read_globs.chain(self.fs.write.iter()) {
+			get_glob_set(&[glob.as_str()]).map_err(|err| {
+				Error::custom(format!(
+					"Invalid [permissions] fs glob '{glob}' in {toml_path}. Cause: {err}"
+				))
+			})?;
+		}
+		for (kind, values) in [("cmd", &self.cmd), ("net", &self.net)] {
+			if let Some(value) = values.iter().find(|v| v.trim().is_empty() || v.contains(char::is_whitespace)) {
+				return Err(Error::custom(format!(
+					"Invalid [permissions] {kind} '{value}' in {toml_path} (cannot be empty or contain spaces)"
+				)));
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,83 @@
+//! The install record of an installed pack (`.aipack-install.toml` in the installed pack dir),
+//! which records where the pack was installed from, the SHA-256 of its .aipack file, and when it was installed.
+//!
+//! This way, a pack already installed (e.g., as the dependency of another pack) can still be recorded
+//! in the workspace `.aipack/pack.lock` without being fetched again.
+
+use crate::dir_context::DirContext;
+use crate::packer::installer_impl::{PackUri, pack_uri_from_source, wks_relative_source};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use simple_fs::SPath;
+use std::fs;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+pub(super) const INSTALL_RECORD_FILE_NAME: &str = ".aipack-install.toml";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct InstallRecord {
+	/// The uri of the .aipack file (local paths are absolute, as the pack is shared by all workspaces)
+	pub source: String,
+	/// The SHA-256 (hex) of the .aipack file
+	pub sha256: String,
+	/// The install time (RFC 3339, UTC), None for the records of an older aip
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub installed_at: Option<String>,
+}
+
+impl InstallRecord {
+	/// Create the record of a pack installed now, from a pack.lock source (local paths relative to the workspace dir)
+	pub fn new(dir_context: &DirContext, source: &str, sha256: impl Into<String>) -> Result<Self> {
+		let source = match pack_uri_from_source(dir_context, source)? {
+			PackUri::LocalPath(path) => path,
+			_ => source.to_string(),
+		};
+		let installed_at = OffsetDateTime::now_utc()
+			.format(&Rfc3339)
+			.map_err(|err| Error::cc("Cannot format install time", err))?;
+		Ok(InstallRecord {
+			source,
+			sha256: sha256.into(),
+			installed_at: Some(installed_at),
+		})
+	}
+
+	/// The install time, None if not recorded (or invalid)
+	pub fn installed_at(&self) -> Option<OffsetDateTime> {
+		let installed_at = self.installed_at.as_deref()?;
+		OffsetDateTime::parse(installed_at, &Rfc3339).ok()
+	}
+
+	/// The source for the workspace pack.lock (local paths relative to the workspace dir when inside it)
+	pub fn lock_source(&self, dir_context: &DirContext) -> Result<String> {
+		let source = match PackUri::parse(&self.source)? {
+			PackUri::LocalPath(path) => wks_relative_source(dir_context, &SPath::new(path)),
+			_ => self.source.clone(),
+		};
+		Ok(source)
+	}
+}
+
+/// Load & Save
+impl InstallRecord {
+	/// Load the install record of the installed pack dir, None if it has none (e.g., installed by an older aip)
+	pub fn load(pack_dir: &SPath) -> Result<Option<Self>> {
+		let path = pack_dir.join(INSTALL_RECORD_FILE_NAME);
+		if !path.exists() {
+			return Ok(None);
+		}
+
+		let content = fs::read_to_string(&path)?;
+		let record =
+			toml::from_str(&content).map_err(|err| Error::cc(format!("Invalid install record at '{path}'"), err))?;
+		Ok(Some(record))
+	}
+
+	pub fn save(&self, pack_dir: &SPath) -> Result<()> {
+		let path = pack_dir.join(INSTALL_RECORD_FILE_NAME);
+		let content = toml::to_string(self).map_err(|err| Error::cc("Cannot serialize install record", err))?;
+		fs::write(&path, content)?;
+		Ok(())
+	}
+}
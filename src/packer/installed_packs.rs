@@ -0,0 +1,408 @@
+//! Management of the installed packs (`~/.aipack-base/pack/installed/`)
+//! - `aip uninstall ns@name`
+//! - `aip outdated` (installed versions vs the repo `latest.toml`)
+//! - `aip upgrade [ns@name]`, which keeps the previous version in `~/.aipack-base/pack/.backup/` for `--rollback`
+
+use crate::dir_context::DirContext;
+use crate::pack::PackIdentity;
+use crate::packer::install_record::InstallRecord;
+use crate::packer::installer_impl::{InstallOptions, fetch_repo_latest, install_repo_pack, load_installed_pack_toml};
+use crate::packer::pack_lock::{LockedPack, PackLock};
+use crate::packer::pack_registry::is_registry_pack_url;
+use crate::packer::{PackToml, support};
+use crate::{Error, Result};
+use simple_fs::{SPath, ensure_dir};
+use std::fs;
+use std::str::FromStr;
+
+const BACKUP_PACK_DIR_NAME: &str = "pack";
+const BACKUP_LOCKED_PACK_FILE_NAME: &str = "locked-pack.toml";
+
+/// The repo status of an installed pack (for `aip outdated`)
+pub struct InstalledPackStatus {
+	pub pack_toml: PackToml,
+	/// The source of the pack when not installed from a registry (e.g., a local file), which is then not checked
+	pub non_registry_source: Option<String>,
+	/// None when the pack is not from a registry, or could not be found in the registries
+	pub latest_version: Option<String>,
+	/// The url of the .aipack file of the latest version (to upgrade without fetching the `latest.toml` again)
+	latest_aipack_url: Option<String>,
+}
+
+impl InstalledPackStatus {
+	pub fn is_outdated(&self) -> bool {
+		self.latest_version
+			.as_deref()
+			.is_some_and(|latest_version| support::is_version_newer(&self.pack_toml.version, latest_version))
+	}
+}
+
+/// A version change of an installed pack (upgrade or rollback)
+pub struct PackVersionChange {
+	pub identity: String,
+	pub from_version: String,
+	pub to_version: String,
+}
+
+impl std::fmt::Display for PackVersionChange {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} {} -> {}", self.identity, self.from_version, self.to_version)
+	}
+}
+
+/// List the installed packs, sorted by identity
+pub fn list_installed_packs(dir_context: &DirContext) -> Result<Vec<PackToml>> {
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	if !installed_dir.exists() {
+		return Ok(Vec::new());
+	}
+
+	let mut pack_tomls = Vec::new();
+	for ns_entry in fs::read_dir(&installed_dir)?.flatten() {
+		if !ns_entry.path().is_dir() {
+			continue;
+		}
+		for pack_entry in fs::read_dir(ns_entry.path())?.flatten() {
+			let (namespace, name) = (ns_entry.file_name(), pack_entry.file_name());
+			let identity = format!("{}@{}", namespace.to_string_lossy(), name.to_string_lossy());
+			let Ok(pack_identity) = PackIdentity::from_str(&identity) else {
+				continue;
+			};
+			if let Some(pack_toml) = load_installed_pack_toml(dir_context, &pack_identity)? {
+				pack_tomls.push(pack_toml);
+			}
+		}
+	}
+	pack_tomls.sort_by_key(|p| p.identity().to_string());
+
+	Ok(pack_tomls)
+}
+
+/// Uninstall the pack, and remove it from the workspace pack.lock
+///
+/// Fails if other installed packs depend on it, unless `force`
+pub fn uninstall_pack(dir_context: &DirContext, pack_identity: &str, force: bool) -> Result<PackToml> {
+	let pack_identity = PackIdentity::from_str(pack_identity)?;
+	let identity = pack_identity.to_string();
+	let pack_toml = load_installed_pack_toml(dir_context, &pack_identity)?
+		.ok_or_else(|| Error::custom(format!("Pack {identity} is not installed")))?;
+
+	// -- Check the installed packs depending on it
+	if !force {
+		let dependents: Vec<String> = list_installed_packs(dir_context)?
+			.into_iter()
+			.filter(|p| p.dependencies.iter().any(|d| d.identity.to_string() == identity))
+			.map(|p| p.identity().to_string())
+			.collect();
+		if !dependents.is_empty() {
+			return Err(Error::custom(format!(
+				"Pack {identity} is required by {}.\nUse '--force' to uninstall it anyway.",
+				dependents.join(", ")
+			)));
+		}
+	}
+
+	// -- Remove the pack dir (and the namespace dir if now empty)
+	let pack_dir = installed_pack_dir(dir_context, &pack_identity)?;
+	fs::remove_dir_all(&pack_dir).map_err(|err| Error::cc(format!("Cannot remove pack dir '{pack_dir}'"), err))?;
+	if let Some(ns_dir) = pack_dir.parent() {
+		if fs::read_dir(&ns_dir).is_ok_and(|mut entries| entries.next().is_none()) {
+			fs::remove_dir(&ns_dir)?;
+		}
+	}
+
+	// -- Remove it from the pack.lock
+	if let Some(mut pack_lock) = PackLock::load(dir_context)? {
+		if pack_lock.remove(&identity).is_some() {
+			pack_lock.save(dir_context)?;
+		}
+	}
+
+	Ok(pack_toml)
+}
+
+/// Check the installed packs against their `latest.toml` in the repo
+///
+/// Note: The packs not installed from a registry (e.g., from a local file) are not checked.
+pub async fn check_outdated_packs(dir_context: &DirContext) -> Result<Vec<InstalledPackStatus>> {
+	let mut statuses = Vec::new();
+	for pack_toml in list_installed_packs(dir_context)? {
+		let non_registry_source = load_non_registry_source(dir_context, &pack_toml.identity())?;
+		let latest = match non_registry_source {
+			Some(_) => None,
+			None => fetch_repo_latest(dir_context, &pack_toml.identity()).await.ok(),
+		};
+		let (latest_version, latest_aipack_url) = latest.unzip();
+		statuses.push(InstalledPackStatus {
+			pack_toml,
+			non_registry_source,
+			latest_version,
+			latest_aipack_url,
+		});
+	}
+
+	Ok(statuses)
+}
+
+/// Upgrade the installed pack (or all the installed packs) to the latest version of the repo
+///
+/// The previous version is kept for `rollback_pack`, and restored if the install fails.
+///
+/// Returns the changes (the packs already up to date are not listed),
+/// or a `Error::PackUpgradeFailed` with the packs upgraded before the failing one.
+pub async fn upgrade_packs(
+	dir_context: &DirContext,
+	pack_identity: Option<&str>,
+	options: &InstallOptions,
+) -> Result<Vec<PackVersionChange>> {
+	// -- The packs to upgrade, with their latest version and .aipack url
+	//    (when all, the packs not from a registry or not found in the registries are ignored)
+	let candidates: Vec<(PackToml, String, String)> = match pack_identity {
+		Some(pack_identity) => {
+			let pack_identity = PackIdentity::from_str(pack_identity)?;
+			let pack_toml = load_installed_pack_toml(dir_context, &pack_identity)?
+				.ok_or_else(|| Error::custom(format!("Pack {pack_identity} is not installed")))?;
+			if let Some(source) = load_non_registry_source(dir_context, &pack_identity)? {
+				return Err(Error::custom(format!(
+					"Pack {pack_identity} was not installed from a registry (installed from '{source}').\nUse 'aip install {pack_identity}' to install it from the registry."
+				)));
+			}
+			let (latest_version, latest_aipack_url) = fetch_repo_latest(dir_context, &pack_identity).await?;
+			vec![(pack_toml, latest_version, latest_aipack_url)]
+		}
+		None => check_outdated_packs(dir_context)
+			.await?
+			.into_iter()
+			.filter_map(|status| {
+				let latest_version = status.latest_version?;
+				let latest_aipack_url = status.latest_aipack_url?;
+				Some((status.pack_toml, latest_version, latest_aipack_url))
+			})
+			.collect(),
+	};
+
+	let mut changes: Vec<PackVersionChange> = Vec::new();
+	for (pack_toml, latest_version, latest_aipack_url) in candidates {
+		if !support::is_version_newer(&pack_toml.version, &latest_version) {
+			continue;
+		}
+		let pack_identity = pack_toml.identity();
+		match upgrade_pack(dir_context, pack_toml, &latest_version, &latest_aipack_url, options).await {
+			Ok(change) => changes.push(change),
+			Err(err) => {
+				return Err(Error::PackUpgradeFailed {
+					pack_identity: pack_identity.to_string(),
+					upgraded: changes.iter().map(|change| change.to_string()).collect(),
+					cause: Box::new(err),
+				});
+			}
+		}
+	}
+
+	Ok(changes)
+}
+
+/// Swap the installed pack with its backup (the version before the last upgrade)
+///
+/// Note: The swapped version becomes the backup, so a rollback can be rolled back.
+pub fn rollback_pack(dir_context: &DirContext, pack_identity: &str) -> Result<PackVersionChange> {
+	let pack_identity = PackIdentity::from_str(pack_identity)?;
+	let identity = pack_identity.to_string();
+	let backup_dir = backup_dir(dir_context, &pack_identity)?;
+	let backup_pack_dir = backup_dir.join(BACKUP_PACK_DIR_NAME);
+	if !backup_pack_dir.exists() {
+		return Err(Error::custom(format!(
+			"No previous version of {identity} to rollback to (only kept after 'aip upgrade')"
+		)));
+	}
+
+	let from_version = load_installed_pack_toml(dir_context, &pack_identity)?
+		.map(|p| p.version)
+		.unwrap_or_else(|| "(none)".to_string());
+
+	// -- Swap the pack dirs
+	let pack_dir = installed_pack_dir(dir_context, &pack_identity)?;
+	let swap_dir = backup_dir.join(format!("{BACKUP_PACK_DIR_NAME}.swap"));
+	if pack_dir.exists() {
+		fs::rename(&pack_dir, &swap_dir)?;
+	}
+	if let Some(ns_dir) = pack_dir.parent() {
+		ensure_dir(ns_dir)?;
+	}
+	fs::rename(&backup_pack_dir, &pack_dir)?;
+	if swap_dir.exists() {
+		fs::rename(&swap_dir, &backup_pack_dir)?;
+	}
+
+	// -- Swap the pack.lock entries
+	let backup_locked_pack = load_backup_locked_pack(&backup_dir)?;
+	let mut pack_lock = PackLock::load(dir_context)?.unwrap_or_default();
+	let current_locked_pack = pack_lock.remove(&identity);
+	save_backup_locked_pack(&backup_dir, current_locked_pack.as_ref())?;
+	if let Some(backup_locked_pack) = backup_locked_pack {
+		pack_lock.upsert(backup_locked_pack);
+	}
+	pack_lock.save(dir_context)?;
+
+	let to_version = load_installed_pack_toml(dir_context, &pack_identity)?
+		.map(|p| p.version)
+		.unwrap_or_default();
+
+	Ok(PackVersionChange {
+		identity,
+		from_version,
+		to_version,
+	})
+}
+
+// region:    --- Upgrade
+
+/// Upgrade the installed pack to this version, keeping the installed version as backup
+/// (the previous backup is only replaced once the new version is installed)
+async fn upgrade_pack(
+	dir_context: &DirContext,
+	pack_toml: PackToml,
+	latest_version: &str,
+	latest_aipack_url: &str,
+	options: &InstallOptions,
+) -> Result<PackVersionChange> {
+	support::validate_version_update(&pack_toml.version, latest_version)?;
+
+	let pack_identity = pack_toml.identity();
+	backup_installed_pack(dir_context, &pack_identity)?;
+	match install_repo_pack(dir_context, &pack_identity, latest_aipack_url, options).await {
+		Ok(installed_pack) => {
+			commit_pack_backup(dir_context, &pack_identity)?;
+			Ok(PackVersionChange {
+				identity: pack_identity.to_string(),
+				from_version: pack_toml.version,
+				to_version: installed_pack.pack_toml.version,
+			})
+		}
+		Err(err) => {
+			restore_installed_pack(dir_context, &pack_identity)?;
+			Err(err)
+		}
+	}
+}
+
+// endregion: --- Upgrade
+
+// region:    --- Backup
+
+/// Move the installed pack (and its pack.lock entry) to the pending backup dir
+///
+/// Note: The previous backup is kept until `commit_pack_backup` (the install of the new version succeeded).
+fn backup_installed_pack(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<()> {
+	let backup_dir = pending_backup_dir(dir_context, pack_identity)?;
+	// Note: The left over of an interrupted upgrade
+	if backup_dir.exists() {
+		fs::remove_dir_all(&backup_dir)?;
+	}
+	ensure_dir(&backup_dir)?;
+
+	let pack_dir = installed_pack_dir(dir_context, pack_identity)?;
+	fs::rename(&pack_dir, backup_dir.join(BACKUP_PACK_DIR_NAME))
+		.map_err(|err| Error::cc(format!("Cannot backup pack dir '{pack_dir}'"), err))?;
+
+	let locked_pack =
+		PackLock::load(dir_context)?.and_then(|pack_lock| pack_lock.get(&pack_identity.to_string()).cloned());
+	save_backup_locked_pack(&backup_dir, locked_pack.as_ref())?;
+
+	Ok(())
+}
+
+/// Replace the previous backup by the pending one (after a successful upgrade)
+fn commit_pack_backup(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<()> {
+	let backup_dir = backup_dir(dir_context, pack_identity)?;
+	if backup_dir.exists() {
+		fs::remove_dir_all(&backup_dir)?;
+	}
+	let pending_backup_dir = pending_backup_dir(dir_context, pack_identity)?;
+	fs::rename(&pending_backup_dir, &backup_dir)
+		.map_err(|err| Error::cc(format!("Cannot save backup dir '{backup_dir}'"), err))?;
+
+	Ok(())
+}
+
+/// Restore the pending backup of a failed upgrade (the pack.lock is only updated on successful install)
+///
+/// Note: The previous backup, if any, is left as is.
+fn restore_installed_pack(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<()> {
+	let pack_dir = installed_pack_dir(dir_context, pack_identity)?;
+	if pack_dir.exists() {
+		fs::remove_dir_all(&pack_dir)?;
+	}
+	let pending_backup_dir = pending_backup_dir(dir_context, pack_identity)?;
+	fs::rename(pending_backup_dir.join(BACKUP_PACK_DIR_NAME), &pack_dir)
+		.map_err(|err| Error::cc(format!("Cannot restore pack dir '{pack_dir}'"), err))?;
+	fs::remove_dir_all(&pending_backup_dir)?;
+
+	Ok(())
+}
+
+fn load_backup_locked_pack(backup_dir: &SPath) -> Result<Option<LockedPack>> {
+	let file = backup_dir.join(BACKUP_LOCKED_PACK_FILE_NAME);
+	if !file.exists() {
+		return Ok(None);
+	}
+	let content = fs::read_to_string(&file)?;
+	Ok(Some(toml::from_str(&content)?))
+}
+
+fn save_backup_locked_pack(backup_dir: &SPath, locked_pack: Option<&LockedPack>) -> Result<()> {
+	let file = backup_dir.join(BACKUP_LOCKED_PACK_FILE_NAME);
+	match locked_pack {
+		Some(locked_pack) => {
+			let content =
+				toml::to_string(locked_pack).map_err(|err| Error::cc("Cannot serialize the locked pack", err))?;
+			fs::write(&file, content)?;
+		}
+		None if file.exists() => fs::remove_file(&file)?,
+		None => (),
+	}
+	Ok(())
+}
+
+// endregion: --- Backup
+
+// region:    --- Support
+
+/// The source of the installed pack when it was not installed from a registry (None when from a registry)
+///
+/// Note: The packs without install record (installed by an older aip) are considered from a registry.
+fn load_non_registry_source(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<Option<String>> {
+	let pack_dir = installed_pack_dir(dir_context, pack_identity)?;
+	let source = InstallRecord::load(&pack_dir)?
+		.map(|record| record.source)
+		.filter(|source| !is_registry_pack_url(source, pack_identity));
+	Ok(source)
+}
+
+fn installed_pack_dir(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<SPath> {
+	let installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	Ok(installed_dir.join(&pack_identity.namespace).join(&pack_identity.name))
+}
+
+fn backup_dir(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<SPath> {
+	let backup_dir = dir_context.aipack_paths().get_base_pack_backup_dir()?;
+	Ok(backup_dir.join(&pack_identity.namespace).join(&pack_identity.name))
+}
+
+/// The backup of an upgrade in progress (e.g., `.backup/ns/name.pending`)
+fn pending_backup_dir(dir_context: &DirContext, pack_identity: &PackIdentity) -> Result<SPath> {
+	let backup_dir = dir_context.aipack_paths().get_base_pack_backup_dir()?;
+	Ok(backup_dir
+		.join(&pack_identity.namespace)
+		.join(format!("{}.pending", pack_identity.name)))
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_installed_packs.rs"]
+mod tests_installed_packs;
+
+// endregion: --- Tests
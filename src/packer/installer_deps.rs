@@ -0,0 +1,185 @@
+//! Resolution of the pack `[dependencies]` (transitively), before anything gets installed.
+//!
+//! A dependency with a `uri` is fetched from it (local file or http link), otherwise, from the repo.
+//! A dependency already installed with a matching version is kept as is (and locked from its install record).
+
+use crate::dir_context::DirContext;
+use crate::packer::install_record::InstallRecord;
+use crate::packer::installer_impl::{FetchedPack, PackUri, fetch_pack_file, load_installed_pack_toml};
+use crate::packer::pack_lock::LockedPack;
+use crate::packer::support;
+use crate::packer::{PackDependency, PackToml};
+use crate::{Error, Result};
+use simple_fs::SPath;
+use std::collections::{HashMap, VecDeque};
+
+/// A dependency pack fetched and validated, ready to be installed
+pub(super) struct ResolvedPack {
+	pub aipack_file: SPath,
+	pub pack_uri: PackUri,
+	/// The uri of this exact .aipack file (for the pack.lock)
+	pub source: String,
+	pub pack_toml: PackToml,
+}
+
+/// The result of the dependency resolution
+pub(super) struct ResolvedDependencies {
+	/// The packs to install, in resolution order (the dependencies of a pack come after it)
+	pub to_install: Vec<ResolvedPack>,
+	/// The dependencies already installed with a matching version, for the pack.lock
+	pub installed: Vec<LockedPack>,
+}
+
+/// A version requirement on a pack, with the pack requiring it (for the error messages)
+struct Requirement {
+	required_by: String,
+	version_req: String,
+}
+
+/// Resolve the dependencies of the pack transitively.
+///
+/// Returns the packs to install, and the dependencies already installed (with their lock entry).
+///
+/// Note: An installed dependency without install record (e.g., installed by an older aip) is fetched again,
+///       so that its source and SHA-256 are known.
+///
+/// Fails (without installing anything) when:
+/// - A dependency cannot be fetched, or does not satisfy its version requirement.
+/// - Two packs require incompatible versions of the same pack.
+pub(super) async fn resolve_dependencies(
+	dir_context: &DirContext,
+	pack_toml: &PackToml,
+) -> Result<ResolvedDependencies> {
+	// The version selected for each pack identity (the root pack, the installed ones, and the fetched ones)
+	let mut resolved_versions: HashMap<String, String> = HashMap::new();
+	let mut requirements: HashMap<String, Vec<Requirement>> = HashMap::new();
+	let mut resolved_packs: Vec<ResolvedPack> = Vec::new();
+	let mut installed_packs: Vec<LockedPack> = Vec::new();
+
+	resolved_versions.insert(pack_toml.identity().to_string(), pack_toml.version.clone());
+
+	let mut queue: VecDeque<(String, PackDependency)> = pack_toml
+		.dependencies
+		.iter()
+		.map(|dependency| (required_by(pack_toml), dependency.clone()))
+		.collect();
+
+	while let Some((required_by_pack, dependency)) = queue.pop_front() {
+		let identity = dependency.identity.to_string();
+		let pack_requirements = requirements.entry(identity.clone()).or_default();
+		pack_requirements.push(Requirement {
+			required_by: required_by_pack.clone(),
+			version_req: dependency.version_req.to_string(),
+		});
+
+		// -- Already resolved, the version must match this requirement as well
+		if let Some(version) = resolved_versions.get(&identity) {
+			if !support::version_matches(version, &dependency.version_req) {
+				return Err(conflict_error(&identity, version, pack_requirements));
+			}
+			continue;
+		}
+
+		// -- Already installed with a matching version
+		if let Some(installed_pack_toml) = load_installed_pack_toml(dir_context, &dependency.identity)? {
+			if support::version_matches(&installed_pack_toml.version, &dependency.version_req) {
+				if let Some(install_record) = load_install_record(dir_context, &installed_pack_toml)? {
+					resolved_versions.insert(identity, installed_pack_toml.version.clone());
+					for sub_dependency in installed_pack_toml.dependencies.iter() {
+						queue.push_back((required_by(&installed_pack_toml), sub_dependency.clone()));
+					}
+					installed_packs.push(LockedPack::new(
+						&installed_pack_toml,
+						install_record.lock_source(dir_context)?,
+						install_record.sha256,
+					));
+					continue;
+				}
+			}
+		}
+
+		// -- Fetch the dependency (from its uri, or from the repo)
+		let unresolved = |cause: String| Error::InstallDependencyUnresolved {
+			pack_identity: identity.clone(),
+			version_req: dependency.version_req.to_string(),
+			required_by: required_by_pack.clone(),
+			cause,
+		};
+
+		let pack_uri = match dependency.uri.as_deref() {
+			Some(uri) => PackUri::parse(uri).map_err(|err| unresolved(err.to_string()))?,
+			None => PackUri::RepoPack(dependency.identity.clone()),
+		};
+		let FetchedPack {
+			aipack_file,
+			pack_uri,
+			source,
+		} = fetch_pack_file(dir_context, pack_uri)
+			.await
+			.map_err(|err| unresolved(err.to_string()))?;
+		support::validate_aipack_file(&aipack_file, &pack_uri.to_string())
+			.map_err(|err| unresolved(err.to_string()))?;
+		let dependency_pack_toml =
+			support::extract_pack_toml_from_pack_file(&aipack_file).map_err(|err| unresolved(err.to_string()))?;
+
+		let fetched_identity = dependency_pack_toml.identity().to_string();
+		if fetched_identity != identity {
+			return Err(unresolved(format!("{pack_uri} is the pack {fetched_identity}")));
+		}
+		if !support::version_matches(&dependency_pack_toml.version, &dependency.version_req) {
+			return Err(unresolved(format!(
+				"{pack_uri} has the version {}",
+				dependency_pack_toml.version
+			)));
+		}
+
+		// -- Resolved, now, its own dependencies
+		resolved_versions.insert(identity, dependency_pack_toml.version.clone());
+		for sub_dependency in dependency_pack_toml.dependencies.iter() {
+			queue.push_back((required_by(&dependency_pack_toml), sub_dependency.clone()));
+		}
+		resolved_packs.push(ResolvedPack {
+			aipack_file,
+			pack_uri,
+			source,
+			pack_toml: dependency_pack_toml,
+		});
+	}
+
+	Ok(ResolvedDependencies {
+		to_install: resolved_packs,
+		installed: installed_packs,
+	})
+}
+
+// region:    --- Support
+
+fn required_by(pack_toml: &PackToml) -> String {
+	format!("{} v{}", pack_toml.identity(), pack_toml.version)
+}
+
+fn load_install_record(dir_context: &DirContext, pack_toml: &PackToml) -> Result<Option<InstallRecord>> {
+	let pack_dir = dir_context
+		.aipack_paths()
+		.get_base_pack_installed_dir()?
+		.join(&pack_toml.namespace)
+		.join(&pack_toml.name);
+	InstallRecord::load(&pack_dir)
+}
+
+fn conflict_error(identity: &str, resolved_version: &str, requirements: &[Requirement]) -> Error {
+	let mut details = format!("Resolved version: {resolved_version}");
+	for requirement in requirements {
+		details.push_str(&format!(
+			"\n  - '{}' required by {}",
+			requirement.version_req, requirement.required_by
+		));
+	}
+
+	Error::InstallDependencyConflict {
+		pack_identity: identity.to_string(),
+		requirements: details,
+	}
+}
+
+// endregion: --- Support
@@ -0,0 +1,258 @@
+//! Install the packs from a git repository (`aip install git+https://host/repo.git#v1.0.0`)
+//!
+//! The uri is `git+{url}[#{ref}][:{subdir}]`
+//! - `url` any url the `git` command supports (e.g., `https://...`, `file:///path/to/repo.git`)
+//! - `ref` a tag, branch, or commit (the default branch when absent)
+//! - `subdir` the dir of the `pack.toml` in the repository (the root when absent)
+//!
+//! The repository is cloned (or fetched) in `~/.aipack-base/pack/.git-cache/`, the commit of the ref is checked out,
+//! and the pack dir is packed into a .aipack file, which then follows the same install path as the other packs.
+//! The source recorded in the pack.lock is the uri with the commit hash as the ref.
+//!
+//! Note: Requires the `git` command.
+
+use crate::dir_context::DirContext;
+use crate::packer::packer_impl::pack_dir;
+use crate::{Error, Result};
+use sha2::{Digest, Sha256};
+use simple_fs::{SPath, ensure_dir};
+use std::fs;
+use std::process::Command;
+
+const GIT_URI_PREFIX: &str = "git+";
+
+/// The parsed `git+{url}[#{ref}][:{subdir}]` pack uri
+#[derive(Debug, Clone)]
+pub struct GitSource {
+	pub url: String,
+	pub git_ref: Option<String>,
+	pub subdir: Option<String>,
+}
+
+impl GitSource {
+	/// Returns None if the uri does not start with `git+`
+	///
+	/// Returns an error if the url or the ref starts with `-` (would be taken as a git option),
+	/// or if the subdir is absolute or has a `..` (would be outside of the checkout)
+	pub fn parse(uri: &str) -> Result<Option<Self>> {
+		let Some(git_uri) = uri.strip_prefix(GIT_URI_PREFIX) else {
+			return Ok(None);
+		};
+		let (url, fragment) = match git_uri.split_once('#') {
+			Some((url, fragment)) => (url, Some(fragment)),
+			None => (git_uri, None),
+		};
+		// Note: A git ref cannot contain `:`
+		let (git_ref, subdir) = match fragment.map(|fragment| fragment.split_once(':')) {
+			Some(Some((git_ref, subdir))) => (git_ref, Some(subdir)),
+			Some(None) => (fragment.unwrap_or_default(), None),
+			None => ("", None),
+		};
+		let non_empty = |value: &str| {
+			let value = value.trim().trim_matches('/');
+			(!value.is_empty()).then(|| value.to_string())
+		};
+
+		let git_source = GitSource {
+			url: url.trim().to_string(),
+			git_ref: non_empty(git_ref),
+			subdir: subdir.and_then(non_empty),
+		};
+		let invalid = [Some(git_source.url.as_str()), git_source.git_ref.as_deref()]
+			.into_iter()
+			.flatten()
+			.find(|value| value.is_empty() || value.starts_with('-'));
+		if let Some(invalid) = invalid {
+			return Err(Error::FailToInstall {
+				aipack_ref: uri.to_string(),
+				cause: format!("Invalid git url or ref '{invalid}' (cannot be empty or start with '-')"),
+			});
+		}
+		// Note: Checked before the trim, as `/etc` would become `etc`
+		if let Some(subdir) = subdir.map(str::trim) {
+			let is_absolute = subdir.starts_with(['/', '\\']) || subdir.contains(':');
+			if is_absolute || subdir.split(['/', '\\']).any(|part| part == "..") {
+				return Err(Error::FailToInstall {
+					aipack_ref: uri.to_string(),
+					cause: format!("Invalid git subdir '{subdir}' (must be relative, without '..')"),
+				});
+			}
+		}
+
+		Ok(Some(git_source))
+	}
+
+	/// The uri of this exact commit (for the pack.lock)
+	fn to_uri_with_commit(&self, commit: &str) -> String {
+		match self.subdir.as_deref() {
+			Some(subdir) => format!("{GIT_URI_PREFIX}{}#{commit}:{subdir}", self.url),
+			None => format!("{GIT_URI_PREFIX}{}#{commit}", self.url),
+		}
+	}
+}
+
+impl std::fmt::Display for GitSource {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.url)?;
+		if let Some(git_ref) = self.git_ref.as_deref() {
+			write!(f, "#{git_ref}")?;
+		}
+		if let Some(subdir) = self.subdir.as_deref() {
+			write!(f, " (dir '{subdir}')")?;
+		}
+		Ok(())
+	}
+}
+
+/// Checkout the git source in the cache, and pack its pack dir
+///
+/// Returns the .aipack file, and the source for the pack.lock (with the commit hash)
+///
+/// Note: Runs the blocking git commands, so call it with `tokio::task::spawn_blocking` from async code.
+pub(super) fn fetch_git_pack(dir_context: &DirContext, git_source: &GitSource) -> Result<(SPath, String)> {
+	let aipack_ref = format!("git '{git_source}'");
+	let fail = |cause: String| Error::FailToInstall {
+		aipack_ref: aipack_ref.clone(),
+		cause,
+	};
+
+	// -- Clone or fetch the repository in the cache
+	let cache_dir = dir_context.aipack_paths().get_base_pack_git_cache_dir()?;
+	ensure_dir(&cache_dir)?;
+	let repo_dir = cache_dir.join(url_cache_name(&git_source.url));
+	if repo_dir.join(".git").exists() {
+		run_git(
+			Some(&repo_dir),
+			&[
+				"fetch",
+				"--quiet",
+				"--force",
+				"--tags",
+				"--prune",
+				"origin",
+				"+refs/heads/*:refs/remotes/origin/*",
+			],
+		)
+		.map_err(fail)?;
+		if git_source.git_ref.is_none() {
+			// The default branch might have changed
+			run_git(Some(&repo_dir), &["remote", "set-head", "origin", "--auto"]).map_err(fail)?;
+		}
+	} else {
+		if repo_dir.exists() {
+			fs::remove_dir_all(&repo_dir)?;
+		}
+		if let Err(cause) = run_git(
+			None,
+			&["clone", "--quiet", "--no-checkout", "--", &git_source.url, repo_dir.to_str()],
+		) {
+			let _ = fs::remove_dir_all(&repo_dir);
+			return Err(fail(cause));
+		}
+	}
+
+	// -- Checkout the commit of the ref
+	// Note: For a branch, the fetched `origin/branch` first, as the local branch is not updated by the fetch
+	let candidates = match git_source.git_ref.as_deref() {
+		Some(git_ref) => vec![format!("origin/{git_ref}"), git_ref.to_string()],
+		None => vec!["origin/HEAD".to_string()],
+	};
+	let commit = candidates
+		.iter()
+		.find_map(|candidate| {
+			run_git(
+				Some(&repo_dir),
+				&["rev-parse", "--verify", "--quiet", &format!("{candidate}^{{commit}}")],
+			)
+			.ok()
+		})
+		.ok_or_else(|| {
+			fail(format!(
+				"ref '{}' not found in the repository",
+				git_source.git_ref.as_deref().unwrap_or("HEAD")
+			))
+		})?;
+	run_git(
+		Some(&repo_dir),
+		&[
+			"-c",
+			"advice.detachedHead=false",
+			"checkout",
+			"--quiet",
+			"--force",
+			"--detach",
+			&commit,
+		],
+	)
+	.map_err(fail)?;
+	run_git(Some(&repo_dir), &["clean", "--quiet", "-ffdx"]).map_err(fail)?;
+
+	// -- Pack the pack dir
+	let pack_src_dir = match git_source.subdir.as_deref() {
+		Some(subdir) => repo_dir.join(subdir),
+		None => repo_dir.clone(),
+	};
+	if !pack_src_dir.join("pack.toml").exists() {
+		return Err(fail(format!(
+			"No pack.toml in the {} of commit {commit}",
+			git_source
+				.subdir
+				.as_deref()
+				.map(|subdir| format!("dir '{subdir}'"))
+				.unwrap_or_else(|| "repository root".to_string())
+		)));
+	}
+	let download_dir = dir_context.aipack_paths().get_base_pack_download_dir()?;
+	let pack_data = pack_dir(&pack_src_dir, &download_dir).map_err(|err| fail(err.to_string()))?;
+
+	Ok((pack_data.pack_file, git_source.to_uri_with_commit(&commit)))
+}
+
+// region:    --- Support
+
+/// The cache dir name of the repository url (readable name, and a hash of the url)
+fn url_cache_name(url: &str) -> String {
+	let name: String = url
+		.trim_end_matches('/')
+		.trim_end_matches(".git")
+		.rsplit('/')
+		.next()
+		.unwrap_or_default()
+		.chars()
+		.filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+		.collect();
+	let hash = Sha256::digest(url.as_bytes());
+	let hash: String = hash.iter().take(8).map(|b| format!("{b:02x}")).collect();
+	format!("{name}-{hash}")
+}
+
+/// Run the git command, and returns its stdout (trimmed), or the stderr as error
+fn run_git(cwd: Option<&SPath>, args: &[&str]) -> core::result::Result<String, String> {
+	let mut cmd = Command::new("git");
+	if let Some(cwd) = cwd {
+		cmd.current_dir(cwd.path());
+	}
+	// Fail rather than prompting for the credentials
+	cmd.env("GIT_TERMINAL_PROMPT", "0");
+	let output = cmd
+		.args(args)
+		.output()
+		.map_err(|err| format!("Cannot execute 'git' (is git installed?). Cause: {err}"))?;
+
+	if output.status.success() {
+		Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+	} else {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		Err(format!("'git {}' failed: {}", args.join(" "), stderr.trim()))
+	}
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_installer_git.rs"]
+mod tests_installer_git;
+
+// endregion: --- Tests
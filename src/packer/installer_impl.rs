@@ -1,6 +1,12 @@
 use crate::dir_context::DirContext;
 use crate::pack::PackIdentity;
 use crate::packer::PackToml;
+use crate::packer::install_record::InstallRecord;
+use crate::packer::installer_deps::{ResolvedDependencies, resolve_dependencies};
+use crate::packer::installer_git::{GitSource, fetch_git_pack};
+use crate::packer::pack_lock::{LockedPack, PackLock};
+use crate::packer::pack_registry::{load_registries, registry_pack_base_url};
+use crate::packer::pack_signature::{SIGNATURE_FILE_NAME, verify_aipack_file_for_install};
 use crate::packer::pack_toml::parse_validate_pack_toml;
 use crate::packer::support;
 use crate::support::zip;
@@ -19,22 +25,30 @@ pub enum PackUri {
 	RepoPack(PackIdentity),
 	LocalPath(String),
 	HttpLink(String),
+	/// `git+{url}[#{ref}][:{subdir}]`
+	Git(GitSource),
 }
 
 impl PackUri {
-	pub fn parse(uri: &str) -> Self {
+	/// Returns an error for an invalid `git+` uri (see `GitSource::parse`)
+	pub fn parse(uri: &str) -> Result<Self> {
 		// Try to parse as PackIdentity first
 		if let Ok(pack_identity) = PackIdentity::from_str(uri) {
-			return PackUri::RepoPack(pack_identity);
+			return Ok(PackUri::RepoPack(pack_identity));
+		}
+
+		if let Some(git_source) = GitSource::parse(uri)? {
+			return Ok(PackUri::Git(git_source));
 		}
 
 		// If not a PackIdentity, check if it's an HTTP link
-		if uri.starts_with("http://") || uri.starts_with("https://") {
+		let pack_uri = if uri.starts_with("http://") || uri.starts_with("https://") {
 			PackUri::HttpLink(uri.to_string())
 		} else {
 			// Otherwise, treat as local path
 			PackUri::LocalPath(uri.to_string())
-		}
+		};
+		Ok(pack_uri)
 	}
 }
 
@@ -44,6 +58,7 @@ impl std::fmt::Display for PackUri {
 			PackUri::RepoPack(identity) => write!(f, "{}", identity),
 			PackUri::LocalPath(path) => write!(f, "local file '{}'", path),
 			PackUri::HttpLink(url) => write!(f, "URL '{}'", url),
+			PackUri::Git(git_source) => write!(f, "git '{}'", git_source),
 		}
 	}
 }
@@ -95,98 +110,315 @@ pub struct InstalledPack {
 	#[allow(unused)]
 	pub size: usize,
 	pub zip_size: usize,
+	/// The dependencies installed with this pack (the ones already installed are not listed)
+	pub dependencies: Vec<InstalledPack>,
+}
+
+/// The options of `install_pack`
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+	/// Install the packs that are not signed, or signed by a key not in the trusted keys
+	/// (packs with an invalid signature are always refused)
+	pub allow_unsigned: bool,
 }
 
 /// Install a `file.aipack` into the .aipack-base/pack/installed directory
 ///
-/// IMPORTANT: Right now, very prelimealy. Should do the following:
+/// The signature of the pack (and of its dependencies) is verified against the trusted keys
+/// of the `~/.aipack-base/config.toml` (see `InstallOptions::allow_unsigned`).
+///
+/// The `[dependencies]` of the pack are resolved transitively (local files, http links, git, and repo)
+/// and validated before any file gets installed. The dependencies already installed with a matching version are kept.
 ///
 /// TODO:
-/// - Check for an existing installed pack.
 /// - If an already installed pack has a semver greater than the new one,
 ///   return an error so that the caller can handle it with a prompt, and then provide a force flag, for example.
-/// - Probably need to remove the existing pack files; otherwise, some leftover files can be an issue.
 ///
 /// Returns the InstalledPack with information about the installed pack.
-pub async fn install_pack(dir_context: &DirContext, pack_uri: &str) -> Result<InstalledPack> {
-	let pack_uri = PackUri::parse(pack_uri);
+pub async fn install_pack(dir_context: &DirContext, pack_uri: &str, options: &InstallOptions) -> Result<InstalledPack> {
+	let pack_uri = PackUri::parse(pack_uri)?;
 
 	// Get the aipack file path, downloading if needed
-	let (aipack_zipped_file, pack_uri) = match pack_uri {
-		pack_uri @ PackUri::RepoPack(_) => download_from_repo(dir_context, pack_uri).await?,
-		pack_uri @ PackUri::LocalPath(_) => resolve_local_path(dir_context, pack_uri)?,
-		pack_uri @ PackUri::HttpLink(_) => download_pack(dir_context, pack_uri).await?,
-	};
+	let fetched_pack = fetch_pack_file(dir_context, pack_uri).await?;
+
+	install_fetched_pack(dir_context, fetched_pack, options).await
+}
+
+/// Install the repo pack from the .aipack url of its `latest.toml` (see `fetch_repo_latest`), without fetching it again
+pub(super) async fn install_repo_pack(
+	dir_context: &DirContext,
+	pack_identity: &PackIdentity,
+	aipack_url: &str,
+	options: &InstallOptions,
+) -> Result<InstalledPack> {
+	let fetched_pack = fetch_repo_pack_file(dir_context, pack_identity, aipack_url).await?;
+
+	install_fetched_pack(dir_context, fetched_pack, options).await
+}
+
+/// Install the fetched .aipack file, with its dependencies (see `install_pack`)
+async fn install_fetched_pack(
+	dir_context: &DirContext,
+	fetched_pack: FetchedPack,
+	options: &InstallOptions,
+) -> Result<InstalledPack> {
+	let FetchedPack {
+		aipack_file: aipack_zipped_file,
+		pack_uri,
+		source,
+	} = fetched_pack;
 
 	// Validate file exists and has correct extension
 	support::validate_aipack_file(&aipack_zipped_file, &pack_uri.to_string())?;
+	verify_aipack_file_for_install(
+		dir_context,
+		&aipack_zipped_file,
+		&pack_uri.to_string(),
+		options.allow_unsigned,
+	)?;
+
+	// -- Resolve the dependencies and validate everything before installing anything
+	let pack_toml = support::extract_pack_toml_from_pack_file(&aipack_zipped_file)?;
+	let ResolvedDependencies {
+		to_install: resolved_dependencies,
+		installed: installed_dependencies,
+	} = resolve_dependencies(dir_context, &pack_toml).await?;
+	validate_pack_install(dir_context, &pack_toml, &pack_uri)?;
+	for resolved in resolved_dependencies.iter() {
+		verify_aipack_file_for_install(
+			dir_context,
+			&resolved.aipack_file,
+			&resolved.pack_uri.to_string(),
+			options.allow_unsigned,
+		)?;
+		validate_pack_install(dir_context, &resolved.pack_toml, &resolved.pack_uri)?;
+	}
+
+	// -- Install the dependencies (the deepest ones first)
+	// Note: The dependencies already installed are locked as they were installed
+	let mut locked_packs = installed_dependencies;
+	let mut dependencies = Vec::with_capacity(resolved_dependencies.len());
+	for resolved in resolved_dependencies.into_iter().rev() {
+		let zip_size = support::get_file_size(&resolved.aipack_file, &resolved.pack_uri.to_string())?;
+		let sha256 = support::compute_file_sha256(&resolved.aipack_file)?;
+		let mut installed_dependency =
+			install_aipack_file(dir_context, &resolved.aipack_file, &resolved.pack_uri, &resolved.source)?;
+		installed_dependency.zip_size = zip_size;
+		locked_packs.push(LockedPack::new(
+			&installed_dependency.pack_toml,
+			resolved.source,
+			sha256,
+		));
+		dependencies.push(installed_dependency);
+	}
 
 	// Get the zip file size
 	let zip_size = support::get_file_size(&aipack_zipped_file, &pack_uri.to_string())?;
+	let sha256 = support::compute_file_sha256(&aipack_zipped_file)?;
 
 	// Common installation steps for both local and remote files
-	let mut installed_pack = install_aipack_file(dir_context, &aipack_zipped_file, &pack_uri)?;
+	let mut installed_pack = install_aipack_file(dir_context, &aipack_zipped_file, &pack_uri, &source)?;
 	installed_pack.zip_size = zip_size;
+	installed_pack.dependencies = dependencies;
+	locked_packs.push(LockedPack::new(&installed_pack.pack_toml, source, sha256));
+
+	// -- Record the installed packs in the workspace pack.lock
+	let mut pack_lock = PackLock::load(dir_context)?.unwrap_or_default();
+	for locked_pack in locked_packs {
+		pack_lock.upsert(locked_pack);
+	}
+	pack_lock.save(dir_context)?;
 
 	Ok(installed_pack)
 }
 
-/// Downloads a pack from the repository based on PackIdentity
-async fn download_from_repo(dir_context: &DirContext, pack_uri: PackUri) -> Result<(SPath, PackUri)> {
-	if let PackUri::RepoPack(ref pack_identity) = pack_uri {
-		// Construct the URL to the latest.toml file
-		let latest_toml_url = format!(
-			"https://repo.aipack.ai/pack/{}/{}/stable/latest.toml",
-			pack_identity.namespace, pack_identity.name
-		);
+/// The .aipack file of a pack uri (downloaded if needed)
+pub(super) struct FetchedPack {
+	pub aipack_file: SPath,
+	pub pack_uri: PackUri,
+	/// The uri of this exact .aipack file (for the pack.lock)
+	/// e.g., the .aipack url for a repo pack, or the path relative to the workspace for a local file
+	pub source: String,
+}
 
-		// Fetch the latest.toml file
-		let client = Client::new();
-		let response = client.get(&latest_toml_url).send().await.map_err(|e| Error::FailToInstall {
-			aipack_ref: pack_uri.to_string(),
-			cause: format!("Failed to download latest.toml: {}", e),
-		})?;
+/// Get the aipack file for this pack uri, downloading it if needed
+pub(super) async fn fetch_pack_file(dir_context: &DirContext, pack_uri: PackUri) -> Result<FetchedPack> {
+	let fetched_pack = match pack_uri {
+		PackUri::RepoPack(pack_identity) => {
+			let (_version, aipack_url) = fetch_repo_latest(dir_context, &pack_identity).await?;
+			fetch_repo_pack_file(dir_context, &pack_identity, &aipack_url).await?
+		}
+		pack_uri @ PackUri::LocalPath(_) => {
+			let (aipack_file, pack_uri) = resolve_local_path(dir_context, pack_uri)?;
+			let source = wks_relative_source(dir_context, &aipack_file);
+			FetchedPack {
+				aipack_file,
+				pack_uri,
+				source,
+			}
+		}
+		PackUri::HttpLink(url) => {
+			let (aipack_file, pack_uri) = download_pack(dir_context, PackUri::HttpLink(url.clone())).await?;
+			FetchedPack {
+				aipack_file,
+				pack_uri,
+				source: url,
+			}
+		}
+		PackUri::Git(git_source) => {
+			let (dir_context, git_source_for_fetch) = (dir_context.clone(), git_source.clone());
+			let (aipack_file, source) =
+				tokio::task::spawn_blocking(move || fetch_git_pack(&dir_context, &git_source_for_fetch))
+					.await
+					.map_err(|err| Error::FailToInstall {
+						aipack_ref: format!("git '{git_source}'"),
+						cause: err.to_string(),
+					})??;
+			FetchedPack {
+				aipack_file,
+				pack_uri: PackUri::Git(git_source),
+				source,
+			}
+		}
+	};
 
-		// Check if the request was successful
-		if !response.status().is_success() {
-			return Err(Error::FailToInstall {
-				aipack_ref: pack_uri.to_string(),
-				cause: format!("HTTP error when fetching latest.toml: {}", response.status()),
-			});
+	Ok(fetched_pack)
+}
+
+/// The pack uri of a pack.lock source (a relative local path is relative to the workspace dir)
+pub(super) fn pack_uri_from_source(dir_context: &DirContext, source: &str) -> Result<PackUri> {
+	let pack_uri = match PackUri::parse(source)? {
+		PackUri::LocalPath(path) if SPath::new(&path).path().is_relative() => {
+			PackUri::LocalPath(dir_context.wks_dir().join(&path).to_string())
 		}
+		pack_uri => pack_uri,
+	};
+	Ok(pack_uri)
+}
 
-		// Parse the latest.toml content
-		let latest_toml_content = response.text().await.map_err(|e| Error::FailToInstall {
-			aipack_ref: pack_uri.to_string(),
-			cause: format!("Failed to read latest.toml content: {}", e),
-		})?;
+/// The local path relative to the workspace dir when inside it, so that the pack.lock can be shared
+pub(super) fn wks_relative_source(dir_context: &DirContext, aipack_file: &SPath) -> String {
+	let wks_dir = dir_context.wks_dir();
+	let wks_dir = wks_dir.canonicalize().unwrap_or_else(|_| wks_dir.clone());
+	match aipack_file.path().strip_prefix(wks_dir.path()) {
+		Ok(rel_path) => rel_path.to_string(),
+		Err(_) => aipack_file.to_string(),
+	}
+}
 
-		let latest_toml: LatestToml = toml::from_str(&latest_toml_content).map_err(|e| Error::FailToInstall {
-			aipack_ref: pack_uri.to_string(),
-			cause: format!("Failed to parse latest.toml: {}", e),
-		})?;
+/// Read the pack.toml of the installed pack with this identity, if installed
+pub(super) fn load_installed_pack_toml(
+	dir_context: &DirContext,
+	pack_identity: &PackIdentity,
+) -> Result<Option<PackToml>> {
+	let pack_installed_dir = dir_context.aipack_paths().get_base_pack_installed_dir()?;
+	let pack_toml_path = pack_installed_dir
+		.join(&pack_identity.namespace)
+		.join(&pack_identity.name)
+		.join("pack.toml");
 
-		// Validate the latest.toml content
-		let (_version, rel_path) = latest_toml.validate()?;
+	if !pack_toml_path.exists() {
+		return Ok(None);
+	}
 
-		// Construct the full URL to the .aipack file
-		let base_url = format!(
-			"https://repo.aipack.ai/pack/{}/{}/stable/",
-			pack_identity.namespace, pack_identity.name
-		);
+	let toml_content = std::fs::read_to_string(pack_toml_path.path()).map_err(|e| Error::FailToInstall {
+		aipack_ref: pack_identity.to_string(),
+		cause: format!("Failed to read existing pack.toml: {}", e),
+	})?;
+	let pack_toml = parse_validate_pack_toml(&toml_content, pack_toml_path.to_str())?;
 
-		let aipack_url = format!("{}{}", base_url, rel_path);
+	Ok(Some(pack_toml))
+}
 
-		// Use HttpLink to download the actual pack
-		let http_uri = PackUri::HttpLink(aipack_url);
-		let (aipack_file, _) = download_pack(dir_context, http_uri).await?;
+/// Downloads the .aipack file of a repo pack, from the url of its `latest.toml` (see `fetch_repo_latest`)
+async fn fetch_repo_pack_file(
+	dir_context: &DirContext,
+	pack_identity: &PackIdentity,
+	aipack_url: &str,
+) -> Result<FetchedPack> {
+	// Use HttpLink to download the actual pack
+	let http_uri = PackUri::HttpLink(aipack_url.to_string());
+	let (aipack_file, _) = download_pack(dir_context, http_uri).await?;
+
+	Ok(FetchedPack {
+		aipack_file,
+		pack_uri: PackUri::RepoPack(pack_identity.clone()),
+		source: aipack_url.to_string(),
+	})
+}
 
-		return Ok((aipack_file, pack_uri));
+/// Fetch the `latest.toml` of the pack from the registries (see `load_registries`), tried in order
+///
+/// Returns the latest stable version and the url of its .aipack file
+pub(super) async fn fetch_repo_latest(
+	dir_context: &DirContext,
+	pack_identity: &PackIdentity,
+) -> Result<(String, String)> {
+	let registries = load_registries(dir_context)?;
+
+	let mut causes = Vec::with_capacity(registries.len());
+	for registry in registries.iter() {
+		match fetch_registry_latest(registry, pack_identity).await {
+			Ok(latest) => return Ok(latest),
+			Err(err) => causes.push(format!("- {registry}: {}", fail_cause(err))),
+		}
 	}
 
-	Err(Error::custom(
-		"Expected RepoPack variant but got a different one".to_string(),
-	))
+	Err(Error::FailToInstall {
+		aipack_ref: pack_identity.to_string(),
+		cause: format!("Not found in the registries:\n{}", causes.join("\n")),
+	})
+}
+
+/// Fetch the `latest.toml` of the pack from this registry
+async fn fetch_registry_latest(registry: &str, pack_identity: &PackIdentity) -> Result<(String, String)> {
+	let pack_uri = PackUri::RepoPack(pack_identity.clone());
+
+	// Construct the URL to the latest.toml file
+	let base_url = registry_pack_base_url(registry, pack_identity);
+	let latest_toml_url = format!("{base_url}latest.toml");
+
+	// Fetch the latest.toml file
+	let client = Client::new();
+	let response = client.get(&latest_toml_url).send().await.map_err(|e| Error::FailToInstall {
+		aipack_ref: pack_uri.to_string(),
+		cause: format!("Failed to download latest.toml: {}", e),
+	})?;
+
+	// Check if the request was successful
+	if !response.status().is_success() {
+		return Err(Error::FailToInstall {
+			aipack_ref: pack_uri.to_string(),
+			cause: format!("HTTP error when fetching latest.toml: {}", response.status()),
+		});
+	}
+
+	// Parse the latest.toml content
+	let latest_toml_content = response.text().await.map_err(|e| Error::FailToInstall {
+		aipack_ref: pack_uri.to_string(),
+		cause: format!("Failed to read latest.toml content: {}", e),
+	})?;
+
+	let latest_toml: LatestToml = toml::from_str(&latest_toml_content).map_err(|e| Error::FailToInstall {
+		aipack_ref: pack_uri.to_string(),
+		cause: format!("Failed to parse latest.toml: {}", e),
+	})?;
+
+	// Validate the latest.toml content
+	let (version, rel_path) = latest_toml.validate()?;
+
+	// Construct the full URL to the .aipack file
+	let aipack_url = format!("{}{}", base_url, rel_path);
+
+	Ok((version.to_string(), aipack_url))
+}
+
+/// The cause of the error (without the pack reference for a `FailToInstall`)
+fn fail_cause(err: Error) -> String {
+	match err {
+		Error::FailToInstall { cause, .. } => cause,
+		other => other.to_string(),
+	}
 }
 
 /// Resolves a local path to an absolute SPath
@@ -303,6 +535,26 @@ fn install_aipack_file(
 	dir_context: &DirContext,
 	aipack_zipped_file: &SPath,
 	pack_uri: &PackUri,
+	source: &str,
+) -> Result<InstalledPack> {
+	// -- Extract the pack.toml from zip and validate
+	let new_pack_toml = support::extract_pack_toml_from_pack_file(aipack_zipped_file)?;
+	validate_pack_install(dir_context, &new_pack_toml, pack_uri)?;
+
+	// If we've gotten here, either there's no existing pack or the new version is greater than or equal to the installed version
+	unpack_aipack_file(dir_context, aipack_zipped_file, new_pack_toml, pack_uri, source)
+}
+
+/// Unzip the aipack file in the installed pack dir (replacing the eventual installed one),
+/// with its install record (the `source` is the one of the pack.lock)
+///
+/// Note: Does not validate the version against the installed one (see `validate_pack_install`)
+pub(super) fn unpack_aipack_file(
+	dir_context: &DirContext,
+	aipack_zipped_file: &SPath,
+	new_pack_toml: PackToml,
+	pack_uri: &PackUri,
+	source: &str,
 ) -> Result<InstalledPack> {
 	// -- Get the aipack base pack install dir
 	// This is the pack base dir and now, we need ot add `namespace/pack_name`
@@ -322,37 +574,6 @@ fn install_aipack_file(
 		});
 	}
 
-	// -- Extract the pack.toml from zip and validate
-	let new_pack_toml = support::extract_pack_toml_from_pack_file(aipack_zipped_file)?;
-
-	// NEW: Validate prerelease format for installation
-	support::validate_version_for_install(&new_pack_toml.version)?;
-
-	// -- Check if a pack with the same namespace/name is already installed
-	let potential_existing_path = pack_installed_dir.join(&new_pack_toml.namespace).join(&new_pack_toml.name);
-
-	if potential_existing_path.exists() {
-		// If an existing pack is found, we need to check its version
-		let existing_pack_toml_path = potential_existing_path.join("pack.toml");
-
-		if existing_pack_toml_path.exists() {
-			// Read the existing pack.toml file
-			let existing_toml_content =
-				std::fs::read_to_string(existing_pack_toml_path.path()).map_err(|e| Error::FailToInstall {
-					aipack_ref: pack_uri.to_string(),
-					cause: format!("Failed to read existing pack.toml: {}", e),
-				})?;
-
-			// Parse the existing pack.toml
-			let existing_pack_toml =
-				parse_validate_pack_toml(&existing_toml_content, existing_pack_toml_path.to_str())?;
-
-			// Check if the installed version is greater than the new version
-			support::validate_version_update(&existing_pack_toml.version, &new_pack_toml.version)?;
-		}
-	}
-
-	// If we've gotten here, either there's no existing pack or the new version is greater than or equal to the installed version
 	let pack_target_dir = pack_installed_dir.join(&new_pack_toml.namespace).join(&new_pack_toml.name);
 
 	// If the directory exists, remove it first to ensure clean installation
@@ -368,6 +589,15 @@ fn install_aipack_file(
 		cause: format!("Failed to unzip pack: {}", e),
 	})?;
 
+	// The signature was verified before, and is not part of the pack content
+	let signature_file = pack_target_dir.join(SIGNATURE_FILE_NAME);
+	if signature_file.exists() {
+		std::fs::remove_file(signature_file.path())?;
+	}
+
+	let sha256 = support::compute_file_sha256(aipack_zipped_file)?;
+	InstallRecord::new(dir_context, source, sha256)?.save(&pack_target_dir)?;
+
 	// Calculate the size of the installed pack
 	let size = support::calculate_directory_size(&pack_target_dir)?;
 
@@ -376,9 +606,30 @@ fn install_aipack_file(
 		path: pack_target_dir,
 		size,
 		zip_size: 0, // This will be populated by the caller
+		dependencies: Vec::new(),
 	})
 }
 
+/// Validate that this pack can be installed (version format, and not older than the installed one)
+fn validate_pack_install(dir_context: &DirContext, new_pack_toml: &PackToml, pack_uri: &PackUri) -> Result<()> {
+	// Validate prerelease format for installation
+	support::validate_version_for_install(&new_pack_toml.version)?;
+
+	// -- Check if a pack with the same namespace/name is already installed
+	let existing_pack_toml =
+		load_installed_pack_toml(dir_context, &new_pack_toml.identity()).map_err(|e| Error::FailToInstall {
+			aipack_ref: pack_uri.to_string(),
+			cause: format!("Failed to read the installed pack.toml: {}", e),
+		})?;
+
+	// Check if the installed version is greater than the new version
+	if let Some(existing_pack_toml) = existing_pack_toml {
+		support::validate_version_update(&existing_pack_toml.version, &new_pack_toml.version)?;
+	}
+
+	Ok(())
+}
+
 // region:    --- Tests
 
 #[cfg(test)]
@@ -0,0 +1,127 @@
+//! Install the packs exactly as listed in the workspace `.aipack/pack.lock` (`aip sync`, `aip install --locked`)
+
+use crate::dir_context::DirContext;
+use crate::pack::PackIdentity;
+use crate::packer::installer_impl::{
+	FetchedPack, InstallOptions, InstalledPack, fetch_pack_file, load_installed_pack_toml, pack_uri_from_source,
+	unpack_aipack_file,
+};
+use crate::packer::pack_lock::{LockedPack, PackLock};
+use crate::packer::pack_signature::verify_aipack_file_for_install;
+use crate::packer::support;
+use crate::{Error, Result};
+use std::str::FromStr;
+
+/// A pack of the pack.lock, after the sync
+pub struct SyncedPack {
+	pub locked_pack: LockedPack,
+	/// None when the locked version was already installed
+	pub installed: Option<InstalledPack>,
+}
+
+/// Install the packs of the pack.lock, all of them, or only the one with this identity (and its dependencies)
+///
+/// All the packs are fetched and verified (SHA-256, identity, version, and signature like `install_pack`)
+/// before any is installed. The packs already installed at the locked version are kept.
+///
+/// Note: The locked version is installed even if the installed one is newer.
+pub async fn install_locked_packs(
+	dir_context: &DirContext,
+	pack_identity: Option<&str>,
+	options: &InstallOptions,
+) -> Result<Vec<SyncedPack>> {
+	let pack_lock = PackLock::load(dir_context)?.ok_or_else(|| {
+		Error::custom(format!(
+			"No pack.lock found in '{}'. Run 'aip install ...' first to create it.",
+			dir_context.aipack_paths().wks_aipack_dir()
+		))
+	})?;
+
+	let locked_packs = match pack_identity {
+		Some(pack_identity) => pack_lock.with_dependencies(&PackIdentity::from_str(pack_identity)?.to_string())?,
+		None => pack_lock.packs().to_vec(),
+	};
+
+	// -- Fetch and verify all the packs first
+	let mut to_install: Vec<(LockedPack, Option<FetchedPack>)> = Vec::with_capacity(locked_packs.len());
+	for locked_pack in locked_packs {
+		let identity = PackIdentity::from_str(&locked_pack.identity())?;
+		let installed_version = load_installed_pack_toml(dir_context, &identity)?.map(|p| p.version);
+		if installed_version.as_deref() == Some(locked_pack.version.as_str()) {
+			to_install.push((locked_pack, None));
+			continue;
+		}
+
+		let fetched_pack = fetch_locked_pack(dir_context, &locked_pack, options).await?;
+		to_install.push((locked_pack, Some(fetched_pack)));
+	}
+
+	// -- Install
+	let mut synced_packs = Vec::with_capacity(to_install.len());
+	for (locked_pack, fetched_pack) in to_install {
+		let installed = match fetched_pack {
+			Some(fetched_pack) => {
+				let pack_toml = support::extract_pack_toml_from_pack_file(&fetched_pack.aipack_file)?;
+				let zip_size = support::get_file_size(&fetched_pack.aipack_file, &fetched_pack.pack_uri.to_string())?;
+				let mut installed_pack = unpack_aipack_file(
+					dir_context,
+					&fetched_pack.aipack_file,
+					pack_toml,
+					&fetched_pack.pack_uri,
+					&locked_pack.source,
+				)?;
+				installed_pack.zip_size = zip_size;
+				Some(installed_pack)
+			}
+			None => None,
+		};
+		synced_packs.push(SyncedPack { locked_pack, installed });
+	}
+
+	Ok(synced_packs)
+}
+
+/// Fetch the .aipack file of the locked pack, and verify it matches the pack.lock, and its signature
+async fn fetch_locked_pack(
+	dir_context: &DirContext,
+	locked_pack: &LockedPack,
+	options: &InstallOptions,
+) -> Result<FetchedPack> {
+	let identity = locked_pack.identity();
+	let pack_uri = pack_uri_from_source(dir_context, &locked_pack.source)?;
+	let fetched_pack = fetch_pack_file(dir_context, pack_uri).await?;
+	support::validate_aipack_file(&fetched_pack.aipack_file, &fetched_pack.pack_uri.to_string())?;
+
+	let sha256 = support::compute_file_sha256(&fetched_pack.aipack_file)?;
+	if sha256 != locked_pack.sha256 {
+		return Err(Error::PackLockChecksumMismatch {
+			pack_identity: identity,
+			source: locked_pack.source.clone(),
+			expected: locked_pack.sha256.clone(),
+			actual: sha256,
+		});
+	}
+
+	// Note: With a matching checksum, this should always match, unless the pack.lock was edited
+	let pack_toml = support::extract_pack_toml_from_pack_file(&fetched_pack.aipack_file)?;
+	if pack_toml.identity().to_string() != identity || pack_toml.version != locked_pack.version {
+		return Err(Error::FailToInstall {
+			aipack_ref: fetched_pack.pack_uri.to_string(),
+			cause: format!(
+				"pack.lock lists {identity} v{}, but the file is {} v{}",
+				locked_pack.version,
+				pack_toml.identity(),
+				pack_toml.version
+			),
+		});
+	}
+	support::validate_version_for_install(&pack_toml.version)?;
+	verify_aipack_file_for_install(
+		dir_context,
+		&fetched_pack.aipack_file,
+		&fetched_pack.pack_uri.to_string(),
+		options.allow_unsigned,
+	)?;
+
+	Ok(fetched_pack)
+}
@@ -3,11 +3,30 @@
 mod pack_toml;
 mod support;
 
+mod install_record;
+mod installed_packs;
+mod installer_deps;
+mod installer_git;
 mod installer_impl;
+mod installer_locked;
+mod pack_check;
+mod pack_files;
+mod pack_info;
+mod pack_link;
+mod pack_lock;
+mod pack_registry;
+mod pack_signature;
 mod packer_impl;
 
+pub use installed_packs::*;
 pub use installer_impl::*;
-pub use pack_toml::PackToml;
+pub use installer_locked::*;
+pub use pack_check::*;
+pub use pack_info::*;
+pub use pack_link::*;
+pub use pack_registry::{RegistryIndex, RegistryServer};
+pub use pack_signature::{generate_signing_key, sign_aipack_file};
+pub use pack_toml::{PackDependency, PackToml};
 pub use packer_impl::*;
 
 // endregion: --- Modules
@@ -0,0 +1,298 @@
+//! The lint of a pack directory (`aip pack check path/to/pack-dir`, also run by `aip pack`)
+//!
+//! Without executing anything, it checks (only the files to be packed, see `[pack.files]`):
+//! - The `pack.toml`
+//! - Each `.aip` agent file (parsed as the `AgentDoc`)
+//!     - The Lua blocks syntax (`# Before All`, `# Data`, `# Tools`, `# Output`, `# After All`)
+//!     - The handlebars templates of the prompt parts
+//!     - The `# Options` (invalid values, unknown keys) and the `# Output Schema` json
+//! - The Lua syntax of the `lua/**/*.lua` modules
+
+use crate::Result;
+use crate::agent::{AgentDoc, AgentOptions, DocBlock};
+use crate::packer::pack_files::list_pack_files;
+use crate::packer::pack_toml::parse_validate_pack_toml;
+use crate::support::tomls::parse_toml;
+use simple_fs::{SPath, list_files};
+use std::fs;
+
+// region:    --- Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticLevel {
+	Error,
+	Warning,
+}
+
+/// A problem found in a file of the pack
+#[derive(Debug)]
+pub struct PackDiagnostic {
+	pub level: DiagnosticLevel,
+	/// The path of the file, relative to the pack dir
+	pub file: String,
+	/// The line number (1-based) in the file, when known
+	pub line: Option<usize>,
+	pub message: String,
+}
+
+impl std::fmt::Display for PackDiagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let level = match self.level {
+			DiagnosticLevel::Error => "error",
+			DiagnosticLevel::Warning => "warning",
+		};
+		match self.line {
+			Some(line) => write!(f, "{}:{line}: {level}: {}", self.file, self.message),
+			None => write!(f, "{}: {level}: {}", self.file, self.message),
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct PackCheckReport {
+	pub diagnostics: Vec<PackDiagnostic>,
+}
+
+impl PackCheckReport {
+	pub fn error_count(&self) -> usize {
+		self.diagnostics.iter().filter(|d| d.level == DiagnosticLevel::Error).count()
+	}
+
+	pub fn warning_count(&self) -> usize {
+		self.diagnostics.iter().filter(|d| d.level == DiagnosticLevel::Warning).count()
+	}
+
+	pub fn has_errors(&self) -> bool {
+		self.error_count() > 0
+	}
+}
+
+// endregion: --- Types
+
+/// Check the pack directory, and returns the diagnostics (errors and warnings)
+///
+/// Note: Only fails on io errors, the problems of the pack files are in the report.
+pub fn check_pack_dir(pack_dir: &SPath) -> Result<PackCheckReport> {
+	let mut checker = PackChecker {
+		lua: mlua::Lua::new(),
+		known_option_keys: AgentOptions::known_keys(),
+		report: PackCheckReport::default(),
+	};
+
+	// -- pack.toml
+	let toml_path = pack_dir.join("pack.toml");
+	let mut pack_toml = None;
+	if toml_path.exists() {
+		let toml_content = fs::read_to_string(&toml_path)?;
+		match parse_validate_pack_toml(&toml_content, toml_path.to_str()) {
+			Ok(valid_pack_toml) => pack_toml = Some(valid_pack_toml),
+			Err(err) => checker.error("pack.toml", None, err.to_string()),
+		}
+	} else {
+		checker.error("pack.toml", None, "pack.toml is missing".to_string());
+	}
+
+	// -- The files to check (only the packed ones when the pack.toml is valid)
+	let file_names: Vec<String> = match pack_toml.as_ref() {
+		Some(pack_toml) => list_pack_files(pack_dir, pack_toml)?.into_iter().map(|f| f.name).collect(),
+		None => list_files(pack_dir, Some(&["**/*.aip", "lua/**/*.lua"]), None)?
+			.into_iter()
+			.map(|file| rel_path(pack_dir, &SPath::from(file)))
+			.collect(),
+	};
+
+	// -- Agent files
+	for name in file_names.iter().filter(|name| name.ends_with(".aip")) {
+		checker.check_agent_file(&pack_dir.join(name), name)?;
+	}
+
+	// -- Lua modules
+	for name in file_names
+		.iter()
+		.filter(|name| name.starts_with("lua/") && name.ends_with(".lua"))
+	{
+		let content = fs::read_to_string(pack_dir.join(name))?;
+		checker.check_lua(name, "lua module", &content, 1);
+	}
+
+	Ok(checker.report)
+}
+
+// region:    --- PackChecker
+
+struct PackChecker {
+	/// Only used to compile (syntax check) the Lua code, nothing gets executed
+	lua: mlua::Lua,
+	known_option_keys: Vec<String>,
+	report: PackCheckReport,
+}
+
+impl PackChecker {
+	fn check_agent_file(&mut self, aip_file: &SPath, rel_path: &str) -> Result<()> {
+		let doc = AgentDoc::from_file(aip_file)?;
+		let parts = doc.parse_parts();
+
+		// -- Options
+		if let Some(options_toml) = parts.options_toml.as_ref() {
+			self.check_options(rel_path, options_toml);
+		}
+
+		// -- Lua blocks
+		let scripts = [
+			("# Before All", &parts.before_all_script),
+			("# Data", &parts.data_script),
+			("# Output", &parts.output_script),
+			("# After All", &parts.after_all_script),
+		];
+		for (section, block) in scripts {
+			if let Some(block) = block {
+				self.check_lua(rel_path, section, &block.content, block.start_line);
+			}
+		}
+
+		// -- Tools
+		for tool in parts.tools.iter() {
+			let section = format!("# Tools '{}'", tool.name);
+			match tool.script.as_ref() {
+				Some(script) => self.check_lua(rel_path, &section, &script.content, script.start_line),
+				None => self.error(rel_path, Some(tool.line), format!("{section} lua block is missing")),
+			}
+			if let Some(schema) = tool.schema.as_ref() {
+				self.check_json(rel_path, &format!("{section} parameters"), schema);
+			}
+		}
+
+		// -- Output Schema
+		if let Some(output_schema) = parts.output_schema_json.as_ref() {
+			self.check_json(rel_path, "# Output Schema", output_schema);
+		}
+
+		// -- Prompt parts
+		for (_, prompt_part) in parts.prompt_parts.iter() {
+			if let Err(err) = handlebars::Template::compile(&prompt_part.content) {
+				let line = err.pos().map(|(line, _)| prompt_part.start_line + line - 1);
+				self.error(
+					rel_path,
+					line.or(Some(prompt_part.start_line)),
+					format!("invalid handlebars template: {}", err.reason()),
+				);
+			}
+		}
+
+		Ok(())
+	}
+
+	fn check_options(&mut self, rel_path: &str, options_toml: &DocBlock) {
+		let value = match parse_toml(&options_toml.content) {
+			Ok(value) => value,
+			Err(err) => {
+				self.error(
+					rel_path,
+					Some(options_toml.start_line),
+					format!("# Options invalid toml: {err}"),
+				);
+				return;
+			}
+		};
+
+		if let Some(map) = value.as_object() {
+			for key in map.keys() {
+				if !self.known_option_keys.contains(key) {
+					let line = find_toml_key_line(options_toml, key);
+					self.warning(rel_path, Some(line), format!("# Options unknown key '{key}'"));
+				}
+			}
+		}
+
+		if let Err(err) = AgentOptions::from_options_value(value) {
+			self.error(
+				rel_path,
+				Some(options_toml.start_line),
+				format!("# Options invalid: {err}"),
+			);
+		}
+	}
+
+	fn check_json(&mut self, rel_path: &str, section: &str, block: &DocBlock) {
+		if let Err(err) = serde_json::from_str::<serde_json::Value>(&block.content) {
+			let line = block.start_line + err.line().max(1) - 1;
+			self.error(rel_path, Some(line), format!("{section} invalid json: {err}"));
+		}
+	}
+
+	/// Syntax check (compile, without executing) the Lua code starting at this line of the file
+	fn check_lua(&mut self, rel_path: &str, section: &str, code: &str, start_line: usize) {
+		const CHUNK_NAME: &str = "chunk";
+
+		let res = self.lua.load(code).set_name(format!("={CHUNK_NAME}")).into_function();
+		if let Err(err) = res {
+			let message = match err {
+				mlua::Error::SyntaxError { message, .. } => message,
+				other => other.to_string(),
+			};
+			// The message is `chunk:LINE: cause`
+			let (line, cause) = match parse_lua_error_line(&message, CHUNK_NAME) {
+				Some((line, cause)) => (Some(start_line + line - 1), cause.to_string()),
+				None => (Some(start_line), message.clone()),
+			};
+			self.error(rel_path, line, format!("{section} lua syntax error: {cause}"));
+		}
+	}
+
+	fn error(&mut self, file: &str, line: Option<usize>, message: String) {
+		self.push(DiagnosticLevel::Error, file, line, message);
+	}
+
+	fn warning(&mut self, file: &str, line: Option<usize>, message: String) {
+		self.push(DiagnosticLevel::Warning, file, line, message);
+	}
+
+	fn push(&mut self, level: DiagnosticLevel, file: &str, line: Option<usize>, message: String) {
+		self.report.diagnostics.push(PackDiagnostic {
+			level,
+			file: file.to_string(),
+			line,
+			message,
+		});
+	}
+}
+
+// endregion: --- PackChecker
+
+// region:    --- Support
+
+fn rel_path(pack_dir: &SPath, file: &SPath) -> String {
+	file.diff(pack_dir).map(|p| p.to_string()).unwrap_or_else(|_| file.to_string())
+}
+
+/// Parse the `chunk:LINE: cause` Lua error message (line 1-based)
+fn parse_lua_error_line<'a>(message: &'a str, chunk_name: &str) -> Option<(usize, &'a str)> {
+	let rest = message.strip_prefix(chunk_name)?.strip_prefix(':')?;
+	let (line, cause) = rest.split_once(':')?;
+	let line: usize = line.parse().ok()?;
+	Some((line.max(1), cause.trim()))
+}
+
+/// The line of the toml key in the block (the first line of the block when not found)
+fn find_toml_key_line(block: &DocBlock, key: &str) -> usize {
+	for (idx, line) in block.content.lines().enumerate() {
+		let line = line.trim_start();
+		let line = line.strip_prefix('[').unwrap_or(line);
+		if let Some(rest) = line.strip_prefix(key) {
+			if rest.trim_start().starts_with(['=', '.', ']']) {
+				return block.start_line + idx;
+			}
+		}
+	}
+	block.start_line
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_pack_check.rs"]
+mod tests_pack_check;
+
+// endregion: --- Tests
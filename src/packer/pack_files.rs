@@ -0,0 +1,133 @@
+//! The selection of the files of a pack dir to put in the .aipack file
+//!
+//! A file is packed when it matches the `[pack.files] include` globs (all files when absent),
+//! and neither the file nor one of its parent dirs matches:
+//! - The default excludes (e.g., `.git/`, editor backups, .aipack files)
+//! - The `[pack.files] exclude` globs
+//! - The patterns of the `.aipackignore` file of the pack dir (one per line, `#` for comments)
+//!
+//! Note: The `pack.toml` is always packed.
+
+use crate::packer::PackToml;
+use crate::packer::pack_signature::SIGNATURE_FILE_NAME;
+use crate::{Error, Result};
+use globset::GlobSet;
+use simple_fs::{SPath, get_glob_set};
+use std::fs;
+use walkdir::WalkDir;
+
+pub const AIPACK_IGNORE_FILE_NAME: &str = ".aipackignore";
+
+const DEFAULT_EXCLUDE_GLOBS: &[&str] = &[
+	"**/.git",
+	"**/.DS_Store",
+	"**/*~",
+	"**/*.swp",
+	"**/*.swo",
+	"**/*.aipack",
+	AIPACK_IGNORE_FILE_NAME,
+	SIGNATURE_FILE_NAME,
+];
+
+/// A file of the pack dir to put in the .aipack file
+#[derive(Debug, Clone)]
+pub struct PackFile {
+	/// The path relative to the pack dir (with `/`), which is the name in the archive
+	pub name: String,
+	pub size: u64,
+}
+
+/// Returns the files of the pack dir to put in the .aipack file, sorted by name
+pub fn list_pack_files(pack_dir: &SPath, pack_toml: &PackToml) -> Result<Vec<PackFile>> {
+	let include_set = if pack_toml.files.include.is_empty() {
+		None
+	} else {
+		Some(glob_set(&pack_toml.files.include)?)
+	};
+
+	let mut exclude_globs: Vec<String> = DEFAULT_EXCLUDE_GLOBS.iter().map(|g| g.to_string()).collect();
+	exclude_globs.extend(pack_toml.files.exclude.iter().cloned());
+	exclude_globs.extend(load_aipackignore_globs(pack_dir)?);
+	let exclude_set = glob_set(&exclude_globs)?;
+
+	let mut files = Vec::new();
+	let walker = WalkDir::new(pack_dir.path()).into_iter().filter_entry(|entry| {
+		// Do not walk the excluded dirs
+		match rel_name(pack_dir, entry.path()) {
+			Some(name) if entry.file_type().is_dir() && !name.is_empty() => !exclude_set.is_match(&name),
+			_ => true,
+		}
+	});
+	for entry in walker {
+		let entry =
+			entry.map_err(|err| Error::custom(format!("Fail to list the files of '{pack_dir}'. Cause: {err}")))?;
+		if !entry.file_type().is_file() {
+			continue;
+		}
+		let Some(name) = rel_name(pack_dir, entry.path()) else {
+			continue;
+		};
+
+		let is_pack_toml = name == "pack.toml";
+		if !is_pack_toml {
+			if exclude_set.is_match(&name) {
+				continue;
+			}
+			if let Some(include_set) = include_set.as_ref() {
+				if !include_set.is_match(&name) {
+					continue;
+				}
+			}
+		}
+
+		let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+		files.push(PackFile { name, size });
+	}
+
+	files.sort_by(|a, b| a.name.cmp(&b.name));
+
+	Ok(files)
+}
+
+// region:    --- Support
+
+fn glob_set(globs: &[String]) -> Result<GlobSet> {
+	let globs: Vec<&str> = globs.iter().map(|g| g.as_str()).collect();
+	get_glob_set(&globs).map_err(|err| Error::custom(format!("Invalid pack files glob. Cause: {err}")))
+}
+
+/// The path relative to the pack dir, with `/` separators
+fn rel_name(pack_dir: &SPath, path: &std::path::Path) -> Option<String> {
+	let rel_path = path.strip_prefix(pack_dir.path()).ok()?;
+	let name = rel_path.to_str()?.replace('\\', "/");
+	Some(name)
+}
+
+/// The `.aipackignore` patterns as globs (gitignore like)
+/// - `/name` is relative to the pack dir, otherwise, `name` matches at any depth
+/// - `name/` is the same as `name` (a matching dir excludes all its files)
+fn load_aipackignore_globs(pack_dir: &SPath) -> Result<Vec<String>> {
+	let ignore_file = pack_dir.join(AIPACK_IGNORE_FILE_NAME);
+	if !ignore_file.exists() {
+		return Ok(Vec::new());
+	}
+
+	let content = fs::read_to_string(&ignore_file)?;
+	let globs = content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(|line| {
+			let pattern = line.trim_end_matches('/');
+			match pattern.strip_prefix('/') {
+				Some(anchored) => anchored.to_string(),
+				None if pattern.contains('/') || pattern.starts_with("**") => pattern.to_string(),
+				None => format!("**/{pattern}"),
+			}
+		})
+		.collect();
+
+	Ok(globs)
+}
+
+// endregion: --- Support
@@ -0,0 +1,249 @@
+//! The information and search of the packs
+//! - `aip info ns@name` the pack.toml metadata, source, install date, size, and agents of a pack
+//! - `aip search term` the packs (custom, installed, and of the registries `index.toml`) matching the term
+//!
+//! The description of an agent is the first paragraph of its `.aip` file (not a heading, nor in a code block).
+
+use crate::dir_context::{DirContext, PackDir, RepoKind, find_pack_dirs};
+use crate::pack::PackIdentity;
+use crate::packer::install_record::InstallRecord;
+use crate::packer::pack_lock::PackLock;
+use crate::packer::pack_registry::{fetch_registry_index, load_registries};
+use crate::packer::pack_toml::parse_validate_pack_toml;
+use crate::packer::{PackToml, support};
+use crate::{Error, Result};
+use simple_fs::{SPath, list_files};
+use std::fs;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+// region:    --- Types
+
+/// The information of a custom or installed pack (`aip info`)
+#[derive(Debug)]
+pub struct PackInfo {
+	pub pack_dir: PackDir,
+	/// None when the pack dir has no `pack.toml` (custom packs)
+	pub pack_toml: Option<PackToml>,
+	/// The source of the installed pack, from the workspace `.aipack/pack.lock`
+	pub source: Option<String>,
+	/// The time the installed pack was installed (None for custom packs, or when not recorded)
+	pub installed_at: Option<OffsetDateTime>,
+	pub size: usize,
+	pub agents: Vec<AgentSummary>,
+	/// The pack dirs skipped before this one, because of an invalid `pack.toml` (pack dir, cause)
+	pub skipped_pack_dirs: Vec<(String, String)>,
+}
+
+/// An agent (`.aip` file) of a pack
+#[derive(Debug)]
+pub struct AgentSummary {
+	/// The path relative to the pack dir (e.g., `main.aip`)
+	pub name: String,
+	pub description: Option<String>,
+}
+
+/// A pack matching the search term (`aip search`)
+#[derive(Debug)]
+pub struct PackSearchHit {
+	pub identity: String,
+	pub version: Option<String>,
+	pub description: Option<String>,
+	/// Where the pack is (e.g., `base installed`, or the registry url)
+	pub location: String,
+}
+
+#[derive(Debug, Default)]
+pub struct PackSearchResult {
+	pub hits: Vec<PackSearchHit>,
+	/// The registries whose `index.toml` could not be fetched (registry, cause)
+	pub registry_errors: Vec<(String, String)>,
+}
+
+// endregion: --- Types
+
+/// Load the information of the pack (the one `aip run ns@name` would use, custom first)
+///
+/// Note: The pack dirs with an invalid `pack.toml` are skipped (see `PackInfo::skipped_pack_dirs`).
+pub fn load_pack_info(dir_context: &DirContext, pack_identity: &str) -> Result<PackInfo> {
+	let pack_identity = PackIdentity::from_str(pack_identity)?;
+
+	// -- The first pack dir with a valid pack.toml (or none)
+	// Note: Listed by namespace, as the direct match only returns the first pack dir
+	let pack_dirs = find_pack_dirs(dir_context, Some(&pack_identity.namespace), None)?
+		.into_iter()
+		.filter(|pack_dir| pack_dir.name == pack_identity.name);
+	let mut skipped_pack_dirs: Vec<(String, String)> = Vec::new();
+	let mut found: Option<(PackDir, Option<PackToml>)> = None;
+	for pack_dir in pack_dirs {
+		match load_pack_dir_toml(&pack_dir.path) {
+			Ok(pack_toml) => {
+				found = Some((pack_dir, pack_toml));
+				break;
+			}
+			Err(err) => skipped_pack_dirs.push((pack_dir.pretty_path(), err.to_string())),
+		}
+	}
+	let Some((pack_dir, pack_toml)) = found else {
+		let mut msg = format!("Pack {pack_identity} is not installed (nor a custom pack)");
+		for (pack_dir, cause) in skipped_pack_dirs.iter() {
+			msg.push_str(&format!("\n- '{pack_dir}' skipped. Cause: {cause}"));
+		}
+		return Err(Error::custom(msg));
+	};
+
+	let agents = list_agents(&pack_dir.path)?;
+	let size = support::calculate_directory_size(&pack_dir.path)?;
+
+	let (source, installed_at) = if matches!(pack_dir.repo_kind, RepoKind::BaseInstalled) {
+		let source = PackLock::load(dir_context)?
+			.and_then(|pack_lock| pack_lock.get(&pack_identity.to_string()).map(|p| p.source.clone()));
+		let installed_at = InstallRecord::load(&pack_dir.path)?.and_then(|record| record.installed_at());
+		(source, installed_at)
+	} else {
+		(None, None)
+	};
+
+	Ok(PackInfo {
+		pack_dir,
+		pack_toml,
+		source,
+		installed_at,
+		size,
+		agents,
+		skipped_pack_dirs,
+	})
+}
+
+/// Search the term (case insensitive) in the names and descriptions of the packs
+/// - The custom and installed packs (also in the names and descriptions of their agents)
+/// - The packs of the registries `index.toml` (the registries without index are in the `registry_errors`)
+pub async fn search_packs(dir_context: &DirContext, term: &str) -> Result<PackSearchResult> {
+	let term = term.trim().to_lowercase();
+	let matches = |text: &str| text.to_lowercase().contains(&term);
+
+	let mut result = PackSearchResult::default();
+
+	// -- The custom and installed packs
+	for pack_dir in find_pack_dirs(dir_context, None, None)? {
+		let identity = pack_dir.to_string();
+		let pack_toml = load_pack_dir_toml(&pack_dir.path).ok().flatten();
+		let description = pack_toml.as_ref().and_then(|p| p.description.clone());
+		let agents = list_agents(&pack_dir.path)?;
+
+		let is_match = matches(&identity)
+			|| description.as_deref().is_some_and(matches)
+			|| agents
+				.iter()
+				.any(|agent| matches(&agent.name) || agent.description.as_deref().is_some_and(matches));
+		if is_match {
+			result.hits.push(PackSearchHit {
+				identity,
+				version: pack_toml.map(|p| p.version),
+				description,
+				location: repo_kind_name(pack_dir.repo_kind).to_string(),
+			});
+		}
+	}
+
+	// -- The registries
+	for registry in load_registries(dir_context)? {
+		match fetch_registry_index(&registry).await {
+			Ok(entries) => {
+				for entry in entries {
+					let identity = format!("{}@{}", entry.namespace, entry.name);
+					if matches(&identity) || entry.description.as_deref().is_some_and(matches) {
+						result.hits.push(PackSearchHit {
+							identity,
+							version: Some(entry.version),
+							description: entry.description,
+							location: registry.clone(),
+						});
+					}
+				}
+			}
+			Err(err) => result.registry_errors.push((registry, err.to_string())),
+		}
+	}
+
+	Ok(result)
+}
+
+// region:    --- Support
+
+fn repo_kind_name(repo_kind: RepoKind) -> &'static str {
+	match repo_kind {
+		RepoKind::WksCustom => "workspace custom",
+		RepoKind::BaseCustom => "base custom",
+		RepoKind::BaseLinked => "base linked",
+		RepoKind::BaseInstalled => "base installed",
+	}
+}
+
+/// The validated `pack.toml` of the pack dir, None if the pack dir does not have one
+fn load_pack_dir_toml(pack_dir: &SPath) -> Result<Option<PackToml>> {
+	let toml_path = pack_dir.join("pack.toml");
+	if !toml_path.exists() {
+		return Ok(None);
+	}
+	let toml_content = fs::read_to_string(&toml_path)?;
+	let pack_toml = parse_validate_pack_toml(&toml_content, toml_path.to_str())?;
+	Ok(Some(pack_toml))
+}
+
+/// The `.aip` files of the pack dir (sorted by name), with their description
+fn list_agents(pack_dir: &SPath) -> Result<Vec<AgentSummary>> {
+	let mut agents = Vec::new();
+	for file in list_files(pack_dir, Some(&["**/*.aip"]), None)? {
+		let file = SPath::from(file);
+		let name = file.diff(pack_dir).map(|p| p.to_string()).unwrap_or_else(|_| file.to_string());
+		let description = fs::read_to_string(&file).ok().and_then(|content| first_paragraph(&content));
+		agents.push(AgentSummary { name, description });
+	}
+	agents.sort_by(|a, b| a.name.cmp(&b.name));
+
+	Ok(agents)
+}
+
+/// The first paragraph of the markdown content (not a heading, nor in a code block), on one line
+fn first_paragraph(content: &str) -> Option<String> {
+	let mut in_code_block = false;
+	let mut paragraph: Vec<&str> = Vec::new();
+
+	for line in content.lines() {
+		let line = line.trim();
+		if line.starts_with("```") {
+			if !paragraph.is_empty() {
+				break;
+			}
+			in_code_block = !in_code_block;
+			continue;
+		}
+		if in_code_block {
+			continue;
+		}
+		if line.is_empty() || line.starts_with('#') {
+			if !paragraph.is_empty() {
+				break;
+			}
+			continue;
+		}
+		paragraph.push(line);
+	}
+
+	if paragraph.is_empty() {
+		None
+	} else {
+		Some(paragraph.join(" "))
+	}
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_pack_info.rs"]
+mod tests_pack_info;
+
+// endregion: --- Tests
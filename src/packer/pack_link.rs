@@ -0,0 +1,83 @@
+//! Link a pack source dir as the installed location of its pack (`aip link path/to/pack-dir`, `aip unlink ns@name`)
+//!
+//! See `dir_context::PackLinks` for how the linked packs are resolved.
+
+use crate::dir_context::{DirContext, PackLinks, PathResolver};
+use crate::pack::PackIdentity;
+use crate::packer::pack_toml::parse_validate_pack_toml;
+use crate::{Error, Result};
+use simple_fs::SPath;
+use std::fs;
+use std::str::FromStr;
+
+/// A pack identity (`namespace@name`) with its linked source dir
+#[derive(Debug)]
+pub struct LinkedPack {
+	pub identity: String,
+	pub version: Option<String>,
+	pub dir: SPath,
+}
+
+/// Link the pack dir (with a `pack.toml`) as the installed location of its `namespace@name`
+///
+/// The previous link of this identity, if any, is replaced.
+pub fn link_pack(dir_context: &DirContext, pack_dir: &str) -> Result<LinkedPack> {
+	let pack_dir = dir_context.resolve_path(SPath::new(pack_dir), PathResolver::CurrentDir)?;
+	let toml_path = pack_dir.join("pack.toml");
+	if !toml_path.exists() {
+		return Err(Error::custom(format!(
+			"Cannot link '{pack_dir}', it does not have a pack.toml"
+		)));
+	}
+	let pack_dir = pack_dir.canonicalize()?;
+	let pack_toml = parse_validate_pack_toml(&fs::read_to_string(&toml_path)?, toml_path.to_str())?;
+	let identity = format!("{}@{}", pack_toml.namespace, pack_toml.name);
+
+	let aipack_paths = dir_context.aipack_paths();
+	let mut pack_links = PackLinks::load(aipack_paths)?;
+	pack_links.insert(identity.clone(), &pack_dir);
+	pack_links.save(aipack_paths)?;
+
+	Ok(LinkedPack {
+		identity,
+		version: Some(pack_toml.version),
+		dir: pack_dir,
+	})
+}
+
+/// Remove the link of the pack, by identity (`ns@name`) or by linked source dir
+pub fn unlink_pack(dir_context: &DirContext, pack_ref: &str) -> Result<LinkedPack> {
+	let aipack_paths = dir_context.aipack_paths();
+	let mut pack_links = PackLinks::load(aipack_paths)?;
+
+	let identity = if pack_ref.contains('@') {
+		PackIdentity::from_str(pack_ref)?.to_string()
+	} else {
+		let dir = dir_context.resolve_path(SPath::new(pack_ref), PathResolver::CurrentDir)?;
+		let dir = dir.canonicalize().unwrap_or(dir);
+		pack_links
+			.links()
+			.find(|(_, linked_dir)| linked_dir.path() == dir.path())
+			.map(|(identity, _)| identity.to_string())
+			.unwrap_or_else(|| pack_ref.to_string())
+	};
+
+	let dir = pack_links
+		.remove(&identity)
+		.ok_or_else(|| Error::custom(format!("No linked pack for '{pack_ref}'")))?;
+	pack_links.save(aipack_paths)?;
+
+	Ok(LinkedPack {
+		identity,
+		version: None,
+		dir,
+	})
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_pack_link.rs"]
+mod tests_pack_link;
+
+// endregion: --- Tests
@@ -0,0 +1,162 @@
+//! The workspace `.aipack/pack.lock`, which records the packs installed by `aip install`
+//! (identity, version, source, and SHA-256 of the .aipack file),
+//! so that `aip sync` (or `aip install --locked`) installs exactly the same packs.
+
+use crate::dir_context::DirContext;
+use crate::packer::PackToml;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use simple_fs::ensure_file_dir;
+use std::collections::HashSet;
+use std::fs;
+
+const PACK_LOCK_HEADER: &str =
+	"# This file is generated by `aip install`, and used by `aip sync`.\n# Do not edit manually.\n\n";
+
+const PACK_LOCK_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackLock {
+	version: u32,
+	#[serde(default, rename = "pack")]
+	packs: Vec<LockedPack>,
+}
+
+/// One installed pack in the pack.lock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedPack {
+	pub namespace: String,
+	pub name: String,
+	pub version: String,
+	/// The uri of this exact .aipack file
+	/// (http url, or local path, relative to the workspace dir when inside it)
+	pub source: String,
+	/// The SHA-256 (hex) of the .aipack file
+	pub sha256: String,
+	/// The identities (`namespace@name`) of the dependencies of this pack
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub dependencies: Vec<String>,
+}
+
+impl LockedPack {
+	pub fn new(pack_toml: &PackToml, source: impl Into<String>, sha256: impl Into<String>) -> Self {
+		LockedPack {
+			namespace: pack_toml.namespace.clone(),
+			name: pack_toml.name.clone(),
+			version: pack_toml.version.clone(),
+			source: source.into(),
+			sha256: sha256.into(),
+			dependencies: pack_toml.dependencies.iter().map(|d| d.identity.to_string()).collect(),
+		}
+	}
+
+	pub fn identity(&self) -> String {
+		format!("{}@{}", self.namespace, self.name)
+	}
+}
+
+impl Default for PackLock {
+	fn default() -> Self {
+		PackLock {
+			version: PACK_LOCK_VERSION,
+			packs: Vec::new(),
+		}
+	}
+}
+
+/// Load & Save
+impl PackLock {
+	/// Load the workspace pack.lock, None if it does not exist
+	pub fn load(dir_context: &DirContext) -> Result<Option<Self>> {
+		let path = dir_context.aipack_paths().get_wks_pack_lock_path()?;
+		if !path.exists() {
+			return Ok(None);
+		}
+
+		let content = fs::read_to_string(&path)?;
+		let pack_lock: PackLock =
+			toml::from_str(&content).map_err(|err| Error::cc(format!("Invalid pack.lock at '{path}'"), err))?;
+		if pack_lock.version > PACK_LOCK_VERSION {
+			return Err(Error::custom(format!(
+				"pack.lock at '{path}' has version {}, but this aip only supports version {PACK_LOCK_VERSION}",
+				pack_lock.version
+			)));
+		}
+
+		Ok(Some(pack_lock))
+	}
+
+	pub fn save(&self, dir_context: &DirContext) -> Result<()> {
+		let path = dir_context.aipack_paths().get_wks_pack_lock_path()?;
+		ensure_file_dir(&path)?;
+		let content = toml::to_string(self).map_err(|err| Error::cc("Cannot serialize pack.lock", err))?;
+		fs::write(&path, format!("{PACK_LOCK_HEADER}{content}"))?;
+		Ok(())
+	}
+}
+
+impl PackLock {
+	pub fn packs(&self) -> &[LockedPack] {
+		&self.packs
+	}
+
+	pub fn get(&self, identity: &str) -> Option<&LockedPack> {
+		self.packs.iter().find(|p| p.identity() == identity)
+	}
+
+	/// Add or replace the locked pack (the packs are kept sorted by identity, for stable diffs)
+	pub fn upsert(&mut self, locked_pack: LockedPack) {
+		let identity = locked_pack.identity();
+		self.packs.retain(|p| p.identity() != identity);
+		self.packs.push(locked_pack);
+		self.packs.sort_by_key(|p| p.identity());
+	}
+
+	/// Remove the locked pack with this identity, and return it
+	pub fn remove(&mut self, identity: &str) -> Option<LockedPack> {
+		let idx = self.packs.iter().position(|p| p.identity() == identity)?;
+		Some(self.packs.remove(idx))
+	}
+
+	/// Returns the locked pack with this identity and all its locked dependencies (transitively).
+	///
+	/// The dependencies come first.
+	pub fn with_dependencies(&self, identity: &str) -> Result<Vec<LockedPack>> {
+		let mut packs: Vec<LockedPack> = Vec::new();
+		let mut visited: HashSet<String> = HashSet::new();
+		self.collect_with_dependencies(identity, None, &mut visited, &mut packs)?;
+		Ok(packs)
+	}
+}
+
+/// Private
+impl PackLock {
+	fn collect_with_dependencies(
+		&self,
+		identity: &str,
+		required_by: Option<&str>,
+		visited: &mut HashSet<String>,
+		packs: &mut Vec<LockedPack>,
+	) -> Result<()> {
+		// Note: Also protects from dependency cycles
+		if !visited.insert(identity.to_string()) {
+			return Ok(());
+		}
+
+		let locked_pack = self.get(identity).ok_or_else(|| match required_by {
+			Some(required_by) => Error::custom(format!(
+				"Pack {identity} (dependency of {required_by}) is not in the pack.lock. Run 'aip install {required_by}' first."
+			)),
+			None => Error::custom(format!(
+				"Pack {identity} is not in the pack.lock. Run 'aip install {identity}' first."
+			)),
+		})?;
+
+		for dependency in locked_pack.dependencies.iter() {
+			self.collect_with_dependencies(dependency, Some(identity), visited, packs)?;
+		}
+		packs.push(locked_pack.clone());
+
+		Ok(())
+	}
+}
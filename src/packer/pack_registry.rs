@@ -0,0 +1,499 @@
+//! The pack registries, used to resolve the `namespace@name` packs (`aip install`, `aip outdated`, `aip upgrade`)
+//!
+//! A registry has the layout of the public registry (`https://repo.aipack.ai/`):
+//! - `{registry}pack/{namespace}/{name}/stable/latest.toml` with `[latest_stable] version = "..", rel_path = ".."`
+//! - `{registry}pack/{namespace}/{name}/stable/{rel_path}` the .aipack file (`rel_path` may have sub dirs)
+//! - `{registry}index.toml` the `[[pack]]` list (`namespace`, `name`, `version`, `description`), for `aip search`
+//!
+//! The registries are the `[install] registries` of the workspace `.aipack/config.toml`,
+//! then of the `~/.aipack-base/config.toml`, tried in order, followed by the public registry
+//! (unless `[install] default_registry = false`).
+//!
+//! `aip registry serve path/to/dir` serves a directory of .aipack files as a registry (see `RegistryServer`).
+
+use crate::dir_context::DirContext;
+use crate::pack::PackIdentity;
+use crate::packer::support;
+use crate::support::tomls::parse_toml;
+use crate::{Error, Result};
+use reqwest::Client;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use simple_fs::{SPath, list_files};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::net::{TcpListener, TcpStream};
+use value_ext::JsonValueExt as _;
+
+/// The public registry, tried after the configured ones (unless `[install] default_registry = false`)
+pub const DEFAULT_REGISTRY_URL: &str = "https://repo.aipack.ai/";
+
+/// Load the `[install] registries` of the workspace and base config.toml (in this order, without duplicates)
+///
+/// The public registry is appended, unless `[install] default_registry = false`
+/// (the workspace config.toml value takes precedence over the base one).
+pub fn load_registries(dir_context: &DirContext) -> Result<Vec<String>> {
+	let aipack_paths = dir_context.aipack_paths();
+	let config_paths = [
+		aipack_paths.get_wks_config_toml_path()?,
+		aipack_paths.get_base_config_toml_path()?,
+	];
+
+	let mut registries: Vec<String> = Vec::new();
+	let mut default_registry: Option<bool> = None;
+	for config_path in config_paths {
+		if !config_path.exists() {
+			continue;
+		}
+		let config_value = parse_toml(&fs::read_to_string(&config_path)?)?;
+		let config_registries: Vec<String> = config_value.x_get("/install/registries").unwrap_or_default();
+		for registry in config_registries {
+			let registry = normalize_registry_url(&registry);
+			if !registries.contains(&registry) {
+				registries.push(registry);
+			}
+		}
+		if default_registry.is_none() {
+			default_registry = config_value.x_get("/install/default_registry").ok();
+		}
+	}
+
+	if default_registry.unwrap_or(true) && !registries.iter().any(|r| r == DEFAULT_REGISTRY_URL) {
+		registries.push(DEFAULT_REGISTRY_URL.to_string());
+	}
+
+	Ok(registries)
+}
+
+/// The base url of the stable channel of the pack in this registry (ending with `/`)
+pub fn registry_pack_base_url(registry: &str, pack_identity: &PackIdentity) -> String {
+	format!(
+		"{}pack/{}/{}/stable/",
+		normalize_registry_url(registry),
+		pack_identity.namespace,
+		pack_identity.name
+	)
+}
+
+/// True if the url is the .aipack url of the pack in a registry (see `registry_pack_base_url`)
+pub fn is_registry_pack_url(url: &str, pack_identity: &PackIdentity) -> bool {
+	let pack_path = format!("/pack/{}/{}/stable/", pack_identity.namespace, pack_identity.name);
+	(url.starts_with("http://") || url.starts_with("https://")) && url.contains(&pack_path)
+}
+
+/// Fetch the `index.toml` of the registry (the packs with their latest version)
+///
+/// Note: A registry without `index.toml` (404) has no packs to list.
+pub async fn fetch_registry_index(registry: &str) -> Result<Vec<RegistryIndexEntry>> {
+	let index_url = format!("{}index.toml", normalize_registry_url(registry));
+
+	let response = Client::new()
+		.get(&index_url)
+		.send()
+		.await
+		.map_err(|err| Error::cc(format!("Failed to download '{index_url}'"), err))?;
+	if response.status() == reqwest::StatusCode::NOT_FOUND {
+		return Ok(Vec::new());
+	}
+	if !response.status().is_success() {
+		return Err(Error::custom(format!(
+			"HTTP error when fetching '{index_url}': {}",
+			response.status()
+		)));
+	}
+	let content = response
+		.text()
+		.await
+		.map_err(|err| Error::cc(format!("Failed to read '{index_url}'"), err))?;
+	let index_toml: RegistryIndexToml =
+		toml::from_str(&content).map_err(|err| Error::cc(format!("Failed to parse '{index_url}'"), err))?;
+
+	Ok(index_toml.packs)
+}
+
+/// The registry url, ending with `/`
+fn normalize_registry_url(registry: &str) -> String {
+	let registry = registry.trim();
+	if registry.ends_with('/') {
+		registry.to_string()
+	} else {
+		format!("{registry}/")
+	}
+}
+
+// region:    --- RegistryIndex
+
+/// The `index.toml` of a registry
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryIndexToml {
+	#[serde(default, rename = "pack")]
+	packs: Vec<RegistryIndexEntry>,
+}
+
+/// A pack of the registry `index.toml`, with its latest version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryIndexEntry {
+	pub namespace: String,
+	pub name: String,
+	pub version: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+}
+
+/// The packs of a registry directory (all the .aipack files of the dir, at any depth)
+#[derive(Debug, Default)]
+pub struct RegistryIndex {
+	packs: BTreeMap<(String, String), Vec<RegistryPackFile>>,
+	/// The .aipack files which could not be read (file, cause)
+	pub invalid_files: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct RegistryPackFile {
+	pub version: Version,
+	/// The path relative to the registry dir (with `/`), which is the `rel_path` of the `latest.toml`
+	pub rel_path: String,
+	pub file: SPath,
+	pub description: Option<String>,
+}
+
+impl RegistryIndex {
+	pub fn load(registry_dir: &SPath) -> Result<Self> {
+		let mut index = RegistryIndex::default();
+
+		for file in list_files(registry_dir, Some(&["**/*.aipack"]), None)? {
+			let file = SPath::from(file);
+			let pack_toml = match support::extract_pack_toml_from_pack_file(&file) {
+				Ok(pack_toml) => pack_toml,
+				Err(err) => {
+					index.invalid_files.push((file.to_string(), err.to_string()));
+					continue;
+				}
+			};
+			let Ok(version) = Version::parse(pack_toml.version.trim_start_matches('v')) else {
+				index.invalid_files.push((
+					file.to_string(),
+					format!("version '{}' is not semver", pack_toml.version),
+				));
+				continue;
+			};
+
+			let rel_path = file
+				.diff(registry_dir)
+				.map(|rel_path| rel_path.to_string().replace('\\', "/"))
+				.unwrap_or_else(|_| file.name().to_string());
+
+			index
+				.packs
+				.entry((pack_toml.namespace, pack_toml.name))
+				.or_default()
+				.push(RegistryPackFile {
+					version,
+					rel_path,
+					file,
+					description: pack_toml.description,
+				});
+		}
+
+		Ok(index)
+	}
+
+	/// The number of distinct packs (namespace@name)
+	pub fn pack_count(&self) -> usize {
+		self.packs.len()
+	}
+
+	/// The pack file of this pack at this `rel_path` (see `latest_toml`)
+	pub fn pack_file(&self, namespace: &str, name: &str, rel_path: &str) -> Option<&RegistryPackFile> {
+		self.pack_files(namespace, name)
+			.iter()
+			.find(|pack_file| pack_file.rel_path == rel_path)
+	}
+
+	/// The pack files of this pack (all versions)
+	pub fn pack_files(&self, namespace: &str, name: &str) -> &[RegistryPackFile] {
+		self.packs
+			.get(&(namespace.to_string(), name.to_string()))
+			.map(|files| files.as_slice())
+			.unwrap_or_default()
+	}
+
+	/// The greatest version, without prerelease, of this pack
+	pub fn latest_stable(&self, namespace: &str, name: &str) -> Option<&RegistryPackFile> {
+		self.pack_files(namespace, name)
+			.iter()
+			.filter(|pack_file| pack_file.version.pre.is_empty())
+			.max_by(|a, b| a.version.cmp(&b.version))
+	}
+
+	/// The `latest.toml` content of this pack (None when no stable version)
+	pub fn latest_toml(&self, namespace: &str, name: &str) -> Option<String> {
+		let latest = self.latest_stable(namespace, name)?;
+		Some(format!(
+			"[latest_stable]\nversion = \"{}\"\nrel_path = \"{}\"\n",
+			latest.version, latest.rel_path
+		))
+	}
+
+	/// The `index.toml` content, with the latest stable version of each pack (or the latest prerelease when none)
+	pub fn index_toml(&self) -> Result<String> {
+		let mut index_toml = RegistryIndexToml::default();
+		for ((namespace, name), pack_files) in self.packs.iter() {
+			let latest = self
+				.latest_stable(namespace, name)
+				.or_else(|| pack_files.iter().max_by(|a, b| a.version.cmp(&b.version)));
+			if let Some(latest) = latest {
+				index_toml.packs.push(RegistryIndexEntry {
+					namespace: namespace.clone(),
+					name: name.clone(),
+					version: latest.version.to_string(),
+					description: latest.description.clone(),
+				});
+			}
+		}
+
+		toml::to_string(&index_toml).map_err(|err| Error::cc("Cannot serialize the registry index.toml", err))
+	}
+}
+
+// endregion: --- RegistryIndex
+
+// region:    --- RegistryServer
+
+/// A minimal HTTP server serving a directory of .aipack files as a registry (GET only)
+///
+/// Note: The index is reloaded when the .aipack files of the dir change (see `RegistryIndexCache`),
+///       so the .aipack files added to the dir are served right away.
+pub struct RegistryServer {
+	index_cache: Arc<RegistryIndexCache>,
+	listener: TcpListener,
+}
+
+impl RegistryServer {
+	/// Bind the server to this address (e.g., `127.0.0.1:8787`, or `127.0.0.1:0` for any port)
+	pub async fn bind(registry_dir: SPath, addr: &str) -> Result<Self> {
+		if !registry_dir.is_dir() {
+			return Err(Error::custom(format!("Registry dir '{registry_dir}' does not exist")));
+		}
+		let listener = TcpListener::bind(addr)
+			.await
+			.map_err(|err| Error::cc(format!("Cannot bind the registry server to '{addr}'"), err))?;
+
+		Ok(RegistryServer {
+			index_cache: Arc::new(RegistryIndexCache::new(registry_dir)),
+			listener,
+		})
+	}
+
+	/// The url of the registry, to be added to the `[install] registries` of the config.toml
+	pub fn url(&self) -> Result<String> {
+		Ok(format!("http://{}/", self.listener.local_addr()?))
+	}
+
+	/// Serve the requests (until the process ends)
+	pub async fn serve(self) -> Result<()> {
+		loop {
+			let (stream, _) = self.listener.accept().await?;
+			let index_cache = self.index_cache.clone();
+			tokio::spawn(async move {
+				// Note: The connection errors (e.g., client gone) only concern this request
+				let _ = handle_connection(stream, &index_cache).await;
+			});
+		}
+	}
+}
+
+/// The index of the registry dir, reloaded only when its .aipack files change (added, removed, or modified)
+struct RegistryIndexCache {
+	registry_dir: SPath,
+	/// The index, with the fingerprint of the .aipack files it was loaded from
+	entry: Mutex<Option<(Vec<PackFileStamp>, Arc<RegistryIndex>)>>,
+}
+
+/// The path, modification time, and size of a .aipack file
+type PackFileStamp = (String, Option<SystemTime>, u64);
+
+impl RegistryIndexCache {
+	fn new(registry_dir: SPath) -> Self {
+		RegistryIndexCache {
+			registry_dir,
+			entry: Mutex::new(None),
+		}
+	}
+
+	/// The index of the registry dir, loaded again only if the .aipack files changed
+	fn get(&self) -> Result<Arc<RegistryIndex>> {
+		let fingerprint = self.fingerprint()?;
+		let mut entry = self
+			.entry
+			.lock()
+			.map_err(|err| Error::custom(format!("Registry index cache lock poisoned: {err}")))?;
+		if let Some((cached_fingerprint, index)) = entry.as_ref() {
+			if cached_fingerprint == &fingerprint {
+				return Ok(index.clone());
+			}
+		}
+
+		let index = Arc::new(RegistryIndex::load(&self.registry_dir)?);
+		*entry = Some((fingerprint, index.clone()));
+		Ok(index)
+	}
+
+	/// The stamps of the .aipack files of the dir (does not read the files)
+	fn fingerprint(&self) -> Result<Vec<PackFileStamp>> {
+		let mut stamps: Vec<PackFileStamp> = Vec::new();
+		for file in list_files(&self.registry_dir, Some(&["**/*.aipack"]), None)? {
+			let metadata = fs::metadata(&file).ok();
+			stamps.push((
+				file.to_string(),
+				metadata.as_ref().and_then(|m| m.modified().ok()),
+				metadata.map(|m| m.len()).unwrap_or_default(),
+			));
+		}
+		stamps.sort();
+		Ok(stamps)
+	}
+}
+
+async fn handle_connection(mut stream: TcpStream, index_cache: &RegistryIndexCache) -> std::io::Result<()> {
+	const MAX_HEADER_SIZE: usize = 16 * 1024;
+	// Note: So that an idle (or slow) client does not hold the connection forever
+	const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+	// -- Read the request head (the body, if any, is ignored)
+	let mut buf: Vec<u8> = Vec::new();
+	let mut chunk = [0u8; 4096];
+	let read_head = async {
+		loop {
+			let n = stream.read(&mut chunk).await?;
+			if n == 0 {
+				return Ok(false);
+			}
+			buf.extend_from_slice(&chunk[..n]);
+			if buf.windows(4).any(|w| w == b"\r\n\r\n") || buf.len() > MAX_HEADER_SIZE {
+				return Ok::<bool, std::io::Error>(true);
+			}
+		}
+	};
+	match tokio::time::timeout(READ_TIMEOUT, read_head).await {
+		Ok(Ok(true)) => (),
+		Ok(Ok(false)) => return Ok(()),
+		Ok(Err(err)) => return Err(err),
+		Err(_) => return write_response(&mut stream, 408, "text/plain", b"Request timeout", true).await,
+	}
+	if buf.len() > MAX_HEADER_SIZE {
+		return write_response(&mut stream, 431, "text/plain", b"Request header too large", true).await;
+	}
+
+	let head = String::from_utf8_lossy(&buf);
+	let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+	let method = request_line.next().unwrap_or_default();
+	let path = request_line.next().unwrap_or_default();
+	let with_body = method != "HEAD";
+	if method != "GET" && method != "HEAD" {
+		return write_response(&mut stream, 405, "text/plain", b"Method not allowed", with_body).await;
+	}
+
+	let (status, content_type, body) = match route(index_cache, path) {
+		Ok(Some((content_type, body))) => (200, content_type, body),
+		Ok(None) => (404, "text/plain", b"Not found".to_vec()),
+		Err(err) => (500, "text/plain", err.to_string().into_bytes()),
+	};
+
+	write_response(&mut stream, status, content_type, &body, with_body).await
+}
+
+/// Returns the content type and the body for this request path, or None if not found
+fn route(index_cache: &RegistryIndexCache, path: &str) -> Result<Option<(&'static str, Vec<u8>)>> {
+	let path = path.split(['?', '#']).next().unwrap_or_default();
+	let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect();
+
+	if let [index_toml] = segments.as_slice() {
+		if index_toml == "index.toml" {
+			let index = index_cache.get()?;
+			return Ok(Some(("text/plain", index.index_toml()?.into_bytes())));
+		}
+	}
+
+	// Note: The rel_path of the .aipack file may have sub dirs
+	let [pack, namespace, name, stable, rel_path @ ..] = segments.as_slice() else {
+		return Ok(None);
+	};
+	if pack != "pack" || stable != "stable" || rel_path.is_empty() {
+		return Ok(None);
+	}
+	let rel_path = rel_path.join("/");
+
+	let index = index_cache.get()?;
+
+	if rel_path == "latest.toml" {
+		return Ok(index.latest_toml(namespace, name).map(|toml| ("text/plain", toml.into_bytes())));
+	}
+
+	let Some(pack_file) = index.pack_file(namespace, name, &rel_path) else {
+		return Ok(None);
+	};
+	let content = fs::read(&pack_file.file)?;
+
+	Ok(Some(("application/octet-stream", content)))
+}
+
+async fn write_response(
+	stream: &mut TcpStream,
+	status: u16,
+	content_type: &str,
+	body: &[u8],
+	with_body: bool,
+) -> std::io::Result<()> {
+	let reason = match status {
+		200 => "OK",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		408 => "Request Timeout",
+		431 => "Request Header Fields Too Large",
+		_ => "Internal Server Error",
+	};
+	let head = format!(
+		"HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+		body.len()
+	);
+	stream.write_all(head.as_bytes()).await?;
+	if with_body {
+		stream.write_all(body).await?;
+	}
+	stream.flush().await?;
+	stream.shutdown().await
+}
+
+/// Decode the `%XX` of the url path segment (invalid sequences are kept as is)
+fn percent_decode(segment: &str) -> String {
+	let bytes = segment.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut idx = 0;
+	while idx < bytes.len() {
+		if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+			let hex = std::str::from_utf8(&bytes[idx + 1..idx + 3]).unwrap_or_default();
+			if let Ok(byte) = u8::from_str_radix(hex, 16) {
+				decoded.push(byte);
+				idx += 3;
+				continue;
+			}
+		}
+		decoded.push(bytes[idx]);
+		idx += 1;
+	}
+	String::from_utf8_lossy(&decoded).to_string()
+}
+
+// endregion: --- RegistryServer
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_pack_registry.rs"]
+mod tests_pack_registry;
+
+// endregion: --- Tests
@@ -0,0 +1,221 @@
+//! The ed25519 signature of the .aipack files (`aip pack --sign key-file`), verified on `aip install`.
+//!
+//! The signature is over the sorted `sha256  name` lines of all the files of the archive (including the `pack.toml`),
+//! and is embedded in the archive as the `.aipack-signature.toml` file.
+//!
+//! The keys are base64 encoded (32 bytes). The trusted public keys are in the `~/.aipack-base/config.toml`
+//!
+//! ```toml
+//! [install]
+//! trusted_keys = ["base64-public-key"]
+//! ```
+
+use crate::dir_context::DirContext;
+use crate::support::tomls::parse_toml;
+use crate::support::zip;
+use crate::{Error, Result};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ed25519_dalek::{Signature, Signer as _, SigningKey, Verifier as _, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use simple_fs::SPath;
+use std::fs;
+use value_ext::JsonValueExt as _;
+
+/// The signature file embedded in the .aipack archive
+pub const SIGNATURE_FILE_NAME: &str = ".aipack-signature.toml";
+
+const SIGNATURE_MESSAGE_HEADER: &str = "aipack-signature-v1\n";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PackSignature {
+	/// The public key (base64) of the signing key
+	public_key: String,
+	/// The ed25519 signature (base64)
+	signature: String,
+}
+
+/// The signature status of a .aipack file (when the signature, if any, is valid)
+#[derive(Debug)]
+pub enum PackSignatureStatus {
+	Unsigned,
+	Signed { public_key: String },
+}
+
+/// Generate a new signing key, and write it (base64) in the `key_file`, and its public key in `key_file.pub`
+///
+/// Returns the public key (base64)
+pub fn generate_signing_key(key_file: &SPath) -> Result<String> {
+	if key_file.exists() {
+		return Err(Error::custom(format!("Key file '{key_file}' already exists")));
+	}
+
+	let mut secret = [0u8; 32];
+	getrandom::getrandom(&mut secret).map_err(|err| Error::cc("Cannot generate the signing key", err))?;
+	let signing_key = SigningKey::from_bytes(&secret);
+	let public_key = BASE64.encode(signing_key.verifying_key().as_bytes());
+
+	write_secret_file(key_file, &format!("{}\n", BASE64.encode(secret)))?;
+	fs::write(format!("{key_file}.pub"), format!("{public_key}\n"))?;
+
+	Ok(public_key)
+}
+
+/// Sign the .aipack file with the key of the `key_file` (base64 secret key, as generated by `aip keygen`)
+///
+/// Returns the public key (base64) of the signing key
+pub fn sign_aipack_file(aipack_file: &SPath, key_file: &SPath) -> Result<String> {
+	let signing_key = load_signing_key(key_file)?;
+
+	let files = zip::zip_files_sha256(aipack_file)?;
+	if files.iter().any(|(name, _)| name == SIGNATURE_FILE_NAME) {
+		return Err(Error::custom(format!("'{aipack_file}' is already signed")));
+	}
+
+	let message = signature_message(&files);
+	let signature = signing_key.sign(message.as_bytes());
+	let pack_signature = PackSignature {
+		public_key: BASE64.encode(signing_key.verifying_key().as_bytes()),
+		signature: BASE64.encode(signature.to_bytes()),
+	};
+	let content =
+		toml::to_string(&pack_signature).map_err(|err| Error::cc("Cannot serialize the pack signature", err))?;
+	zip::append_file_content(aipack_file, SIGNATURE_FILE_NAME, &content)?;
+
+	Ok(pack_signature.public_key)
+}
+
+/// Verify the signature of the .aipack file (if signed)
+///
+/// Returns an error if the signature does not match the content (e.g., tampered archive)
+pub fn verify_aipack_file(aipack_file: &SPath, aipack_ref: &str) -> Result<PackSignatureStatus> {
+	let mut files = zip::zip_files_sha256(aipack_file)?;
+	let Some(sig_idx) = files.iter().position(|(name, _)| name == SIGNATURE_FILE_NAME) else {
+		return Ok(PackSignatureStatus::Unsigned);
+	};
+	files.remove(sig_idx);
+
+	let invalid = |cause: String| Error::PackSignatureInvalid {
+		aipack_ref: aipack_ref.to_string(),
+		cause,
+	};
+
+	let content = zip::extract_text_content(aipack_file, SIGNATURE_FILE_NAME)?;
+	let pack_signature: PackSignature = toml::from_str(&content).map_err(|err| invalid(err.to_string()))?;
+	let verifying_key = decode_verifying_key(&pack_signature.public_key).map_err(|err| invalid(err.to_string()))?;
+	let signature_bytes: [u8; 64] = BASE64
+		.decode(pack_signature.signature.trim())
+		.map_err(|err| invalid(err.to_string()))?
+		.try_into()
+		.map_err(|_| invalid("signature must be 64 bytes".to_string()))?;
+	let signature = Signature::from_bytes(&signature_bytes);
+
+	let message = signature_message(&files);
+	verifying_key
+		.verify(message.as_bytes(), &signature)
+		.map_err(|_| invalid("the content does not match the signature".to_string()))?;
+
+	Ok(PackSignatureStatus::Signed {
+		public_key: pack_signature.public_key,
+	})
+}
+
+/// Verify that the .aipack file can be installed
+/// - Always fails on an invalid signature.
+/// - Fails if unsigned or signed by a key not in the trusted keys, unless `allow_unsigned`.
+pub fn verify_aipack_file_for_install(
+	dir_context: &DirContext,
+	aipack_file: &SPath,
+	aipack_ref: &str,
+	allow_unsigned: bool,
+) -> Result<()> {
+	let status = verify_aipack_file(aipack_file, aipack_ref)?;
+	if allow_unsigned {
+		return Ok(());
+	}
+
+	match status {
+		PackSignatureStatus::Unsigned => Err(Error::PackUnsigned {
+			aipack_ref: aipack_ref.to_string(),
+		}),
+		PackSignatureStatus::Signed { public_key } => {
+			if load_trusted_keys(dir_context)?.contains(&public_key) {
+				Ok(())
+			} else {
+				Err(Error::PackSignatureUntrusted {
+					aipack_ref: aipack_ref.to_string(),
+					public_key,
+				})
+			}
+		}
+	}
+}
+
+/// Load the `[install] trusted_keys` of the `~/.aipack-base/config.toml`
+pub fn load_trusted_keys(dir_context: &DirContext) -> Result<Vec<String>> {
+	let config_path = dir_context.aipack_paths().get_base_config_toml_path()?;
+	if !config_path.exists() {
+		return Ok(Vec::new());
+	}
+
+	let config_value = parse_toml(&fs::read_to_string(&config_path)?)?;
+	let trusted_keys: Vec<String> = config_value.x_get("/install/trusted_keys").unwrap_or_default();
+
+	Ok(trusted_keys.into_iter().map(|key| key.trim().to_string()).collect())
+}
+
+// region:    --- Support
+
+fn signature_message(files: &[(String, String)]) -> String {
+	let mut message = SIGNATURE_MESSAGE_HEADER.to_string();
+	for (name, sha256) in files {
+		message.push_str(&format!("{sha256}  {name}\n"));
+	}
+	message
+}
+
+fn load_signing_key(key_file: &SPath) -> Result<SigningKey> {
+	let content = fs::read_to_string(key_file)
+		.map_err(|err| Error::cc(format!("Cannot read signing key file '{key_file}'"), err))?;
+	let secret: [u8; 32] = BASE64
+		.decode(content.trim())
+		.map_err(|err| Error::cc(format!("Signing key file '{key_file}' is not base64"), err))?
+		.try_into()
+		.map_err(|_| Error::custom(format!("Signing key in '{key_file}' must be 32 bytes")))?;
+	Ok(SigningKey::from_bytes(&secret))
+}
+
+fn decode_verifying_key(public_key: &str) -> Result<VerifyingKey> {
+	let bytes: [u8; 32] = BASE64
+		.decode(public_key.trim())
+		.map_err(|err| Error::cc("Public key is not base64", err))?
+		.try_into()
+		.map_err(|_| Error::custom("Public key must be 32 bytes"))?;
+	VerifyingKey::from_bytes(&bytes).map_err(|err| Error::cc("Invalid public key", err))
+}
+
+#[cfg(unix)]
+fn write_secret_file(file: &SPath, content: &str) -> Result<()> {
+	use std::io::Write as _;
+	use std::os::unix::fs::OpenOptionsExt as _;
+
+	let mut f = fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(file)?;
+	f.write_all(content.as_bytes())?;
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_secret_file(file: &SPath, content: &str) -> Result<()> {
+	fs::write(file, content)?;
+	Ok(())
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../_tests/tests_pack_signature.rs"]
+mod tests_pack_signature;
+
+// endregion: --- Tests
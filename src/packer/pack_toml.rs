@@ -1,13 +1,21 @@
-use crate::pack::PackIdentity;
+use crate::pack::{PackIdentity, PackPermissions};
+use crate::packer::support;
 use crate::{Error, Result};
 use lazy_regex::regex;
+use semver::VersionReq;
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
 /// Represents the partial structure of pack.toml with optional fields
 /// for initial parsing and validation
 #[derive(Deserialize)]
 pub struct PartialPackToml {
 	pub pack: Option<PartialPackInfo>,
+	/// The `[dependencies]` table, keyed by pack identity (e.g., `"demo@craft" = "^0.2"`)
+	pub dependencies: Option<BTreeMap<String, PartialPackDependency>>,
+	/// The `[permissions]` of the pack agents (see `PackPermissions`)
+	pub permissions: Option<PackPermissions>,
 }
 
 /// Contains the inner pack information that may be partial/incomplete
@@ -16,6 +24,45 @@ pub struct PartialPackInfo {
 	pub version: Option<String>,
 	pub namespace: Option<String>,
 	pub name: Option<String>,
+	pub description: Option<String>,
+	pub license: Option<String>,
+	pub homepage: Option<String>,
+	pub repo: Option<String>,
+	pub author: Option<String>,
+	pub email: Option<String>,
+	/// The `[pack.files]` table
+	pub files: Option<PartialPackFiles>,
+}
+
+/// The files to put in the .aipack file (globs relative to the pack dir)
+///
+/// ```toml
+/// [pack.files]
+/// include = ["**/*.aip", "lua/**", "README.md"]
+/// exclude = ["tests/**"]
+/// ```
+#[derive(Deserialize)]
+pub struct PartialPackFiles {
+	pub include: Option<Vec<String>>,
+	pub exclude: Option<Vec<String>>,
+}
+
+/// A dependency value, either just the semver requirement,
+/// or a table with the requirement and an optional uri (local path or http link)
+///
+/// ```toml
+/// [dependencies]
+/// "demo@craft" = "^0.2"
+/// "demo@utils" = { version = "^1.0", uri = "https://example.com/demo@utils-v1-0-0.aipack" }
+/// ```
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum PartialPackDependency {
+	Version(String),
+	Detailed {
+		version: Option<String>,
+		uri: Option<String>,
+	},
 }
 
 /// Contains the validated required fields from pack.toml
@@ -24,6 +71,42 @@ pub struct PackToml {
 	pub version: String,
 	pub namespace: String,
 	pub name: String,
+	/// The optional metadata (`aip info`), the description is also used by `aip search`
+	pub description: Option<String>,
+	pub license: Option<String>,
+	pub homepage: Option<String>,
+	pub repo: Option<String>,
+	pub author: Option<String>,
+	pub email: Option<String>,
+	pub dependencies: Vec<PackDependency>,
+	pub files: PackFiles,
+	pub permissions: Option<PackPermissions>,
+}
+
+/// The validated `[pack.files]` globs (empty when not set)
+#[derive(Debug, Clone, Default)]
+pub struct PackFiles {
+	/// When empty, all the files are included
+	pub include: Vec<String>,
+	pub exclude: Vec<String>,
+}
+
+impl PackToml {
+	pub fn identity(&self) -> PackIdentity {
+		PackIdentity {
+			namespace: self.namespace.clone(),
+			name: self.name.clone(),
+		}
+	}
+}
+
+/// A validated dependency of a pack
+#[derive(Debug, Clone)]
+pub struct PackDependency {
+	pub identity: PackIdentity,
+	pub version_req: VersionReq,
+	/// When None, the dependency is resolved from the repo
+	pub uri: Option<String>,
 }
 
 /// Validates the pack.toml content and returns a PackToml struct if valid
@@ -67,13 +150,86 @@ pub(super) fn parse_validate_pack_toml(toml_content: &str, toml_path: &str) -> R
 	// Validate namespace and name format
 	validate_names(&namespace, &name, toml_path)?;
 
+	let dependencies = validate_dependencies(partial_config.dependencies, toml_path)?;
+
+	let files = validate_files(pack_info.files, toml_path)?;
+
+	if let Some(permissions) = partial_config.permissions.as_ref() {
+		permissions.validate(toml_path)?;
+	}
+
 	Ok(PackToml {
 		version,
 		namespace,
 		name,
+		description: non_empty(pack_info.description),
+		license: non_empty(pack_info.license),
+		homepage: non_empty(pack_info.homepage),
+		repo: non_empty(pack_info.repo),
+		author: non_empty(pack_info.author),
+		email: non_empty(pack_info.email),
+		dependencies,
+		files,
+		permissions: partial_config.permissions,
 	})
 }
 
+/// The trimmed value, None when empty
+fn non_empty(value: Option<String>) -> Option<String> {
+	value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Validates the `[dependencies]` table (pack identities and semver requirements)
+fn validate_dependencies(
+	dependencies: Option<BTreeMap<String, PartialPackDependency>>,
+	toml_path: &str,
+) -> Result<Vec<PackDependency>> {
+	let Some(dependencies) = dependencies else {
+		return Ok(Vec::new());
+	};
+
+	let mut pack_dependencies = Vec::with_capacity(dependencies.len());
+	for (identity, dependency) in dependencies {
+		let identity = PackIdentity::from_str(&identity)?;
+		let (version, uri) = match dependency {
+			PartialPackDependency::Version(version) => (Some(version), None),
+			PartialPackDependency::Detailed { version, uri } => (version, uri),
+		};
+		let version = version.unwrap_or_else(|| "*".to_string());
+		let version_req = support::parse_version_req(&version).map_err(|err| {
+			Error::custom(format!(
+				"Invalid version requirement '{version}' for dependency '{identity}' in {toml_path}. Cause: {err}"
+			))
+		})?;
+		pack_dependencies.push(PackDependency {
+			identity,
+			version_req,
+			uri,
+		});
+	}
+
+	Ok(pack_dependencies)
+}
+
+/// Validates the `[pack.files]` globs
+fn validate_files(files: Option<PartialPackFiles>, toml_path: &str) -> Result<PackFiles> {
+	let Some(files) = files else {
+		return Ok(PackFiles::default());
+	};
+
+	let include = files.include.unwrap_or_default();
+	let exclude = files.exclude.unwrap_or_default();
+	for glob in include.iter().chain(exclude.iter()) {
+		simple_fs::get_glob_set(&[glob.as_str()]).map_err(|err| {
+			Error::custom(format!(
+				"Invalid [pack.files] glob '{glob}' in {toml_path}. Cause: {err}"
+			))
+		})?;
+	}
+
+	Ok(PackFiles { include, exclude })
+}
+
 /// Validates the version string according to semver compatibility
 ///
 /// Version must follow the format x.y.z and can optionally have a -suffix.number
@@ -130,6 +286,88 @@ name = "pack"
 		assert_eq!(pack_toml.version, "1.0.0");
 		assert_eq!(pack_toml.namespace, "test");
 		assert_eq!(pack_toml.name, "pack");
+		assert!(pack_toml.description.is_none());
+		assert!(pack_toml.dependencies.is_empty());
+		assert!(pack_toml.files.include.is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_packer_pack_toml_validate_files() -> Result<()> {
+		// -- Setup & Fixtures
+		let valid_toml = r#"
+[pack]
+version = "1.0.0"
+namespace = "test"
+name = "pack"
+description = "  Some test pack  "
+license = "MIT"
+author = ""
+
+[pack.files]
+include = ["**/*.aip", "lua/**"]
+exclude = ["tests/**"]
+"#;
+		let toml_path = Utf8PathBuf::from("dummy/path/pack.toml");
+
+		// -- Exec
+		let pack_toml = parse_validate_pack_toml(valid_toml, toml_path.as_str())?;
+
+		// -- Check
+		assert_eq!(pack_toml.description.as_deref(), Some("Some test pack"));
+		assert_eq!(pack_toml.license.as_deref(), Some("MIT"));
+		assert!(pack_toml.author.is_none());
+		assert_eq!(pack_toml.files.include, vec!["**/*.aip", "lua/**"]);
+		assert_eq!(pack_toml.files.exclude, vec!["tests/**"]);
+
+		// Invalid glob
+		let invalid_toml = valid_toml.replace(r#"exclude = ["tests/**"]"#, r#"exclude = ["tests/[a"]"#);
+		assert!(parse_validate_pack_toml(&invalid_toml, toml_path.as_str()).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_packer_pack_toml_validate_dependencies() -> Result<()> {
+		// -- Setup & Fixtures
+		let valid_toml = r#"
+[pack]
+version = "1.0.0"
+namespace = "test"
+name = "pack"
+
+[dependencies]
+"demo@craft" = "^0.2"
+"demo@utils" = { version = ">=1.0.0, <2.0.0", uri = "some/path/demo@utils-v1-0-0.aipack" }
+"#;
+		let toml_path = Utf8PathBuf::from("dummy/path/pack.toml");
+
+		// -- Exec
+		let pack_toml = parse_validate_pack_toml(valid_toml, toml_path.as_str())?;
+
+		// -- Check
+		assert_eq!(pack_toml.dependencies.len(), 2);
+		let craft = &pack_toml.dependencies[0];
+		assert_eq!(craft.identity.to_string(), "demo@craft");
+		assert!(craft.version_req.matches(&semver::Version::parse("0.2.3")?));
+		assert!(!craft.version_req.matches(&semver::Version::parse("0.3.0")?));
+		assert!(craft.uri.is_none());
+		let utils = &pack_toml.dependencies[1];
+		assert_eq!(utils.identity.to_string(), "demo@utils");
+		assert_eq!(utils.uri.as_deref(), Some("some/path/demo@utils-v1-0-0.aipack"));
+
+		// Invalid requirement
+		let invalid_toml = r#"
+[pack]
+version = "1.0.0"
+namespace = "test"
+name = "pack"
+
+[dependencies]
+"demo@craft" = "not-a-version"
+"#;
+		assert!(parse_validate_pack_toml(invalid_toml, toml_path.as_str()).is_err());
 
 		Ok(())
 	}
@@ -1,6 +1,7 @@
 //! Module that pack the files into their .aipack
 
 use crate::packer::PackToml;
+use crate::packer::pack_files::{PackFile, list_pack_files};
 use crate::packer::pack_toml::parse_validate_pack_toml;
 use crate::packer::support;
 use crate::support::zip;
@@ -30,15 +31,7 @@ pub fn pack_dir(pack_dir: impl AsRef<Utf8Path>, dest_dir: impl AsRef<Utf8Path>)
 	let pack_dir = pack_dir.as_ref();
 	let dest_dir = dest_dir.as_ref();
 
-	// Verify if pack.toml exists
-	let toml_path = pack_dir.join("pack.toml");
-	if !toml_path.exists() {
-		return Err(Error::AipackTomlMissing(toml_path.into()));
-	}
-
-	// Read and validate the TOML file
-	let toml_content = fs::read_to_string(&toml_path)?;
-	let pack_toml = parse_validate_pack_toml(&toml_content, toml_path.as_str())?;
+	let pack_toml = load_pack_dir_toml(pack_dir)?;
 
 	// Normalize version - replace special characters with hyphens
 	let normalized_version = support::normalize_version(&pack_toml.version);
@@ -55,8 +48,10 @@ pub fn pack_dir(pack_dir: impl AsRef<Utf8Path>, dest_dir: impl AsRef<Utf8Path>)
 		fs::create_dir_all(dest_dir)?;
 	}
 
-	// Zip the directory
-	zip::zip_dir(pack_dir, &aipack_path)?;
+	// Zip the pack files (see `[pack.files]` and `.aipackignore`)
+	let files = list_pack_files(&SPath::new(pack_dir.as_str()), &pack_toml)?;
+	let file_names: Vec<String> = files.into_iter().map(|f| f.name).collect();
+	zip::zip_files(pack_dir, &file_names, &aipack_path)?;
 
 	Ok(PackDirData {
 		pack_file: aipack_path.into(),
@@ -64,6 +59,27 @@ pub fn pack_dir(pack_dir: impl AsRef<Utf8Path>, dest_dir: impl AsRef<Utf8Path>)
 	})
 }
 
+/// Returns the files of the directory that `pack_dir` would put in the .aipack file (sorted by name)
+pub fn list_pack_dir_files(pack_dir: impl AsRef<Utf8Path>) -> Result<(PackToml, Vec<PackFile>)> {
+	let pack_dir = pack_dir.as_ref();
+	let pack_toml = load_pack_dir_toml(pack_dir)?;
+	let files = list_pack_files(&SPath::new(pack_dir.as_str()), &pack_toml)?;
+	Ok((pack_toml, files))
+}
+
+/// Read and validate the `pack.toml` of the pack dir
+fn load_pack_dir_toml(pack_dir: &Utf8Path) -> Result<PackToml> {
+	// Verify if pack.toml exists
+	let toml_path = pack_dir.join("pack.toml");
+	if !toml_path.exists() {
+		return Err(Error::AipackTomlMissing(toml_path.into()));
+	}
+
+	// Read and validate the TOML file
+	let toml_content = fs::read_to_string(&toml_path)?;
+	parse_validate_pack_toml(&toml_content, toml_path.as_str())
+}
+
 // region:    --- Tests
 
 #[cfg(test)]
@@ -3,7 +3,8 @@ use crate::packer::pack_toml::{PartialPackToml, parse_validate_pack_toml};
 use crate::support::zip;
 use crate::{Error, Result};
 use lazy_regex::regex;
-use semver::Version;
+use semver::{Version, VersionReq};
+use sha2::{Digest, Sha256};
 use simple_fs::SPath;
 
 /// Extracts and validates the pack.toml from an .aipack file
@@ -127,6 +128,33 @@ pub fn validate_version_update(installed_version: &str, new_version: &str) -> Re
 	Ok(())
 }
 
+/// Returns true if the candidate version is strictly greater than the current version
+///
+/// Returns false if either version cannot be parsed as semver.
+pub fn is_version_newer(current_version: &str, candidate_version: &str) -> bool {
+	let current = Version::parse(current_version.trim_start_matches('v'));
+	let candidate = Version::parse(candidate_version.trim_start_matches('v'));
+	match (current, candidate) {
+		(Ok(current), Ok(candidate)) => candidate > current,
+		_ => false,
+	}
+}
+
+/// Parses a dependency version requirement (e.g., `^0.2`, `>=1.0.0, <2.0.0`, `*`)
+///
+/// Note: A leading 'v' is removed, as for the versions (e.g., `v1.0.0` is `^1.0.0`)
+pub fn parse_version_req(version_req: &str) -> Result<VersionReq> {
+	let version_req = version_req.trim().trim_start_matches('v');
+	VersionReq::parse(version_req).map_err(|err| Error::custom(err.to_string()))
+}
+
+/// Returns true if the pack version satisfies the version requirement
+///
+/// A version that cannot be parsed as semver does not satisfy any requirement.
+pub fn version_matches(version: &str, version_req: &VersionReq) -> bool {
+	Version::parse(version.trim_start_matches('v')).is_ok_and(|version| version_req.matches(&version))
+}
+
 /// Validates if the version format is valid for installation
 ///
 /// In addition to standard semver validation, this function checks that
@@ -205,6 +233,16 @@ pub fn get_file_size(file_path: &SPath, reference: &str) -> Result<usize> {
 	Ok(metadata.len() as usize)
 }
 
+/// Compute the SHA-256 (hex) of a file (e.g., the .aipack file, for the pack.lock)
+pub fn compute_file_sha256(file_path: &SPath) -> Result<String> {
+	let content = std::fs::read(file_path.path()).map_err(|e| Error::FailToInstall {
+		aipack_ref: file_path.to_str().to_string(),
+		cause: format!("Failed to read file for checksum: {}", e),
+	})?;
+	let hash = Sha256::digest(&content);
+	Ok(hash.iter().map(|b| format!("{b:02x}")).collect())
+}
+
 /// Calculate the total size of a directory recursively
 pub fn calculate_directory_size(dir_path: &SPath) -> Result<usize> {
 	use walkdir::WalkDir;
@@ -277,6 +315,34 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn test_is_version_newer() -> Result<()> {
+		assert!(is_version_newer("0.1.0", "0.2.0"));
+		assert!(is_version_newer("v0.1.0", "0.1.1"));
+		assert!(is_version_newer("0.1.0-alpha.1", "0.1.0"));
+		assert!(!is_version_newer("0.2.0", "0.2.0"));
+		assert!(!is_version_newer("0.2.0", "0.1.0"));
+		assert!(!is_version_newer("invalid", "0.1.0"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_version_matches() -> Result<()> {
+		let req = parse_version_req("^0.2")?;
+		assert!(version_matches("0.2.0", &req));
+		assert!(version_matches("v0.2.5", &req));
+		assert!(!version_matches("0.3.0", &req));
+		assert!(!version_matches("invalid", &req));
+
+		let req = parse_version_req("*")?;
+		assert!(version_matches("1.2.3", &req));
+
+		assert!(parse_version_req("not-a-version").is_err());
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_validate_version_for_install() -> Result<()> {
 		// Test valid versions
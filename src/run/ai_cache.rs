@@ -0,0 +1,164 @@
+//! The AI response cache, stored in `.aipack/.cache/ai_responses/`
+//!
+//! Each entry is a json file named by the SHA-256 of the (resolved model, chat options, chat request).
+
+use crate::Result;
+use crate::agent::CacheOptions;
+use crate::dir_context::DirContext;
+use genai::chat::{ChatOptions, ChatRequest, ChatResponse};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use simple_fs::{SPath, ensure_dir};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const AI_RESPONSES_DIR: &str = "ai_responses";
+const DEFAULT_MAX_SIZE_MB: u64 = 100;
+
+#[derive(Debug)]
+pub struct AiCache {
+	dir: SPath,
+	ttl_secs: Option<u64>,
+	max_size_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AiCacheEntry {
+	created_epoch_secs: u64,
+	chat_response: ChatResponse,
+}
+
+/// Constructors
+impl AiCache {
+	/// Returns the cache if enabled in the options, otherwise None
+	pub fn from_options(dir_context: &DirContext, cache_options: Option<&CacheOptions>) -> Result<Option<Self>> {
+		let Some(cache_options) = cache_options.filter(|c| c.enabled()) else {
+			return Ok(None);
+		};
+
+		let dir = dir_context.aipack_paths().get_wks_cache_dir()?.join(AI_RESPONSES_DIR);
+		let max_size_mb = cache_options.max_size_mb().unwrap_or(DEFAULT_MAX_SIZE_MB);
+
+		Ok(Some(AiCache {
+			dir,
+			ttl_secs: cache_options.ttl_secs(),
+			max_size_bytes: max_size_mb * 1024 * 1024,
+		}))
+	}
+}
+
+impl AiCache {
+	/// Compute the cache key (SHA-256 hex) for this chat request.
+	pub fn compute_key(model: &str, chat_options: &ChatOptions, chat_req: &ChatRequest) -> Result<String> {
+		let key_content = serde_json::to_vec(&(model, chat_options, chat_req))?;
+		let hash = Sha256::digest(&key_content);
+		Ok(hash.iter().map(|b| format!("{b:02x}")).collect())
+	}
+
+	/// Returns the cached response if present and not expired.
+	///
+	/// Note: Expired or unreadable entries are removed, and treated as absent.
+	pub fn get(&self, key: &str) -> Result<Option<ChatResponse>> {
+		let file = self.entry_path(key);
+		if !file.exists() {
+			return Ok(None);
+		}
+
+		let entry = fs::read(&file)
+			.ok()
+			.and_then(|content| serde_json::from_slice::<AiCacheEntry>(&content).ok());
+		let Some(entry) = entry else {
+			let _ = fs::remove_file(&file);
+			return Ok(None);
+		};
+
+		if let Some(ttl_secs) = self.ttl_secs {
+			if now_epoch_secs().saturating_sub(entry.created_epoch_secs) > ttl_secs {
+				let _ = fs::remove_file(&file);
+				return Ok(None);
+			}
+		}
+
+		Ok(Some(entry.chat_response))
+	}
+
+	/// Save the response in the cache, and evict the oldest entries if the cache is above its max size.
+	pub fn put(&self, key: &str, chat_response: &ChatResponse) -> Result<()> {
+		ensure_dir(&self.dir)?;
+
+		let entry = AiCacheEntry {
+			created_epoch_secs: now_epoch_secs(),
+			chat_response: chat_response.clone(),
+		};
+		let content = serde_json::to_vec(&entry)?;
+
+		// Note: Write to a unique temp file first, so that a concurrent `get` never reads a partial entry
+		let file = self.entry_path(key);
+		let tmp_file = self.dir.join(format!("{key}.{}.tmp", now_epoch_nanos()));
+		fs::write(&tmp_file, content)?;
+		fs::rename(&tmp_file, &file)?;
+
+		self.evict_above_max_size()?;
+
+		Ok(())
+	}
+}
+
+/// Private
+impl AiCache {
+	fn entry_path(&self, key: &str) -> SPath {
+		self.dir.join(format!("{key}.json"))
+	}
+
+	fn evict_above_max_size(&self) -> Result<()> {
+		// (modified, size, path)
+		let mut entries: Vec<(SystemTime, u64, std::path::PathBuf)> = Vec::new();
+		let mut total_size = 0;
+		for dir_entry in fs::read_dir(&self.dir)?.flatten() {
+			let Ok(meta) = dir_entry.metadata() else {
+				continue;
+			};
+			// Note: Only the entries (not the in-progress temp files)
+			let path = dir_entry.path();
+			if !meta.is_file() || path.extension().is_none_or(|ext| ext != "json") {
+				continue;
+			}
+			total_size += meta.len();
+			entries.push((meta.modified().unwrap_or(UNIX_EPOCH), meta.len(), path));
+		}
+
+		if total_size <= self.max_size_bytes {
+			return Ok(());
+		}
+
+		entries.sort_by_key(|(modified, _, _)| *modified);
+		for (_, size, path) in entries {
+			if total_size <= self.max_size_bytes {
+				break;
+			}
+			if fs::remove_file(&path).is_ok() {
+				total_size -= size;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+// region:    --- Support
+
+fn now_epoch_secs() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or_default()
+}
+
+fn now_epoch_nanos() -> u128 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_nanos())
+		.unwrap_or_default()
+}
+
+// endregion: --- Support
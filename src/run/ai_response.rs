@@ -1,22 +1,28 @@
 // region:    --- AiResponse
 
+use crate::script::serde_to_lua_value;
 use crate::support::W;
 use genai::ModelName;
 use genai::adapter::AdapterKind;
 use genai::chat::MetaUsage;
 use mlua::IntoLua;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiResponse {
 	pub content: Option<String>,
 	pub reasoning_content: Option<String>,
+	/// The parsed and validated json content, when the agent has an `# Output Schema`
+	pub json: Option<Value>,
 	pub model_name: ModelName,
 	pub adapter_kind: AdapterKind,
 	pub usage: MetaUsage,
 	pub price_usd: Option<f64>,
 	pub duration_sec: f64,
 	pub info: String,
+	/// True if the response came from the AI response cache
+	pub cached: bool,
 }
 
 impl IntoLua for AiResponse {
@@ -25,12 +31,16 @@ impl IntoLua for AiResponse {
 
 		table.set("content", self.content.into_lua(lua)?)?;
 		table.set("reasoning_content", self.reasoning_content.into_lua(lua)?)?;
+		if let Some(json) = self.json {
+			table.set("json", serde_to_lua_value(lua, json)?)?;
+		}
 		table.set("model_name", self.model_name.into_lua(lua)?)?;
 		table.set("adapter_kind", self.adapter_kind.as_str().into_lua(lua)?)?;
 		table.set("usage", W(&self.usage).into_lua(lua)?)?;
 		table.set("price_usd", self.price_usd.into_lua(lua)?)?;
 		table.set("duration_sec", self.duration_sec.into_lua(lua)?)?;
 		table.set("info", self.info.into_lua(lua)?)?;
+		table.set("cached", self.cached.into_lua(lua)?)?;
 
 		Ok(mlua::Value::Table(table))
 	}
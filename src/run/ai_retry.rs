@@ -0,0 +1,161 @@
+use crate::agent::{RetryErrorKind, RetryOptions};
+use crate::hub::get_hub;
+use crate::{Error, Result};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher as _, Hasher as _};
+use std::time::Duration;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 1;
+const DEFAULT_BASE_DELAY_MS: u64 = 1000;
+const DEFAULT_JITTER: bool = true;
+const DEFAULT_RETRY_ON: &[RetryErrorKind] =
+	&[RetryErrorKind::RateLimit, RetryErrorKind::Server, RetryErrorKind::Network];
+/// The max delay between two attempts, regardless of the number of attempts
+const MAX_DELAY_MS: u64 = 60_000;
+
+// region:    --- RetryPolicy
+
+/// The resolved retry policy (from the agent `RetryOptions`, with the defaults applied)
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	base_delay_ms: u64,
+	jitter: bool,
+	retry_on: Vec<RetryErrorKind>,
+}
+
+impl From<Option<&RetryOptions>> for RetryPolicy {
+	fn from(retry_options: Option<&RetryOptions>) -> Self {
+		let max_attempts = retry_options.and_then(|r| r.max_attempts()).unwrap_or(DEFAULT_MAX_ATTEMPTS);
+		let base_delay_ms = retry_options.and_then(|r| r.base_delay_ms()).unwrap_or(DEFAULT_BASE_DELAY_MS);
+		let jitter = retry_options.and_then(|r| r.jitter()).unwrap_or(DEFAULT_JITTER);
+		let retry_on = retry_options.and_then(|r| r.retry_on()).unwrap_or(DEFAULT_RETRY_ON).to_vec();
+
+		RetryPolicy {
+			// Note: 0 does not make sense, so, same as 1 (no retry)
+			max_attempts: max_attempts.max(1),
+			base_delay_ms,
+			jitter,
+			retry_on,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Returns the retryable error kind if this error should be retried with this policy
+	fn retryable_kind(&self, err: &Error) -> Option<RetryErrorKind> {
+		let kind = classify_error(err)?;
+		self.retry_on.contains(&kind).then_some(kind)
+	}
+
+	/// The delay before the next attempt (attempt is the 1-based number of the attempt that just failed)
+	fn delay(&self, attempt: u32) -> Duration {
+		let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+		let delay_ms = self.base_delay_ms.saturating_mul(factor).min(MAX_DELAY_MS);
+
+		// Equal jitter: half fixed, half random
+		let delay_ms = if self.jitter {
+			let half = delay_ms / 2;
+			half + (random_ratio() * half as f64) as u64
+		} else {
+			delay_ms
+		};
+
+		Duration::from_millis(delay_ms)
+	}
+}
+
+// endregion: --- RetryPolicy
+
+/// Execute the `exec_fn` with the retry policy, and returns the result with the number of retries performed.
+///
+/// Each retry is published to the hub, with the error cause.
+pub async fn exec_with_retry<T, F, Fut>(policy: &RetryPolicy, label: &str, mut exec_fn: F) -> Result<(T, u32)>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T>>,
+{
+	let hub = get_hub();
+	let mut attempt = 1;
+
+	loop {
+		let err = match exec_fn().await {
+			Ok(res) => return Ok((res, attempt - 1)),
+			Err(err) => err,
+		};
+
+		if attempt >= policy.max_attempts {
+			return Err(err);
+		}
+		let Some(kind) = policy.retryable_kind(&err) else {
+			return Err(err);
+		};
+
+		let delay = policy.delay(attempt);
+		hub.publish(format!(
+			"-! Retry {attempt}/{} for input '{label}' in {}ms ({}) - Cause: {err}",
+			policy.max_attempts - 1,
+			delay.as_millis(),
+			kind.as_ref()
+		))
+		.await;
+
+		tokio::time::sleep(delay).await;
+		attempt += 1;
+	}
+}
+
+// region:    --- Support
+
+/// Classify the error into a retryable kind, or None if the error is not transient.
+fn classify_error(err: &Error) -> Option<RetryErrorKind> {
+	let Error::GenAI(genai_err) = err else {
+		return None;
+	};
+
+	let webc_error = match genai_err {
+		genai::Error::WebModelCall { webc_error, .. } => webc_error,
+		genai::Error::WebAdapterCall { webc_error, .. } => webc_error,
+		// The stream requests (the connection can also fail mid-stream)
+		genai::Error::ReqwestEventSource(event_source_error) => {
+			return match event_source_error {
+				reqwest_eventsource::Error::InvalidStatusCode(status, _) => classify_status(*status),
+				reqwest_eventsource::Error::Transport(_) => Some(RetryErrorKind::Network),
+				_ => None,
+			};
+		}
+		genai::Error::WebStream { .. } => return Some(RetryErrorKind::Network),
+		_ => return None,
+	};
+
+	match webc_error {
+		genai::webc::Error::ResponseFailedStatus { status, .. } => classify_status(*status),
+		genai::webc::Error::Reqwest(_) => Some(RetryErrorKind::Network),
+		_ => None,
+	}
+}
+
+fn classify_status(status: reqwest::StatusCode) -> Option<RetryErrorKind> {
+	if status.as_u16() == 429 {
+		Some(RetryErrorKind::RateLimit)
+	} else if status.is_server_error() {
+		Some(RetryErrorKind::Server)
+	} else {
+		None
+	}
+}
+
+/// Returns a pseudo random number between 0.0 and 1.0 (good enough for jitter)
+fn random_ratio() -> f64 {
+	let mut hasher = RandomState::new().build_hasher();
+	hasher.write_u128(
+		std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.map(|d| d.as_nanos())
+			.unwrap_or_default(),
+	);
+	(hasher.finish() % 10_000) as f64 / 10_000.0
+}
+
+// endregion: --- Support
@@ -0,0 +1,92 @@
+use crate::Result;
+use crate::hub::get_hub;
+use crate::tui::AiStreamEvent;
+use genai::Client;
+use genai::chat::{ChatOptions, ChatRequest, ChatResponse, ChatStreamEvent, MessageContent, StreamEnd};
+use tokio_stream::StreamExt as _;
+
+/// Execute the chat request in stream mode, publishing each chunk to the hub as it arrives,
+/// and return the assembled `ChatResponse` (same as what `client.exec_chat` would return).
+///
+/// Note: The capture flags are forced on, so that the content, reasoning content, and usage
+///       get captured by genai at the end of the stream.
+pub async fn exec_chat_stream(
+	client: &Client,
+	model: &str,
+	chat_req: ChatRequest,
+	chat_options: &ChatOptions,
+	input_idx: usize,
+	label: &str,
+) -> Result<ChatResponse> {
+	let hub = get_hub();
+
+	let chat_options = chat_options
+		.clone()
+		.with_capture_usage(true)
+		.with_capture_content(true)
+		.with_capture_reasoning_content(true);
+
+	let stream_res = client.exec_chat_stream(model, chat_req, Some(&chat_options)).await?;
+	let model_iden = stream_res.model_iden;
+	let mut stream = stream_res.stream;
+
+	// Fallback buffers, in case the provider adapter does not capture the content
+	let mut content_buff = String::new();
+	let mut reasoning_buff = String::new();
+	let mut stream_end: Option<StreamEnd> = None;
+
+	hub.publish(AiStreamEvent::Start {
+		input_idx,
+		label: label.to_string(),
+	})
+	.await;
+
+	while let Some(event) = stream.next().await {
+		let event = match event {
+			Ok(event) => event,
+			Err(err) => {
+				// Note: So that the printer does not keep the partial output open (a retry streams again from the start)
+				hub.publish(AiStreamEvent::Interrupted { input_idx }).await;
+				return Err(err.into());
+			}
+		};
+		match event {
+			ChatStreamEvent::Start => (),
+			ChatStreamEvent::Chunk(chunk) => {
+				content_buff.push_str(&chunk.content);
+				hub.publish(AiStreamEvent::Chunk {
+					input_idx,
+					content: chunk.content,
+				})
+				.await;
+			}
+			ChatStreamEvent::ReasoningChunk(chunk) => {
+				reasoning_buff.push_str(&chunk.content);
+				hub.publish(AiStreamEvent::ReasoningChunk {
+					input_idx,
+					content: chunk.content,
+				})
+				.await;
+			}
+			ChatStreamEvent::End(end) => stream_end = Some(end),
+		}
+	}
+
+	hub.publish(AiStreamEvent::End { input_idx }).await;
+
+	// -- Assemble the ChatResponse
+	let (captured_content, captured_reasoning_content, captured_usage) = match stream_end {
+		Some(end) => (end.captured_content, end.captured_reasoning_content, end.captured_usage),
+		None => (None, None, None),
+	};
+
+	let content = captured_content.or_else(|| (!content_buff.is_empty()).then(|| MessageContent::from(content_buff)));
+	let reasoning_content = captured_reasoning_content.or((!reasoning_buff.is_empty()).then_some(reasoning_buff));
+
+	Ok(ChatResponse {
+		content,
+		reasoning_content,
+		model_iden,
+		usage: captured_usage.unwrap_or_default(),
+	})
+}
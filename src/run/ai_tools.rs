@@ -0,0 +1,96 @@
+//! Execution of the agent `# Tools` called by the AI.
+
+use crate::Result;
+use crate::agent::Agent;
+use crate::hub::get_hub;
+use crate::run::Runtime;
+use crate::run::literals::Literals;
+use crate::script::LuaStage;
+use genai::chat::{ToolCall, ToolResponse};
+use serde_json::Value;
+
+pub const DEFAULT_TOOLS_MAX_ITERATIONS: u32 = 10;
+
+/// The values put in the scope of the tool Lua script (on top of the `args`)
+pub struct ToolScope<'a> {
+	pub runtime: &'a Runtime,
+	pub agent: &'a Agent,
+	pub literals: &'a Literals,
+	pub input: &'a Value,
+	pub data: &'a Value,
+	pub before_all: &'a Value,
+}
+
+/// Execute the tool call in a new Lua engine (within the `tools` Lua limits), and return the tool response for the AI.
+///
+/// Note: A failing tool (unknown, or lua error) does not fail the input.
+///       The error is returned as the tool response content, so that the AI can recover.
+pub async fn exec_tool_call(tool_scope: &ToolScope<'_>, tool_call: &ToolCall, label: &str) -> ToolResponse {
+	let hub = get_hub();
+	let ToolCall {
+		call_id,
+		fn_name,
+		fn_arguments,
+	} = tool_call;
+
+	hub.publish(format!("-> Tool call {fn_name}({fn_arguments}) for input {label}"))
+		.await;
+
+	let content = match exec_tool_script(tool_scope, fn_name, fn_arguments.clone(), label) {
+		Ok(content) => {
+			hub.publish(format!("<- Tool response {fn_name}: {content}")).await;
+			content
+		}
+		Err(err) => {
+			let content = format!("Error: {err}");
+			hub.publish(format!("-! Tool {fn_name} failed. {content}")).await;
+			content
+		}
+	};
+
+	ToolResponse::new(call_id, content)
+}
+
+// region:    --- Support
+
+fn exec_tool_script(tool_scope: &ToolScope<'_>, fn_name: &str, args: Value, label: &str) -> Result<String> {
+	let ToolScope {
+		runtime,
+		agent,
+		literals,
+		input,
+		data,
+		before_all,
+	} = tool_scope;
+
+	let tool = agent
+		.tools()
+		.iter()
+		.find(|t| t.name == fn_name)
+		.ok_or_else(|| format!("Tool '{fn_name}' does not exist"))?;
+
+	let agent_dir = agent.file_dir()?;
+
+	let lua_engine = runtime.lua_engine_for_agent(agent)?;
+	let lua_scope = lua_engine.create_table()?;
+	lua_scope.set("args", lua_engine.serde_to_lua_value(args)?)?;
+	lua_scope.set("input", lua_engine.serde_to_lua_value((*input).clone())?)?;
+	lua_scope.set("data", lua_engine.serde_to_lua_value((*data).clone())?)?;
+	lua_scope.set("before_all", lua_engine.serde_to_lua_value((*before_all).clone())?)?;
+	lua_scope.set("CTX", literals.to_lua(&lua_engine)?)?;
+	lua_scope.set("options", agent.options_as_ref())?;
+
+	let lua_stage = LuaStage::new(agent, "tools", Some(label));
+	let lua_value = lua_engine.eval_stage(&tool.script, Some(lua_scope), Some(&[agent_dir.to_str()]), &lua_stage)?;
+	let value = serde_json::to_value(lua_value)?;
+
+	// Note: A string is given as is to the AI, other values as json
+	let content = match value {
+		Value::String(content) => content,
+		other => other.to_string(),
+	};
+
+	Ok(content)
+}
+
+// endregion: --- Support
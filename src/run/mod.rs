@@ -2,17 +2,35 @@
 mod literals;
 mod run_input;
 
+mod ai_cache;
 mod ai_response;
+mod ai_retry;
+mod ai_stream;
+mod ai_tools;
 mod genai_client;
+mod run_budget;
 mod run_command;
 mod run_options;
+mod run_recorder;
+mod run_replay;
+mod run_summary;
+mod run_tracker;
 mod runtime;
 
-use ai_response::*;
+use ai_cache::*;
+use ai_retry::*;
+use ai_stream::*;
+use ai_tools::*;
 
+pub use ai_response::*;
 pub use genai_client::*;
+pub use run_budget::*;
 pub use run_command::*;
 pub use run_options::*;
+pub use run_recorder::*;
+pub use run_replay::*;
+pub use run_summary::*;
+pub use run_tracker::*;
 pub use runtime::*;
 
 // endregion: --- Modules
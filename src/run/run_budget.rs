@@ -0,0 +1,229 @@
+//! The run budget (`max_cost_usd` and `max_tokens_total`), shared across the concurrent inputs of a run.
+//!
+//! Each input reserves its estimated prompt and completion before calling the AI (pre-flight),
+//! and settles its actual usage as the AI responses arrive. An input that would exceed the budget is skipped.
+
+use crate::pricing::price_it;
+use genai::adapter::AdapterKind;
+use genai::chat::{ChatMessage, MetaUsage};
+use std::sync::{Arc, Mutex};
+
+/// Rough estimation of the number of chars per token (for the pre-flight estimation)
+const CHARS_PER_TOKEN: usize = 4;
+/// The estimated token overhead per message (role, separators)
+const TOKENS_PER_MESSAGE: u64 = 4;
+/// The estimated completion tokens of an AI call (reserved with the prompt, until the actual usage is settled)
+pub const EST_COMPLETION_TOKENS: u64 = 500;
+
+#[derive(Debug)]
+pub struct RunBudget {
+	max_cost_usd: Option<f64>,
+	max_tokens_total: Option<u64>,
+	state: Mutex<BudgetState>,
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+	cost_usd: f64,
+	tokens: u64,
+	reserved_cost_usd: f64,
+	reserved_tokens: u64,
+	skipped_inputs: usize,
+}
+
+/// Constructors
+impl RunBudget {
+	/// Returns None if there is no budget limit
+	pub fn new(max_cost_usd: Option<f64>, max_tokens_total: Option<u64>) -> Option<Arc<Self>> {
+		if max_cost_usd.is_none() && max_tokens_total.is_none() {
+			return None;
+		}
+
+		Some(Arc::new(RunBudget {
+			max_cost_usd,
+			max_tokens_total,
+			state: Mutex::new(BudgetState::default()),
+		}))
+	}
+}
+
+impl RunBudget {
+	/// Reserve the estimated tokens and cost of an input before calling the AI.
+	///
+	/// Returns the reason as error if the budget would be exceeded (the input should then be skipped)
+	pub fn reserve(
+		self: &Arc<Self>,
+		est_tokens: u64,
+		est_cost_usd: Option<f64>,
+	) -> core::result::Result<BudgetReservation, String> {
+		let mut state = self.state.lock().map_err(|_| "Run budget lock poisoned".to_string())?;
+
+		if let Some(max_tokens_total) = self.max_tokens_total {
+			let projected = state.tokens + state.reserved_tokens + est_tokens;
+			if projected > max_tokens_total {
+				state.skipped_inputs += 1;
+				return Err(format!(
+					"max_tokens_total budget of {max_tokens_total} would be exceeded (used: {}, in progress: {}, estimated: {est_tokens})",
+					state.tokens, state.reserved_tokens
+				));
+			}
+		}
+
+		if let Some(max_cost_usd) = self.max_cost_usd {
+			let est_cost = est_cost_usd.unwrap_or_default();
+			let projected = state.cost_usd + state.reserved_cost_usd + est_cost;
+			// Note: When the model price is unknown, only the cost already spent is checked
+			if projected > max_cost_usd || state.cost_usd >= max_cost_usd {
+				state.skipped_inputs += 1;
+				return Err(format!(
+					"max_cost_usd budget of ${max_cost_usd} would be exceeded (used: ~${:.4}, in progress: ~${:.4}, estimated: ~${est_cost:.4})",
+					state.cost_usd, state.reserved_cost_usd
+				));
+			}
+		}
+
+		state.reserved_tokens += est_tokens;
+		let est_cost_usd = est_cost_usd.unwrap_or_default();
+		state.reserved_cost_usd += est_cost_usd;
+
+		Ok(BudgetReservation {
+			budget: self.clone(),
+			tokens: est_tokens,
+			cost_usd: est_cost_usd,
+		})
+	}
+
+	/// Returns the budget summary if some inputs were skipped
+	pub fn exceeded_summary(&self) -> Option<String> {
+		let state = self.state.lock().ok()?;
+		if state.skipped_inputs == 0 {
+			return None;
+		}
+
+		let mut summary = format!("{} input(s) skipped because of the run budget", state.skipped_inputs);
+		if let Some(max_cost_usd) = self.max_cost_usd {
+			summary.push_str(&format!(" | Cost: ~${:.4} of ${max_cost_usd}", state.cost_usd));
+		}
+		if let Some(max_tokens_total) = self.max_tokens_total {
+			summary.push_str(&format!(" | Tokens: {} of {max_tokens_total}", state.tokens));
+		}
+		Some(summary)
+	}
+}
+
+// region:    --- BudgetReservation
+
+/// The reservation of an input in progress.
+///
+/// Note: When dropped (input done or failed), what remains of the reservation is released.
+#[derive(Debug)]
+pub struct BudgetReservation {
+	budget: Arc<RunBudget>,
+	tokens: u64,
+	cost_usd: f64,
+}
+
+impl BudgetReservation {
+	/// Settle the actual usage of an AI response of the input (the reservation is reduced by as much)
+	///
+	/// Note: An input can have many AI responses (e.g., tools, repairs), so this can be called many times.
+	pub fn settle(&mut self, tokens: u64, cost_usd: Option<f64>) {
+		let cost_usd = cost_usd.unwrap_or_default();
+		if let Ok(mut state) = self.budget.state.lock() {
+			state.tokens += tokens;
+			state.cost_usd += cost_usd;
+
+			let settled_tokens = tokens.min(self.tokens);
+			let settled_cost_usd = cost_usd.min(self.cost_usd);
+			state.reserved_tokens = state.reserved_tokens.saturating_sub(settled_tokens);
+			state.reserved_cost_usd = (state.reserved_cost_usd - settled_cost_usd).max(0.);
+			self.tokens -= settled_tokens;
+			self.cost_usd -= settled_cost_usd;
+		}
+	}
+}
+
+impl Drop for BudgetReservation {
+	fn drop(&mut self) {
+		if let Ok(mut state) = self.budget.state.lock() {
+			state.reserved_tokens = state.reserved_tokens.saturating_sub(self.tokens);
+			state.reserved_cost_usd = (state.reserved_cost_usd - self.cost_usd).max(0.);
+		}
+	}
+}
+
+// endregion: --- BudgetReservation
+
+// region:    --- Estimation
+
+/// Estimate the number of prompt tokens of the rendered messages (about 4 chars per token)
+pub fn estimate_prompt_tokens(chat_messages: &[ChatMessage]) -> u64 {
+	chat_messages
+		.iter()
+		.map(|msg| {
+			let chars = msg.content.text_as_str().map(|t| t.chars().count()).unwrap_or_default();
+			chars.div_ceil(CHARS_PER_TOKEN) as u64 + TOKENS_PER_MESSAGE
+		})
+		.sum()
+}
+
+/// Estimate the cost of the prompt and completion tokens for this model (None if the model price is unknown)
+pub fn estimate_cost(model: &str, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+	let adapter_kind = AdapterKind::from_model(model).ok()?;
+	let usage = MetaUsage {
+		prompt_tokens: Some(prompt_tokens as i32),
+		completion_tokens: Some(completion_tokens as i32),
+		..Default::default()
+	};
+	price_it(adapter_kind.as_lower_str(), model, &usage)
+}
+
+// endregion: --- Estimation
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+	use super::*;
+
+	#[test]
+	fn test_run_budget_reserve_settle_release() -> Result<()> {
+		// -- Setup & Fixtures
+		let budget = RunBudget::new(None, Some(100)).ok_or("Should have a budget")?;
+
+		// -- Exec & Check
+		let mut reservation_1 = budget.reserve(60, None)?;
+		// 60 in progress + 50 > 100
+		assert!(
+			budget.reserve(50, None).is_err(),
+			"Should exceed with the reservation in progress"
+		);
+
+		// first response of the input, the rest of the reservation is still in progress (30 used + 30 reserved)
+		reservation_1.settle(30, None);
+		assert!(
+			budget.reserve(41, None).is_err(),
+			"Should exceed with the rest of the reservation"
+		);
+		// actual usage was lower than the estimate, the rest is released when the input is done
+		drop(reservation_1);
+		let reservation_2 = budget.reserve(50, None)?;
+		// the failed input releases its reservation
+		drop(reservation_2);
+		let mut reservation_3 = budget.reserve(20, None)?;
+		// actual usage was higher than the estimate
+		reservation_3.settle(70, None);
+		drop(reservation_3);
+
+		assert!(budget.reserve(1, None).is_err(), "Should exceed the budget");
+		let summary = budget.exceeded_summary().ok_or("Should have a summary")?;
+		assert!(summary.starts_with("3 input(s) skipped"), "Wrong summary: {summary}");
+		assert!(summary.contains("Tokens: 100 of 100"), "Wrong summary: {summary}");
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
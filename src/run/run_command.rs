@@ -1,14 +1,16 @@
-use crate::agent::{Agent, AgentOptions, AgentRef};
+use crate::agent::{Agent, AgentOptions, AgentRef, OnError};
 use crate::dir_context::DirContext;
 use crate::hub::get_hub;
 use crate::run::literals::Literals;
 use crate::run::run_input::{RunAgentInputResponse, run_agent_input};
-use crate::run::{RunBaseOptions, Runtime};
-use crate::script::{AipackCustom, BeforeAllResponse, FromValue};
+use crate::run::{RunBaseOptions, RunBudget, RunRecorder, RunSummary, RunTracker, Runtime};
+use crate::script::{AipackCustom, BeforeAllResponse, FromValue, LuaStage};
 use crate::{Error, Result};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use simple_fs::SPath;
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::task::JoinSet;
 use value_ext::JsonValueExt;
 
@@ -18,6 +20,40 @@ const DEFAULT_CONCURRENCY: usize = 1;
 pub struct RunCommandResponse {
 	pub outputs: Option<Vec<Value>>,
 	pub after_all: Option<Value>,
+	/// The errors of the failed inputs (only when `on_error = "continue"`)
+	pub errors: Vec<InputError>,
+	/// The aggregated usage, cost, and timing of the run
+	pub summary: RunSummary,
+}
+
+/// The error of one input, captured when `on_error = "continue"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputError {
+	/// The index of the input (same as in `inputs`)
+	pub index: usize,
+	pub label: String,
+	/// The display of the error
+	pub error: String,
+	/// The error and its eventual sources (first is the error itself)
+	pub chain: Vec<String>,
+}
+
+impl InputError {
+	fn new(index: usize, label: String, error: &Error) -> Self {
+		let mut chain = vec![error.to_string()];
+		let mut source = std::error::Error::source(error);
+		while let Some(cause) = source {
+			chain.push(cause.to_string());
+			source = cause.source();
+		}
+
+		InputError {
+			index,
+			label,
+			error: error.to_string(),
+			chain,
+		}
+	}
 }
 
 /// Return the display path
@@ -42,8 +78,12 @@ pub async fn run_command_agent(
 	return_output_values: bool,
 ) -> Result<RunCommandResponse> {
 	let hub = get_hub();
+	let start = Instant::now();
 	let concurrency = agent.options().input_concurrency().unwrap_or(DEFAULT_CONCURRENCY);
 
+	// -- The runtime with the sandbox of the agent pack (if any)
+	let runtime = &runtime.for_agent(&agent, run_base_options.allow_all())?;
+
 	let literals = Literals::from_dir_context_and_agent_path(runtime.dir_context(), &agent)?;
 
 	// display relative agent path if possible
@@ -58,20 +98,38 @@ pub async fn run_command_agent(
 	))
 	.await;
 
+	// -- The inputs of a session share its history, so they run one at a time (in order)
+	let concurrency = match agent.options().session() {
+		Some(session_name) if concurrency > 1 => {
+			hub.publish(format!(
+				"-! input_concurrency = {concurrency} ignored, as the inputs share the session '{session_name}' (one input at a time)"
+			))
+			.await;
+			1
+		}
+		_ => concurrency,
+	};
+
 	// -- Run the before all
 	let BeforeAllResponse {
 		inputs,
 		before_all,
 		options: options_to_merge,
 	} = if let Some(before_all_script) = agent.before_all_script() {
-		let lua_engine = runtime.new_lua_engine()?;
+		let lua_engine = runtime.lua_engine_for_agent(&agent)?;
 		let lua_scope = lua_engine.create_table()?;
 		let lua_inputs = inputs.clone().map(Value::Array).unwrap_or_default();
 		lua_scope.set("inputs", lua_engine.serde_to_lua_value(lua_inputs)?)?;
 		lua_scope.set("CTX", literals.to_lua(&lua_engine)?)?;
 		lua_scope.set("options", agent.options_as_ref())?;
 
-		let lua_value = lua_engine.eval(before_all_script, Some(lua_scope), Some(&[agent.file_dir()?.to_str()]))?;
+		let lua_stage = LuaStage::new(&agent, "before_all", None);
+		let lua_value = lua_engine.eval_stage(
+			before_all_script,
+			Some(lua_scope),
+			Some(&[agent.file_dir()?.to_str()]),
+			&lua_stage,
+		)?;
 		let before_all_res = serde_json::to_value(lua_value)?;
 
 		match AipackCustom::from_value(before_all_res)? {
@@ -162,6 +220,32 @@ pub async fn run_command_agent(
 			None
 		};
 
+	let on_error = run_base_options
+		.on_error()
+		.or(agent.options_as_ref().on_error())
+		.unwrap_or_default();
+	let mut input_errors: Vec<InputError> = Vec::new();
+
+	// -- The run tracker (eventual budget, summary, and eventual run history), shared across the inputs
+	let agent_options = agent.options_as_ref();
+	let budget = RunBudget::new(
+		run_base_options.max_cost_usd().or(agent_options.max_cost_usd()),
+		run_base_options.max_tokens_total().or(agent_options.max_tokens_total()),
+	);
+	let recorder = if run_base_options.history() {
+		match RunRecorder::start(runtime.dir_context(), &agent, &inputs, &before_all) {
+			Ok(recorder) => Some(recorder),
+			Err(err) => {
+				hub.publish(format!("-! Cannot record the run in the run history. Cause: {err}"))
+					.await;
+				None
+			}
+		}
+	} else {
+		None
+	};
+	let run_tracker = Arc::new(RunTracker::new(budget, recorder));
+
 	// -- Run the inputs
 	let mut join_set = JoinSet::new();
 	let mut in_progress = 0;
@@ -170,46 +254,57 @@ pub async fn run_command_agent(
 		let agent_clone = agent.clone();
 		let before_all_clone = before_all.clone();
 		let literals = literals.clone();
+		let run_tracker_clone = run_tracker.clone();
 
 		let base_run_config_clone = run_base_options.clone();
 
 		// Spawn tasks up to the concurrency limit
 		join_set.spawn(async move {
-			// Execute the command agent (this will perform do Data, Instruction, and Output stages)
-			let run_input_response = run_command_agent_input(
-				input_idx,
-				&runtime_clone,
-				&agent_clone,
-				before_all_clone,
-				input,
-				&literals,
-				&base_run_config_clone,
-			)
-			.await?;
-
-			// Process the output
-			let run_input_value = run_input_response.map(|v| v.into_value()).unwrap_or_default();
-			let output = match AipackCustom::from_value(run_input_value)? {
-				// if it is a skip, we skip
-				FromValue::AipackCustom(AipackCustom::Skip { reason }) => {
-					let reason_msg = reason.map(|reason| format!(" (Reason: {reason})")).unwrap_or_default();
-					hub.publish(format!("-! Aipack Skip input at Output stage{reason_msg}")).await;
-					Value::Null
-				}
-
-				// Any other AipackCustom is not supported at output stage
-				FromValue::AipackCustom(other) => {
-					return Err(Error::custom(format!(
-						"Aipack custom '{}' not supported at the Output stage",
-						other.as_ref()
-					)));
-				}
-
-				// Plain value passthrough
-				FromValue::OriginalValue(value) => value,
-			};
-
-			Ok((input_idx, output))
+			let label = get_input_label(input_idx, &input);
+
+			let res = async {
+				// Execute the command agent (this will perform do Data, Instruction, and Output stages)
+				let run_input_response = run_command_agent_input(
+					input_idx,
+					&runtime_clone,
+					&agent_clone,
+					before_all_clone,
+					input,
+					&literals,
+					&base_run_config_clone,
+					&run_tracker_clone,
+				)
+				.await?;
+
+				// Process the output
+				let run_input_value = run_input_response.map(|v| v.into_value()).unwrap_or_default();
+				let output = match AipackCustom::from_value(run_input_value)? {
+					// if it is a skip, we skip
+					FromValue::AipackCustom(AipackCustom::Skip { reason }) => {
+						let reason_msg =
+							reason.as_ref().map(|reason| format!(" (Reason: {reason})")).unwrap_or_default();
+						hub.publish(format!("-! Aipack Skip input at Output stage{reason_msg}")).await;
+						run_tracker_clone.record_skip(input_idx, reason);
+						Value::Null
+					}
+
+					// Any other AipackCustom is not supported at output stage
+					FromValue::AipackCustom(other) => {
+						return Err(Error::custom(format!(
+							"Aipack custom '{}' not supported at the Output stage",
+							other.as_ref()
+						)));
+					}
+
+					// Plain value passthrough
+					FromValue::OriginalValue(value) => value,
+				};
+
+				Ok(output)
+			}
+			.await;
+
+			(input_idx, label, res)
 		});
 
 		in_progress += 1;
@@ -218,15 +313,8 @@ pub async fn run_command_agent(
 		if in_progress >= concurrency {
 			if let Some(res) = join_set.join_next().await {
 				in_progress -= 1;
-				match res {
-					Ok(Ok((input_idx, output))) => {
-						if let Some(outputs_vec) = &mut captured_outputs {
-							outputs_vec.push((input_idx, output));
-						}
-					}
-					Ok(Err(e)) => return Err(e),
-					Err(e) => return Err(Error::custom(format!("Error while running input. Cause {e}"))),
-				}
+				let res = res.map_err(|e| Error::custom(format!("Error while running input. Cause {e}")))?;
+				capture_input_result(res, on_error, &run_tracker, &mut captured_outputs, &mut input_errors).await?;
 			}
 		}
 	}
@@ -235,15 +323,8 @@ pub async fn run_command_agent(
 	while in_progress > 0 {
 		if let Some(res) = join_set.join_next().await {
 			in_progress -= 1;
-			match res {
-				Ok(Ok((input_idx, output))) => {
-					if let Some(outputs_vec) = &mut captured_outputs {
-						outputs_vec.push((input_idx, output));
-					}
-				}
-				Ok(Err(e)) => return Err(e),
-				Err(e) => return Err(Error::custom(format!("Error while remaining input. Cause {e}"))),
-			}
+			let res = res.map_err(|e| Error::custom(format!("Error while remaining input. Cause {e}")))?;
+			capture_input_result(res, on_error, &run_tracker, &mut captured_outputs, &mut input_errors).await?;
 		}
 	}
 
@@ -255,6 +336,16 @@ pub async fn run_command_agent(
 		None
 	};
 
+	// -- Build the run summary
+	let inputs_len = inputs.len();
+	let summary = RunSummary {
+		agent_name: agent.name().to_string(),
+		inputs: inputs_len,
+		failed: input_errors.len(),
+		duration_sec: start.elapsed().as_secs_f64(),
+		..run_tracker.summary()
+	};
+
 	// -- Run the after all
 	let after_all = if let Some(after_all_script) = agent.after_all_script() {
 		let outputs_value = if let Some(outputs) = outputs.as_ref() {
@@ -263,29 +354,73 @@ pub async fn run_command_agent(
 			Value::Null
 		};
 
-		let lua_engine = runtime.new_lua_engine()?;
+		let lua_engine = runtime.lua_engine_for_agent(&agent)?;
 		let lua_scope = lua_engine.create_table()?;
 		let inputs = Value::Array(inputs);
 		lua_scope.set("inputs", lua_engine.serde_to_lua_value(inputs)?)?;
 		// Will be Value::Null if outputs were not collected
 		lua_scope.set("outputs", lua_engine.serde_to_lua_value(outputs_value)?)?;
+		lua_scope.set(
+			"errors",
+			lua_engine.serde_to_lua_value(serde_json::to_value(&input_errors)?)?,
+		)?;
+		lua_scope.set(
+			"run_summary",
+			lua_engine.serde_to_lua_value(serde_json::to_value(&summary)?)?,
+		)?;
 		lua_scope.set("before_all", lua_engine.serde_to_lua_value(before_all)?)?;
 		lua_scope.set("CTX", literals.to_lua(&lua_engine)?)?;
 		lua_scope.set("options", agent.options_as_ref())?;
 
-		let lua_value = lua_engine.eval(after_all_script, Some(lua_scope), Some(&[agent.file_dir()?.to_str()]))?;
+		let lua_stage = LuaStage::new(&agent, "after_all", None);
+		let lua_value = lua_engine.eval_stage(
+			after_all_script,
+			Some(lua_scope),
+			Some(&[agent.file_dir()?.to_str()]),
+			&lua_stage,
+		)?;
 		Some(serde_json::to_value(lua_value)?)
 	} else {
 		None
 	};
 
-	hub.publish(format!("\n======= COMPLETED: {}", agent.name())).await;
+	if let Some(budget_summary) = run_tracker.budget().and_then(|b| b.exceeded_summary()) {
+		hub.publish(format!("\n-! Run budget exceeded: {budget_summary}")).await;
+	}
 
-	Ok(RunCommandResponse { after_all, outputs })
+	if input_errors.is_empty() {
+		hub.publish(format!("\n======= COMPLETED: {}", agent.name())).await;
+	} else {
+		hub.publish(format!(
+			"\n======= COMPLETED WITH ERRORS: {} ({} of {} inputs failed)",
+			agent.name(),
+			input_errors.len(),
+			inputs_len
+		))
+		.await;
+	}
+	hub.publish(format!("{summary}")).await;
+
+	run_tracker.record_run_completed(&summary, &input_errors);
+	if let Some(recorder) = run_tracker.recorder() {
+		let run_id = recorder.run_id();
+		hub.publish(format!(
+			"-> Run recorded as '{run_id}' (replay with 'aip runs replay {run_id}')"
+		))
+		.await;
+	}
+
+	Ok(RunCommandResponse {
+		after_all,
+		outputs,
+		errors: input_errors,
+		summary,
+	})
 }
 
 /// Run the command agent input for the run_command_agent_inputs
 /// Not public by design, should be only used in the context of run_command_agent_inputs
+#[allow(clippy::too_many_arguments)]
 async fn run_command_agent_input(
 	input_idx: usize,
 	runtime: &Runtime,
@@ -294,6 +429,7 @@ async fn run_command_agent_input(
 	input: impl Serialize,
 	literals: &Literals,
 	run_base_options: &RunBaseOptions,
+	run_tracker: &RunTracker,
 ) -> Result<Option<RunAgentInputResponse>> {
 	let hub = get_hub();
 
@@ -302,10 +438,21 @@ async fn run_command_agent_input(
 
 	// get the eventual "._label" property of the input
 	// try to get the path, name
-	let label = get_input_label(&input).unwrap_or_else(|| format!("input index: {input_idx}"));
+	let label = get_input_label(input_idx, &input);
 	hub.publish(format!("\n==== Running input: {}", label)).await;
 
-	let run_response = run_agent_input(runtime, agent, before_all, &label, input, literals, run_base_options).await?;
+	let run_response = run_agent_input(
+		runtime,
+		agent,
+		before_all,
+		input_idx,
+		&label,
+		input,
+		literals,
+		run_base_options,
+		run_tracker,
+	)
+	.await?;
 
 	// if the response value is a String, then, print it
 	if let Some(response_txt) = run_response.as_ref().and_then(|r| r.as_str()) {
@@ -338,20 +485,53 @@ pub async fn run_command_agent_input_for_test(
 		input,
 		&literals,
 		run_base_options,
+		&RunTracker::default(),
 	)
 	.await
 }
 
 // region:    --- Support
 
-fn get_input_label(input: &Value) -> Option<String> {
+fn get_input_label(input_idx: usize, input: &Value) -> String {
 	const LABEL_KEYS: &[&str] = &["path", "name", "label", "_label"];
 	for &key in LABEL_KEYS {
 		if let Ok(value) = input.x_get::<String>(key) {
-			return Some(value);
+			return value;
 		}
 	}
-	None
+	format!("input index: {input_idx}")
+}
+
+/// Capture the result of one input task, either in the outputs or in the errors (when on_error is continue)
+///
+/// Returns the error when the run must be aborted.
+async fn capture_input_result(
+	(input_idx, label, res): (usize, String, Result<Value>),
+	on_error: OnError,
+	run_tracker: &RunTracker,
+	captured_outputs: &mut Option<Vec<(usize, Value)>>,
+	input_errors: &mut Vec<InputError>,
+) -> Result<()> {
+	run_tracker.record_input_result(input_idx, &label, &res);
+
+	let output = match (res, on_error) {
+		(Ok(output), _) => output,
+		(Err(err), OnError::Abort) => return Err(err),
+		(Err(err), OnError::Continue) => {
+			get_hub()
+				.publish(format!("-! Input failed (continuing): {label}\n    Cause: {err}"))
+				.await;
+			input_errors.push(InputError::new(input_idx, label, &err));
+			// Note: Null output to keep the outputs in sync with the inputs
+			Value::Null
+		}
+	};
+
+	if let Some(outputs_vec) = captured_outputs {
+		outputs_vec.push((input_idx, output));
+	}
+
+	Ok(())
 }
 
 fn get_genai_info(agent: &Agent) -> String {
@@ -375,6 +555,10 @@ fn get_genai_info(agent: &Agent) -> String {
 #[path = "../_tests/tests_run_agent_llm.rs"]
 mod tests_run_agent_llm;
 
+#[cfg(test)]
+#[path = "../_tests/tests_run_agent_mock.rs"]
+mod tests_run_agent_mock;
+
 #[cfg(test)]
 #[path = "../_tests/tests_run_agent_script.rs"]
 mod tests_run_agent_script;
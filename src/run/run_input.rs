@@ -1,16 +1,23 @@
-use crate::Result;
 use crate::agent::{Agent, PromptPart};
 use crate::hub::get_hub;
 use crate::pricing::price_it;
 use crate::run::AiResponse;
 use crate::run::literals::Literals;
+use crate::run::{AiCache, RetryPolicy, exec_chat_stream, exec_with_retry};
+use crate::run::{DEFAULT_TOOLS_MAX_ITERATIONS, ToolScope, exec_tool_call};
 use crate::run::{DryMode, RunBaseOptions, Runtime};
-use crate::script::{AipackCustom, FromValue};
+use crate::run::{EST_COMPLETION_TOKENS, RunTracker, estimate_cost, estimate_prompt_tokens};
+use crate::script::{AipackCustom, FromValue, LuaStage};
+use crate::session::{SessionMessage, SessionRole, SessionStore};
 use crate::support::hbs::hbs_render;
+use crate::support::json_schema;
+use crate::support::md::outer_block_content_or_raw;
 use crate::support::text::{format_duration, format_num};
-use genai::chat::{ChatMessage, ChatRequest, ChatResponse, MetaUsage};
+use crate::{Error, Result};
+use genai::chat::{ChatMessage, ChatRequest, ChatResponse, ChatRole, MetaUsage, Tool, ToolCall};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::time::Duration;
 use tokio::time::Instant;
 
 // region:    --- RunAgentInputResponse
@@ -31,12 +38,14 @@ impl RunAgentInputResponse {
 	}
 
 	/// Note: for now, we do like this. Might want to change that.
-	/// Note: There is something to do about AI being able to structured output and manage it her
-	/// - If AiResposne take the String as value or Null
+	/// - If AiResposne take the validated json (when `# Output Schema`), otherwise the String as value or Null
 	/// - If OutputResponse, then, the value is result
 	pub fn into_value(self) -> Value {
 		match self {
-			RunAgentInputResponse::AiReponse(ai_response) => ai_response.content.into(),
+			RunAgentInputResponse::AiReponse(ai_response) => match ai_response.json {
+				Some(json) => json,
+				None => ai_response.content.into(),
+			},
 			RunAgentInputResponse::OutputResponse(value) => value,
 		}
 	}
@@ -58,17 +67,18 @@ pub async fn run_agent_input(
 	runtime: &Runtime,
 	agent: &Agent,
 	before_all_result: Value,
+	input_idx: usize,
 	label: &str,
 	input: Value,
 	literals: &Literals,
 	run_base_options: &RunBaseOptions,
+	run_tracker: &RunTracker,
 ) -> Result<Option<RunAgentInputResponse>> {
 	let hub = get_hub();
-	let client = runtime.genai_client();
 
 	// -- Build the scope
 	// Fix me: Probably need to get the engine from the arg
-	let lua_engine = runtime.new_lua_engine()?;
+	let lua_engine = runtime.lua_engine_for_agent(agent)?;
 	let lua_scope = lua_engine.create_table()?;
 	lua_scope.set("input", lua_engine.serde_to_lua_value(input.clone())?)?;
 	lua_scope.set("before_all", lua_engine.serde_to_lua_value(before_all_result.clone())?)?;
@@ -80,7 +90,8 @@ pub async fn run_agent_input(
 
 	// -- Execute data
 	let data = if let Some(data_script) = agent.data_script().as_ref() {
-		let lua_value = lua_engine.eval(data_script, Some(lua_scope), Some(&[agent_dir_str]))?;
+		let lua_stage = LuaStage::new(agent, "data", Some(label));
+		let lua_value = lua_engine.eval_stage(data_script, Some(lua_scope), Some(&[agent_dir_str]), &lua_stage)?;
 		serde_json::to_value(lua_value)?
 	} else {
 		Value::Null
@@ -93,10 +104,11 @@ pub async fn run_agent_input(
 
 		// If we have a skip, we can skip
 		FromValue::AipackCustom(AipackCustom::Skip { reason }) => {
-			let reason_txt = reason.map(|r| format!(" (Reason: {r})")).unwrap_or_default();
+			let reason_txt = reason.as_ref().map(|r| format!(" (Reason: {r})")).unwrap_or_default();
 
 			hub.publish(format!("-! Aipack Skip input at Data stage: {label}{reason_txt}"))
 				.await;
+			run_tracker.record_skip(input_idx, reason);
 			return Ok(None);
 		}
 
@@ -129,6 +141,30 @@ pub async fn run_agent_input(
 
 	let is_inst_empty = chat_messages.is_empty();
 
+	// -- Replay the eventual session history (after the system messages)
+	let session = match agent.options_as_ref().session() {
+		Some(session_name) => Some((session_name, SessionStore::new(runtime.dir_context())?)),
+		None => None,
+	};
+	// Note: The system messages are rendered from the agent each time, so they are not part of the session
+	let new_session_messages: Vec<SessionMessage> = chat_messages
+		.iter()
+		.filter_map(SessionMessage::from_chat_message)
+		.filter(|m| m.role != SessionRole::System)
+		.collect();
+	if let (Some((session_name, session_store)), false) = (session.as_ref(), is_inst_empty) {
+		let history = session_store.load(session_name)?;
+		if !history.is_empty() {
+			let (system_messages, other_messages): (Vec<_>, Vec<_>) =
+				chat_messages.into_iter().partition(|m| matches!(m.role, ChatRole::System));
+			chat_messages = system_messages
+				.into_iter()
+				.chain(history.into_iter().map(ChatMessage::from))
+				.chain(other_messages)
+				.collect();
+		}
+	}
+
 	// TODO: Might want to handle if no instruction.
 	if run_base_options.verbose() {
 		hub.publish("\n").await;
@@ -147,20 +183,183 @@ pub async fn run_agent_input(
 		return Ok(None);
 	}
 
-	// -- Now execute the instruction
-	let model_resolved = agent.model_resolved();
+	// -- Keep the rendered messages for the eventual run history
+	let recorded_messages: Vec<SessionMessage> = if run_tracker.recorder().is_some() {
+		chat_messages.iter().filter_map(SessionMessage::from_chat_message).collect()
+	} else {
+		Vec::new()
+	};
 
+	// -- Now execute the instruction
 	let ai_response: Option<AiResponse> = if !is_inst_empty {
-		let chat_req = ChatRequest::from_messages(chat_messages);
-
-		hub.publish(format!("-> Sending rendered instruction to {model_resolved} ..."))
-			.await;
+		// -- Reserve the estimated prompt and completion in the eventual run budget (skip the input if it would be exceeded)
+		let mut budget_reservation = match run_tracker.budget() {
+			Some(budget) => {
+				let est_prompt_tokens = estimate_prompt_tokens(&chat_messages);
+				let est_cost = estimate_cost(agent.model_resolved(), est_prompt_tokens, EST_COMPLETION_TOKENS);
+				match budget.reserve(est_prompt_tokens + EST_COMPLETION_TOKENS, est_cost) {
+					Ok(budget_reservation) => Some(budget_reservation),
+					Err(reason) => {
+						hub.publish(format!(
+							"-! Aipack Skip input, run budget exceeded: {label} (Reason: {reason})"
+						))
+						.await;
+						run_tracker.record_skip(input_idx, Some(format!("Run budget exceeded: {reason}")));
+						return Ok(None);
+					}
+				}
+			}
+			None => None,
+		};
+
+		let mut chat_req = ChatRequest::from_messages(chat_messages);
+		if !agent.tools().is_empty() {
+			chat_req = chat_req.with_tools(agent.tools().iter().map(Tool::from).collect());
+		}
 
-		let start = Instant::now();
-		let chat_res = client
-			.exec_chat(model_resolved, chat_req, Some(agent.genai_chat_options()))
+		// -- Get the eventual cache
+		let ai_cache = if run_base_options.no_cache() {
+			None
+		} else {
+			AiCache::from_options(runtime.dir_context(), agent.options_as_ref().cache())?
+		};
+
+		// -- Exec the chat request, and re-ask for a repair if the response does not match the output schema
+		let output_schema = agent.output_schema();
+		let max_repairs = if output_schema.is_some() {
+			agent
+				.options_as_ref()
+				.output_schema_max_repairs()
+				.unwrap_or(DEFAULT_OUTPUT_SCHEMA_MAX_REPAIRS)
+		} else {
+			0
+		};
+
+		let max_tool_iterations = agent
+			.options_as_ref()
+			.tools_max_iterations()
+			.unwrap_or(DEFAULT_TOOLS_MAX_ITERATIONS);
+		let tool_scope = ToolScope {
+			runtime,
+			agent,
+			literals,
+			input: &input,
+			data: &data,
+			before_all: &before_all_result,
+		};
+
+		let mut repairs: u32 = 0;
+		let mut tool_iterations: u32 = 0;
+		let mut tool_calls_count: usize = 0;
+		let mut duration = Duration::ZERO;
+		let mut retries: u32 = 0;
+		let mut price_usd: Option<f64> = None;
+		let mut total_usage: Option<MetaUsage> = None;
+
+		let (chat_res, cached, ai_response_json) = loop {
+			let ChatExec {
+				chat_res,
+				cache_key,
+				cached,
+				retries: exec_retries,
+				duration: exec_duration,
+			} = exec_chat_req(
+				runtime,
+				agent,
+				ai_cache.as_ref(),
+				&chat_req,
+				input_idx,
+				label,
+				run_base_options,
+			)
 			.await?;
-		let duration = start.elapsed();
+
+			duration += exec_duration;
+			retries += exec_retries;
+			// Note: A cached response does not cost anything
+			if !cached {
+				let exec_price = get_price(&chat_res);
+				if let Some(exec_price) = exec_price {
+					price_usd = Some(price_usd.unwrap_or_default() + exec_price);
+				}
+				// -- Settle the actual usage in the eventual run budget
+				if let Some(budget_reservation) = budget_reservation.as_mut() {
+					let tokens = chat_res.usage.total_tokens.unwrap_or_default() as u64;
+					budget_reservation.settle(tokens, exec_price);
+				}
+			}
+			total_usage = Some(match total_usage {
+				Some(total_usage) => add_usage(total_usage, &chat_res.usage),
+				None => chat_res.usage.clone(),
+			});
+
+			// -- Call the tools, and send back the tool responses
+			let tool_calls: Vec<ToolCall> = chat_res
+				.tool_calls()
+				.map(|tool_calls| tool_calls.into_iter().cloned().collect())
+				.unwrap_or_default();
+			// Note: The responses with tool calls are not cached, as a cache hit would run the tools without the AI
+			if !tool_calls.is_empty() {
+				if tool_iterations >= max_tool_iterations {
+					return Err(Error::ToolsMaxIterations {
+						max_iterations: max_tool_iterations,
+					});
+				}
+				tool_iterations += 1;
+				tool_calls_count += tool_calls.len();
+
+				let mut tool_responses = Vec::new();
+				for tool_call in tool_calls.iter() {
+					tool_responses.push(exec_tool_call(&tool_scope, tool_call, label).await);
+				}
+
+				chat_req = chat_req.append_message(tool_calls);
+				for tool_response in tool_responses {
+					chat_req = chat_req.append_message(tool_response);
+				}
+				continue;
+			}
+
+			// -- Validate against the output schema
+			let validation = match output_schema {
+				Some(output_schema) => match parse_and_validate_json(output_schema, chat_res.content_text_as_str()) {
+					Ok(json) => Ok(Some(json)),
+					Err(errors) => Err(errors),
+				},
+				None => Ok(None),
+			};
+
+			match validation {
+				Ok(ai_response_json) => {
+					// Note: Only valid responses are saved to the cache
+					save_to_cache(ai_cache.as_ref(), cache_key.as_deref(), cached, &chat_res).await;
+					break (chat_res, cached, ai_response_json);
+				}
+				Err(errors) => {
+					if repairs >= max_repairs {
+						return Err(Error::OutputSchemaValidationFail {
+							attempts: repairs + 1,
+							errors: errors.join("\n"),
+						});
+					}
+					repairs += 1;
+					hub.publish(format!(
+						"-! AI response does not match the Output Schema, asking for a repair {repairs}/{max_repairs} for input {label}"
+					))
+					.await;
+
+					let content = chat_res.content_text_into_string().unwrap_or_default();
+					chat_req = chat_req
+						.append_message(ChatMessage::assistant(content))
+						.append_message(ChatMessage::user(repair_prompt(&errors)));
+				}
+			}
+		};
+		let usage = total_usage.unwrap_or_default();
+
+		// -- Release what remains of the eventual budget reservation
+		drop(budget_reservation);
+
 		let duration_msg = format!("Duration: {}", format_duration(duration));
 		// this is for the duration in second with 3 digit for milli (for the AI Response)
 		let duration_sec = duration.as_secs_f64(); // Convert to f64
@@ -168,21 +367,35 @@ pub async fn run_agent_input(
 
 		let mut info = duration_msg;
 
-		let price_usd = get_price(&chat_res);
 		if let Some(price_usd) = price_usd {
 			info = format!("{info} | ~${price_usd}")
 		}
 
-		let usage_msg = format_usage(&chat_res.usage);
+		let usage_msg = format_usage(&usage);
 		info = format!("{info} | {usage_msg}");
 
+		if retries > 0 {
+			info = format!("{info} | Retries: {retries}");
+		}
+
+		if tool_calls_count > 0 {
+			info = format!("{info} | Tool calls: {tool_calls_count}");
+		}
+
+		if repairs > 0 {
+			info = format!("{info} | Repairs: {repairs}");
+		}
+
+		if cached {
+			info = format!("{info} | Cached");
+		}
+
 		hub.publish(format!("<- ai_response content received - {info}")).await;
 
 		let chat_res_mode_iden = chat_res.model_iden.clone();
 		let ChatResponse {
 			content,
 			reasoning_content,
-			usage,
 			..
 		} = chat_res;
 
@@ -204,22 +417,36 @@ pub async fn run_agent_input(
 			chat_res_mode_iden.model_name, chat_res_mode_iden.adapter_kind,
 		);
 
-		Some(AiResponse {
+		// -- Append the new messages and the AI reply to the eventual session
+		if let Some((session_name, session_store)) = session.as_ref() {
+			let mut session_messages = new_session_messages;
+			if let Some(content) = ai_response_content.as_ref() {
+				session_messages.push(SessionMessage::new(SessionRole::Assistant, content.clone()));
+			}
+			session_store.append(session_name, &session_messages)?;
+		}
+
+		let ai_response = AiResponse {
 			content: ai_response_content,
 			reasoning_content: ai_response_reasoning_content,
+			json: ai_response_json,
 			model_name: chat_res_mode_iden.model_name,
 			adapter_kind: chat_res_mode_iden.adapter_kind,
 			duration_sec,
 			price_usd,
 			usage,
 			info,
-		})
+			cached,
+		};
+
+		Some(ai_response)
 	}
 	// if we do not have an instruction, just return null
 	else {
 		hub.publish("-! No instruction, skipping genai.").await;
 		None
 	};
+	run_tracker.record_ai_response(input_idx, &data, recorded_messages, ai_response.as_ref());
 
 	// -- if dry_mode res, we stop
 	if matches!(run_base_options.dry_mode(), DryMode::Res) {
@@ -227,8 +454,37 @@ pub async fn run_agent_input(
 	}
 
 	// -- Exec output
+	run_agent_output(
+		runtime,
+		agent,
+		before_all_result,
+		input,
+		data,
+		ai_response,
+		literals,
+		label,
+	)
+}
+
+/// Run the `# Output` stage of the agent for one input
+/// (or returns the AI response if the agent does not have an `# Output` stage)
+///
+/// Note: Also used to replay the `# Output` stage of a past run, with the recorded AI response.
+#[allow(clippy::too_many_arguments)]
+pub fn run_agent_output(
+	runtime: &Runtime,
+	agent: &Agent,
+	before_all_result: Value,
+	input: Value,
+	data: Value,
+	ai_response: Option<AiResponse>,
+	literals: &Literals,
+	label: &str,
+) -> Result<Option<RunAgentInputResponse>> {
 	let res = if let Some(output_script) = agent.output_script() {
-		let lua_engine = runtime.new_lua_engine()?;
+		let agent_dir = agent.file_dir()?;
+
+		let lua_engine = runtime.lua_engine_for_agent(agent)?;
 		let lua_scope = lua_engine.create_table()?;
 		lua_scope.set("input", lua_engine.serde_to_lua_value(input)?)?;
 		lua_scope.set("data", lua_engine.serde_to_lua_value(data)?)?;
@@ -237,7 +493,9 @@ pub async fn run_agent_input(
 		lua_scope.set("CTX", literals.to_lua(&lua_engine)?)?;
 		lua_scope.set("options", agent.options_as_ref())?;
 
-		let lua_value = lua_engine.eval(output_script, Some(lua_scope), Some(&[agent_dir_str]))?;
+		let lua_stage = LuaStage::new(agent, "output", Some(label));
+		let lua_value =
+			lua_engine.eval_stage(output_script, Some(lua_scope), Some(&[agent_dir.to_str()]), &lua_stage)?;
 		let output_response = serde_json::to_value(lua_value)?;
 
 		Some(RunAgentInputResponse::OutputResponse(output_response))
@@ -248,8 +506,152 @@ pub async fn run_agent_input(
 	Ok(res)
 }
 
+// region:    --- Chat Exec
+
+const DEFAULT_OUTPUT_SCHEMA_MAX_REPAIRS: u32 = 1;
+
+struct ChatExec {
+	chat_res: ChatResponse,
+	/// The cache key, when the cache is enabled
+	cache_key: Option<String>,
+	cached: bool,
+	retries: u32,
+	duration: Duration,
+}
+
+/// Execute the chat request, from the cache if present, otherwise with the retry policy (and streaming if enabled)
+///
+/// Note: Saving the response to the cache is the responsibility of the caller (only valid responses get cached)
+async fn exec_chat_req(
+	runtime: &Runtime,
+	agent: &Agent,
+	ai_cache: Option<&AiCache>,
+	chat_req: &ChatRequest,
+	input_idx: usize,
+	label: &str,
+	run_base_options: &RunBaseOptions,
+) -> Result<ChatExec> {
+	let hub = get_hub();
+	let client = runtime.genai_client();
+	let model_resolved = agent.model_resolved();
+
+	// -- Get the eventual cached response
+	let cache_key = if ai_cache.is_some() {
+		Some(AiCache::compute_key(
+			model_resolved,
+			agent.genai_chat_options(),
+			chat_req,
+		)?)
+	} else {
+		None
+	};
+	// Note: The tool calls responses cached by an older version are ignored (see the tools loop)
+	let cached_chat_res = match (ai_cache, cache_key.as_deref()) {
+		(Some(ai_cache), Some(cache_key)) => ai_cache
+			.get(cache_key)?
+			.filter(|chat_res| chat_res.tool_calls().is_none_or(|tool_calls| tool_calls.is_empty())),
+		_ => None,
+	};
+	let cached = cached_chat_res.is_some();
+
+	let start = Instant::now();
+	let (chat_res, retries) = if let Some(chat_res) = cached_chat_res {
+		hub.publish(format!("-> Using cached response for {model_resolved}")).await;
+		(chat_res, 0)
+	} else {
+		hub.publish(format!("-> Sending rendered instruction to {model_resolved} ..."))
+			.await;
+
+		// Note: The tool calls are not supported with streaming, so, no stream when the agent has tools
+		let stream =
+			chat_req.tools.is_none() && (run_base_options.stream() || agent.options_as_ref().stream().unwrap_or(false));
+
+		let retry_policy = RetryPolicy::from(agent.options_as_ref().retry());
+
+		exec_with_retry(&retry_policy, label, || async {
+			let chat_req = chat_req.clone();
+			if stream {
+				exec_chat_stream(
+					client,
+					model_resolved,
+					chat_req,
+					agent.genai_chat_options(),
+					input_idx,
+					label,
+				)
+				.await
+			} else {
+				let chat_res = client
+					.exec_chat(model_resolved, chat_req, Some(agent.genai_chat_options()))
+					.await?;
+				Ok(chat_res)
+			}
+		})
+		.await?
+	};
+
+	Ok(ChatExec {
+		chat_res,
+		cache_key,
+		cached,
+		retries,
+		duration: start.elapsed(),
+	})
+}
+
+/// Save the response to the cache (if enabled, and not already from the cache)
+///
+/// Note: A cache write failure does not fail the input
+async fn save_to_cache(ai_cache: Option<&AiCache>, cache_key: Option<&str>, cached: bool, chat_res: &ChatResponse) {
+	if let (Some(ai_cache), Some(cache_key), false) = (ai_cache, cache_key, cached) {
+		if let Err(err) = ai_cache.put(cache_key, chat_res) {
+			get_hub()
+				.publish(format!("-! Fail to save the ai response to the cache. Cause: {err}"))
+				.await;
+		}
+	}
+}
+
+// endregion: --- Chat Exec
+
 // region:    --- Support
 
+/// Parse the AI response content as json (removing the eventual markdown code block fence),
+/// and validate it against the output schema.
+///
+/// Returns the list of errors if not valid
+fn parse_and_validate_json(output_schema: &Value, content: Option<&str>) -> core::result::Result<Value, Vec<String>> {
+	let content = content.unwrap_or_default().trim();
+	let content = outer_block_content_or_raw(content);
+
+	let json: Value =
+		serde_json::from_str(&content).map_err(|err| vec![format!("response is not valid json: {err}")])?;
+
+	let errors = json_schema::validate(output_schema, &json);
+	if errors.is_empty() { Ok(json) } else { Err(errors) }
+}
+
+fn repair_prompt(errors: &[String]) -> String {
+	format!(
+		"Your response does not match the required JSON Schema. Errors:\n- {}\n\nRespond again with only the corrected JSON, without any explanation.",
+		errors.join("\n- ")
+	)
+}
+
+/// Add the tokens of the `other` usage to the `usage` (used for the repair requests).
+fn add_usage(mut usage: MetaUsage, other: &MetaUsage) -> MetaUsage {
+	fn add(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+		match (a, b) {
+			(None, None) => None,
+			(a, b) => Some(a.unwrap_or_default() + b.unwrap_or_default()),
+		}
+	}
+	usage.prompt_tokens = add(usage.prompt_tokens, other.prompt_tokens);
+	usage.completion_tokens = add(usage.completion_tokens, other.completion_tokens);
+	usage.total_tokens = add(usage.total_tokens, other.total_tokens);
+	usage
+}
+
 fn get_price(chat_res: &ChatResponse) -> Option<f64> {
 	let provider = chat_res.model_iden.adapter_kind.as_lower_str();
 	let model_name = &*chat_res.model_iden.model_name;
@@ -1,4 +1,5 @@
 use crate::Result;
+use crate::agent::OnError;
 use crate::cli::RunArgs;
 use std::sync::Arc;
 
@@ -10,10 +11,12 @@ pub struct RunCommandOptions {
 	inner: Arc<RunCommandOptionsInner>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RunCommandOptionsInner {
 	on_file_globs: Option<Vec<String>>,
 	on_inputs: Option<Vec<String>>,
+	/// The eventual file path of the json run summary report
+	report: Option<String>,
 
 	base_run_options: RunBaseOptions,
 }
@@ -37,6 +40,10 @@ impl RunCommandOptions {
 		self.inner.on_inputs.as_ref().map(|v| v.iter().map(|s| s.as_str()).collect())
 	}
 
+	pub fn report(&self) -> Option<&str> {
+		self.inner.report.as_deref()
+	}
+
 	pub fn base_run_config(&self) -> &RunBaseOptions {
 		&self.inner.base_run_options
 	}
@@ -44,6 +51,13 @@ impl RunCommandOptions {
 
 /// Constructors
 impl RunCommandOptions {
+	/// The same options, but running the agents without sandbox (for the redo after a permission denied)
+	pub fn with_allow_all(&self) -> Self {
+		let mut inner = (*self.inner).clone();
+		inner.base_run_options.allow_all = true;
+		inner.into()
+	}
+
 	pub fn new(args: RunArgs) -> Result<Self> {
 		// -- Validate the run_args
 		if let (Some(_), Some(_)) = (args.on_inputs.as_ref(), args.on_files.as_ref()) {
@@ -78,11 +92,19 @@ impl RunCommandOptions {
 			verbose: args.verbose,
 			dry_mode,
 			open: args.open,
+			stream: args.stream,
+			no_cache: args.no_cache,
+			on_error: args.on_error,
+			max_cost_usd: args.max_cost_usd,
+			max_tokens_total: args.max_tokens_total,
+			history: !args.no_history,
+			allow_all: args.allow_all,
 		};
 
 		Ok(RunCommandOptionsInner {
 			on_file_globs,
 			on_inputs: args.on_inputs,
+			report: args.report,
 			base_run_options,
 		}
 		.into())
@@ -110,6 +132,13 @@ pub struct RunBaseOptions {
 	verbose: bool,
 	dry_mode: DryMode,
 	open: bool,
+	stream: bool,
+	no_cache: bool,
+	on_error: Option<OnError>,
+	max_cost_usd: Option<f64>,
+	max_tokens_total: Option<u64>,
+	history: bool,
+	allow_all: bool,
 }
 
 impl RunBaseOptions {
@@ -128,6 +157,41 @@ impl RunBaseOptions {
 	pub fn open(&self) -> bool {
 		self.open
 	}
+
+	pub fn stream(&self) -> bool {
+		self.stream
+	}
+
+	pub fn no_cache(&self) -> bool {
+		self.no_cache
+	}
+
+	/// The on_error from the command line (takes precedence over the agent option)
+	pub fn on_error(&self) -> Option<OnError> {
+		self.on_error
+	}
+
+	/// The max_cost_usd from the command line (takes precedence over the agent option)
+	pub fn max_cost_usd(&self) -> Option<f64> {
+		self.max_cost_usd
+	}
+
+	/// The max_tokens_total from the command line (takes precedence over the agent option)
+	pub fn max_tokens_total(&self) -> Option<u64> {
+		self.max_tokens_total
+	}
+
+	/// When true, the run is recorded in the run history (`.aipack/runs/`)
+	///
+	/// Note: On by default from the command line, off by default otherwise (e.g., tests).
+	pub fn history(&self) -> bool {
+		self.history
+	}
+
+	/// When true, the agent runs without the sandbox of its pack (see `Sandbox`)
+	pub fn allow_all(&self) -> bool {
+		self.allow_all
+	}
 }
 
 // endregion: --- Common
@@ -143,3 +207,15 @@ fn parse_dry_mode(dry_mode: Option<&str>) -> DryMode {
 }
 
 // endregion: --- Support
+
+/// Implementations for various test.
+#[cfg(test)]
+impl RunBaseOptions {
+	/// Creates a new `RunBaseOptions` recording the run in the run history (for test)
+	pub fn new_with_history() -> Self {
+		RunBaseOptions {
+			history: true,
+			..Default::default()
+		}
+	}
+}
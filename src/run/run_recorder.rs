@@ -0,0 +1,122 @@
+//! Records the run in the run history (`.aipack/runs/`), for `aip runs list|show|replay`.
+//!
+//! The AI part of an input (data, rendered messages, AI response) is kept until the input completes,
+//! and then written with its output or error.
+
+use crate::Result;
+use crate::agent::Agent;
+use crate::dir_context::DirContext;
+use crate::history::{InputRecord, RunRecord, RunStore};
+use crate::run::{AiResponse, InputError, RunSummary};
+use crate::session::SessionMessage;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Debug)]
+pub struct RunRecorder {
+	store: RunStore,
+	run_record: RunRecord,
+	pending_inputs: Mutex<HashMap<usize, InputRecord>>,
+}
+
+/// Constructors
+impl RunRecorder {
+	/// Create the run in the run history (the `run.json` gets the summary when the run completes)
+	pub fn start(dir_context: &DirContext, agent: &Agent, inputs: &[Value], before_all: &Value) -> Result<Self> {
+		let store = RunStore::new(dir_context)?;
+		let (id, started_at) = store.create_run()?;
+		// Note: Keep the history from growing forever (e.g., with `--watch`)
+		store.prune()?;
+
+		let run_record = RunRecord {
+			id,
+			started_at,
+			agent_name: agent.name().to_string(),
+			agent_path: agent.file_path().to_string(),
+			options: serde_json::to_value(agent.options_as_ref())?,
+			inputs: inputs.to_vec(),
+			before_all: before_all.clone(),
+			summary: None,
+			errors: Vec::new(),
+		};
+		store.save_run(&run_record)?;
+
+		Ok(RunRecorder {
+			store,
+			run_record,
+			pending_inputs: Mutex::new(HashMap::new()),
+		})
+	}
+}
+
+impl RunRecorder {
+	pub fn run_id(&self) -> &str {
+		&self.run_record.id
+	}
+
+	pub fn record_input_ai(
+		&self,
+		input_idx: usize,
+		data: &Value,
+		messages: Vec<SessionMessage>,
+		ai_response: Option<&AiResponse>,
+	) {
+		self.with_pending_input(input_idx, |input_record| {
+			input_record.data = data.clone();
+			input_record.messages = messages;
+			input_record.ai_response = ai_response.cloned();
+		});
+	}
+
+	pub fn record_input_skip(&self, input_idx: usize, reason: Option<String>) {
+		self.with_pending_input(input_idx, |input_record| {
+			input_record.skip_reason = Some(reason.unwrap_or_else(|| "Skipped".to_string()));
+		});
+	}
+
+	/// Write the input record with its output or error
+	pub fn finish_input(&self, input_idx: usize, label: &str, res: &Result<Value>) -> Result<()> {
+		let mut input_record = self
+			.pending_inputs
+			.lock()
+			.ok()
+			.and_then(|mut pending_inputs| pending_inputs.remove(&input_idx))
+			.unwrap_or_else(|| self.new_input_record(input_idx));
+		input_record.label = label.to_string();
+
+		match res {
+			Ok(output) => input_record.output = output.clone(),
+			Err(err) => input_record.error = Some(err.to_string()),
+		}
+
+		self.store.save_input(self.run_id(), &input_record)
+	}
+
+	/// Write the run record with its summary and errors
+	pub fn finish(&self, summary: &RunSummary, errors: &[InputError]) -> Result<()> {
+		let run_record = RunRecord {
+			summary: Some(summary.clone()),
+			errors: errors.to_vec(),
+			..self.run_record.clone()
+		};
+		self.store.save_run(&run_record)
+	}
+}
+
+/// Private
+impl RunRecorder {
+	fn new_input_record(&self, input_idx: usize) -> InputRecord {
+		let input = self.run_record.inputs.get(input_idx).cloned().unwrap_or_default();
+		InputRecord::new(input_idx, "", input)
+	}
+
+	fn with_pending_input(&self, input_idx: usize, f: impl FnOnce(&mut InputRecord)) {
+		if let Ok(mut pending_inputs) = self.pending_inputs.lock() {
+			let input_record = pending_inputs
+				.entry(input_idx)
+				.or_insert_with(|| self.new_input_record(input_idx));
+			f(input_record);
+		}
+	}
+}
@@ -0,0 +1,76 @@
+//! Replay of the `# Output` stage of a past run (from the run history), with the recorded AI responses.
+//!
+//! No AI call is made, so, a replay does not cost anything.
+
+use crate::Result;
+use crate::agent::Agent;
+use crate::history::InputRecord;
+use crate::hub::get_hub;
+use crate::run::Runtime;
+use crate::run::literals::Literals;
+use crate::run::run_input::run_agent_output;
+use serde_json::Value;
+
+/// Replay the `# Output` stage of the agent for each input record.
+///
+/// - When `dry_res`, only print the recorded AI response (as `aip run --dry res`).
+/// - An input without a recorded AI response (skipped, failed before the AI, no instruction) is skipped.
+///
+/// Returns the outputs, in the same order as the input records (`Null` for the skipped ones)
+pub async fn replay_run_inputs(
+	runtime: &Runtime,
+	agent: &Agent,
+	before_all: Value,
+	input_records: Vec<InputRecord>,
+	dry_res: bool,
+) -> Result<Vec<Value>> {
+	let hub = get_hub();
+	let literals = Literals::from_dir_context_and_agent_path(runtime.dir_context(), agent)?;
+
+	let mut outputs = Vec::with_capacity(input_records.len());
+	for input_record in input_records {
+		let InputRecord {
+			label,
+			input,
+			data,
+			ai_response,
+			..
+		} = input_record;
+
+		hub.publish(format!("\n==== Replaying input: {label}")).await;
+
+		let Some(ai_response) = ai_response else {
+			hub.publish(format!("-! No AI response recorded for input {label}, skipping"))
+				.await;
+			outputs.push(Value::Null);
+			continue;
+		};
+
+		let output = if dry_res {
+			let content = ai_response.content.clone().unwrap_or_default();
+			hub.publish(format!("-> Recorded AI response ({}):\n{content}", ai_response.info))
+				.await;
+			Value::String(content)
+		} else {
+			let run_response = run_agent_output(
+				runtime,
+				agent,
+				before_all.clone(),
+				input,
+				data,
+				Some(ai_response),
+				&literals,
+				&label,
+			)?;
+			if let Some(response_txt) = run_response.as_ref().and_then(|r| r.as_str()) {
+				hub.publish(format!("-> Agent Output:\n{response_txt}")).await;
+			}
+			run_response.map(|r| r.into_value()).unwrap_or_default()
+		};
+
+		hub.publish(format!("==== DONE (input: {label})")).await;
+		outputs.push(output);
+	}
+
+	Ok(outputs)
+}
@@ -0,0 +1,177 @@
+//! The summary of a run (aggregated usage, cost, and timing of the inputs).
+//!
+//! Printed at the end of the run, given to the `# After All` stage as `run_summary`,
+//! and written as json with `aip run --report path.json`.
+
+use crate::run::AiResponse;
+use crate::support::text::{format_duration, format_num};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+	pub agent_name: String,
+	pub inputs: usize,
+	pub skipped: usize,
+	pub failed: usize,
+
+	/// The number of AI responses (one per input with an instruction)
+	pub ai_responses: usize,
+	/// The number of AI responses from the AI response cache
+	pub cached_responses: usize,
+
+	// -- Tokens (cached responses do not count, as they did not call the AI)
+	pub prompt_tokens: u64,
+	/// The prompt tokens cached by the provider (part of the `prompt_tokens`)
+	pub cached_tokens: u64,
+	pub completion_tokens: u64,
+	/// The reasoning tokens (part of the `completion_tokens`)
+	pub reasoning_tokens: u64,
+	pub total_tokens: u64,
+
+	/// None if no price is known for the models used
+	pub price_usd: Option<f64>,
+	/// The sum of the AI durations of the inputs (can be more than `duration_sec` with concurrency)
+	pub ai_duration_sec: f64,
+	/// The duration of the whole run
+	pub duration_sec: f64,
+
+	/// The breakdown per model
+	pub models: Vec<ModelSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSummary {
+	pub model_name: String,
+	pub adapter_kind: String,
+	pub ai_responses: usize,
+	pub prompt_tokens: u64,
+	pub completion_tokens: u64,
+	pub price_usd: Option<f64>,
+}
+
+impl RunSummary {
+	/// Add the usage, cost, and timing of the AI response of one input
+	pub fn add_ai_response(&mut self, ai_response: &AiResponse) {
+		self.ai_responses += 1;
+		self.ai_duration_sec += ai_response.duration_sec;
+
+		let model_name = ai_response.model_name.to_string();
+		let adapter_kind = ai_response.adapter_kind.as_str().to_string();
+		let model_idx = match self
+			.models
+			.iter()
+			.position(|m| m.model_name == model_name && m.adapter_kind == adapter_kind)
+		{
+			Some(idx) => idx,
+			None => {
+				self.models.push(ModelSummary {
+					model_name,
+					adapter_kind,
+					ai_responses: 0,
+					prompt_tokens: 0,
+					completion_tokens: 0,
+					price_usd: None,
+				});
+				self.models.len() - 1
+			}
+		};
+		let model = &mut self.models[model_idx];
+		model.ai_responses += 1;
+
+		if ai_response.cached {
+			self.cached_responses += 1;
+			return;
+		}
+
+		let usage = &ai_response.usage;
+		let prompt_tokens = to_u64(usage.prompt_tokens);
+		let completion_tokens = to_u64(usage.completion_tokens);
+		let cached_tokens = to_u64(usage.prompt_tokens_details.as_ref().and_then(|d| d.cached_tokens));
+		let reasoning_tokens = to_u64(usage.completion_tokens_details.as_ref().and_then(|d| d.reasoning_tokens));
+		let total_tokens = usage
+			.total_tokens
+			.map(|v| v.max(0) as u64)
+			.unwrap_or(prompt_tokens + completion_tokens);
+
+		self.prompt_tokens += prompt_tokens;
+		self.cached_tokens += cached_tokens;
+		self.completion_tokens += completion_tokens;
+		self.reasoning_tokens += reasoning_tokens;
+		self.total_tokens += total_tokens;
+		model.prompt_tokens += prompt_tokens;
+		model.completion_tokens += completion_tokens;
+
+		if let Some(price_usd) = ai_response.price_usd {
+			self.price_usd = Some(self.price_usd.unwrap_or_default() + price_usd);
+			model.price_usd = Some(model.price_usd.unwrap_or_default() + price_usd);
+		}
+	}
+}
+
+// region:    --- Display
+
+impl fmt::Display for RunSummary {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			f,
+			"Inputs: {} | Skipped: {} | Failed: {}",
+			self.inputs, self.skipped, self.failed
+		)?;
+
+		write!(
+			f,
+			"AI responses: {} (cached: {}) | Duration: {} (AI: {})",
+			self.ai_responses,
+			self.cached_responses,
+			format_duration(Duration::from_secs_f64(self.duration_sec)),
+			format_duration(Duration::from_secs_f64(self.ai_duration_sec)),
+		)?;
+		if let Some(price_usd) = self.price_usd {
+			write!(f, " | ~${}", round_price(price_usd))?;
+		}
+
+		write!(
+			f,
+			"\nTokens: Prompt: {} (cached: {}) | Completion: {} (reasoning: {}) | Total: {}",
+			format_num(self.prompt_tokens as i64),
+			format_num(self.cached_tokens as i64),
+			format_num(self.completion_tokens as i64),
+			format_num(self.reasoning_tokens as i64),
+			format_num(self.total_tokens as i64),
+		)?;
+
+		for model in self.models.iter() {
+			write!(
+				f,
+				"\nModel: {} ({}) | AI responses: {} | Prompt: {} | Completion: {}",
+				model.model_name,
+				model.adapter_kind,
+				model.ai_responses,
+				format_num(model.prompt_tokens as i64),
+				format_num(model.completion_tokens as i64),
+			)?;
+			if let Some(price_usd) = model.price_usd {
+				write!(f, " | ~${}", round_price(price_usd))?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+// endregion: --- Display
+
+// region:    --- Support
+
+fn to_u64(tokens: Option<i32>) -> u64 {
+	tokens.map(|v| v.max(0) as u64).unwrap_or_default()
+}
+
+/// Round to 4 decimals (the sum of f64 prices can have a long tail)
+fn round_price(price_usd: f64) -> f64 {
+	(price_usd * 10_000.0).round() / 10_000.0
+}
+
+// endregion: --- Support
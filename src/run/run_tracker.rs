@@ -0,0 +1,89 @@
+//! The state shared across the concurrent inputs of a run (the eventual budget, the summary, and the eventual recorder).
+
+use crate::Result;
+use crate::hub::get_hub;
+use crate::run::{AiResponse, InputError, RunBudget, RunRecorder, RunSummary};
+use crate::session::SessionMessage;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+pub struct RunTracker {
+	budget: Option<Arc<RunBudget>>,
+	summary: Mutex<RunSummary>,
+	recorder: Option<RunRecorder>,
+}
+
+/// Constructors
+impl RunTracker {
+	pub fn new(budget: Option<Arc<RunBudget>>, recorder: Option<RunRecorder>) -> Self {
+		RunTracker {
+			budget,
+			summary: Mutex::new(RunSummary::default()),
+			recorder,
+		}
+	}
+}
+
+impl RunTracker {
+	pub fn budget(&self) -> Option<&Arc<RunBudget>> {
+		self.budget.as_ref()
+	}
+
+	pub fn recorder(&self) -> Option<&RunRecorder> {
+		self.recorder.as_ref()
+	}
+
+	/// Record the data, rendered messages, and AI response of the input
+	pub fn record_ai_response(
+		&self,
+		input_idx: usize,
+		data: &Value,
+		messages: Vec<SessionMessage>,
+		ai_response: Option<&AiResponse>,
+	) {
+		if let (Some(ai_response), Ok(mut summary)) = (ai_response, self.summary.lock()) {
+			summary.add_ai_response(ai_response);
+		}
+		if let Some(recorder) = self.recorder.as_ref() {
+			recorder.record_input_ai(input_idx, data, messages, ai_response);
+		}
+	}
+
+	/// Record an input skipped (at the Data or Output stage, or because of the budget)
+	pub fn record_skip(&self, input_idx: usize, reason: Option<String>) {
+		if let Ok(mut summary) = self.summary.lock() {
+			summary.skipped += 1;
+		}
+		if let Some(recorder) = self.recorder.as_ref() {
+			recorder.record_input_skip(input_idx, reason);
+		}
+	}
+
+	/// Record the output or error of the input in the eventual run history
+	///
+	/// Note: A failure to record does not fail the run.
+	pub fn record_input_result(&self, input_idx: usize, label: &str, res: &Result<Value>) {
+		if let Some(recorder) = self.recorder.as_ref() {
+			if let Err(err) = recorder.finish_input(input_idx, label, res) {
+				get_hub().publish_sync(format!(
+					"-! Cannot record input {label} in the run history. Cause: {err}"
+				));
+			}
+		}
+	}
+
+	/// Record the summary and errors of the completed run in the eventual run history
+	pub fn record_run_completed(&self, summary: &RunSummary, errors: &[InputError]) {
+		if let Some(recorder) = self.recorder.as_ref() {
+			if let Err(err) = recorder.finish(summary, errors) {
+				get_hub().publish_sync(format!("-! Cannot record the run in the run history. Cause: {err}"));
+			}
+		}
+	}
+
+	/// Returns a copy of the summary recorded so far
+	pub fn summary(&self) -> RunSummary {
+		self.summary.lock().map(|summary| summary.clone()).unwrap_or_default()
+	}
+}
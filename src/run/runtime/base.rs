@@ -1,12 +1,15 @@
 use crate::Result;
+use crate::agent::Agent;
 use crate::dir_context::DirContext;
-use crate::run::{RuntimeContext, get_genai_client};
+use crate::run::{LuaEnginePool, PooledLuaEngine, RuntimeContext, Sandbox, get_genai_client};
 use crate::script::LuaEngine;
 use genai::Client;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Runtime {
 	context: RuntimeContext,
+	lua_engine_pool: Arc<LuaEnginePool>,
 }
 
 /// Constructors
@@ -17,19 +20,47 @@ impl Runtime {
 
 		let context = RuntimeContext::new(dir_context, client);
 
-		let runtime = Self { context };
+		let runtime = Self::from_context(context);
 
 		Ok(runtime)
 	}
+
+	fn from_context(context: RuntimeContext) -> Self {
+		Self {
+			context,
+			lua_engine_pool: Arc::default(),
+		}
+	}
+
+	/// The runtime to run this agent, with the sandbox of its pack (see `Sandbox::for_agent`), unless `allow_all`
+	pub fn for_agent(&self, agent: &Agent, allow_all: bool) -> Result<Self> {
+		let sandbox = if allow_all {
+			None
+		} else {
+			Sandbox::for_agent(self.dir_context(), agent)?
+		};
+		// Note: A new engine pool, as the engines are bound to the context (and its sandbox)
+		Ok(Self::from_context(self.context.with_sandbox(sandbox)))
+	}
 }
 
 /// lua engine
-/// NOTE: For now, we do not keep any Lau engine in the Runtime, but just create new ones.
-///       Later, we might have an optmized reuse strategy of lua engines (but need to be cautious as not multi-threaded)
+/// NOTE: The agent stage and tool scripts use the engines of the pool (see `LuaEnginePool`).
 impl Runtime {
+	#[allow(unused)]
 	pub fn new_lua_engine(&self) -> Result<LuaEngine> {
 		LuaEngine::new(self.context.clone())
 	}
+
+	/// An engine of the pool for this agent (goes back to the pool when dropped)
+	pub fn lua_engine_for_agent(&self, agent: &Agent) -> Result<PooledLuaEngine> {
+		self.lua_engine_pool.acquire(&self.context, agent)
+	}
+
+	#[allow(unused)]
+	pub fn lua_engine_pool(&self) -> &LuaEnginePool {
+		&self.lua_engine_pool
+	}
 }
 
 /// Getters
@@ -59,6 +90,16 @@ mod tests_support {
 	impl Runtime {
 		/// This will create a new Runtime for the .tests-data/sandbox-01/ folder
 		pub fn new_test_runtime_sandbox_01() -> Result<Self> {
+			Self::new(Self::test_sandbox_01_dir_context()?)
+		}
+
+		/// Same as `new_test_runtime_sandbox_01` but with a custom genai client (e.g., pointing to a mock server)
+		pub fn new_test_runtime_sandbox_01_with_genai_client(client: Client) -> Result<Self> {
+			let context = RuntimeContext::new(Self::test_sandbox_01_dir_context()?, client);
+			Ok(Self::from_context(context))
+		}
+
+		fn test_sandbox_01_dir_context() -> Result<DirContext> {
 			let current_dir = SPath::new(SANDBOX_01_WKS_DIR).canonicalize()?;
 			let current_dir = SPath::new(current_dir);
 
@@ -70,11 +111,21 @@ mod tests_support {
 
 			let dir_context = DirContext::from_current_and_aipack_paths(current_dir, aipack_paths)?;
 
-			Self::new(dir_context)
+			Ok(dir_context)
 		}
 
 		/// This dir is relative to `./tests-data/.tmp`
 		pub fn new_test_runtime_for_temp_dir() -> Result<Self> {
+			Self::new(Self::test_temp_dir_context()?)
+		}
+
+		/// Same as `new_test_runtime_for_temp_dir` but with a custom genai client (e.g., pointing to a mock server)
+		pub fn new_test_runtime_for_temp_dir_with_genai_client(client: Client) -> Result<Self> {
+			let context = RuntimeContext::new(Self::test_temp_dir_context()?, client);
+			Ok(Self::from_context(context))
+		}
+
+		fn test_temp_dir_context() -> Result<DirContext> {
 			let current_dir = gen_test_dir_path();
 			// should not be the case with the above case, but just as a double prec
 			if current_dir.path().is_absolute() {
@@ -93,7 +144,7 @@ mod tests_support {
 
 			let dir_context = DirContext::from_current_and_aipack_paths(current_dir, aipack_paths)?;
 
-			Self::new(dir_context)
+			Ok(dir_context)
 		}
 	}
 }
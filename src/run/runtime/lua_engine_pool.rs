@@ -0,0 +1,114 @@
+//! The pool of initialized Lua engines of a runtime, per agent
+//!
+//! Creating a `LuaEngine` (new Lua VM, and all the `aip.*` modules) is the main cost of the small stage scripts,
+//! so the engines are reused across the stages and inputs of the same agent.
+//!
+//! - Each eval already gets its own environment table (the scope)
+//! - When released, the engine is reset (globals, `package.loaded`, and `package.path`) before going back to the pool
+
+use crate::Result;
+use crate::agent::Agent;
+use crate::run::RuntimeContext;
+use crate::script::LuaEngine;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The max number of idle engines kept per agent (the others are dropped when released)
+const MAX_IDLE_ENGINES_PER_AGENT: usize = 32;
+
+#[derive(Default)]
+pub struct LuaEnginePool {
+	/// The idle engines by agent file path
+	idle_engines: Mutex<HashMap<String, Vec<LuaEngine>>>,
+	/// The number of engines created by the pool (all agents)
+	created_count: AtomicUsize,
+}
+
+impl LuaEnginePool {
+	/// An engine for this agent, from the pool if one is idle, otherwise a new one
+	pub fn acquire(self: &Arc<Self>, runtime_context: &RuntimeContext, agent: &Agent) -> Result<PooledLuaEngine> {
+		let key = agent.file_path().to_string();
+		let engine = self
+			.idle_engines
+			.lock()
+			.ok()
+			.and_then(|mut idle_engines| idle_engines.get_mut(&key).and_then(|engines| engines.pop()));
+		let engine = match engine {
+			Some(engine) => engine,
+			None => {
+				let engine = LuaEngine::new(runtime_context.clone())?;
+				self.created_count.fetch_add(1, Ordering::Relaxed);
+				engine
+			}
+		};
+
+		Ok(PooledLuaEngine {
+			pool: self.clone(),
+			key,
+			engine: Some(engine),
+		})
+	}
+
+	/// The number of idle engines of this agent
+	#[allow(unused)]
+	pub fn idle_count(&self, agent: &Agent) -> usize {
+		self.idle_engines
+			.lock()
+			.ok()
+			.and_then(|idle_engines| idle_engines.get(agent.file_path()).map(|engines| engines.len()))
+			.unwrap_or_default()
+	}
+
+	/// The number of engines created by the pool (all agents), the others were reused
+	#[allow(unused)]
+	pub fn created_count(&self) -> usize {
+		self.created_count.load(Ordering::Relaxed)
+	}
+
+	fn release(&self, key: String, engine: LuaEngine) {
+		// Note: An engine which cannot be reset is dropped
+		if engine.reset().is_err() {
+			return;
+		}
+		if let Ok(mut idle_engines) = self.idle_engines.lock() {
+			let engines = idle_engines.entry(key).or_default();
+			if engines.len() < MAX_IDLE_ENGINES_PER_AGENT {
+				engines.push(engine);
+			}
+		}
+	}
+}
+
+/// A `LuaEngine` of the pool, which goes back to the pool when dropped
+pub struct PooledLuaEngine {
+	pool: Arc<LuaEnginePool>,
+	key: String,
+	/// Always Some, but taken on drop
+	engine: Option<LuaEngine>,
+}
+
+impl Deref for PooledLuaEngine {
+	type Target = LuaEngine;
+
+	fn deref(&self) -> &Self::Target {
+		self.engine.as_ref().expect("PooledLuaEngine engine should be set until drop")
+	}
+}
+
+impl Drop for PooledLuaEngine {
+	fn drop(&mut self) {
+		if let Some(engine) = self.engine.take() {
+			self.pool.release(std::mem::take(&mut self.key), engine);
+		}
+	}
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../../_tests/tests_lua_engine_pool.rs"]
+mod tests_lua_engine_pool;
+
+// endregion: --- Tests
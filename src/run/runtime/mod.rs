@@ -1,9 +1,13 @@
 // region:    --- Modules
 
 mod base;
+mod lua_engine_pool;
 mod runtime_context;
+mod sandbox;
 
 pub use base::*;
+pub use lua_engine_pool::*;
 pub use runtime_context::*;
+pub use sandbox::*;
 
 // endregion: --- Modules
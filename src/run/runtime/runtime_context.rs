@@ -1,5 +1,8 @@
+use crate::Result;
 use crate::dir_context::DirContext;
+use crate::run::Sandbox;
 use genai::Client;
+use simple_fs::SPath;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -14,6 +17,18 @@ impl RuntimeContext {
 			inner: Arc::new(RuntimeContextInner {
 				dir_context,
 				genai_client,
+				sandbox: None,
+			}),
+		}
+	}
+
+	/// A new context with the same dir context and genai client, but with this sandbox (None for no sandbox)
+	pub fn with_sandbox(&self, sandbox: Option<Sandbox>) -> Self {
+		Self {
+			inner: Arc::new(RuntimeContextInner {
+				dir_context: self.inner.dir_context.clone(),
+				genai_client: self.inner.genai_client.clone(),
+				sandbox: sandbox.map(Arc::new),
 			}),
 		}
 	}
@@ -28,9 +43,34 @@ impl RuntimeContext {
 	pub fn genai_client(&self) -> &Client {
 		&self.inner.genai_client
 	}
+
+	/// The sandbox of the pack agent being run, None when not sandboxed
+	pub fn sandbox(&self) -> Option<&Sandbox> {
+		self.inner.sandbox.as_deref()
+	}
+}
+
+/// Sandbox checks (always ok when not sandboxed)
+impl RuntimeContext {
+	pub fn check_read(&self, path: &SPath) -> Result<()> {
+		self.sandbox().map_or(Ok(()), |sandbox| sandbox.check_read(path))
+	}
+
+	pub fn check_write(&self, path: &SPath) -> Result<()> {
+		self.sandbox().map_or(Ok(()), |sandbox| sandbox.check_write(path))
+	}
+
+	pub fn check_cmd(&self, cmd_name: &str) -> Result<()> {
+		self.sandbox().map_or(Ok(()), |sandbox| sandbox.check_cmd(cmd_name))
+	}
+
+	pub fn check_net(&self, url: &str) -> Result<()> {
+		self.sandbox().map_or(Ok(()), |sandbox| sandbox.check_net(url))
+	}
 }
 
 struct RuntimeContextInner {
 	dir_context: DirContext,
 	genai_client: Client,
+	sandbox: Option<Arc<Sandbox>>,
 }
@@ -0,0 +1,217 @@
+//! The sandbox of the Lua scripts of a pack agent, from the `[permissions]` of its `pack.toml`
+//!
+//! - The agents of the installed packs are always sandboxed (no `[permissions]` means no write, cmd, nor net)
+//! - The agents of the custom and linked packs are sandboxed only when their `pack.toml` has `[permissions]`
+//! - The agents run from a file path (not a pack) are not sandboxed
+//! - `aip run --allow-all` runs any agent without sandbox
+//!
+//! The checks are done by the `aip.file.*`, `aip.cmd.exec`, `aip.git.*`, and `aip.web.*` functions,
+//! and the unsafe Lua globals (`io`, `os.execute`, `debug`, `dofile`, ...) are removed by `LuaEngine::new`
+//! (`require` reads the Lua modules with the read check, and cannot load C modules).
+//!
+//! Note: The paths are checked lexically (after resolving `.` and `..`), the symlinks are not followed.
+
+use crate::agent::{Agent, AgentRef};
+use crate::dir_context::{DirContext, RepoKind};
+use crate::pack::PackPermissions;
+use crate::{Error, Result};
+use camino::{Utf8Component, Utf8PathBuf};
+use globset::GlobSet;
+use simple_fs::{SPath, get_glob_set};
+
+#[derive(Debug)]
+pub struct Sandbox {
+	/// The pack of the agent (e.g., `demo@craft`), for the error messages
+	pack_identity: String,
+	wks_dir: SPath,
+	pack_dir: SPath,
+	permissions: PackPermissions,
+	/// None when no `fs.read` (the workspace and the pack dir can be read)
+	read_globs: Option<GlobSet>,
+	write_globs: Option<GlobSet>,
+}
+
+/// Constructors
+impl Sandbox {
+	pub fn new(
+		pack_identity: impl Into<String>,
+		wks_dir: SPath,
+		pack_dir: SPath,
+		permissions: PackPermissions,
+	) -> Result<Self> {
+		let to_glob_set = |globs: &[String]| -> Result<Option<GlobSet>> {
+			if globs.is_empty() {
+				return Ok(None);
+			}
+			let globs: Vec<&str> = globs.iter().map(|g| g.as_str()).collect();
+			let glob_set = get_glob_set(&globs)
+				.map_err(|err| Error::custom(format!("Invalid [permissions] fs glob. Cause: {err}")))?;
+			Ok(Some(glob_set))
+		};
+		let read_globs = match permissions.fs.read.as_deref() {
+			Some(read) => Some(to_glob_set(read)?.unwrap_or_else(GlobSet::empty)),
+			None => None,
+		};
+		let write_globs = to_glob_set(&permissions.fs.write)?;
+		let wks_dir = wks_dir.canonicalize().unwrap_or(wks_dir);
+		let pack_dir = pack_dir.canonicalize().unwrap_or(pack_dir);
+
+		Ok(Sandbox {
+			pack_identity: pack_identity.into(),
+			wks_dir: normalize_path(&wks_dir, &wks_dir),
+			pack_dir: normalize_path(&wks_dir, &pack_dir),
+			permissions,
+			read_globs,
+			write_globs,
+		})
+	}
+
+	/// The sandbox of this agent, None if the agent is not sandboxed (see the module doc)
+	pub fn for_agent(dir_context: &DirContext, agent: &Agent) -> Result<Option<Self>> {
+		let AgentRef::PackRef(pack_ref) = agent.agent_ref() else {
+			return Ok(None);
+		};
+
+		let permissions = match (
+			PackPermissions::load_from_pack_dir(&pack_ref.pack_dir)?,
+			pack_ref.repo_kind,
+		) {
+			(Some(permissions), _) => permissions,
+			(None, RepoKind::BaseInstalled) => PackPermissions::default(),
+			(None, _) => return Ok(None),
+		};
+
+		let sandbox = Sandbox::new(
+			pack_ref.identity.to_string(),
+			dir_context.wks_dir().clone(),
+			pack_ref.pack_dir.clone(),
+			permissions,
+		)?;
+
+		Ok(Some(sandbox))
+	}
+}
+
+/// Checks
+impl Sandbox {
+	pub fn check_read(&self, path: &SPath) -> Result<()> {
+		let path = normalize_path(&self.wks_dir, path);
+		if path.path().starts_with(self.pack_dir.path()) {
+			return Ok(());
+		}
+		let allowed = match self.read_globs.as_ref() {
+			Some(read_globs) => self.matches_fs_globs(read_globs, &path),
+			None => path.path().starts_with(self.wks_dir.path()),
+		};
+		if allowed {
+			Ok(())
+		} else {
+			Err(self.denied(format!("cannot read '{path}' (not in the fs.read permissions)")))
+		}
+	}
+
+	pub fn check_write(&self, path: &SPath) -> Result<()> {
+		let path = normalize_path(&self.wks_dir, path);
+		let allowed = self
+			.write_globs
+			.as_ref()
+			.is_some_and(|write_globs| self.matches_fs_globs(write_globs, &path));
+		if allowed {
+			Ok(())
+		} else {
+			Err(self.denied(format!("cannot write '{path}' (not in the fs.write permissions)")))
+		}
+	}
+
+	/// Check the command name (e.g., `cargo`), matched exactly
+	/// Note: A path to a program (e.g., `./cargo` or `/usr/bin/cargo`) needs to be in the cmd permissions as is.
+	pub fn check_cmd(&self, cmd_name: &str) -> Result<()> {
+		if self.permissions.cmd.iter().any(|cmd| cmd == cmd_name) {
+			Ok(())
+		} else {
+			Err(self.denied(format!("cannot execute '{cmd_name}' (not in the cmd permissions)")))
+		}
+	}
+
+	/// Check the host of the url (`*.example.com` allows `example.com` and all its sub domains)
+	pub fn check_net(&self, url: &str) -> Result<()> {
+		let host = reqwest::Url::parse(url)
+			.ok()
+			.and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+			.unwrap_or_default();
+		let allowed = !host.is_empty()
+			&& self.permissions.net.iter().any(|net| {
+				let net = net.to_lowercase();
+				match net.strip_prefix("*.") {
+					Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+					None => host == net,
+				}
+			});
+		if allowed {
+			Ok(())
+		} else {
+			Err(self.denied(format!("cannot access '{url}' (host not in the net permissions)")))
+		}
+	}
+
+	/// The error for a Lua global removed from the sandbox (e.g., `io`)
+	pub fn denied_global(&self, name: &str) -> Error {
+		self.denied(format!(
+			"'{name}' is not available in the sandbox (use the aip.file.* functions)"
+		))
+	}
+}
+
+/// Private
+impl Sandbox {
+	/// The relative globs are matched against the path relative to the workspace dir, the absolute ones against the full path
+	fn matches_fs_globs(&self, globs: &GlobSet, path: &SPath) -> bool {
+		if globs.is_match(path.path()) {
+			return true;
+		}
+		path.path()
+			.strip_prefix(self.wks_dir.path())
+			.is_ok_and(|rel_path| globs.is_match(rel_path))
+	}
+
+	fn denied(&self, action: String) -> Error {
+		Error::PermissionDenied {
+			pack_identity: self.pack_identity.clone(),
+			action,
+		}
+	}
+}
+
+// region:    --- Support
+
+/// The absolute path (relative to the workspace dir), with the `.` and `..` resolved lexically
+fn normalize_path(wks_dir: &SPath, path: &SPath) -> SPath {
+	let path = if path.path().is_absolute() {
+		path.path().to_path_buf()
+	} else {
+		wks_dir.path().join(path.path())
+	};
+
+	let mut normalized = Utf8PathBuf::new();
+	for component in path.components() {
+		match component {
+			Utf8Component::CurDir => (),
+			Utf8Component::ParentDir => {
+				normalized.pop();
+			}
+			other => normalized.push(other),
+		}
+	}
+
+	SPath::from(normalized)
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+#[path = "../../_tests/tests_sandbox.rs"]
+mod tests_sandbox;
+
+// endregion: --- Tests
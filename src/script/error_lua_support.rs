@@ -5,26 +5,165 @@ use lazy_regex::regex;
 use std::borrow::Cow;
 use std::sync::Arc;
 
+/// Where a script is in its agent file, so that the Lua errors point to the `.aip` file lines
+#[derive(Debug, Clone)]
+pub struct LuaScriptSource {
+	pub file_path: String,
+	/// The section heading (e.g., `Data` for `# Data`)
+	pub section: &'static str,
+	/// The line (1-based) of the first script line in the file
+	pub start_line: usize,
+}
+
 impl Error {
 	pub fn from_error_with_script(lua_error: &mlua::Error, script: &str) -> Error {
+		Self::from_error_with_script_source(lua_error, script, None)
+	}
+
+	/// Same as `from_error_with_script`, but when the source is given, the error starts with the
+	/// `path/to/agent.aip:LINE:COL: message` of the first script location, followed by the annotated line,
+	/// and the other script locations are `path/to/agent.aip:LINE`.
+	pub fn from_error_with_script_source(
+		lua_error: &mlua::Error,
+		script: &str,
+		source: Option<&LuaScriptSource>,
+	) -> Error {
 		let mut buff: Vec<String> = Vec::new();
 		for item in lua_error.chain() {
 			if let Some(lua_item) = item.downcast_ref::<mlua::Error>() {
 				let msg = lua_item.to_string();
-				let msg = if msg.contains("traceback") | msg.contains("syntax") {
-					process_stack_with_script(&msg, script)
-				} else {
-					msg
+				let msg = match source {
+					// Note: Only the first item gets the location header (the next ones are its causes)
+					Some(source) if buff.is_empty() => process_msg_with_source(&msg, script, source),
+					Some(source) => replace_script_locations(&msg, source),
+					None if msg.contains("traceback") | msg.contains("syntax") => {
+						process_stack_with_script(&msg, script)
+					}
+					None => msg,
 				};
 				buff.push(format!("Lua error:\n{}", msg));
 			} else {
 				buff.push(format!("Other lua error:\n{}", item));
 			}
 		}
-		Error::Lua(buff.join("\n"))
+		lua_error_with_cause(lua_error, buff.join("\n"))
+	}
+}
+
+/// The `Error::Lua` of the message, or the `Error::LuaPermissionDenied` when caused by a `PermissionDenied`
+/// (the `crate::Error` given to mlua as `ExternalError`, see `From<Error> for mlua::Error`)
+fn lua_error_with_cause(lua_error: &mlua::Error, lua_msg: String) -> Error {
+	let permission_denied = lua_error
+		.chain()
+		.find_map(|item| item.downcast_ref::<Error>().and_then(permission_denied_of));
+
+	match permission_denied {
+		Some(cause) => Error::LuaPermissionDenied {
+			lua_msg,
+			cause: Box::new(cause),
+		},
+		None => Error::Lua(lua_msg),
+	}
+}
+
+/// A copy of the `PermissionDenied` of this error (which is not `Clone`), if any
+fn permission_denied_of(err: &Error) -> Option<Error> {
+	match err {
+		Error::PermissionDenied { pack_identity, action } => Some(Error::PermissionDenied {
+			pack_identity: pack_identity.clone(),
+			action: action.clone(),
+		}),
+		Error::LuaPermissionDenied { cause, .. } => permission_denied_of(cause),
+		_ => None,
+	}
+}
+
+/// The `[string "..."]:LINE:` script locations of the Lua messages
+fn script_location_rx() -> &'static regex::Regex {
+	regex!(r#"\[string .*?\]:([\d]+):"#)
+}
+
+/// Build the `path:LINE:COL: message (in # Section)` header and the annotated line, from the first script location of the message
+fn process_msg_with_source(msg: &str, script: &str, source: &LuaScriptSource) -> String {
+	let rx = script_location_rx();
+	let script_line_num = rx
+		.captures(msg)
+		.and_then(|caps| caps.get(1))
+		.and_then(|m| m.as_str().parse::<usize>().ok());
+	let Some(script_line_num) = script_line_num else {
+		return msg.to_string();
+	};
+	let script_line = script.lines().nth(script_line_num.saturating_sub(1)).unwrap_or_default();
+	let file_line_num = source.start_line + script_line_num - 1;
+
+	// -- The message (first line, without its location) and the rest (e.g., the stack traceback)
+	let (first_line, rest) = msg.split_once('\n').unwrap_or((msg, ""));
+	let first_line = regex!(r#"\[string .*?\]:[\d]+: ?"#).replace(first_line, "");
+	let first_line = first_line.trim();
+
+	// -- The column, from the name in the message (e.g., `(global 'foo')` or `in field 'load'`)
+	let col = error_name(msg)
+		.and_then(|name| find_name_col(script_line, name))
+		.unwrap_or_else(|| script_line.len() - script_line.trim_start().len() + 1);
+
+	// -- Build the message
+	let file_path = &source.file_path;
+	let section = source.section;
+	let num_width = file_line_num.to_string().len();
+	let gutter = " ".repeat(num_width);
+	let indent = script_line
+		.chars()
+		.take(col - 1)
+		.map(|c| if c == '\t' { '\t' } else { ' ' })
+		.collect::<String>();
+	let mut buff = vec![
+		format!("{file_path}:{file_line_num}:{col}: {first_line} (in # {section})"),
+		format!("{gutter} |"),
+		format!("{file_line_num} | {script_line}"),
+		format!("{gutter} | {indent}^"),
+	];
+	if !rest.is_empty() {
+		buff.push(replace_script_locations(rest, source));
 	}
+
+	buff.join("\n")
+}
+
+/// Replace the `[string "..."]:LINE:` script locations by the `path:LINE:` of the agent file
+fn replace_script_locations(msg: &str, source: &LuaScriptSource) -> String {
+	script_location_rx()
+		.replace_all(msg, |caps: &regex::Captures| {
+			match caps.get(1).and_then(|m| m.as_str().parse::<usize>().ok()) {
+				Some(num) => format!("{}:{}:", source.file_path, source.start_line + num - 1),
+				None => caps[0].to_string(),
+			}
+		})
+		.to_string()
+}
+
+/// The name the error is about (e.g., `foo` for `attempt to index a nil value (global 'foo')`,
+/// or `load` for a `[C]: in field 'load'` of the traceback)
+fn error_name(msg: &str) -> Option<&str> {
+	let rx = regex!(
+		r#"\((?:global|local|field|method|upvalue|constant) '([\w.]+)'\)|in (?:field|method|function) '([\w.:]+)'"#
+	);
+	let caps = rx.captures(msg)?;
+	let name = caps.get(1).or_else(|| caps.get(2))?.as_str();
+	// For `aip.file.load`, the last part (the line might use it with another prefix)
+	name.rsplit(['.', ':']).next()
+}
+
+/// The column (1-based) of the name in the line, as a whole word
+fn find_name_col(line: &str, name: &str) -> Option<usize> {
+	let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+	line.match_indices(name).find_map(|(idx, _)| {
+		let before_ok = line[..idx].chars().next_back().is_none_or(|c| !is_word_char(c));
+		let after_ok = line[idx + name.len()..].chars().next().is_none_or(|c| !is_word_char(c));
+		(before_ok && after_ok).then(|| line[..idx].chars().count() + 1)
+	})
 }
 
+/// Note: Without the agent file source, the locations are annotated with the script line content
 fn process_stack_with_script(stack: &str, script: &str) -> String {
 	let script_lines: Vec<&str> = script.lines().collect();
 	let mut buff: Vec<Cow<str>> = Vec::new();
@@ -77,7 +216,7 @@ impl From<&mlua::Error> for Error {
 		}
 		let msg = buff.join("\n");
 		// Note: here is Self::lua, it gets a stackoverflow
-		Self::Lua(msg)
+		lua_error_with_cause(lua_error, msg)
 	}
 }
 
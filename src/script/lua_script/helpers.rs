@@ -1,20 +1,27 @@
+use crate::script::LuaScriptSource;
 use crate::support::W;
 use crate::{Error, Result};
-use mlua::{IntoLua, Lua, LuaSerdeExt as _, Value};
+use mlua::{Function, IntoLua, Lua, LuaSerdeExt as _, Value};
 
 // region:    --- mlua::Value utils
 
 /// Process correctly the lua eval result
-pub fn process_lua_eval_result(_lua: &Lua, res: mlua::Result<Value>, script: &str) -> Result<Value> {
+/// Note: When the `source` is given, the errors point to the lines of the agent file (see `LuaScriptSource`)
+pub fn process_lua_eval_result(
+	_lua: &Lua,
+	res: mlua::Result<Value>,
+	script: &str,
+	source: Option<&LuaScriptSource>,
+) -> Result<Value> {
 	let res = match res {
 		Ok(res) => res,
-		Err(err) => return Err(Error::from_error_with_script(&err, script)),
+		Err(err) => return Err(Error::from_error_with_script_source(&err, script, source)),
 	};
 
 	let res = match res {
 		// This is when we d with pcall(...), see test_lua_json_parse_invalid
 		Value::Error(err) => {
-			return Err(Error::from_error_with_script(&err, script));
+			return Err(Error::from_error_with_script_source(&err, script, source));
 			// return Err(Error::from(&*err));
 		}
 		res => res,
@@ -115,3 +122,31 @@ impl IntoLua for W<String> {
 }
 
 // endregion: --- mlua::Value utils
+
+// region:    --- Async Functions
+
+/// Lua chunk returning the function dispatching to the async or blocking function
+const ASYNC_OR_BLOCKING_FN_SCRIPT: &str = r#"
+local async_fn, blocking_fn = ...
+local isyieldable = coroutine.isyieldable
+return function(...)
+	if isyieldable() then
+		return async_fn(...)
+	end
+	return blocking_fn(...)
+end
+"#;
+
+/// The function calling the async function within a task (a coroutine of `utils.task.*`),
+/// and the blocking one otherwise (as an async function cannot yield outside of a coroutine)
+///
+/// Note: The chunk is named `aip.async` (`=` for as is), so that its traceback lines are not taken for the script ones.
+pub fn async_or_blocking_fn(lua: &Lua, async_fn: Function, blocking_fn: Function) -> Result<Function> {
+	let func = lua
+		.load(ASYNC_OR_BLOCKING_FN_SCRIPT)
+		.set_name("=aip.async")
+		.call::<Function>((async_fn, blocking_fn))?;
+	Ok(func)
+}
+
+// endregion: --- Async Functions
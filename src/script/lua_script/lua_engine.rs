@@ -1,13 +1,56 @@
-use crate::Result;
+use crate::agent::{Agent, LuaLimits};
 use crate::hub::{HubEvent, get_hub};
 use crate::run::RuntimeContext;
+use crate::script::LuaScriptSource;
 use crate::script::lua_script::helpers::{process_lua_eval_result, serde_to_lua_value};
-use mlua::{IntoLua, Lua, Table, Value};
+use crate::{Error, Result};
+use mlua::{Chunk, Function, HookTriggers, IntoLua, IntoLuaMulti, Lua, MultiValue, Table, Value, VmState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The number of instructions between two checks of the timeout and instruction limits
+const LIMITS_HOOK_INSTRUCTIONS: u32 = 1000;
+
+/// The stage of an agent script evaluation, with its Lua limits (see the `lua_limits` option)
+pub struct LuaStage<'a> {
+	/// `before_all`, `data`, `output`, `after_all`, or `tools`
+	pub name: &'static str,
+	pub agent_name: &'a str,
+	pub input_label: Option<&'a str>,
+	pub limits: LuaLimits,
+	/// The location of the script in the agent file, for the errors
+	pub source: Option<LuaScriptSource>,
+}
+
+impl<'a> LuaStage<'a> {
+	pub fn new(agent: &'a Agent, name: &'static str, input_label: Option<&'a str>) -> Self {
+		let limits = agent
+			.options_as_ref()
+			.lua_limits()
+			.map(|lua_limits| lua_limits.for_stage(name))
+			.unwrap_or_default();
+		let source = agent.script_start_line(name).map(|start_line| LuaScriptSource {
+			file_path: agent.file_path().to_string(),
+			section: section_heading(name),
+			start_line,
+		});
+		LuaStage {
+			name,
+			agent_name: agent.name(),
+			input_label,
+			limits,
+			source,
+		}
+	}
+}
 
 pub struct LuaEngine {
 	lua: Lua,
 	#[allow(unused)]
 	runtime_context: RuntimeContext,
+	/// Restores the state after the init (see `reset`), when the engine is reused
+	reset_fn: Function,
 }
 
 /// Constructors
@@ -24,8 +67,25 @@ impl LuaEngine {
 		// -- Init print
 		init_print(&lua)?;
 
+		// -- Remove the unsafe globals when the agent is sandboxed
+		if runtime_context.sandbox().is_some() {
+			init_sandbox(&lua, &runtime_context)?;
+		}
+
 		// -- Build and return
-		let engine = LuaEngine { lua, runtime_context };
+		let set_metatable = lua.create_function(|_, (table, metatable): (Table, Option<Table>)| {
+			table.set_metatable(metatable);
+			Ok(())
+		})?;
+		let reset_fn = lua
+			.load(LUA_RESET_FN_SCRIPT)
+			.set_name("reset")
+			.call::<Function>(set_metatable)?;
+		let engine = LuaEngine {
+			lua,
+			runtime_context,
+			reset_fn,
+		};
 
 		Ok(engine)
 	}
@@ -33,26 +93,112 @@ impl LuaEngine {
 
 /// Public Function
 impl LuaEngine {
+	/// Eval the script without Lua limits, nor agent file line mapping (for the tests)
+	///
+	/// Note: The runtime evaluates the agent scripts with `eval_stage`.
+	#[cfg(test)]
 	pub fn eval(&self, script: &str, scope: Option<Table>, addl_lua_paths: Option<&[&str]>) -> Result<Value> {
-		let lua = &self.lua;
-
-		let chunck = lua.load(script);
-
-		let chunck = if let Some(scope) = scope {
-			let env = self.upgrade_scope(scope, addl_lua_paths)?;
-			chunck.set_environment(env)
-		} else {
-			chunck
-		};
+		let chunck = self.load_chunk(script, scope, addl_lua_paths)?;
 
 		let res = chunck.eval::<Value>();
 		// let res = res?;
 
-		let res = process_lua_eval_result(&self.lua, res, script)?;
+		let res = process_lua_eval_result(&self.lua, res, script, None)?;
 
 		Ok(res)
 	}
 
+	/// Eval the script within the Lua limits of the stage (timeout, memory, and instructions),
+	/// and with the errors pointing to the agent file lines of the stage script
+	///
+	/// Returns a `Error::LuaLimitExceeded` when one of the limits is exceeded.
+	///
+	/// Note: The timeout and instruction limits are checked while Lua code runs, so a long `aip.*` call
+	///       (e.g., `aip.cmd.exec`) is only interrupted once it returns to the script.
+	pub fn eval_stage(
+		&self,
+		script: &str,
+		scope: Option<Table>,
+		addl_lua_paths: Option<&[&str]>,
+		stage: &LuaStage,
+	) -> Result<Value> {
+		let limits = stage.limits;
+		let chunck = self.load_chunk(script, scope, addl_lua_paths)?;
+		if limits.is_empty() {
+			let res = chunck.eval::<Value>();
+			return process_lua_eval_result(&self.lua, res, script, stage.source.as_ref());
+		}
+
+		// -- Set the limits
+		// The limit exceeded, if any (kept, as the Lua script might catch the hook error with a pcall)
+		let exceeded: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+		if let Some(memory_mb) = limits.memory_mb() {
+			let used = self.lua.used_memory();
+			self.lua.set_memory_limit(used + (memory_mb as usize) * 1024 * 1024)?;
+		}
+		if limits.timeout_ms().is_some() || limits.max_instructions().is_some() {
+			let start = Instant::now();
+			let timeout = limits.timeout_ms().map(Duration::from_millis);
+			let max_instructions = limits.max_instructions();
+			let instructions = AtomicU64::new(0);
+			let exceeded = exceeded.clone();
+			self.lua.set_hook(
+				HookTriggers::new().every_nth_instruction(LIMITS_HOOK_INSTRUCTIONS),
+				move |_, _| {
+					let instructions = instructions.fetch_add(LIMITS_HOOK_INSTRUCTIONS as u64, Ordering::Relaxed)
+						+ LIMITS_HOOK_INSTRUCTIONS as u64;
+					let limit = match (timeout, max_instructions) {
+						(Some(timeout), _) if start.elapsed() > timeout => {
+							format!("timeout ({} ms)", timeout.as_millis())
+						}
+						(_, Some(max)) if instructions > max => format!("instruction budget ({max} instructions)"),
+						_ => return Ok(VmState::Continue),
+					};
+					let err = mlua::Error::runtime(format!("Lua {limit} exceeded"));
+					if let Ok(mut exceeded) = exceeded.lock() {
+						exceeded.get_or_insert(limit);
+					}
+					Err(err)
+				},
+			);
+		}
+
+		// -- Eval
+		let res = chunck.eval::<Value>();
+
+		// -- Remove the limits
+		self.lua.remove_hook();
+		if limits.memory_mb().is_some() {
+			self.lua.set_memory_limit(0)?;
+		}
+
+		// -- Process the result
+		let exceeded = exceeded.lock().ok().and_then(|mut exceeded| exceeded.take());
+		let exceeded = exceeded.or_else(|| match (&res, limits.memory_mb()) {
+			(Err(err), Some(memory_mb)) if is_memory_error(err) => Some(format!("memory limit ({memory_mb} MB)")),
+			_ => None,
+		});
+		if let Some(limit) = exceeded {
+			return Err(Error::LuaLimitExceeded {
+				stage: stage.name.to_string(),
+				agent_name: stage.agent_name.to_string(),
+				input_label: stage.input_label.map(|label| label.to_string()),
+				limit,
+			});
+		}
+
+		process_lua_eval_result(&self.lua, res, script, stage.source.as_ref())
+	}
+
+	/// Restore the state after the init (globals, and the tables reachable from them, e.g., `aip`, `string`, `package.loaded`),
+	/// so that the engine can be reused for another eval without what the previous scripts left
+	///
+	/// Note: The state of the functions (upvalues) and userdata is not restored.
+	pub fn reset(&self) -> Result<()> {
+		self.reset_fn.call::<()>(())?;
+		Ok(())
+	}
+
 	pub fn create_table(&self) -> Result<Table> {
 		let res = self.lua.create_table()?;
 		Ok(res)
@@ -77,6 +223,24 @@ impl LuaEngine {
 
 /// private
 impl LuaEngine {
+	fn load_chunk<'a>(
+		&'a self,
+		script: &'a str,
+		scope: Option<Table>,
+		addl_lua_paths: Option<&[&str]>,
+	) -> Result<Chunk<'a>> {
+		let chunck = self.lua.load(script);
+
+		let chunck = if let Some(scope) = scope {
+			let env = self.upgrade_scope(scope, addl_lua_paths)?;
+			chunck.set_environment(env)
+		} else {
+			chunck
+		};
+
+		Ok(chunck)
+	}
+
 	/// Upgrade a custom scope to full scope with all of the globals added.
 	/// NOTE: A `base_lua_path` is the container of the `lua/` dir. So
 	///       `base_lua_path = /some/dir`, the path added to lua package path will be `/some/dir/lua/?.lua,/some/dir/lua/?/init.lua`
@@ -136,8 +300,192 @@ fn init_print(lua: &Lua) -> Result<()> {
 	Ok(())
 }
 
+/// Remove the globals giving access to the file system and processes outside of the `aip.*` checks
+/// (`io`, `os` but its time functions, `debug`, `dofile`, `loadfile`, the binary chunks of `load`,
+/// and the C modules of `require`)
+///
+/// Note: `package.loaded` is updated as well, as `require("os")` returns it.
+fn init_sandbox(lua: &Lua, runtime_context: &RuntimeContext) -> Result<()> {
+	let globals = lua.globals();
+
+	let denied_fn = |name: &'static str| {
+		let ctx = runtime_context.clone();
+		lua.create_function(move |_, _: mlua::MultiValue| -> mlua::Result<()> { Err(denied_global(&ctx, name).into()) })
+	};
+	let denied_metatable = |prefix: &'static str| -> Result<Table> {
+		let ctx = runtime_context.clone();
+		let metatable = lua.create_table()?;
+		metatable.set(
+			"__index",
+			lua.create_function(move |_, (_, key): (Value, String)| -> mlua::Result<()> {
+				Err(denied_global(&ctx, &format!("{prefix}.{key}")).into())
+			})?,
+		)?;
+		Ok(metatable)
+	};
+
+	// -- io
+	let io = lua.create_table()?;
+	io.set_metatable(Some(denied_metatable("io")?));
+	globals.set("io", io.clone())?;
+
+	// -- os (only the time functions)
+	let os: Table = globals.get("os")?;
+	let safe_os = lua.create_table()?;
+	for name in ["time", "clock", "date", "difftime"] {
+		safe_os.set(name, os.get::<Value>(name)?)?;
+	}
+	safe_os.set_metatable(Some(denied_metatable("os")?));
+	globals.set("os", safe_os.clone())?;
+
+	// -- debug (can reach the original libraries, e.g., with `debug.getregistry`)
+	let debug = lua.create_table()?;
+	debug.set_metatable(Some(denied_metatable("debug")?));
+	globals.set("debug", debug.clone())?;
+
+	// -- dofile, loadfile
+	globals.set("dofile", denied_fn("dofile")?)?;
+	globals.set("loadfile", denied_fn("loadfile")?)?;
+
+	// -- load (text chunks only, as the binary chunks can break the VM)
+	let text_load = lua
+		.load(LUA_TEXT_LOAD_FN_SCRIPT)
+		.set_name("=aip.load")
+		.call::<Function>((globals.get::<Function>("load")?, globals.get::<Function>("select")?))?;
+	globals.set("load", text_load)?;
+
+	// -- package (the lua modules of `require` are read through the sandbox, and no C modules)
+	if let Ok(package) = globals.get::<Table>("package") {
+		package.set("loadlib", denied_fn("package.loadlib")?)?;
+		package.set("cpath", "")?;
+		let searchers: Table = package.get("searchers")?;
+		let sandbox_searchers = lua.create_table()?;
+		// Note: The `package.preload` searcher, and ours in place of the Lua file one (the C ones are removed)
+		sandbox_searchers.push(searchers.get::<Value>(1)?)?;
+		sandbox_searchers.push(sandbox_lua_searcher(lua, runtime_context)?)?;
+		package.set("searchers", sandbox_searchers)?;
+
+		let loaded: Table = package.get("loaded")?;
+		loaded.set("io", io)?;
+		loaded.set("os", safe_os)?;
+		loaded.set("debug", debug)?;
+	}
+
+	Ok(())
+}
+
+/// The `require` searcher of the Lua files of `package.path`, reading them through the sandbox
+/// (as `package.path` can be changed by the scripts)
+///
+/// Note: The relative paths are relative to the workspace dir (as for the sandbox checks).
+fn sandbox_lua_searcher(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Function> {
+	let ctx = runtime_context.clone();
+	let searcher = lua.create_function(move |lua, name: String| -> mlua::Result<MultiValue> {
+		let package: Table = lua.globals().get("package")?;
+		let package_path: String = package.get("path")?;
+		let module_path = name.replace('.', "/");
+
+		let mut not_found = String::new();
+		for template in package_path.split(';').filter(|template| !template.is_empty()) {
+			let path = ctx.dir_context().wks_dir().join(template.replace('?', &module_path));
+			if !path.is_file() {
+				not_found.push_str(&format!("\n\tno file '{path}'"));
+				continue;
+			}
+			ctx.check_read(&path)?;
+			let content = std::fs::read_to_string(&path).map_err(crate::Error::from)?;
+			let loader = lua.load(content).set_name(format!("@{path}")).into_function()?;
+			return (loader, path.to_string()).into_lua_multi(lua);
+		}
+
+		not_found.into_lua_multi(lua)
+	})?;
+
+	Ok(searcher)
+}
+
+fn denied_global(runtime_context: &RuntimeContext, name: &str) -> crate::Error {
+	match runtime_context.sandbox() {
+		Some(sandbox) => sandbox.denied_global(name),
+		None => crate::Error::custom(format!("'{name}' is not available")),
+	}
+}
+
 // endregion: --- Init Globals
 
+// region:    --- Support
+
+/// Captures all the tables reachable from the globals (e.g., `aip`, `utils.file`, `string`, `package.loaded`)
+/// and from the string metatable, with their metatables, and returns the function restoring them.
+///
+/// Note: Done in Lua (rather than with the mlua table API), as it is called for each reuse of a pooled engine.
+///       The metatables are restored with the raw `set_metatable` given as argument (ignores `__metatable`).
+const LUA_RESET_FN_SCRIPT: &str = r#"
+local set_metatable = ...
+local next, rawget, rawset, getmetatable, type = next, rawget, rawset, getmetatable, type
+
+local snapshots = {}
+local function snapshot(t)
+	if snapshots[t] then return end
+	local entries = {}
+	local metatable = getmetatable(t)
+	snapshots[t] = { entries = entries, metatable = metatable }
+	for k, v in next, t do
+		rawset(entries, k, v)
+		if type(v) == "table" then snapshot(v) end
+	end
+	if type(metatable) == "table" then snapshot(metatable) end
+end
+snapshot(_G)
+snapshot(getmetatable(""))
+
+return function()
+	for t, s in next, snapshots do
+		local entries = s.entries
+		for k in next, t do
+			if rawget(entries, k) == nil then rawset(t, k, nil) end
+		end
+		for k, v in next, entries do
+			if rawget(t, k) ~= v then rawset(t, k, v) end
+		end
+		set_metatable(t, s.metatable)
+	end
+end
+"#;
+
+/// The sandbox `load`, calling the original one (given as argument) with the `t` (text only) mode
+///
+/// Note: The `env` is only passed when given, as a `nil` env would be the chunk `_ENV`.
+const LUA_TEXT_LOAD_FN_SCRIPT: &str = r##"
+local load, select = ...
+return function(chunk, chunkname, _mode, ...)
+	if select("#", ...) > 0 then
+		return load(chunk, chunkname, "t", ...)
+	end
+	return load(chunk, chunkname, "t")
+end
+"##;
+
+/// The agent file section heading of the stage (e.g., `Before All` for `before_all`)
+fn section_heading(stage_name: &str) -> &'static str {
+	match stage_name {
+		"before_all" => "Before All",
+		"data" => "Data",
+		"output" => "Output",
+		"after_all" => "After All",
+		"tools" => "Tools",
+		_ => "Lua",
+	}
+}
+
+/// True if the Lua error is (or was caused by) a memory limit error
+fn is_memory_error(err: &mlua::Error) -> bool {
+	err.chain()
+		.any(|item| matches!(item.downcast_ref::<mlua::Error>(), Some(mlua::Error::MemoryError(_))))
+}
+
+// endregion: --- Support
+
 // region:    --- init_utils
 
 /// Just a convenient macro to init/set the lua modules
@@ -180,7 +528,9 @@ fn init_utils(lua_vm: &Lua, runtime_context: &RuntimeContext) -> Result<()> {
 		lua,
 		code,
 		hbs,
-		semver
+		semver,
+		session,
+		task
 	);
 
 	let globals = lua_vm.globals();
@@ -16,11 +16,15 @@ mod utils_md;
 mod utils_path;
 mod utils_rust;
 mod utils_semver;
+mod utils_session;
+mod utils_task;
 mod utils_text;
 mod utils_web;
 
 pub use lua_engine::*;
 pub use lua_value_ext::*;
+// Note: For the `IntoLua` of the types holding json values (e.g., `AiResponse`)
+pub use helpers::serde_to_lua_value;
 
 #[cfg(test)]
 pub use helpers::*;
@@ -7,18 +7,29 @@
 //!
 //! ### Functions
 //! * `utils.cmd.exec(cmd_name: string, args?: string | table) -> {stdout: string, stderr: string, exit: number}`
+//! * `utils.cmd.exec_async(cmd_name: string, args?: string | table) -> {stdout: string, stderr: string, exit: number}`
 
-use crate::Result;
 use crate::run::RuntimeContext;
-use crate::script::lua_script::helpers::to_vec_of_strings;
+use crate::script::lua_script::helpers::{async_or_blocking_fn, to_vec_of_strings};
+use crate::{Error, Result};
 use mlua::{Lua, Table, Value};
-use std::process::Command;
+use std::io;
+use std::process::{Command, Output};
 
-pub fn init_module(lua: &Lua, _runtime_context: &RuntimeContext) -> Result<Table> {
+pub fn init_module(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Table> {
 	let table = lua.create_table()?;
 
-	let exec_fn = lua.create_function(cmd_exec)?;
+	let ctx = runtime_context.clone();
+	let exec_fn =
+		lua.create_function(move |lua, (cmd_name, args): (String, Option<Value>)| cmd_exec(lua, &ctx, cmd_name, args))?;
 
+	let ctx = runtime_context.clone();
+	let exec_async_fn = lua.create_async_function(move |lua, (cmd_name, args): (String, Option<Value>)| {
+		let ctx = ctx.clone();
+		async move { cmd_exec_async(&lua, &ctx, cmd_name, args).await }
+	})?;
+
+	table.set("exec_async", async_or_blocking_fn(lua, exec_async_fn, exec_fn.clone())?)?;
 	table.set("exec", exec_fn)?;
 
 	Ok(table)
@@ -67,8 +78,38 @@ pub fn init_module(lua: &Lua, _runtime_context: &RuntimeContext) -> Result<Table
 ///   error  = string        -- Error message from command execution
 /// }
 /// ```
-fn cmd_exec(lua: &Lua, (cmd_name, args): (String, Option<Value>)) -> mlua::Result<Value> {
-	let mut command = Command::new(&cmd_name);
+fn cmd_exec(lua: &Lua, ctx: &RuntimeContext, cmd_name: String, args: Option<Value>) -> mlua::Result<Value> {
+	let mut command = new_command(ctx, &cmd_name, args)?;
+	let output = command.output();
+	cmd_output_to_lua(lua, &command, output)
+}
+
+/// ## Lua Documentation
+///
+/// Same as `utils.cmd.exec`, but without blocking the other tasks when called within a task (see `utils.task`)
+///
+/// ```lua
+/// local results = utils.task.all({
+///   build = function() return utils.cmd.exec_async("cargo", {"build"}) end,
+///   diff  = function() return utils.cmd.exec_async("git", {"diff"}) end,
+/// })
+/// ```
+async fn cmd_exec_async(lua: &Lua, ctx: &RuntimeContext, cmd_name: String, args: Option<Value>) -> mlua::Result<Value> {
+	let mut command = new_command(ctx, &cmd_name, args)?;
+	let (command, output) = tokio::task::spawn_blocking(move || {
+		let output = command.output();
+		(command, output)
+	})
+	.await
+	.map_err(|err| Error::custom(format!("Fail to execute: {cmd_name}\nCause:\n{err}")))?;
+	cmd_output_to_lua(lua, &command, output)
+}
+
+// region:    --- Support
+
+fn new_command(ctx: &RuntimeContext, cmd_name: &str, args: Option<Value>) -> mlua::Result<Command> {
+	ctx.check_cmd(cmd_name)?;
+	let mut command = Command::new(cmd_name);
 
 	// Handle optional arguments
 	if let Some(args) = args {
@@ -76,7 +117,11 @@ fn cmd_exec(lua: &Lua, (cmd_name, args): (String, Option<Value>)) -> mlua::Resul
 		command.args(args);
 	}
 
-	match command.output() {
+	Ok(command)
+}
+
+fn cmd_output_to_lua(lua: &Lua, command: &Command, output: io::Result<Output>) -> mlua::Result<Value> {
+	match output {
 		Ok(output) => {
 			let stdout = String::from_utf8_lossy(&output.stdout).to_string();
 			let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -124,6 +169,8 @@ Cause:\n{err}"
 	}
 }
 
+// endregion: --- Support
+
 // region:    --- Tests
 
 #[cfg(test)]
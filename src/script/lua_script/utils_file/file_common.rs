@@ -41,8 +41,7 @@ pub(super) fn file_load(
 	rel_path: String,
 	options: Option<Value>,
 ) -> mlua::Result<mlua::Value> {
-	let base_path = compute_base_dir(ctx.dir_context(), options.as_ref())?;
-	let rel_path = SPath::new(rel_path);
+	let (base_path, rel_path) = file_load_paths(ctx, rel_path, options)?;
 
 	let file_record = FileRecord::load(&base_path, &rel_path)?;
 	let res = file_record.into_lua(lua)?;
@@ -50,6 +49,45 @@ pub(super) fn file_load(
 	Ok(res)
 }
 
+/// ## Lua Documentation
+///
+/// Same as `utils.file.load`, but without blocking the other tasks when called within a task (see `utils.task`)
+///
+/// ```lua
+/// local files = utils.task.all({
+///   readme = function() return utils.file.load_async("README.md") end,
+///   main   = function() return utils.file.load_async("src/main.rs") end,
+/// })
+/// ```
+pub(super) async fn file_load_async(
+	lua: &Lua,
+	ctx: &RuntimeContext,
+	rel_path: String,
+	options: Option<Value>,
+) -> mlua::Result<mlua::Value> {
+	let (base_path, rel_path) = file_load_paths(ctx, rel_path, options)?;
+
+	let file_record = tokio::task::spawn_blocking(move || FileRecord::load(&base_path, &rel_path))
+		.await
+		.map_err(|err| Error::custom(format!("Fail to load file. Cause: {err}")))??;
+	let res = file_record.into_lua(lua)?;
+
+	Ok(res)
+}
+
+/// The base dir and the checked path of the file to load
+fn file_load_paths(ctx: &RuntimeContext, rel_path: String, options: Option<Value>) -> mlua::Result<(SPath, SPath)> {
+	let base_path = compute_base_dir(ctx.dir_context(), options.as_ref())?;
+	let rel_path = SPath::new(rel_path);
+	if rel_path.path().is_absolute() {
+		ctx.check_read(&rel_path)?;
+	} else {
+		ctx.check_read(&base_path.join(&rel_path))?;
+	}
+
+	Ok((base_path, rel_path))
+}
+
 /// ## Lua Documentation
 ///
 /// Save a File Content into a path
@@ -64,6 +102,7 @@ pub(super) fn file_load(
 ///
 pub(super) fn file_save(_lua: &Lua, ctx: &RuntimeContext, rel_path: String, content: String) -> mlua::Result<()> {
 	let path = ctx.dir_context().resolve_path((&rel_path).into(), PathResolver::WksDir)?;
+	ctx.check_write(&path)?;
 	ensure_file_dir(&path).map_err(Error::from)?;
 
 	write(&path, content)?;
@@ -87,6 +126,7 @@ pub(super) fn file_save(_lua: &Lua, ctx: &RuntimeContext, rel_path: String, cont
 ///
 pub(super) fn file_append(_lua: &Lua, ctx: &RuntimeContext, rel_path: String, content: String) -> mlua::Result<()> {
 	let path = ctx.dir_context().resolve_path((&rel_path).into(), PathResolver::WksDir)?;
+	ctx.check_write(&path)?;
 	ensure_file_dir(&path).map_err(Error::from)?;
 
 	let mut file = std::fs::OpenOptions::new()
@@ -125,6 +165,7 @@ pub(super) fn file_ensure_exists(
 	let options = options.unwrap_or_default();
 	let rel_path = SPath::new(path);
 	let full_path = ctx.dir_context().resolve_path(rel_path.clone(), PathResolver::WksDir)?;
+	ctx.check_write(&full_path)?;
 
 	// if the file does not exist, create it.
 	if !full_path.exists() {
@@ -182,6 +223,9 @@ pub(super) fn file_list(
 		Some(ListOptions::from_relative_glob(!absolute)),
 	)
 	.map_err(Error::from)?;
+	for sfile in sfiles.iter() {
+		ctx.check_read(&sfile.into())?;
+	}
 
 	// Now, we put back the paths found relative to base_path
 	let sfiles = sfiles
@@ -249,6 +293,9 @@ pub(super) fn file_list_load(
 		Some(ListOptions::from_relative_glob(!absolute)),
 	)
 	.map_err(Error::from)?;
+	for sfile in sfiles.iter() {
+		ctx.check_read(&sfile.into())?;
+	}
 
 	let file_records = sfiles
 		.into_iter()
@@ -324,6 +371,7 @@ pub(super) fn file_first(
 	let Some(sfile) = sfiles.next() else {
 		return Ok(Value::Nil);
 	};
+	ctx.check_read(&(&sfile).into())?;
 
 	let spath = if absolute {
 		sfile.into()
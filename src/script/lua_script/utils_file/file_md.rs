@@ -38,6 +38,7 @@ pub(super) fn file_load_md_sections(
 	});
 
 	let path = ctx.dir_context().resolve_path(path.into(), PathResolver::WksDir)?;
+	ctx.check_read(&path)?;
 
 	let sec_iter = MdSectionIter::from_path(path, headings.as_deref())?;
 	let sections = sec_iter.collect::<Vec<_>>();
@@ -48,6 +49,7 @@ pub(super) fn file_load_md_sections(
 
 pub(super) fn file_load_md_split_first(lua: &Lua, ctx: &RuntimeContext, path: String) -> mlua::Result<Value> {
 	let path = ctx.dir_context().resolve_path(path.into(), PathResolver::WksDir)?;
+	ctx.check_read(&path)?;
 
 	let mut sec_iter = MdSectionIter::from_path(path, None)?;
 	let split_first = sec_iter.split_first();
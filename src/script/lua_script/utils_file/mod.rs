@@ -5,8 +5,10 @@ mod file_md;
 
 use crate::Result;
 use crate::run::RuntimeContext;
+use crate::script::lua_script::helpers::async_or_blocking_fn;
 use crate::script::lua_script::utils_file::file_common::{
-	EnsureExistsOptions, file_append, file_ensure_exists, file_first, file_list, file_list_load, file_load, file_save,
+	EnsureExistsOptions, file_append, file_ensure_exists, file_first, file_list, file_list_load, file_load,
+	file_load_async, file_save,
 };
 use crate::script::lua_script::utils_file::file_md::{file_load_md_sections, file_load_md_split_first};
 use mlua::{Lua, Table, Value};
@@ -21,6 +23,13 @@ pub fn init_module(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Table>
 	let file_load_fn =
 		lua.create_function(move |lua, (path, options): (String, Option<Value>)| file_load(lua, &ctx, path, options))?;
 
+	// -- load_async
+	let ctx = runtime_context.clone();
+	let file_load_async_fn = lua.create_async_function(move |lua, (path, options): (String, Option<Value>)| {
+		let ctx = ctx.clone();
+		async move { file_load_async(&lua, &ctx, path, options).await }
+	})?;
+
 	// -- save
 	let ctx = runtime_context.clone();
 	let file_save_fn =
@@ -68,6 +77,10 @@ pub fn init_module(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Table>
 		lua.create_function(move |lua, (path,): (String,)| file_load_md_split_first(lua, &ctx, path))?;
 
 	// -- All all function to the module
+	table.set(
+		"load_async",
+		async_or_blocking_fn(lua, file_load_async_fn, file_load_fn.clone())?,
+	)?;
 	table.set("load", file_load_fn)?;
 	table.set("save", file_save_fn)?;
 	table.set("append", file_append_fn)?;
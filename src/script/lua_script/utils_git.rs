@@ -8,6 +8,7 @@
 //! ### Functions
 //! * `utils.git.restore(path: string) -> string | table`
 
+use crate::dir_context::PathResolver;
 use crate::hub::get_hub;
 use crate::run::RuntimeContext;
 use crate::{Error, Result};
@@ -44,6 +45,8 @@ pub fn init_module(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Table>
 /// print(result)
 /// ```
 fn git_restore(lua: &Lua, ctx: &RuntimeContext, path: String) -> mlua::Result<Value> {
+	ctx.check_cmd("git")?;
+	ctx.check_write(&ctx.dir_context().resolve_path((&path).into(), PathResolver::WksDir)?)?;
 	let output = std::process::Command::new("git")
 		.current_dir(ctx.dir_context().wks_dir())
 		.arg("restore")
@@ -0,0 +1,242 @@
+//! Defines the `session` module, used in the lua engine.
+//!
+//! ---
+//!
+//! ## Lua documentation
+//! The `session` module exposes functions to read and manage the conversation sessions
+//! stored in `.aipack/sessions/` (see the agent `session` option).
+//!
+//! ### Functions
+//! * `utils.session.list() -> [{name: string, message_count: number}]`
+//! * `utils.session.load(name: string) -> [{role: string, content: string}]`
+//! * `utils.session.append(name: string, role: string, content: string)`
+//! * `utils.session.truncate(name: string, keep_last: number) -> number`
+//! * `utils.session.summarize(name: string, summary: string, keep_last?: number)`
+//! * `utils.session.clear(name: string) -> boolean`
+
+use crate::run::RuntimeContext;
+use crate::session::{SessionMessage, SessionRole, SessionStore};
+use crate::{Error, Result};
+use mlua::{Lua, LuaSerdeExt, Table, Value};
+
+pub fn init_module(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Table> {
+	let table = lua.create_table()?;
+
+	let ctx = runtime_context.clone();
+	let list_fn = lua.create_function(move |lua, ()| session_list(lua, &ctx))?;
+
+	let ctx = runtime_context.clone();
+	let load_fn = lua.create_function(move |lua, name: String| session_load(lua, &ctx, name))?;
+
+	let ctx = runtime_context.clone();
+	let append_fn = lua.create_function(move |_lua, (name, role, content): (String, String, String)| {
+		session_append(&ctx, name, role, content)
+	})?;
+
+	let ctx = runtime_context.clone();
+	let truncate_fn =
+		lua.create_function(move |_lua, (name, keep_last): (String, usize)| session_truncate(&ctx, name, keep_last))?;
+
+	let ctx = runtime_context.clone();
+	let summarize_fn = lua.create_function(
+		move |_lua, (name, summary, keep_last): (String, String, Option<usize>)| {
+			session_summarize(&ctx, name, summary, keep_last)
+		},
+	)?;
+
+	let ctx = runtime_context.clone();
+	let clear_fn = lua.create_function(move |_lua, name: String| session_clear(&ctx, name))?;
+
+	table.set("list", list_fn)?;
+	table.set("load", load_fn)?;
+	table.set("append", append_fn)?;
+	table.set("truncate", truncate_fn)?;
+	table.set("summarize", summarize_fn)?;
+	table.set("clear", clear_fn)?;
+
+	Ok(table)
+}
+
+// region:    --- Lua Functions
+
+/// ## Lua Documentation
+///
+/// List the sessions of the workspace.
+///
+/// ```lua
+/// -- API Signature
+/// utils.session.list() -> [{name: string, message_count: number}]
+/// ```
+fn session_list(lua: &Lua, ctx: &RuntimeContext) -> mlua::Result<Value> {
+	let store = SessionStore::new(ctx.dir_context())?;
+	ctx.check_read(store.dir())?;
+	let table = lua.create_table()?;
+	for info in store.list()? {
+		let info_table = lua.create_table()?;
+		info_table.set("name", info.name)?;
+		info_table.set("message_count", info.message_count)?;
+		table.push(info_table)?;
+	}
+	Ok(Value::Table(table))
+}
+
+/// ## Lua Documentation
+///
+/// Load the messages of a session (empty list if the session does not exist).
+///
+/// ```lua
+/// -- API Signature
+/// utils.session.load(name: string) -> [{role: string, content: string}]
+/// ```
+///
+/// The `role` is `"system"`, `"user"`, or `"assistant"`.
+fn session_load(lua: &Lua, ctx: &RuntimeContext, name: String) -> mlua::Result<Value> {
+	let store = SessionStore::new(ctx.dir_context())?;
+	ctx.check_read(&store.session_path(&name)?)?;
+	let messages = store.load(&name)?;
+	lua.to_value(&messages)
+}
+
+/// ## Lua Documentation
+///
+/// Append a message at the end of a session (creating the session if needed).
+///
+/// ```lua
+/// -- API Signature
+/// utils.session.append(name: string, role: "system" | "user" | "assistant", content: string)
+/// ```
+fn session_append(ctx: &RuntimeContext, name: String, role: String, content: String) -> mlua::Result<()> {
+	let role = parse_role(&role)?;
+	let store = writable_store(ctx, &name)?;
+	store.append(&name, &[SessionMessage::new(role, content)])?;
+	Ok(())
+}
+
+/// ## Lua Documentation
+///
+/// Keep only the last `keep_last` messages of a session.
+///
+/// ```lua
+/// -- API Signature
+/// utils.session.truncate(name: string, keep_last: number) -> number
+/// ```
+///
+/// Returns the number of messages removed.
+fn session_truncate(ctx: &RuntimeContext, name: String, keep_last: usize) -> mlua::Result<usize> {
+	let store = writable_store(ctx, &name)?;
+	Ok(store.truncate(&name, keep_last)?)
+}
+
+/// ## Lua Documentation
+///
+/// Replace the history of a session with a summary, keeping the last `keep_last` messages (default 0).
+///
+/// ```lua
+/// -- API Signature
+/// utils.session.summarize(name: string, summary: string, keep_last?: number)
+/// ```
+///
+/// The summary is stored as a `"system"` message at the start of the session.
+///
+/// ### Example
+/// ```lua
+/// local messages = utils.session.load("my-chat")
+/// if #messages > 20 then
+///   utils.session.summarize("my-chat", "The user is building a Rust CLI named aipack.", 4)
+/// end
+/// ```
+fn session_summarize(
+	ctx: &RuntimeContext,
+	name: String,
+	summary: String,
+	keep_last: Option<usize>,
+) -> mlua::Result<()> {
+	let store = writable_store(ctx, &name)?;
+	let messages = store.load(&name)?;
+	let keep_last = keep_last.unwrap_or_default().min(messages.len());
+
+	let mut new_messages = vec![SessionMessage::new(SessionRole::System, summary)];
+	new_messages.extend_from_slice(&messages[messages.len() - keep_last..]);
+	store.replace(&name, &new_messages)?;
+
+	Ok(())
+}
+
+/// ## Lua Documentation
+///
+/// Delete a session.
+///
+/// ```lua
+/// -- API Signature
+/// utils.session.clear(name: string) -> boolean
+/// ```
+///
+/// Returns false if the session did not exist.
+fn session_clear(ctx: &RuntimeContext, name: String) -> mlua::Result<bool> {
+	let store = writable_store(ctx, &name)?;
+	Ok(store.clear(&name)?)
+}
+
+// endregion: --- Lua Functions
+
+// region:    --- Support
+
+/// The session store, once the sandbox allows to write the session file (and so, to read it)
+fn writable_store(ctx: &RuntimeContext, name: &str) -> Result<SessionStore> {
+	let store = SessionStore::new(ctx.dir_context())?;
+	ctx.check_write(&store.session_path(name)?)?;
+	Ok(store)
+}
+
+fn parse_role(role: &str) -> mlua::Result<SessionRole> {
+	role.parse::<SessionRole>().map_err(|_| {
+		Error::custom(format!(
+			"utils.session role '{role}' is invalid. Should be 'system', 'user', or 'assistant'"
+		))
+		.into()
+	})
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+	use crate::_test_support::remove_test_dir;
+	use crate::run::Runtime;
+	use value_ext::JsonValueExt as _;
+
+	#[tokio::test]
+	async fn test_lua_session_append_summarize_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+		let lua_engine = runtime.new_lua_engine()?;
+		let script = r#"
+utils.session.append("chat-lua", "user", "Hello")
+utils.session.append("chat-lua", "assistant", "Hi")
+utils.session.append("chat-lua", "user", "Bye")
+utils.session.summarize("chat-lua", "Greetings were exchanged", 1)
+return utils.session.load("chat-lua")
+		"#;
+
+		// -- Exec
+		let res = serde_json::to_value(lua_engine.eval(script, None, None)?)?;
+
+		// -- Check
+		assert_eq!(res.x_get_str("/0/role")?, "system");
+		assert_eq!(res.x_get_str("/0/content")?, "Greetings were exchanged");
+		assert_eq!(res.x_get_str("/1/role")?, "user");
+		assert_eq!(res.x_get_str("/1/content")?, "Bye");
+		assert_eq!(res.as_array().map(|a| a.len()), Some(2));
+
+		// -- Cleanup
+		remove_test_dir(runtime.dir_context().current_dir())?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
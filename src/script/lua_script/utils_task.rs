@@ -0,0 +1,269 @@
+//! Defines the `task` module, used in the lua engine
+//!
+//! ---
+//!
+//! ## Lua documentation
+//! This module exposes functions to run Lua functions concurrently, as tasks (Lua coroutines).
+//!
+//! Within a task, the `*_async` functions (e.g., `utils.web.get_async`, `utils.cmd.exec_async`, `utils.file.load_async`)
+//! let the other tasks run while they wait, so that the I/O of the tasks is done concurrently.
+//! (Outside of a task, they block like their non async versions.)
+//!
+//! ### Functions
+//! * `utils.task.spawn(fn: function, ...args) -> Task`
+//! * `utils.task.all(tasks: table) -> table`
+//! * `task:join() -> any`
+
+use crate::run::RuntimeContext;
+use crate::{Error, Result};
+use futures::future::join_all;
+use mlua::{AnyUserData, Function, Lua, MultiValue, Table, UserData, UserDataMethods, Value};
+
+pub fn init_module(lua: &Lua, _runtime_context: &RuntimeContext) -> Result<Table> {
+	let table = lua.create_table()?;
+
+	table.set("spawn", lua.create_function(task_spawn)?)?;
+	table.set("all", lua.create_function(task_all)?)?;
+
+	Ok(table)
+}
+
+/// ## Lua Documentation
+///
+/// Create a task calling the function with the args.
+/// The task runs when joined, by `task:join()`, or with the other tasks by `utils.task.all`.
+///
+/// ```lua
+/// local task = utils.task.spawn(utils.web.get_async, "https://example.com")
+/// local web_response = task:join()
+/// ```
+fn task_spawn(_lua: &Lua, (func, args): (Function, MultiValue)) -> mlua::Result<LuaTask> {
+	Ok(LuaTask {
+		call: Some((func, args.into_vec())),
+	})
+}
+
+/// ## Lua Documentation
+///
+/// Run the tasks (functions or tasks of `utils.task.spawn`) concurrently, and return their results with the same keys.
+///
+/// ```lua
+/// local results = utils.task.all({
+///   function() return utils.web.get_async("https://example.com/a") end,
+///   utils.task.spawn(utils.web.get_async, "https://example.com/b"),
+/// })
+/// -- results[1] and results[2] are the web responses
+///
+/// local results = utils.task.all({
+///   readme = function() return utils.file.load_async("README.md") end,
+///   status = function() return utils.cmd.exec_async("git", {"status"}) end,
+/// })
+/// -- results.readme.content, results.status.stdout
+/// ```
+///
+/// ### Error
+///
+/// When a task fails, the error of the first failed task (after all tasks are done).
+fn task_all(lua: &Lua, tasks: Table) -> mlua::Result<Table> {
+	let mut keys: Vec<Value> = Vec::new();
+	let mut calls: Vec<TaskCall> = Vec::new();
+	for pair in tasks.pairs::<Value, Value>() {
+		let (key, value) = pair?;
+		let call = match value {
+			Value::Function(func) => (func, Vec::new()),
+			Value::UserData(user_data) => LuaTask::take_call(&user_data)?,
+			other => {
+				return Err(Error::custom(format!(
+					"utils.task.all - task '{}' must be a function or a task (from utils.task.spawn), but was a '{}'",
+					key_label(&key),
+					other.type_name()
+				))
+				.into());
+			}
+		};
+		keys.push(key);
+		calls.push(call);
+	}
+
+	let results = run_tasks(calls)?;
+
+	let res = lua.create_table()?;
+	for (key, result) in keys.into_iter().zip(results) {
+		let value = result.map_err(|err| {
+			Error::custom(format!(
+				"utils.task.all - task '{}' failed.\nCause: {err}",
+				key_label(&key)
+			))
+		})?;
+		res.set(key, value)?;
+	}
+
+	Ok(res)
+}
+
+// region:    --- LuaTask
+
+/// The function and args of a task
+type TaskCall = (Function, Vec<Value>);
+
+/// The task of `utils.task.spawn`
+struct LuaTask {
+	/// None once joined
+	call: Option<TaskCall>,
+}
+
+impl LuaTask {
+	/// Take the call of the task (a task can only be joined once)
+	fn take_call(user_data: &AnyUserData) -> mlua::Result<TaskCall> {
+		let mut task = user_data
+			.borrow_mut::<LuaTask>()
+			.map_err(|_| Error::custom("utils.task - value is not a task (tasks are created with utils.task.spawn)"))?;
+		let call = task
+			.call
+			.take()
+			.ok_or_else(|| Error::custom("utils.task - task was already joined"))?;
+		Ok(call)
+	}
+}
+
+impl UserData for LuaTask {
+	fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+		// Note: A function (rather than a method), so that the task is not borrowed while it runs
+		methods.add_function("join", |_lua, user_data: AnyUserData| {
+			let call = LuaTask::take_call(&user_data)?;
+			run_tasks(vec![call])?.pop().unwrap_or(Ok(Value::Nil))
+		});
+	}
+}
+
+// endregion: --- LuaTask
+
+// region:    --- Support
+
+/// Run the calls as Lua coroutines, polled concurrently until all are done
+///
+/// Note: Like the other blocking `utils.*` functions, this blocks the current thread until the tasks are done.
+fn run_tasks(calls: Vec<TaskCall>) -> mlua::Result<Vec<mlua::Result<Value>>> {
+	let rt = tokio::runtime::Handle::try_current().map_err(Error::TokioTryCurrent)?;
+	let futures = calls
+		.into_iter()
+		.map(|(func, args)| func.call_async::<Value>(MultiValue::from_vec(args)));
+	let results = tokio::task::block_in_place(|| rt.block_on(join_all(futures)));
+
+	Ok(results)
+}
+
+fn key_label(key: &Value) -> String {
+	key.to_string().unwrap_or_else(|_| key.type_name().to_string())
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+	use crate::_test_support::assert_contains;
+	use crate::run::Runtime;
+	use std::time::{Duration, Instant};
+	use value_ext::JsonValueExt as _;
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+	async fn test_lua_task_all_cmd_exec_async_concurrent() -> Result<()> {
+		// -- Setup & Fixtures
+		let runtime = Runtime::new_test_runtime_sandbox_01()?;
+		let engine = runtime.new_lua_engine()?;
+		let script = r#"
+local tasks = {}
+for i = 1, 4 do
+	tasks["sleep_" .. i] = function()
+		return utils.cmd.exec_async("sh", {"-c", "sleep 0.5 && echo " .. i}).stdout
+	end
+end
+return utils.task.all(tasks)
+		"#;
+
+		// -- Exec
+		let start = Instant::now();
+		let res = serde_json::to_value(engine.eval(script, None, None)?)?;
+		let duration = start.elapsed();
+
+		// -- Check
+		// Note: 4 x 0.5s when serial
+		assert!(duration < Duration::from_millis(1500), "duration: {duration:?}");
+		for i in 1..=4 {
+			assert_eq!(res.x_get_str(&format!("sleep_{i}"))?.trim(), i.to_string());
+		}
+
+		Ok(())
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+	async fn test_lua_task_spawn_join_and_all_order() -> Result<()> {
+		// -- Setup & Fixtures
+		let runtime = Runtime::new_test_runtime_sandbox_01()?;
+		let engine = runtime.new_lua_engine()?;
+		let script = r#"
+local file_task = utils.task.spawn(utils.file.load_async, "file-01.txt")
+local add_task = utils.task.spawn(function(a, b) return a + b end, 1, 2)
+local results = utils.task.all({
+	add_task,
+	function() return "two" end,
+	utils.task.spawn(utils.cmd.exec_async, "echo", "three"),
+})
+return {
+	file    = file_task:join().content,
+	add     = results[1],
+	two     = results[2],
+	three   = results[3].stdout,
+	four    = utils.cmd.exec_async("echo", "four").stdout,
+}
+		"#;
+
+		// -- Exec
+		let res = serde_json::to_value(engine.eval(script, None, None)?)?;
+
+		// -- Check
+		assert_eq!(res.x_get_str("file")?.trim(), "content of file-01.txt");
+		assert_eq!(res.x_get_i64("add")?, 3);
+		assert_eq!(res.x_get_str("two")?, "two");
+		assert_eq!(res.x_get_str("three")?.trim(), "three");
+		// Note: Outside of a task, the async function blocks
+		assert_eq!(res.x_get_str("four")?.trim(), "four");
+
+		Ok(())
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+	async fn test_lua_task_all_errors() -> Result<()> {
+		// -- Setup & Fixtures
+		let runtime = Runtime::new_test_runtime_sandbox_01()?;
+		let engine = runtime.new_lua_engine()?;
+
+		// -- Exec & Check - failed task
+		let script = r#"
+return utils.task.all({
+	ok     = function() return 1 end,
+	failed = function() error("Some task error") end,
+})
+		"#;
+		let err = engine.eval(script, None, None).err().ok_or("Should have failed")?;
+		assert_contains(&err.to_string(), "task 'failed' failed");
+		assert_contains(&err.to_string(), "Some task error");
+
+		// -- Exec & Check - joined twice
+		let script = r#"
+local task = utils.task.spawn(function() return 1 end)
+task:join()
+return task:join()
+		"#;
+		let err = engine.eval(script, None, None).err().ok_or("Should have failed")?;
+		assert_contains(&err.to_string(), "task was already joined");
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -7,21 +7,46 @@
 //!
 //! ### Functions
 //! * `utils.web.get(url: string) -> string`
+//! * `utils.web.post(url: string, data: string | table) -> string`
+//! * `utils.web.get_async(url: string) -> string`
+//! * `utils.web.post_async(url: string, data: string | table) -> string`
 
 use crate::hub::get_hub;
 use crate::run::RuntimeContext;
+use crate::script::lua_script::helpers::async_or_blocking_fn;
 use crate::support::StrExt as _;
 use crate::{Error, Result};
 use mlua::{Lua, LuaSerdeExt, Table, Value};
 use reqwest::redirect::Policy;
 use reqwest::{Client, Response, header};
 
-pub fn init_module(lua: &Lua, _runtime_context: &RuntimeContext) -> Result<Table> {
+pub fn init_module(lua: &Lua, runtime_context: &RuntimeContext) -> Result<Table> {
 	let table = lua.create_table()?;
 
-	let web_get_fn = lua.create_function(move |lua, (url,): (String,)| web_get(lua, url))?;
-	let web_post_fn = lua.create_function(move |lua, (url, data): (String, Value)| web_post(lua, url, data))?;
-
+	let ctx = runtime_context.clone();
+	let web_get_fn = lua.create_function(move |lua, (url,): (String,)| web_get(lua, &ctx, url))?;
+	let ctx = runtime_context.clone();
+	let web_post_fn = lua.create_function(move |lua, (url, data): (String, Value)| web_post(lua, &ctx, url, data))?;
+
+	let ctx = runtime_context.clone();
+	let web_get_async_fn = lua.create_async_function(move |lua, (url,): (String,)| {
+		let ctx = ctx.clone();
+		async move { web_get_async(&lua, &ctx, url).await }
+	})?;
+	let ctx = runtime_context.clone();
+	let web_post_async_fn = lua.create_async_function(move |lua, (url, data): (String, Value)| {
+		let ctx = ctx.clone();
+		async move { web_post_async(&lua, &ctx, url, data).await }
+	})?;
+
+	table.set(
+		"get_async",
+		async_or_blocking_fn(lua, web_get_async_fn, web_get_fn.clone())?,
+	)?;
+	table.set(
+		"post_async",
+		async_or_blocking_fn(lua, web_post_async_fn, web_post_fn.clone())?,
+	)?;
 	table.set("get", web_get_fn)?;
 	table.set("post", web_post_fn)?;
 
@@ -47,33 +72,44 @@ pub fn init_module(lua: &Lua, _runtime_context: &RuntimeContext) -> Result<Table
 /// Note will not throw error if status is not 2xx,
 /// but will throw error if the web request cannot be made.
 ///
-fn web_get(lua: &Lua, url: String) -> mlua::Result<Value> {
+fn web_get(lua: &Lua, ctx: &RuntimeContext, url: String) -> mlua::Result<Value> {
+	// Note: Also checked here, so that a denied url fails even without a tokio runtime
+	ctx.check_net(&url)?;
 	let rt = tokio::runtime::Handle::try_current().map_err(Error::TokioTryCurrent)?;
-	let res: mlua::Result<Value> = tokio::task::block_in_place(|| {
-		rt.block_on(async {
-			let client = Client::builder()
-				.redirect(Policy::limited(5)) // Set to follow up to 5 redirects
-				.build()
-				.map_err(crate::Error::from)?;
-
-			let res: mlua::Result<Value> = match client.get(&url).send().await {
-				Ok(response) => get_lua_response_value(lua, response, &url).await,
-				Err(err) => Err(crate::Error::Lua(format!(
-					"\
+	tokio::task::block_in_place(|| rt.block_on(web_get_async(lua, ctx, url)))
+}
+
+/// ## Lua Documentation
+///
+/// Same as `utils.web.get`, but without blocking the other tasks when called within a task (see `utils.task`)
+///
+/// ```lua
+/// local responses = utils.task.all({
+///   function() return utils.web.get_async("https://example.com/a") end,
+///   function() return utils.web.get_async("https://example.com/b") end,
+/// })
+/// ```
+///
+async fn web_get_async(lua: &Lua, ctx: &RuntimeContext, url: String) -> mlua::Result<Value> {
+	ctx.check_net(&url)?;
+	let client = Client::builder()
+		.redirect(redirect_policy(ctx))
+		.build()
+		.map_err(crate::Error::from)?;
+
+	let res: mlua::Result<Value> = match client.get(&url).send().await {
+		Ok(response) => get_lua_response_value(lua, response, &url).await,
+		Err(err) => Err(crate::Error::Lua(format!(
+			"\
 Fail to do utils.web.get for url: {url}
 Cause: {err}"
-				))
-				.into()),
-			};
-
-			if res.is_ok() {
-				get_hub().publish_sync(format!("-> lua web::get OK ({}) ", url));
-			}
+		))
+		.into()),
+	};
 
-			// return the Result<Dynamic, Error>
-			res
-		})
-	});
+	if res.is_ok() {
+		get_hub().publish_sync(format!("-> lua web::get OK ({}) ", url));
+	}
 
 	res
 }
@@ -102,66 +138,89 @@ Cause: {err}"
 /// Note will not throw error if status is not 2xx,
 /// but will throw error if the web request cannot be made.
 ///
-fn web_post(lua: &Lua, url: String, data: Value) -> mlua::Result<Value> {
+fn web_post(lua: &Lua, ctx: &RuntimeContext, url: String, data: Value) -> mlua::Result<Value> {
+	// Note: Also checked here, so that a denied url fails even without a tokio runtime
+	ctx.check_net(&url)?;
 	let rt = tokio::runtime::Handle::try_current().map_err(Error::TokioTryCurrent)?;
-	let res: mlua::Result<Value> = tokio::task::block_in_place(|| {
-		rt.block_on(async {
-			let client = Client::builder()
-				.redirect(Policy::limited(5)) // Set to follow up to 5 redirects
-				.build()
-				.map_err(crate::Error::from)?;
-
-			let mut request_builder = client.post(&url);
-
-			// Set Content-Type and body based on the type of 'data'
-			match data {
-				Value::String(s) => {
-					request_builder = request_builder
-						.header(header::CONTENT_TYPE, "plain/text")
-						.body(s.to_string_lossy());
-				}
-				Value::Table(table) => {
-					let json: serde_json::Value = serde_json::to_value(table).map_err(|err| {
-						crate::Error::custom(format!(
-							"Cannot searlize to json the argument given to the post.\n    Cause: {err}"
-						))
-					})?;
-					// mlua provides the serialize features.
-					request_builder = request_builder
-						.header(header::CONTENT_TYPE, "application/json")
-						.body(json.to_string());
-				}
-				_ => {
-					return Err(mlua::Error::RuntimeError(
-						"Data must be a string or a table".to_string(),
-					));
-				}
-			}
-
-			let res: mlua::Result<Value> = match request_builder.send().await {
-				Ok(response) => get_lua_response_value(lua, response, &url).await,
-				Err(err) => Err(crate::Error::Lua(format!(
-					"\
-Fail to do utils.web.post for url: {url}
-Cause: {err}"
+	tokio::task::block_in_place(|| rt.block_on(web_post_async(lua, ctx, url, data)))
+}
+
+/// ## Lua Documentation
+///
+/// Same as `utils.web.post`, but without blocking the other tasks when called within a task (see `utils.task`)
+///
+/// ```lua
+/// local task = utils.task.spawn(utils.web.post_async, "https://example.com/api", { key1 = "value1" })
+/// local web_response = task:join()
+/// ```
+///
+async fn web_post_async(lua: &Lua, ctx: &RuntimeContext, url: String, data: Value) -> mlua::Result<Value> {
+	ctx.check_net(&url)?;
+	let client = Client::builder()
+		.redirect(redirect_policy(ctx))
+		.build()
+		.map_err(crate::Error::from)?;
+
+	let mut request_builder = client.post(&url);
+
+	// Set Content-Type and body based on the type of 'data'
+	match data {
+		Value::String(s) => {
+			request_builder = request_builder
+				.header(header::CONTENT_TYPE, "plain/text")
+				.body(s.to_string_lossy());
+		}
+		Value::Table(table) => {
+			let json: serde_json::Value = serde_json::to_value(table).map_err(|err| {
+				crate::Error::custom(format!(
+					"Cannot searlize to json the argument given to the post.\n    Cause: {err}"
 				))
-				.into()),
-			};
+			})?;
+			// mlua provides the serialize features.
+			request_builder = request_builder
+				.header(header::CONTENT_TYPE, "application/json")
+				.body(json.to_string());
+		}
+		_ => {
+			return Err(mlua::Error::RuntimeError(
+				"Data must be a string or a table".to_string(),
+			));
+		}
+	}
 
-			if res.is_ok() {
-				get_hub().publish_sync(format!("-> lua web::post OK ({}) ", url));
-			}
+	let res: mlua::Result<Value> = match request_builder.send().await {
+		Ok(response) => get_lua_response_value(lua, response, &url).await,
+		Err(err) => Err(crate::Error::Lua(format!(
+			"\
+Fail to do utils.web.post for url: {url}
+Cause: {err}"
+		))
+		.into()),
+	};
 
-			// return the Result<Dynamic, Error>
-			res
-		})
-	});
+	if res.is_ok() {
+		get_hub().publish_sync(format!("-> lua web::post OK ({}) ", url));
+	}
 
 	res
 }
 
 // region:    --- Support
 
+/// Follow up to 5 redirects (only to the allowed hosts when sandboxed)
+fn redirect_policy(ctx: &RuntimeContext) -> Policy {
+	let ctx = ctx.clone();
+	Policy::custom(move |attempt| {
+		if attempt.previous().len() >= 5 {
+			attempt.error("too many redirects")
+		} else if ctx.check_net(attempt.url().as_str()).is_err() {
+			attempt.stop()
+		} else {
+			attempt.follow()
+		}
+	})
+}
+
 async fn get_lua_response_value(lua: &Lua, response: Response, url: &str) -> mlua::Result<Value> {
 	let content_type = get_content_type(&response);
 	//
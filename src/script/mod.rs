@@ -11,6 +11,7 @@ mod error_lua_support;
 mod lua_script;
 
 pub use aipack_custom::*;
+pub use error_lua_support::*;
 pub use lua_script::*;
 
 // endregion: --- Modules
@@ -0,0 +1,9 @@
+// region:    --- Modules
+
+mod session_message;
+mod session_store;
+
+pub use session_message::*;
+pub use session_store::*;
+
+// endregion: --- Modules
@@ -0,0 +1,55 @@
+use genai::chat::{ChatMessage, ChatRole};
+use serde::{Deserialize, Serialize};
+
+/// A message of a conversation session (the text content only)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionMessage {
+	pub role: SessionRole,
+	pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum::AsRefStr, strum::EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum SessionRole {
+	System,
+	User,
+	Assistant,
+}
+
+/// Constructors
+impl SessionMessage {
+	pub fn new(role: SessionRole, content: impl Into<String>) -> Self {
+		SessionMessage {
+			role,
+			content: content.into(),
+		}
+	}
+
+	/// Returns the session message for this chat message, if it has a text content and a supported role
+	pub fn from_chat_message(chat_message: &ChatMessage) -> Option<Self> {
+		let role = match chat_message.role {
+			ChatRole::System => SessionRole::System,
+			ChatRole::User => SessionRole::User,
+			ChatRole::Assistant => SessionRole::Assistant,
+			ChatRole::Tool => return None,
+		};
+		let content = chat_message.content.text_as_str()?;
+
+		Some(SessionMessage::new(role, content))
+	}
+}
+
+// region:    --- Froms
+
+impl From<SessionMessage> for ChatMessage {
+	fn from(message: SessionMessage) -> Self {
+		match message.role {
+			SessionRole::System => ChatMessage::system(message.content),
+			SessionRole::User => ChatMessage::user(message.content),
+			SessionRole::Assistant => ChatMessage::assistant(message.content),
+		}
+	}
+}
+
+// endregion: --- Froms
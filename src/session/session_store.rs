@@ -0,0 +1,223 @@
+//! The conversation session store, stored in `.aipack/sessions/`
+//!
+//! Each session is a jsonl file (one `SessionMessage` per line) named by the session name.
+
+use crate::dir_context::DirContext;
+use crate::session::SessionMessage;
+use crate::{Error, Result};
+use simple_fs::{SPath, ensure_dir};
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+
+const SESSION_EXT: &str = "jsonl";
+
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+	dir: SPath,
+}
+
+/// The summary of a session (for the `aip session list`)
+#[derive(Debug)]
+pub struct SessionInfo {
+	pub name: String,
+	pub message_count: usize,
+}
+
+/// Constructors
+impl SessionStore {
+	pub fn new(dir_context: &DirContext) -> Result<Self> {
+		let dir = dir_context.aipack_paths().get_wks_sessions_dir()?;
+		Ok(SessionStore { dir })
+	}
+}
+
+impl SessionStore {
+	/// List the sessions, sorted by name
+	pub fn list(&self) -> Result<Vec<SessionInfo>> {
+		if !self.dir.exists() {
+			return Ok(Vec::new());
+		}
+
+		let mut infos = Vec::new();
+		for dir_entry in fs::read_dir(&self.dir)?.flatten() {
+			let path = dir_entry.path();
+			if !path.is_file() || path.extension().is_none_or(|ext| ext != SESSION_EXT) {
+				continue;
+			}
+			let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+				continue;
+			};
+			infos.push(SessionInfo {
+				name: name.to_string(),
+				message_count: self.load(name)?.len(),
+			});
+		}
+		infos.sort_by(|a, b| a.name.cmp(&b.name));
+
+		Ok(infos)
+	}
+
+	/// Load the messages of the session (empty if the session does not exist)
+	pub fn load(&self, name: &str) -> Result<Vec<SessionMessage>> {
+		let file = self.session_path(name)?;
+		if !file.exists() {
+			return Ok(Vec::new());
+		}
+
+		let content = fs::read_to_string(&file)?;
+		let messages = content
+			.lines()
+			.filter(|line| !line.trim().is_empty())
+			.map(|line| {
+				serde_json::from_str::<SessionMessage>(line)
+					.map_err(|err| Error::cc(format!("Session '{name}' has an invalid message"), err))
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		Ok(messages)
+	}
+
+	/// Append the messages at the end of the session (creating it if needed)
+	pub fn append(&self, name: &str, messages: &[SessionMessage]) -> Result<()> {
+		let file = self.session_path(name)?;
+		ensure_dir(&self.dir)?;
+
+		let mut content = String::new();
+		for message in messages {
+			content.push_str(&serde_json::to_string(message)?);
+			content.push('\n');
+		}
+
+		// Note: One write per append, so that concurrent appends do not interleave their lines
+		let mut file = OpenOptions::new().create(true).append(true).open(&file)?;
+		file.write_all(content.as_bytes())?;
+
+		Ok(())
+	}
+
+	/// Replace all of the messages of the session
+	pub fn replace(&self, name: &str, messages: &[SessionMessage]) -> Result<()> {
+		let file = self.session_path(name)?;
+		ensure_dir(&self.dir)?;
+
+		let mut content = String::new();
+		for message in messages {
+			content.push_str(&serde_json::to_string(message)?);
+			content.push('\n');
+		}
+
+		// Note: Write to a temp file first, so that a concurrent `load` never reads a partial session
+		let tmp_file = self.dir.join(format!("{name}.{SESSION_EXT}.tmp"));
+		fs::write(&tmp_file, content)?;
+		fs::rename(&tmp_file, &file)?;
+
+		Ok(())
+	}
+
+	/// Keep only the last `keep_last` messages of the session.
+	///
+	/// Returns the number of messages removed
+	pub fn truncate(&self, name: &str, keep_last: usize) -> Result<usize> {
+		let messages = self.load(name)?;
+		if messages.len() <= keep_last {
+			return Ok(0);
+		}
+
+		let removed = messages.len() - keep_last;
+		self.replace(name, &messages[removed..])?;
+
+		Ok(removed)
+	}
+
+	/// Delete the session.
+	///
+	/// Returns false if the session did not exist
+	pub fn clear(&self, name: &str) -> Result<bool> {
+		let file = self.session_path(name)?;
+		if !file.exists() {
+			return Ok(false);
+		}
+		fs::remove_file(&file)?;
+
+		Ok(true)
+	}
+}
+
+/// Getters
+impl SessionStore {
+	pub fn dir(&self) -> &SPath {
+		&self.dir
+	}
+
+	/// The jsonl file of the session (fails if the name is invalid)
+	pub fn session_path(&self, name: &str) -> Result<SPath> {
+		validate_session_name(name)?;
+		Ok(self.dir.join(format!("{name}.{SESSION_EXT}")))
+	}
+}
+
+// region:    --- Support
+
+/// The session name is a file name, so, only alphanumeric, `-`, `_`, and `.` (not first) are allowed.
+fn validate_session_name(name: &str) -> Result<()> {
+	let valid = !name.is_empty()
+		&& !name.starts_with('.')
+		&& name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+	if valid {
+		Ok(())
+	} else {
+		Err(Error::custom(format!(
+			"Session name '{name}' is invalid. Only alphanumeric, '-', '_', and '.' (not first) characters are allowed"
+		)))
+	}
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+	use super::*;
+	use crate::_test_support::remove_test_dir;
+	use crate::run::Runtime;
+	use crate::session::SessionRole;
+
+	#[test]
+	fn test_session_store_append_truncate_clear() -> Result<()> {
+		// -- Setup & Fixtures
+		let runtime = Runtime::new_test_runtime_for_temp_dir()?;
+		let store = SessionStore::new(runtime.dir_context())?;
+		let messages = vec![
+			SessionMessage::new(SessionRole::User, "Hello"),
+			SessionMessage::new(SessionRole::Assistant, "Hi there"),
+			SessionMessage::new(SessionRole::User, "How are you?"),
+		];
+
+		// -- Exec & Check
+		store.append("chat-01", &messages[..2])?;
+		store.append("chat-01", &messages[2..])?;
+		assert_eq!(store.load("chat-01")?, messages);
+
+		let infos = store.list()?;
+		assert_eq!(infos.len(), 1);
+		assert_eq!(infos[0].name, "chat-01");
+		assert_eq!(infos[0].message_count, 3);
+
+		assert_eq!(store.truncate("chat-01", 1)?, 2);
+		assert_eq!(store.load("chat-01")?, &messages[2..]);
+
+		assert!(store.clear("chat-01")?, "session should have been cleared");
+		assert!(store.load("chat-01")?.is_empty(), "session should be empty");
+		assert!(store.append("../escape", &messages).is_err(), "name should be invalid");
+
+		// -- Cleanup
+		remove_test_dir(runtime.dir_context().current_dir())?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
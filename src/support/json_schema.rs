@@ -0,0 +1,255 @@
+//! A minimal JSON Schema validator, used to validate the AI responses against the agent `# Output Schema`
+//! when the provider does not support structured output.
+//!
+//! Supported keywords: `type`, `properties`, `required`, `additionalProperties`, `items`, `enum`, `const`,
+//! `minimum`, `maximum`, `minLength`, `maxLength`, `minItems`, `maxItems`, `anyOf`, `oneOf`, `allOf`.
+//! Other keywords are ignored.
+
+use serde_json::{Map, Value};
+
+/// Validate the `value` against the `schema`.
+///
+/// Returns the list of validation errors (empty if valid), each prefixed by the json path (e.g., `$.items[0].name`)
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+	let mut errors = Vec::new();
+	validate_at(schema, value, "$", &mut errors);
+	errors
+}
+
+// region:    --- Support
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+	// Note: `true` schema (or non object) accepts everything, `false` rejects everything
+	let schema = match schema {
+		Value::Object(schema) => schema,
+		Value::Bool(false) => {
+			errors.push(format!("{path}: no value allowed"));
+			return;
+		}
+		_ => return,
+	};
+
+	// -- type
+	if let Some(type_value) = schema.get("type") {
+		let types: Vec<&str> = match type_value {
+			Value::String(t) => vec![t.as_str()],
+			Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+			_ => Vec::new(),
+		};
+		if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+			errors.push(format!(
+				"{path}: expected type '{}' but got '{}'",
+				types.join("|"),
+				type_name(value)
+			));
+			// Note: No need to go further, other keywords would only add noise
+			return;
+		}
+	}
+
+	// -- enum & const
+	if let Some(Value::Array(variants)) = schema.get("enum") {
+		if !variants.contains(value) {
+			errors.push(format!(
+				"{path}: value {value} is not one of {}",
+				Value::Array(variants.clone())
+			));
+		}
+	}
+	if let Some(const_value) = schema.get("const") {
+		if const_value != value {
+			errors.push(format!("{path}: value {value} should be {const_value}"));
+		}
+	}
+
+	// -- Type specific keywords
+	match value {
+		Value::Object(obj) => validate_object(schema, obj, path, errors),
+		Value::Array(items) => validate_array(schema, items, path, errors),
+		Value::String(s) => {
+			let len = s.chars().count() as u64;
+			if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+				if len < min {
+					errors.push(format!("{path}: string length {len} is less than minLength {min}"));
+				}
+			}
+			if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+				if len > max {
+					errors.push(format!("{path}: string length {len} is greater than maxLength {max}"));
+				}
+			}
+		}
+		Value::Number(num) => {
+			let num = num.as_f64().unwrap_or_default();
+			if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+				if num < min {
+					errors.push(format!("{path}: {num} is less than minimum {min}"));
+				}
+			}
+			if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+				if num > max {
+					errors.push(format!("{path}: {num} is greater than maximum {max}"));
+				}
+			}
+		}
+		_ => (),
+	}
+
+	// -- Combinators
+	if let Some(Value::Array(schemas)) = schema.get("allOf") {
+		for sub_schema in schemas {
+			validate_at(sub_schema, value, path, errors);
+		}
+	}
+	if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+		if !schemas.iter().any(|s| validate(s, value).is_empty()) {
+			errors.push(format!("{path}: value does not match any of the 'anyOf' schemas"));
+		}
+	}
+	if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+		let matches = schemas.iter().filter(|s| validate(s, value).is_empty()).count();
+		if matches != 1 {
+			errors.push(format!(
+				"{path}: value should match exactly one of the 'oneOf' schemas (matched {matches})"
+			));
+		}
+	}
+}
+
+fn validate_object(schema: &Map<String, Value>, obj: &Map<String, Value>, path: &str, errors: &mut Vec<String>) {
+	if let Some(Value::Array(required)) = schema.get("required") {
+		for name in required.iter().filter_map(Value::as_str) {
+			if !obj.contains_key(name) {
+				errors.push(format!("{path}: missing required property '{name}'"));
+			}
+		}
+	}
+
+	let properties = schema.get("properties").and_then(Value::as_object);
+	for (name, prop_value) in obj {
+		let prop_path = format!("{path}.{name}");
+		if let Some(prop_schema) = properties.and_then(|props| props.get(name)) {
+			validate_at(prop_schema, prop_value, &prop_path, errors);
+		} else {
+			match schema.get("additionalProperties") {
+				Some(Value::Bool(false)) => errors.push(format!("{path}: additional property '{name}' is not allowed")),
+				Some(additional_schema @ Value::Object(_)) => {
+					validate_at(additional_schema, prop_value, &prop_path, errors)
+				}
+				_ => (),
+			}
+		}
+	}
+}
+
+fn validate_array(schema: &Map<String, Value>, items: &[Value], path: &str, errors: &mut Vec<String>) {
+	let len = items.len() as u64;
+	if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+		if len < min {
+			errors.push(format!("{path}: array length {len} is less than minItems {min}"));
+		}
+	}
+	if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+		if len > max {
+			errors.push(format!("{path}: array length {len} is greater than maxItems {max}"));
+		}
+	}
+
+	if let Some(items_schema) = schema.get("items") {
+		for (idx, item) in items.iter().enumerate() {
+			validate_at(items_schema, item, &format!("{path}[{idx}]"), errors);
+		}
+	}
+}
+
+fn is_type(value: &Value, type_name: &str) -> bool {
+	match type_name {
+		"object" => value.is_object(),
+		"array" => value.is_array(),
+		"string" => value.is_string(),
+		"number" => value.is_number(),
+		"integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+		"boolean" => value.is_boolean(),
+		"null" => value.is_null(),
+		_ => true,
+	}
+}
+
+fn type_name(value: &Value) -> &'static str {
+	match value {
+		Value::Null => "null",
+		Value::Bool(_) => "boolean",
+		Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+		Value::Number(_) => "number",
+		Value::String(_) => "string",
+		Value::Array(_) => "array",
+		Value::Object(_) => "object",
+	}
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn test_json_schema_validate_valid() -> Result<()> {
+		// -- Setup & Fixtures
+		let schema = json!({
+			"type": "object",
+			"properties": {
+				"name": {"type": "string", "minLength": 1},
+				"tags": {"type": "array", "items": {"type": "string"}},
+				"level": {"type": "integer", "minimum": 0, "maximum": 5},
+				"kind": {"enum": ["a", "b"]}
+			},
+			"required": ["name", "tags"],
+			"additionalProperties": false
+		});
+		let value = json!({"name": "one", "tags": ["x", "y"], "level": 3, "kind": "b"});
+
+		// -- Exec
+		let errors = validate(&schema, &value);
+
+		// -- Check
+		assert!(errors.is_empty(), "Should have no errors, but got: {errors:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_json_schema_validate_invalid() -> Result<()> {
+		// -- Setup & Fixtures
+		let schema = json!({
+			"type": "object",
+			"properties": {
+				"name": {"type": "string"},
+				"tags": {"type": "array", "items": {"type": "string"}},
+				"level": {"type": "integer", "maximum": 5}
+			},
+			"required": ["name"],
+			"additionalProperties": false
+		});
+		let value = json!({"tags": ["x", 2], "level": 7, "extra": true});
+
+		// -- Exec
+		let errors = validate(&schema, &value);
+
+		// -- Check
+		assert_eq!(errors.len(), 4, "Wrong number of errors: {errors:?}");
+		assert!(errors.contains(&"$: missing required property 'name'".to_string()));
+		assert!(errors.contains(&"$.tags[1]: expected type 'string' but got 'integer'".to_string()));
+		assert!(errors.contains(&"$.level: 7 is greater than maximum 5".to_string()));
+		assert!(errors.contains(&"$: additional property 'extra' is not allowed".to_string()));
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -15,6 +15,7 @@ pub mod cred;
 pub mod files;
 pub mod hbs;
 pub mod html;
+pub mod json_schema;
 pub mod jsons;
 pub mod md;
 pub mod paths;
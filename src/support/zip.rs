@@ -3,18 +3,17 @@ use camino::Utf8Path;
 use simple_fs::SPath;
 use std::fs::{self, File};
 use std::io::{self, Read as _};
-use walkdir::WalkDir;
 use zip::ZipArchive;
-use zip::ZipWriter;
 use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
 
-/// Creates a zip archive from the directory `src_dir` and writes it to `dest_file`.
+/// Creates a zip archive with the `files` of the directory `src_dir` and writes it to `dest_file`.
 ///
-/// `src_dir` is the directory to be zipped.
-/// `dest_file` is the destination file path for the zip archive.
+/// `files` are the paths relative to `src_dir` (with `/`), which are the names in the archive.
 ///
-/// This function recursively adds files and subdirectories from `src_dir` to the zip archive.
-pub fn zip_dir(src_dir: impl AsRef<Utf8Path>, dest_file: impl AsRef<Utf8Path>) -> Result<()> {
+/// The archive is reproducible (same files and content, same archive bytes):
+/// the entries are sorted by name, with a fixed timestamp and permissions, and no directory entries.
+pub fn zip_files(src_dir: impl AsRef<Utf8Path>, files: &[String], dest_file: impl AsRef<Utf8Path>) -> Result<()> {
 	let src_dir = src_dir.as_ref();
 	let dest_file = dest_file.as_ref();
 
@@ -22,50 +21,19 @@ pub fn zip_dir(src_dir: impl AsRef<Utf8Path>, dest_file: impl AsRef<Utf8Path>) -
 	let file = File::create(dest_file.as_std_path())?;
 	let mut zip = ZipWriter::new(file);
 
-	// Set default options with deflate compression.
-	let options = SimpleFileOptions::default();
+	let options = reproducible_file_options();
 
-	// Walk through the directory.
-	for entry in WalkDir::new(src_dir.as_std_path()) {
-		let entry = entry.map_err(|err| Error::ZipFail {
-			zip_dir: src_dir.to_string(),
-			cause: format!("Fail to zip directory. Error on entry. Cause {err}"),
-		})?;
-		let Some(path) = Utf8Path::from_path(entry.path()) else {
-			continue;
-		};
+	let mut names: Vec<&String> = files.iter().collect();
+	names.sort();
+	names.dedup();
 
-		let relative_path = path.strip_prefix(src_dir).map_err(|err| Error::ZipFail {
+	for name in names {
+		zip.start_file(name.as_str(), options).map_err(|err| Error::ZipFail {
 			zip_dir: src_dir.to_string(),
-			cause: format!("Fail strip_prefix '{src_dir}' on '{path}'. Cause {err}"),
+			cause: format!("Fail zip.start_file '{name}'. Cause {err}"),
 		})?;
-
-		// Convert relative path to UTF-8 string and normalize slashes for cross-platform compatibility.
-		let name = relative_path.as_str().replace("\\", "/");
-
-		if path.is_dir() {
-			// Add directory entry to zip archive.
-			if !name.is_empty() {
-				// Ensure directory name ends with '/'.
-				let dir_name = if name.ends_with('/') {
-					name.to_string()
-				} else {
-					format!("{}/", name)
-				};
-				zip.add_directory(&dir_name, options).map_err(|err| Error::ZipFail {
-					zip_dir: src_dir.to_string(),
-					cause: format!("Fail add directory '{dir_name}'. Cause {err}"),
-				})?;
-			}
-		} else {
-			// Add file entry to zip archive.
-			zip.start_file(&name, options).map_err(|err| Error::ZipFail {
-				zip_dir: src_dir.to_string(),
-				cause: format!("Fail zip.start_file '{name}'. Cause {err}"),
-			})?;
-			let mut f = File::open(path)?;
-			io::copy(&mut f, &mut zip)?;
-		}
+		let mut f = File::open(src_dir.join(name))?;
+		io::copy(&mut f, &mut zip)?;
 	}
 
 	zip.finish().map_err(|err| Error::ZipFail {
@@ -139,3 +107,69 @@ pub fn extract_text_content(src_zip_path: impl AsRef<SPath>, content_path: &str)
 
 	Ok(content)
 }
+
+/// Returns the `(name, sha256 hex)` of each file of the zip archive (directories excluded), sorted by name.
+pub fn zip_files_sha256(src_zip_path: impl AsRef<SPath>) -> Result<Vec<(String, String)>> {
+	use sha2::{Digest, Sha256};
+
+	let src_zip_path = src_zip_path.as_ref();
+	let file = File::open(src_zip_path)?;
+	let mut archive = ZipArchive::new(file).map_err(|err| Error::Zip {
+		zip_file: src_zip_path.name().to_string(),
+		cause: err.to_string(),
+	})?;
+
+	let mut files = Vec::with_capacity(archive.len());
+	for i in 0..archive.len() {
+		let mut file = archive.by_index(i).map_err(|err| Error::Zip {
+			zip_file: src_zip_path.name().to_string(),
+			cause: format!("Fail to get item by_index {i}. Cause: {err}"),
+		})?;
+		if file.is_dir() {
+			continue;
+		}
+		let mut hasher = Sha256::new();
+		io::copy(&mut file, &mut hasher)?;
+		let hash: String = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+		files.push((file.name().to_string(), hash));
+	}
+	files.sort();
+
+	Ok(files)
+}
+
+/// Appends a file entry with this content to an existing zip archive.
+pub fn append_file_content(zip_path: impl AsRef<SPath>, content_path: &str, content: &str) -> Result<()> {
+	use std::io::Write as _;
+
+	let zip_path = zip_path.as_ref();
+	let file = fs::OpenOptions::new().read(true).write(true).open(zip_path)?;
+	let mut zip = ZipWriter::new_append(file).map_err(|err| Error::Zip {
+		zip_file: zip_path.name().to_string(),
+		cause: format!("Fail to open zip for append. Cause: {err}"),
+	})?;
+	zip.start_file(content_path, reproducible_file_options())
+		.map_err(|err| Error::Zip {
+			zip_file: zip_path.name().to_string(),
+			cause: format!("Fail zip.start_file '{content_path}'. Cause {err}"),
+		})?;
+	zip.write_all(content.as_bytes())?;
+	zip.finish().map_err(|err| Error::Zip {
+		zip_file: zip_path.name().to_string(),
+		cause: format!("Fail zip.finish. Cause {err}"),
+	})?;
+
+	Ok(())
+}
+
+// region:    --- Support
+
+/// Deflate compression, with fixed timestamp (1980-01-01) and permissions
+fn reproducible_file_options() -> SimpleFileOptions {
+	SimpleFileOptions::default()
+		.compression_method(CompressionMethod::Deflated)
+		.last_modified_time(DateTime::default())
+		.unix_permissions(0o644)
+}
+
+// endregion: --- Support
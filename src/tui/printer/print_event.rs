@@ -5,4 +5,21 @@ use derive_more::From;
 pub enum PrintEvent {
 	#[from]
 	PackList(Vec<PackDir>),
+
+	#[from]
+	AiStream(AiStreamEvent),
+}
+
+/// Incremental events sent while an AI response is being streamed (when `stream` is on).
+///
+/// Note: The `input_idx` tells which input the chunks are for, as the inputs can be streamed concurrently.
+///       The `label` is the input label, displayed at the start of the stream (and when switching input).
+///       `Interrupted` replaces `End` when the stream fails before its end (the attempt might be retried).
+#[derive(Debug)]
+pub enum AiStreamEvent {
+	Start { input_idx: usize, label: String },
+	Chunk { input_idx: usize, content: String },
+	ReasoningChunk { input_idx: usize, content: String },
+	End { input_idx: usize },
+	Interrupted { input_idx: usize },
 }
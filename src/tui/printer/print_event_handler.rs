@@ -8,5 +8,6 @@ pub fn handle_print(print_event: Arc<PrintEvent>, interactive: bool) {
 			let pack_dirs: Vec<&_> = pack_dirs.iter().collect();
 			printers::print_pack_list(&pack_dirs, interactive)
 		}
+		PrintEvent::AiStream(stream_event) => printers::print_ai_stream(stream_event, interactive),
 	}
 }
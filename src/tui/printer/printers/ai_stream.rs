@@ -0,0 +1,128 @@
+use crate::tui::AiStreamEvent;
+use crossterm::{
+	cursor, execute,
+	style::{Attribute, Print, ResetColor, SetAttribute},
+	terminal::{self, ClearType},
+};
+use std::collections::HashMap;
+use std::io::{Write as _, stdout};
+use std::sync::{LazyLock, Mutex};
+
+/// The streams being printed, as the chunks of concurrent inputs can be interleaved
+#[derive(Default)]
+struct StreamsState {
+	/// The input of the last printed header or chunk
+	current_input_idx: Option<usize>,
+	/// True when the last printed chunk did not end with a newline
+	mid_line: bool,
+	/// The labels of the inputs being streamed
+	labels: HashMap<usize, String>,
+}
+
+static STREAMS_STATE: LazyLock<Mutex<StreamsState>> = LazyLock::new(Default::default);
+
+/// Print the streamed AI response chunks as they arrive.
+///
+/// Note: Chunks are printed without any added newline. In interactive mode (raw terminal),
+///       each newline also needs to move the cursor back to the first column.
+///       When the chunks of another input arrive, a `<~ ... (label)` line tells which input they are for.
+///       When the stream is interrupted, a `<~ ... interrupted (label)` line marks the printed chunks as dropped.
+#[allow(unused_must_use)] // TODO: need to remove and make this function return error
+pub fn print_ai_stream(stream_event: &AiStreamEvent, interactive: bool) {
+	let stdout = stdout();
+	let mut stdout = stdout.lock();
+	let mut state = STREAMS_STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+	match stream_event {
+		AiStreamEvent::Start { input_idx, label } => {
+			if state.mid_line {
+				print_newline(&mut stdout, interactive);
+			}
+			state.labels.insert(*input_idx, label.clone());
+			state.current_input_idx = Some(*input_idx);
+			state.mid_line = false;
+			execute!(
+				stdout,
+				terminal::Clear(ClearType::CurrentLine),
+				cursor::MoveToColumn(0),
+				Print(format!("<~ ai_response streaming ({label})\n")),
+			);
+			if interactive {
+				execute!(stdout, cursor::MoveToColumn(0));
+			}
+		}
+		AiStreamEvent::Chunk { input_idx, content } => {
+			switch_to_input(&mut stdout, &mut state, *input_idx, interactive);
+			print_chunk(&mut stdout, &mut state, content, interactive);
+		}
+		AiStreamEvent::ReasoningChunk { input_idx, content } => {
+			switch_to_input(&mut stdout, &mut state, *input_idx, interactive);
+			execute!(stdout, SetAttribute(Attribute::Dim));
+			print_chunk(&mut stdout, &mut state, content, interactive);
+			execute!(stdout, ResetColor, SetAttribute(Attribute::Reset));
+		}
+		AiStreamEvent::End { input_idx } => {
+			state.labels.remove(input_idx);
+			if state.current_input_idx == Some(*input_idx) {
+				state.current_input_idx = None;
+				state.mid_line = false;
+				print_newline(&mut stdout, interactive);
+			}
+		}
+		AiStreamEvent::Interrupted { input_idx } => {
+			let label = state.labels.remove(input_idx).unwrap_or_default();
+			if state.mid_line {
+				print_newline(&mut stdout, interactive);
+			}
+			state.current_input_idx = None;
+			state.mid_line = false;
+			execute!(stdout, Print(format!("<~ ai_response streaming interrupted ({label})")));
+			print_newline(&mut stdout, interactive);
+		}
+	}
+
+	stdout.flush();
+}
+
+/// Print the `<~ ... (label)` line when the chunk is not for the input of the last printed chunk
+#[allow(unused_must_use)]
+fn switch_to_input(stdout: &mut impl std::io::Write, state: &mut StreamsState, input_idx: usize, interactive: bool) {
+	if state.current_input_idx == Some(input_idx) {
+		return;
+	}
+	if state.mid_line {
+		print_newline(stdout, interactive);
+	}
+	let label = state.labels.get(&input_idx).map(|label| label.as_str()).unwrap_or_default();
+	execute!(stdout, Print(format!("<~ ai_response streaming, continued ({label})")));
+	print_newline(stdout, interactive);
+	state.current_input_idx = Some(input_idx);
+	state.mid_line = false;
+}
+
+#[allow(unused_must_use)]
+fn print_newline(stdout: &mut impl std::io::Write, interactive: bool) {
+	execute!(stdout, Print("\n"));
+	if interactive {
+		execute!(stdout, cursor::MoveToColumn(0));
+	}
+}
+
+#[allow(unused_must_use)]
+fn print_chunk(stdout: &mut impl std::io::Write, state: &mut StreamsState, content: &str, interactive: bool) {
+	if !content.is_empty() {
+		state.mid_line = !content.ends_with('\n');
+	}
+
+	if !interactive {
+		execute!(stdout, Print(content));
+		return;
+	}
+
+	for (idx, line) in content.split('\n').enumerate() {
+		if idx > 0 {
+			execute!(stdout, Print("\n"), cursor::MoveToColumn(0));
+		}
+		execute!(stdout, Print(line));
+	}
+}
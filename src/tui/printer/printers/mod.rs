@@ -1,8 +1,10 @@
 // region:    --- Modules
 
+mod ai_stream;
 mod common;
 mod pack_list;
 
+pub use ai_stream::*;
 #[allow(unused)]
 pub use common::*;
 pub use pack_list::*;
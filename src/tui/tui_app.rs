@@ -1,15 +1,17 @@
-use crate::Result;
 use crate::cli::CliArgs;
 use crate::exec::{ExecCommand, ExecEvent};
 use crate::hub::{HubEvent, get_hub};
 use crate::tui::hub_event_handler::handle_hub_event;
 use crate::tui::in_reader::InReader;
 use crate::tui::support::{safer_println, send_to_executor};
+use crate::{Error, Result};
 use crossterm::cursor::MoveUp;
 use crossterm::event::{KeyCode, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{Clear, ClearType};
+use std::sync::Arc;
 use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{mpsc, oneshot};
 
 /// Note: Right now the quick channel is a watch, but might be better to be a mpsc.
@@ -98,6 +100,12 @@ impl TuiApp {
 						// -- Quit
 						KeyCode::Char('q') => hub.publish(HubEvent::Quit).await,
 
+						// -- Redo with --allow-all (after a sandbox permission denied)
+						KeyCode::Char('A') => {
+							safer_println("\n-- A pressed - Redo with --allow-all\n", interactive);
+							send_to_executor(&exec_tx, ExecCommand::RedoAllowAll).await;
+						}
+
 						// -- Open agent
 						KeyCode::Char('a') => {
 							// clear_last_n_lines(1);
@@ -135,6 +143,17 @@ impl TuiApp {
 							println!("Tui ERROR while handling handle_hub_event. Cause {err}")
 						}
 					}
+					// Note: Can happen when many events are published quickly (e.g., stream chunks),
+					//       in which case, we skip the missed events rather than stopping the handler,
+					//       and tell the user that the displayed output is missing some of them.
+					Err(RecvError::Lagged(count)) => {
+						safer_println(
+							&format!(
+								"\n-! Output truncated: {count} events could not be displayed (published faster than displayed).\n   The run results are not affected."
+							),
+							interactive,
+						);
+					}
 					Err(err) => {
 						println!("TuiApp handle_hub_event event error: {err}");
 						break;
@@ -172,18 +191,26 @@ impl TuiApp {
 	/// Wait for the exit
 	/// - When interative mode, wait for HubEvent::Quit
 	/// - When not intractive, the first HubEvent::Executor(ExecEvent::End) will end
+	///
+	/// Returns the error of the last run (a redo overrides the previous run), if it failed.
 	async fn wait_for_exit(&self, mut hub_rx: Receiver<HubEvent>, interactive: bool) -> Result<()> {
+		let mut last_run_error: Option<Arc<Error>> = None;
 		loop {
 			if let Ok(hub_event) = hub_rx.recv().await {
 				match (hub_event, interactive) {
 					(HubEvent::Quit, _) => break,
 					(HubEvent::Executor(ExecEvent::EndExec), false) => break,
+					(HubEvent::Executor(ExecEvent::RunStart), _) => last_run_error = None,
+					(HubEvent::Executor(ExecEvent::RunFailed(err)), _) => last_run_error = Some(err),
 					_ => (),
 				}
 			}
 		}
 
-		Ok(())
+		match last_run_error {
+			Some(cause) => Err(Error::RunFailed { cause }),
+			None => Ok(()),
+		}
 	}
 }
 